@@ -55,6 +55,9 @@ pub enum AppError {
 	#[error("version range is not usable")]
 	UnusableRange,
 
+	#[error("the starting version is newer than the target version")]
+	BackwardsRange,
+
 	#[cfg(feature = "ssr")]
 	#[error("timesync: {0}")]
 	Timesync(#[from] timesimp::ParseError),
@@ -135,6 +138,7 @@ impl AppError {
 			Self::NotImplemented => StatusCode::NOT_IMPLEMENTED,
 			Self::NoMatchingVersions => StatusCode::NOT_FOUND,
 			Self::UnusableRange => StatusCode::BAD_REQUEST,
+			Self::BackwardsRange => StatusCode::BAD_REQUEST,
 			#[cfg(feature = "ssr")]
 			Self::DatabaseQuery(diesel::result::Error::NotFound) => StatusCode::NOT_FOUND,
 			Self::AuthMissingHeader(_) => StatusCode::UNAUTHORIZED,
@@ -177,6 +181,7 @@ impl AppError {
 						Self::Io(_) => "io",
 						Self::NoMatchingVersions => "no-matching-versions",
 						Self::UnusableRange => "unusable-range",
+						Self::BackwardsRange => "backwards-range",
 						#[cfg(feature = "ssr")]
 						Self::Timesync(_) => "timesync",
 						Self::AuthMissingHeader(_) => "auth-missing-header",
@@ -38,6 +38,34 @@ macro_rules! device_role_struct {
 				}
 			}
 		}
+
+		/// For endpoints which work anonymously but can do more for an identified device.
+		///
+		/// Yields `None` when no certificate is presented or the device doesn't have the role;
+		/// an invalid certificate is still an error.
+		impl<S> axum::extract::OptionalFromRequestParts<S> for $name
+		where
+			Db: FromRef<S>,
+			S: Send + Sync,
+		{
+			type Rejection = AppError;
+
+			async fn from_request_parts(
+				parts: &mut axum::http::request::Parts,
+				state: &S,
+			) -> Result<Option<Self>, Self::Rejection> {
+				match <Self as axum::extract::FromRequestParts<S>>::from_request_parts(parts, state)
+					.await
+				{
+					Ok(device) => Ok(Some(device)),
+					Err(
+						AppError::AuthMissingCertificate
+						| AppError::AuthInsufficientPermissions { .. },
+					) => Ok(None),
+					Err(err) => Err(err),
+				}
+			}
+		}
 	};
 }
 
@@ -45,6 +73,26 @@ device_role_struct!(AdminDevice, DeviceRole::Admin);
 device_role_struct!(ServerDevice, DeviceRole::Server);
 device_role_struct!(ReleaserDevice, DeviceRole::Releaser);
 
+/// For endpoints which work anonymously but note which device made the request, if any.
+impl<S> axum::extract::OptionalFromRequestParts<S> for AuthDevice
+where
+	Db: FromRef<S>,
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(
+		parts: &mut axum::http::request::Parts,
+		state: &S,
+	) -> Result<Option<Self>, Self::Rejection> {
+		match <Self as axum::extract::FromRequestParts<S>>::from_request_parts(parts, state).await {
+			Ok(device) => Ok(Some(device)),
+			Err(AppError::AuthMissingCertificate) => Ok(None),
+			Err(err) => Err(err),
+		}
+	}
+}
+
 impl<S> axum::extract::FromRequestParts<S> for AuthDevice
 where
 	Db: FromRef<S>,
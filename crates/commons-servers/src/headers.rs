@@ -1,9 +1,19 @@
-use axum::{extract::FromRequestParts, http::request::Parts};
+use std::{convert::Infallible, net::IpAddr};
+
+use axum::{
+	RequestPartsExt as _,
+	extract::FromRequestParts,
+	http::{header::USER_AGENT, request::Parts},
+};
+use axum_client_ip::ClientIp;
 use commons_errors::AppError;
 use commons_types::version::VersionStr;
 
 const X_VERSION: &str = "X-Version";
 
+/// Headers which CDNs and load balancers set to the country of the client.
+const COUNTRY_HEADERS: [&str; 2] = ["CF-IPCountry", "CloudFront-Viewer-Country"];
+
 #[derive(Debug, Clone)]
 pub struct VersionHeader(pub VersionStr);
 
@@ -25,3 +35,50 @@ where
 		Ok(VersionHeader(param))
 	}
 }
+
+/// What can be told about where a request comes from, for analytics.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOrigin {
+	pub ip: Option<IpAddr>,
+
+	/// ISO 3166 country code, if a CDN in front of the server provides one.
+	pub country: Option<String>,
+
+	pub user_agent: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientOrigin
+where
+	S: Send + Sync,
+{
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+		let header = |name: &str| {
+			parts
+				.headers
+				.get(name)
+				.and_then(|value| value.to_str().ok())
+				.map(|value| value.trim().to_owned())
+		};
+
+		let country = COUNTRY_HEADERS
+			.iter()
+			.find_map(|name| header(name))
+			.map(|country| country.to_ascii_uppercase())
+			// XX is what Cloudflare sends when it doesn't know
+			.filter(|country| {
+				country.len() == 2
+					&& country.bytes().all(|b| b.is_ascii_alphanumeric())
+					&& country != "XX"
+			});
+		let user_agent = header(USER_AGENT.as_str()).filter(|agent| !agent.is_empty());
+		let ip = parts.extract::<ClientIp>().await.ok().map(|ip| ip.0);
+
+		Ok(Self {
+			ip,
+			country,
+			user_agent,
+		})
+	}
+}
@@ -1,13 +1,17 @@
 use std::{net::SocketAddr, time::Duration};
 
 use axum::extract::Request;
+use axum::http::{Extensions, HeaderMap, StatusCode, Version};
 use axum::middleware::Next;
 use axum::response::Response;
 use axum::{Router, middleware};
 use axum_client_ip::{ClientIp, ClientIpSource};
 use axum_server_timing::ServerTimingLayer;
 use tokio::net::TcpListener;
-use tower_http::{compression::CompressionLayer, trace::TraceLayer};
+use tower_http::{
+	compression::{CompressionLayer, DefaultPredicate, Predicate as _},
+	trace::TraceLayer,
+};
 use tracing::Span;
 
 pub mod device_auth;
@@ -15,6 +19,11 @@ pub mod headers;
 pub mod health;
 pub mod tailscale_auth;
 
+/// Marks a response to be left alone by the compression layer, like artifact downloads which are
+/// already compressed and need their length and ranges kept intact to be resumed.
+#[derive(Clone, Copy, Debug)]
+pub struct Uncompressed;
+
 pub fn router(routes: Router<()>, client_ip_source: ClientIpSource) -> Router<()> {
 	routes
 		// ordering of the client ip middlewares is critical, do not change
@@ -50,7 +59,13 @@ pub fn router(routes: Router<()>, client_ip_source: ClientIpSource) -> Router<()
 					},
 				),
 		)
-		.layer(CompressionLayer::new())
+		.layer(
+			CompressionLayer::new().compress_when(DefaultPredicate::new().and(
+				|_: StatusCode, _: Version, _: &HeaderMap, extensions: &Extensions| {
+					extensions.get::<Uncompressed>().is_none()
+				},
+			)),
+		)
 		.layer(ServerTimingLayer::new("srv"))
 }
 
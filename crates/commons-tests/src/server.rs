@@ -15,6 +15,9 @@ use x509_parser::prelude::*;
 
 use crate::db::TestDb;
 
+/// Seed of the key the public server signs release manifests with in tests.
+pub const TEST_SIGNING_SEED: [u8; 32] = [7; 32];
+
 #[derive(QueryableByName)]
 struct Device {
 	#[diesel(sql_type = sql_types::Uuid)]
@@ -46,14 +49,32 @@ pub async fn run<F, T, Fut>(test: F) -> T
 where
 	F: FnOnce(AsyncPgConnection, TestServer, TestServer) -> Fut,
 	Fut: Future<Output = T>,
+{
+	run_configured(|_| {}, test).await
+}
+
+/// Like [`run`], with changes to the public server's state.
+#[allow(dead_code)] // when imported into a test that only uses run()
+pub async fn run_configured<C, F, T, Fut>(configure: C, test: F) -> T
+where
+	C: FnOnce(&mut public_server::state::AppState),
+	F: FnOnce(AsyncPgConnection, TestServer, TestServer) -> Fut,
+	Fut: Future<Output = T>,
 {
 	TestDb::run(async |conn, url| {
+		let mut public_state = public_server::state::AppState {
+			db: database::init_to(&url),
+			manifest_signer: Some(public_server::signing::ManifestSigner::from_seed(
+				&TEST_SIGNING_SEED,
+			)),
+			artifact_cache: None,
+			tera: public_server::state::AppState::init_tera().unwrap(),
+			server_versions_secret: Some("test-secret".to_string()),
+			public_url: None,
+		};
+		configure(&mut public_state);
 		let public_router = router(
-			public_server::routes().with_state(public_server::state::AppState {
-				db: database::init_to(&url),
-				tera: public_server::state::AppState::init_tera().unwrap(),
-				server_versions_secret: Some("test-secret".to_string()),
-			}),
+			public_server::routes().with_state(public_state),
 			ClientIpSource::RightmostForwarded,
 		);
 		let private_router = router(
@@ -83,7 +104,22 @@ where
 	F: FnOnce(AsyncPgConnection, String, Uuid, TestServer, TestServer) -> Fut,
 	Fut: Future<Output = T>,
 {
-	run(async |mut conn, mut public, private| {
+	run_with_device_auth_configured(role, |_| {}, test).await
+}
+
+/// Like [`run_with_device_auth`], with changes to the public server's state.
+#[allow(dead_code)] // when imported into a test that only uses run()
+pub async fn run_with_device_auth_configured<C, F, T, Fut>(
+	role: &'static str,
+	configure: C,
+	test: F,
+) -> T
+where
+	C: FnOnce(&mut public_server::state::AppState),
+	F: FnOnce(AsyncPgConnection, String, Uuid, TestServer, TestServer) -> Fut,
+	Fut: Future<Output = T>,
+{
+	run_configured(configure, async |mut conn, mut public, private| {
 		let (key_data, cert) = make_certificate();
 
 		let device_row: Device = sql_query(
@@ -2,6 +2,9 @@ pub use uuid::Uuid;
 
 pub mod device;
 pub mod geo;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod requirements;
 pub mod server;
 pub mod status;
 pub mod version;
@@ -0,0 +1,216 @@
+//! Markdown, as written in changelogs and notes, rendered to HTML which is safe to put in a page.
+//!
+//! Changelogs are uploaded by releaser devices, so any HTML they contain is untrusted: the rendered
+//! output is cleaned against an allowlist of the tags and attributes markdown produces. The classes,
+//! ids, styles and inputs of footnotes, alerts, aligned tables and task lists are only kept in the
+//! exact forms the renderer writes them.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use ammonia::Builder;
+use pulldown_cmark::{Options, Parser, html};
+
+/// The tags markdown renders to.
+const TAGS: &[&str] = &[
+	"a",
+	"blockquote",
+	"br",
+	"code",
+	"del",
+	"div",
+	"em",
+	"h1",
+	"h2",
+	"h3",
+	"h4",
+	"h5",
+	"h6",
+	"hr",
+	"img",
+	"input",
+	"li",
+	"ol",
+	"p",
+	"pre",
+	"s",
+	"strong",
+	"sup",
+	"table",
+	"tbody",
+	"td",
+	"th",
+	"thead",
+	"tr",
+	"ul",
+];
+
+/// Tags which are removed along with everything in them, rather than only unwrapped.
+const REMOVED_WITH_CONTENT: &[&str] = &["script", "style"];
+
+/// The schemes links and images can use. Relative links are kept as they are.
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Table cell alignments, as the renderer writes them.
+const ALIGNMENTS: &[&str] = &[
+	"text-align: left",
+	"text-align: center",
+	"text-align: right",
+];
+
+/// Classes of the blockquotes which `> [!NOTE]` and the like render to.
+const ALERT_CLASSES: &[&str] = &[
+	"markdown-alert-note",
+	"markdown-alert-tip",
+	"markdown-alert-important",
+	"markdown-alert-warning",
+	"markdown-alert-caution",
+];
+
+/// Put in front of footnote ids and the links to them, so they can't clash with ids on the page.
+const FOOTNOTE_ID_PREFIX: &str = "footnote-";
+
+/// Render markdown to sanitised HTML.
+pub fn render(text: &str) -> String {
+	let mut options = Options::empty();
+	options.insert(Options::ENABLE_FOOTNOTES);
+	options.insert(Options::ENABLE_GFM);
+	options.insert(Options::ENABLE_SMART_PUNCTUATION);
+	options.insert(Options::ENABLE_STRIKETHROUGH);
+	options.insert(Options::ENABLE_TABLES);
+	options.insert(Options::ENABLE_TASKLISTS);
+	let parser = Parser::new_ext(text, options);
+	let mut html_output = String::new();
+	html::push_html(&mut html_output, parser);
+	sanitise(&html_output)
+}
+
+/// Remove everything from the HTML which isn't on the allowlist.
+pub fn sanitise(html: &str) -> String {
+	Builder::empty()
+		.add_tags(TAGS)
+		.clean_content_tags(REMOVED_WITH_CONTENT.iter().copied().collect::<HashSet<_>>())
+		.add_tag_attributes("a", &["href", "title"])
+		.add_tag_attributes("img", &["src", "alt", "title"])
+		.add_tag_attributes("ol", &["start"])
+		.add_tag_attributes("div", &["id"])
+		.add_allowed_classes("sup", &["footnote-reference", "footnote-definition-label"])
+		.add_allowed_classes("div", &["footnote-definition"])
+		.add_allowed_classes("blockquote", ALERT_CLASSES)
+		.add_tag_attribute_values("th", "style", ALIGNMENTS)
+		.add_tag_attribute_values("td", "style", ALIGNMENTS)
+		// Task list items are read-only checkboxes, whatever the input was
+		.add_tag_attributes("input", &["checked"])
+		.set_tag_attribute_value("input", "type", "checkbox")
+		.set_tag_attribute_value("input", "disabled", "")
+		.attribute_filter(|_, attribute, value| match attribute {
+			"id" => Some(format!("{FOOTNOTE_ID_PREFIX}{value}").into()),
+			"href" => match value.strip_prefix('#') {
+				Some(id) => Some(format!("#{FOOTNOTE_ID_PREFIX}{id}").into()),
+				None => Some(Cow::Borrowed(value)),
+			},
+			_ => Some(Cow::Borrowed(value)),
+		})
+		.add_url_schemes(URL_SCHEMES)
+		.link_rel(Some("noopener noreferrer"))
+		.clean(html)
+		.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn renders_markdown() {
+		let html = render("# Fixes\n\n- **Faster** sync\n- [Docs](https://example.com/docs)");
+		assert!(html.contains("<h1>Fixes</h1>"), "{html}");
+		assert!(html.contains("<strong>Faster</strong>"), "{html}");
+		assert!(
+			html.contains(
+				r#"<a href="https://example.com/docs" rel="noopener noreferrer">Docs</a>"#
+			),
+			"{html}"
+		);
+	}
+
+	#[test]
+	fn removes_scripts() {
+		let html =
+			render("Fixes\n\n<script>alert('pwned')</script>\n\nMore <script>alert(1)</script>");
+		assert!(!html.contains("<script"), "{html}");
+		assert!(!html.contains("alert"), "{html}");
+		assert!(html.contains("Fixes"), "{html}");
+	}
+
+	#[test]
+	fn removes_event_handlers_and_styles() {
+		let html = render(
+			"<img src=\"https://example.com/a.png\" onerror=\"alert(1)\">\n\n<p style=\"display:none\" onclick=\"alert(2)\">Hi</p>",
+		);
+		assert!(!html.contains("onerror"), "{html}");
+		assert!(!html.contains("onclick"), "{html}");
+		assert!(!html.contains("style"), "{html}");
+		assert!(
+			html.contains(r#"<img src="https://example.com/a.png">"#),
+			"{html}"
+		);
+	}
+
+	#[test]
+	fn removes_unsafe_links() {
+		let html = render(
+			"[click](javascript:alert(1)) <a href=\"data:text/html;base64,PHNjcmlwdD4=\">data</a> <iframe src=\"https://example.com\"></iframe>",
+		);
+		assert!(!html.contains("javascript:"), "{html}");
+		assert!(!html.contains("data:"), "{html}");
+		assert!(!html.contains("<iframe"), "{html}");
+		assert!(html.contains("click"), "{html}");
+	}
+
+	#[test]
+	fn keeps_footnotes() {
+		let html = render("Faster sync[^1]\n\n[^1]: On slow links");
+		assert!(
+			html.contains(r##"<sup class="footnote-reference"><a href="#footnote-1" rel="noopener noreferrer">1</a></sup>"##),
+			"{html}"
+		);
+		assert!(
+			html.contains(r#"<div class="footnote-definition" id="footnote-1">"#),
+			"{html}"
+		);
+	}
+
+	#[test]
+	fn keeps_task_lists_read_only() {
+		let html = render("- [x] Done\n- [ ] To do\n\n<input type=\"text\" name=\"password\">");
+		assert!(html.contains("checked"), "{html}");
+		assert_eq!(html.matches(r#"type="checkbox""#).count(), 3, "{html}");
+		assert_eq!(html.matches("disabled").count(), 3, "{html}");
+		assert!(!html.contains("text"), "{html}");
+		assert!(!html.contains("name"), "{html}");
+	}
+
+	#[test]
+	fn keeps_table_alignment_only() {
+		let html = render(
+			"| a | b |\n|:-:|--:|\n| 1 | 2 |\n\n<p class=\"is-hidden\" id=\"app\">Hi</p><td style=\"color: red\">x</td>",
+		);
+		assert!(
+			html.contains(r#"<th style="text-align: center">a</th>"#),
+			"{html}"
+		);
+		assert!(
+			html.contains(r#"<td style="text-align: right">2</td>"#),
+			"{html}"
+		);
+		assert!(!html.contains("color"), "{html}");
+		assert!(!html.contains("is-hidden"), "{html}");
+		assert!(!html.contains(r#"id="app""#), "{html}");
+	}
+
+	#[test]
+	fn keeps_relative_links() {
+		let html = render("[2.5.1](/versions/2.5.1)");
+		assert!(html.contains(r#"href="/versions/2.5.1""#), "{html}");
+	}
+}
@@ -0,0 +1,271 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+#[cfg(feature = "ssr")]
+use diesel::{
+	backend::Backend,
+	deserialize::{self, FromSql, FromSqlRow},
+	expression::AsExpression,
+	serialize::{self, Output, ToSql},
+	sql_types::Text,
+};
+use serde::{Deserialize, Serialize};
+
+/// Software that a Tamanu release runs on, or is used from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+	Nodejs,
+	Postgres,
+	Chrome,
+	Android,
+	Ios,
+}
+
+impl Platform {
+	pub const ALL: [Self; 5] = [
+		Self::Nodejs,
+		Self::Postgres,
+		Self::Chrome,
+		Self::Android,
+		Self::Ios,
+	];
+
+	/// How the platform is stored and written in APIs.
+	pub fn key(self) -> &'static str {
+		match self {
+			Self::Nodejs => "nodejs",
+			Self::Postgres => "postgres",
+			Self::Chrome => "chrome",
+			Self::Android => "android",
+			Self::Ios => "ios",
+		}
+	}
+}
+
+impl Display for Platform {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Nodejs => write!(f, "Node.js"),
+			Self::Postgres => write!(f, "PostgreSQL"),
+			Self::Chrome => write!(f, "Chrome"),
+			Self::Android => write!(f, "Android"),
+			Self::Ios => write!(f, "iOS"),
+		}
+	}
+}
+
+impl From<Platform> for String {
+	fn from(platform: Platform) -> Self {
+		platform.key().into()
+	}
+}
+
+commons_macros::render_as_string!(Platform, minsize(3));
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid platform: {0}")]
+pub struct PlatformFromStringError(String);
+
+impl FromStr for Platform {
+	type Err = PlatformFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"nodejs" => Ok(Self::Nodejs),
+			"postgres" => Ok(Self::Postgres),
+			"chrome" => Ok(Self::Chrome),
+			"android" => Ok(Self::Android),
+			"ios" => Ok(Self::Ios),
+			s => Err(PlatformFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for Platform {
+	type Error = PlatformFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for Platform
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(Platform::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for Platform
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
+
+/// Whether a version bound is written as dot-separated numbers, like `16` or `20.11`.
+pub fn is_version_bound(bound: &str) -> bool {
+	!bound.is_empty()
+		&& bound
+			.split('.')
+			.all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// The numeric parts of a version as reported by a platform, like `v20.11.1` or `17beta1`.
+///
+/// Parsing stops at the first part which doesn't start with a number, and trailing text in a part
+/// is ignored, so pre-releases compare as the release they lead up to.
+fn numeric_parts(version: &str) -> Vec<u64> {
+	let version = version.trim().trim_start_matches(['v', 'V']);
+	let mut parts = Vec::new();
+	for part in version.split('.') {
+		let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+		let Ok(number) = digits.parse() else {
+			break;
+		};
+		parts.push(number);
+		if digits.len() < part.len() {
+			break;
+		}
+	}
+	parts
+}
+
+/// Compare a version against a bound, only as precisely as the bound is written.
+///
+/// `16.4` is equal to a bound of `16`, so that bounds can name a whole major.
+fn compare_to_bound(version: &[u64], bound: &[u64]) -> Ordering {
+	bound
+		.iter()
+		.enumerate()
+		.map(|(n, bound)| version.get(n).copied().unwrap_or(0).cmp(bound))
+		.find(|ordering| ordering.is_ne())
+		.unwrap_or(Ordering::Equal)
+}
+
+/// How a version falls outside of the versions a release supports.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RequirementViolation {
+	BelowMinimum {
+		platform: Platform,
+		version: String,
+		minimum: String,
+	},
+	AboveMaximum {
+		platform: Platform,
+		version: String,
+		maximum: String,
+	},
+}
+
+impl RequirementViolation {
+	pub fn platform(&self) -> Platform {
+		match self {
+			Self::BelowMinimum { platform, .. } | Self::AboveMaximum { platform, .. } => *platform,
+		}
+	}
+
+	/// Check a version against the bounds for its platform, if it can be read at all.
+	pub fn check(
+		platform: Platform,
+		version: &str,
+		minimum: Option<&str>,
+		maximum: Option<&str>,
+	) -> Option<Self> {
+		let parts = numeric_parts(version);
+		if parts.is_empty() {
+			return None;
+		}
+
+		if let Some(minimum) = minimum
+			&& compare_to_bound(&parts, &numeric_parts(minimum)).is_lt()
+		{
+			return Some(Self::BelowMinimum {
+				platform,
+				version: version.into(),
+				minimum: minimum.into(),
+			});
+		}
+
+		if let Some(maximum) = maximum
+			&& compare_to_bound(&parts, &numeric_parts(maximum)).is_gt()
+		{
+			return Some(Self::AboveMaximum {
+				platform,
+				version: version.into(),
+				maximum: maximum.into(),
+			});
+		}
+
+		None
+	}
+}
+
+impl Display for RequirementViolation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::BelowMinimum {
+				platform,
+				version,
+				minimum,
+			} => write!(f, "{platform} {version} is older than the minimum of {minimum}"),
+			Self::AboveMaximum {
+				platform,
+				version,
+				maximum,
+			} => write!(f, "{platform} {version} is newer than the maximum of {maximum}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_reported_versions() {
+		assert_eq!(numeric_parts("v20.11.1"), vec![20, 11, 1]);
+		assert_eq!(numeric_parts("16.4"), vec![16, 4]);
+		assert_eq!(numeric_parts("17beta1"), vec![17]);
+		assert_eq!(numeric_parts("unknown"), Vec::<u64>::new());
+	}
+
+	#[test]
+	fn bounds_are_as_precise_as_written() {
+		let check = |version, minimum, maximum| {
+			RequirementViolation::check(Platform::Postgres, version, minimum, maximum)
+		};
+
+		assert_eq!(check("16.4", Some("16"), Some("16")), None);
+		assert_eq!(check("17.0", Some("14"), Some("17")), None);
+		assert_eq!(
+			check("14.0", Some("14.2"), None).map(|v| v.to_string()),
+			Some("PostgreSQL 14.0 is older than the minimum of 14.2".into())
+		);
+		assert!(matches!(
+			check("18.1", Some("14"), Some("17")),
+			Some(RequirementViolation::AboveMaximum { .. })
+		));
+		assert_eq!(check("something", Some("14"), None), None);
+	}
+
+	#[test]
+	fn validates_bounds() {
+		assert!(is_version_bound("16"));
+		assert!(is_version_bound("20.11"));
+		assert!(!is_version_bound(""));
+		assert!(!is_version_bound("v20"));
+		assert!(!is_version_bound("20."));
+	}
+}
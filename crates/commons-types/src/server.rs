@@ -1,5 +1,7 @@
 pub mod cards;
+pub mod compat;
 pub mod kind;
+pub mod probe;
 pub mod rank;
 pub mod ticket;
 
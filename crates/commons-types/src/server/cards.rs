@@ -1,13 +1,19 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{server::rank::ServerRank, status::ShortStatus, version::VersionStr};
+use crate::{
+	server::{compat::CompatibilityRule, rank::ServerRank},
+	status::ShortStatus,
+	version::VersionStr,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FacilityServerStatus {
 	pub id: Uuid,
 	pub name: String,
 	pub up: ShortStatus,
+	/// Compatibility rules the facility's version violates relative to its central.
+	pub mismatches: Vec<CompatibilityRule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
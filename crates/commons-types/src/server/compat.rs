@@ -0,0 +1,141 @@
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "ssr")]
+use diesel::{
+	backend::Backend,
+	deserialize::{self, FromSql, FromSqlRow},
+	expression::AsExpression,
+	serialize::{self, Output, ToSql},
+	sql_types::Text,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::version::VersionStr;
+
+/// A rule that a facility server's version must follow relative to its central server.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "snake_case")]
+pub enum CompatibilityRule {
+	/// The facility runs the same major.minor release as its central.
+	SameMinor,
+
+	/// The facility never runs a newer version than its central.
+	FacilityNotAhead,
+}
+
+impl CompatibilityRule {
+	/// Every rule that can be checked; which ones are is configured in the database.
+	pub const ALL: [Self; 2] = [Self::SameMinor, Self::FacilityNotAhead];
+
+	/// How the rule is stored and written in APIs.
+	pub fn key(self) -> &'static str {
+		match self {
+			Self::SameMinor => "same_minor",
+			Self::FacilityNotAhead => "facility_not_ahead",
+		}
+	}
+
+	pub fn is_satisfied(self, central: &VersionStr, facility: &VersionStr) -> bool {
+		let (central, facility) = (&central.0, &facility.0);
+		match self {
+			Self::SameMinor => central.major == facility.major && central.minor == facility.minor,
+			Self::FacilityNotAhead => facility <= central,
+		}
+	}
+
+	/// Check the given rules, returning the ones that are violated.
+	pub fn violations(rules: &[Self], central: &VersionStr, facility: &VersionStr) -> Vec<Self> {
+		rules
+			.iter()
+			.copied()
+			.filter(|rule| !rule.is_satisfied(central, facility))
+			.collect()
+	}
+}
+
+impl Display for CompatibilityRule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::SameMinor => write!(f, "not on the same minor as its central"),
+			Self::FacilityNotAhead => write!(f, "ahead of its central"),
+		}
+	}
+}
+
+impl From<CompatibilityRule> for String {
+	fn from(rule: CompatibilityRule) -> Self {
+		rule.key().into()
+	}
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid compatibility rule: {0}")]
+pub struct CompatibilityRuleFromStringError(String);
+
+impl FromStr for CompatibilityRule {
+	type Err = CompatibilityRuleFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"same_minor" => Ok(Self::SameMinor),
+			"facility_not_ahead" => Ok(Self::FacilityNotAhead),
+			s => Err(CompatibilityRuleFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for CompatibilityRule {
+	type Error = CompatibilityRuleFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for CompatibilityRule
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(CompatibilityRule::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for CompatibilityRule
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
+
+/// A facility whose version violates one or more compatibility rules with its central.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionMismatch {
+	pub central_id: Uuid,
+	pub central_name: String,
+	pub central_version: VersionStr,
+	pub facility_id: Uuid,
+	pub facility_name: String,
+	pub facility_version: VersionStr,
+	pub violations: Vec<CompatibilityRule>,
+}
+
+impl VersionMismatch {
+	/// Human-readable summary of the violated rules.
+	pub fn describe(&self) -> String {
+		self.violations
+			.iter()
+			.map(|rule| rule.to_string())
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
@@ -0,0 +1,94 @@
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "ssr")]
+use diesel::{
+	backend::Backend,
+	deserialize::{self, FromSql},
+	expression::AsExpression,
+	serialize::{self, Output, ToSql},
+	sql_types::Text,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(AsExpression))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeType {
+	/// GET a path on the server and check the response status and body.
+	#[default]
+	Http,
+	/// Open a TCP connection to a port on the server's host.
+	Tcp,
+	/// Resolve the server's hostname.
+	Dns,
+}
+
+impl ProbeType {
+	pub const ALL: [Self; 3] = [Self::Http, Self::Tcp, Self::Dns];
+}
+
+impl Display for ProbeType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ProbeType::Http => write!(f, "http"),
+			ProbeType::Tcp => write!(f, "tcp"),
+			ProbeType::Dns => write!(f, "dns"),
+		}
+	}
+}
+
+impl From<ProbeType> for String {
+	fn from(probe: ProbeType) -> Self {
+		format!("{probe}")
+	}
+}
+
+commons_macros::render_as_string!(ProbeType, minsize(3));
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid probe type: {0}")]
+pub struct ProbeTypeFromStringError(String);
+
+impl FromStr for ProbeType {
+	type Err = ProbeTypeFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"http" => Ok(Self::Http),
+			"tcp" => Ok(Self::Tcp),
+			"dns" => Ok(Self::Dns),
+			s => Err(ProbeTypeFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for ProbeType {
+	type Error = ProbeTypeFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for ProbeType
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(ProbeType::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for ProbeType
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "lowercase")]
 pub enum ShortStatus {
 	Up,
+	Degraded,
 	Down,
 	Away,
 	Blip,
@@ -17,6 +18,7 @@ impl Display for ShortStatus {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			ShortStatus::Up => write!(f, "up"),
+			ShortStatus::Degraded => write!(f, "degraded"),
 			ShortStatus::Down => write!(f, "down"),
 			ShortStatus::Away => write!(f, "away"),
 			ShortStatus::Blip => write!(f, "blip"),
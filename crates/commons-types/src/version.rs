@@ -4,7 +4,7 @@ use commons_errors::AppError;
 #[cfg(feature = "ssr")]
 use diesel::{
 	backend::Backend,
-	deserialize::{self, FromSql},
+	deserialize::{self, FromSql, FromSqlRow},
 	expression::AsExpression,
 	serialize::{self, Output, ToSql},
 	sql_types::Text,
@@ -81,6 +81,314 @@ where
 	}
 }
 
+/// A release channel that a version can be published to.
+#[derive(
+	Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+	/// General availability, what customers get by default.
+	#[default]
+	Stable,
+	/// Release candidates, for test servers and early adopters.
+	Beta,
+	/// Long-term support releases.
+	Lts,
+}
+
+impl ReleaseChannel {
+	pub const ALL: [Self; 3] = [Self::Stable, Self::Beta, Self::Lts];
+
+	/// The channel a version is released to when none is given.
+	///
+	/// Pre-releases go to beta so that stable servers don't see them unless asked to.
+	pub fn default_for(version: &VersionStr) -> Self {
+		if version.0.is_prerelease() {
+			Self::Beta
+		} else {
+			Self::Stable
+		}
+	}
+}
+
+impl Display for ReleaseChannel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ReleaseChannel::Stable => write!(f, "stable"),
+			ReleaseChannel::Beta => write!(f, "beta"),
+			ReleaseChannel::Lts => write!(f, "lts"),
+		}
+	}
+}
+
+impl From<ReleaseChannel> for String {
+	fn from(channel: ReleaseChannel) -> Self {
+		format!("{channel}")
+	}
+}
+
+commons_macros::render_as_string!(ReleaseChannel, minsize(3));
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid release channel: {0}")]
+pub struct ReleaseChannelFromStringError(String);
+
+impl FromStr for ReleaseChannel {
+	type Err = ReleaseChannelFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"stable" => Ok(Self::Stable),
+			"beta" => Ok(Self::Beta),
+			"lts" => Ok(Self::Lts),
+			s => Err(ReleaseChannelFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for ReleaseChannel {
+	type Error = ReleaseChannelFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for ReleaseChannel
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(ReleaseChannel::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for ReleaseChannel
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
+
+/// Where a minor release is in its support lifecycle.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportStatus {
+	/// Receiving bug fixes and security fixes.
+	Active,
+	/// Past the end of active support, only receiving security fixes.
+	SecurityOnly,
+	/// No longer receiving any fixes.
+	EndOfLife,
+	/// No support policy has been set for this minor.
+	#[default]
+	Unknown,
+}
+
+impl Display for SupportStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Active => write!(f, "supported"),
+			Self::SecurityOnly => write!(f, "security fixes only"),
+			Self::EndOfLife => write!(f, "end of life"),
+			Self::Unknown => write!(f, "no support policy"),
+		}
+	}
+}
+
+commons_macros::render_as_string!(SupportStatus);
+
+/// How urgently servers on older versions should update to a version.
+#[derive(
+	Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateUrgency {
+	#[default]
+	Normal,
+	/// Fixes problems most servers will run into.
+	Recommended,
+	/// Fixes a security issue.
+	Security,
+	/// Every server on an older version of the same minor must update to at least this one.
+	Mandatory,
+}
+
+impl UpdateUrgency {
+	pub const ALL: [Self; 4] = [
+		Self::Normal,
+		Self::Recommended,
+		Self::Security,
+		Self::Mandatory,
+	];
+}
+
+impl Display for UpdateUrgency {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			UpdateUrgency::Normal => write!(f, "normal"),
+			UpdateUrgency::Recommended => write!(f, "recommended"),
+			UpdateUrgency::Security => write!(f, "security"),
+			UpdateUrgency::Mandatory => write!(f, "mandatory"),
+		}
+	}
+}
+
+impl From<UpdateUrgency> for String {
+	fn from(urgency: UpdateUrgency) -> Self {
+		format!("{urgency}")
+	}
+}
+
+commons_macros::render_as_string!(UpdateUrgency, minsize(6));
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid update urgency: {0}")]
+pub struct UpdateUrgencyFromStringError(String);
+
+impl FromStr for UpdateUrgency {
+	type Err = UpdateUrgencyFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"normal" => Ok(Self::Normal),
+			"recommended" => Ok(Self::Recommended),
+			"security" => Ok(Self::Security),
+			"mandatory" => Ok(Self::Mandatory),
+			s => Err(UpdateUrgencyFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for UpdateUrgency {
+	type Error = UpdateUrgencyFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for UpdateUrgency
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(UpdateUrgency::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for UpdateUrgency
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
+
+/// How urgently servers on a yanked version should move off it.
+#[derive(
+	Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "ssr", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
+#[serde(rename_all = "lowercase")]
+pub enum YankSeverity {
+	/// A minor problem, upgrade at the next convenient time.
+	Low,
+	#[default]
+	Medium,
+	/// A problem which affects normal use, upgrade soon.
+	High,
+	/// Data loss or a security issue, upgrade immediately.
+	Critical,
+}
+
+impl YankSeverity {
+	pub const ALL: [Self; 4] = [Self::Low, Self::Medium, Self::High, Self::Critical];
+}
+
+impl Display for YankSeverity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			YankSeverity::Low => write!(f, "low"),
+			YankSeverity::Medium => write!(f, "medium"),
+			YankSeverity::High => write!(f, "high"),
+			YankSeverity::Critical => write!(f, "critical"),
+		}
+	}
+}
+
+impl From<YankSeverity> for String {
+	fn from(severity: YankSeverity) -> Self {
+		format!("{severity}")
+	}
+}
+
+commons_macros::render_as_string!(YankSeverity, minsize(3));
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid yank severity: {0}")]
+pub struct YankSeverityFromStringError(String);
+
+impl FromStr for YankSeverity {
+	type Err = YankSeverityFromStringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_ref() {
+			"low" => Ok(Self::Low),
+			"medium" => Ok(Self::Medium),
+			"high" => Ok(Self::High),
+			"critical" => Ok(Self::Critical),
+			s => Err(YankSeverityFromStringError(s.into())),
+		}
+	}
+}
+
+impl TryFrom<String> for YankSeverity {
+	type Error = YankSeverityFromStringError;
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl<DB> FromSql<Text, DB> for YankSeverity
+where
+	DB: Backend,
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let s = String::from_sql(bytes)?;
+		Ok(YankSeverity::try_from(s)?)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl ToSql<Text, diesel::pg::Pg> for YankSeverity
+where
+	String: ToSql<Text, diesel::pg::Pg>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, diesel::pg::Pg>) -> serialize::Result {
+		let v = String::from(*self);
+		<String as ToSql<Text, diesel::pg::Pg>>::to_sql(&v, &mut out.reborrow())
+	}
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(AsExpression))]
 #[cfg_attr(feature = "ssr", diesel(sql_type = Text))]
@@ -94,6 +402,41 @@ impl Display for VersionStr {
 
 commons_macros::render_as_string!(VersionStr, minsize(5));
 
+impl VersionStr {
+	/// The pre-release identifiers, dot-separated, or empty for a release.
+	pub fn prerelease(&self) -> String {
+		format_identifiers(&self.0.pre_release)
+	}
+
+	/// The build metadata identifiers, dot-separated, or empty if there are none.
+	pub fn build(&self) -> String {
+		format_identifiers(&self.0.build)
+	}
+}
+
+/// Format semver identifiers the way they're written in a version, dot-separated.
+pub fn format_identifiers(identifiers: &[node_semver::Identifier]) -> String {
+	identifiers
+		.iter()
+		.map(|identifier| identifier.to_string())
+		.collect::<Vec<_>>()
+		.join(".")
+}
+
+/// Parse dot-separated semver identifiers, as stored for pre-releases and build metadata.
+pub fn parse_identifiers(identifiers: &str) -> Vec<node_semver::Identifier> {
+	identifiers
+		.split('.')
+		.filter(|identifier| !identifier.is_empty())
+		.map(|identifier| match identifier.parse() {
+			Ok(number) if !identifier.starts_with('0') || identifier == "0" => {
+				node_semver::Identifier::Numeric(number)
+			}
+			_ => node_semver::Identifier::AlphaNumeric(identifier.into()),
+		})
+		.collect()
+}
+
 impl Default for VersionStr {
 	fn default() -> Self {
 		Self(node_semver::Version::new(0, 0, 0))
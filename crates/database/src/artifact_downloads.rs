@@ -0,0 +1,114 @@
+use std::net::IpAddr;
+
+use commons_errors::{AppError, Result};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use ipnet::IpNet;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A download of an artifact through the public server.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::artifact_downloads)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ArtifactDownload {
+	pub id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	pub artifact_id: Uuid,
+	pub version_id: Uuid,
+
+	/// Whether the client was sent to the download URL, rather than the download being proxied.
+	pub redirected: bool,
+
+	pub client_network: Option<IpNet>,
+	pub country: Option<String>,
+	pub user_agent: Option<String>,
+	pub device_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::artifact_downloads)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewArtifactDownload {
+	pub artifact_id: Uuid,
+	pub version_id: Uuid,
+	pub redirected: bool,
+	pub client_network: Option<IpNet>,
+	pub country: Option<String>,
+	pub user_agent: Option<String>,
+	pub device_id: Option<Uuid>,
+}
+
+impl NewArtifactDownload {
+	/// The network a client address is in: its /24 for IPv4, or its /48 for IPv6.
+	pub fn network_of(ip: IpAddr) -> IpNet {
+		let prefix = match ip.to_canonical() {
+			IpAddr::V4(_) => 24,
+			IpAddr::V6(_) => 48,
+		};
+		IpNet::new(ip.to_canonical(), prefix)
+			.expect("prefix is in range")
+			.trunc()
+	}
+
+	pub async fn create(&self, db: &mut AsyncPgConnection) -> Result<ArtifactDownload> {
+		diesel::insert_into(crate::schema::artifact_downloads::table)
+			.values(self)
+			.returning(ArtifactDownload::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+}
+
+/// Downloads of one type of artifact on one platform, for a version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadCount {
+	pub artifact_type: String,
+	pub platform: String,
+	pub downloads: i64,
+
+	/// How many different client networks downloaded it.
+	pub networks: i64,
+
+	pub last_downloaded_at: Timestamp,
+}
+
+impl DownloadCount {
+	pub async fn for_version(db: &mut AsyncPgConnection, version: Uuid) -> Result<Vec<Self>> {
+		use crate::schema::{artifact_downloads::dsl::*, artifacts};
+
+		artifact_downloads
+			.inner_join(artifacts::table)
+			.filter(version_id.eq(version))
+			.group_by((artifacts::artifact_type, artifacts::platform))
+			.select((
+				artifacts::artifact_type,
+				artifacts::platform,
+				diesel::dsl::count_star(),
+				diesel::dsl::count(client_network).aggregate_distinct(),
+				diesel::dsl::max(created_at).assume_not_null(),
+			))
+			.order_by((artifacts::artifact_type.asc(), artifacts::platform.asc()))
+			.load::<(String, String, i64, i64, jiff_diesel::Timestamp)>(db)
+			.await
+			.map(|rows| {
+				rows.into_iter()
+					.map(
+						|(artifact_type, platform, downloads, networks, last)| Self {
+							artifact_type,
+							platform,
+							downloads,
+							networks,
+							last_downloaded_at: last.to_jiff(),
+						},
+					)
+					.collect()
+			})
+			.map_err(AppError::from)
+	}
+}
@@ -1,10 +1,17 @@
+use std::collections::HashMap;
+
 use commons_errors::{AppError, Result};
 use diesel::prelude::*;
-use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use diesel_async::{
+	AsyncConnection as _, AsyncPgConnection, RunQueryDsl, scoped_futures::ScopedFutureExt as _,
+};
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tracing::warn;
 use uuid::Uuid;
 
-use crate::versions::Version;
+use crate::versions::{Version, version_str_sort_key};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Associations)]
 #[diesel(belongs_to(Version))]
@@ -18,6 +25,18 @@ pub struct Artifact {
 	pub download_url: String,
 	pub device_id: Option<Uuid>,
 	pub version_range_pattern: Option<String>,
+	/// Hex-encoded SHA-256 of the artifact, as declared by the releaser or found by verification.
+	pub sha256: Option<String>,
+	pub size_bytes: Option<i64>,
+	/// When the artifact was downloaded and checked against its checksum and size.
+	#[diesel(deserialize_as = jiff_diesel::NullableTimestamp, serialize_as = jiff_diesel::NullableTimestamp)]
+	pub verified_at: Option<Timestamp>,
+	/// Why verification failed: a mismatch with what was declared, or a failed download.
+	pub verification_error: Option<String>,
+	/// Bundle identifier of a mobile app, e.g. `com.example.app`, for iOS install manifests.
+	pub bundle_id: Option<String>,
+	/// Build number of a mobile app, as in Android's `versionCode` or iOS's `CFBundleVersion`.
+	pub version_code: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, Insertable)]
@@ -31,6 +50,101 @@ pub struct NewArtifact {
 	pub download_url: String,
 	pub device_id: Option<Uuid>,
 	pub version_range_pattern: Option<String>,
+	/// Lowest version the range pattern can match, if it's bounded below.
+	pub version_range_floor: Option<String>,
+	/// Highest version the range pattern can match, if it's bounded above.
+	pub version_range_ceiling: Option<String>,
+	pub sha256: Option<String>,
+	pub size_bytes: Option<i64>,
+	pub bundle_id: Option<String>,
+	pub version_code: Option<i64>,
+}
+
+/// Whether a checksum is a lowercase hex-encoded SHA-256.
+pub fn is_sha256(checksum: &str) -> bool {
+	checksum.len() == 64
+		&& checksum
+			.bytes()
+			.all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+/// Whether a bundle identifier is in reverse-DNS form, with at least two segments.
+pub fn is_bundle_id(bundle_id: &str) -> bool {
+	let mut segments = 0;
+	for segment in bundle_id.split('.') {
+		if segment.is_empty()
+			|| !segment
+				.bytes()
+				.all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+		{
+			return false;
+		}
+		segments += 1;
+	}
+	segments >= 2
+}
+
+/// The checksum and size of an artifact as downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactDigest {
+	pub sha256: String,
+	pub size_bytes: i64,
+}
+
+impl ArtifactDigest {
+	/// Download the artifact, hashing it as it streams in.
+	pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<Self> {
+		let mut response = client
+			.get(url)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|err| AppError::custom(format!("download failed: {err}")))?;
+
+		let mut hasher = Sha256::new();
+		let mut size_bytes = 0;
+		while let Some(chunk) = response
+			.chunk()
+			.await
+			.map_err(|err| AppError::custom(format!("download failed: {err}")))?
+		{
+			hasher.update(&chunk);
+			size_bytes += chunk.len() as i64;
+		}
+
+		Ok(Self {
+			sha256: hex::encode(hasher.finalize()),
+			size_bytes,
+		})
+	}
+}
+
+impl NewArtifact {
+	/// An artifact for every version matching a range pattern.
+	pub fn for_range(
+		range: &node_semver::Range,
+		pattern: String,
+		artifact_type: String,
+		platform: String,
+		download_url: String,
+		device_id: Option<Uuid>,
+	) -> Self {
+		let (floor, ceiling) = crate::version_ranges::hull(range);
+		Self {
+			version_id: None,
+			artifact_type,
+			platform,
+			download_url,
+			device_id,
+			version_range_pattern: Some(pattern),
+			version_range_floor: floor.map(|version| version.to_string()),
+			version_range_ceiling: ceiling.map(|version| version.to_string()),
+			sha256: None,
+			size_bytes: None,
+			bundle_id: None,
+			version_code: None,
+		}
+	}
 }
 
 impl Artifact {
@@ -38,19 +152,41 @@ impl Artifact {
 		db: &mut AsyncPgConnection,
 		target_version_id: Uuid,
 	) -> Result<Vec<Self>> {
+		let version = Version::get_by_id(db, target_version_id).await?;
+		let mut artifacts = Self::get_for_versions(db, std::slice::from_ref(&version)).await?;
+		Ok(artifacts.remove(&version.id).unwrap_or_default())
+	}
+
+	/// The artifacts of each of the versions, as [`get_for_version`](Self::get_for_version) would
+	/// give them, in a single query.
+	pub async fn get_for_versions(
+		db: &mut AsyncPgConnection,
+		versions: &[Version],
+	) -> Result<HashMap<Uuid, Vec<Self>>> {
 		use crate::schema::artifacts::*;
 
-		// First, get the version from the database to extract semver
-		let version = crate::versions::Version::get_by_id(db, target_version_id).await?;
-		let semver = version.as_semver();
+		let semvers: Vec<_> = versions.iter().map(|version| version.as_semver()).collect();
+		let (Some(lowest), Some(highest)) = (semvers.iter().min(), semvers.iter().max()) else {
+			return Ok(HashMap::new());
+		};
+		let ids: Vec<Uuid> = versions.iter().map(|version| version.id).collect();
 
-		// Query all artifacts (both exact match and range-based)
-		let mut artifacts: Vec<Self> = table
+		// Query exact matches, and range-based artifacts whose range could include any of the versions
+		let candidates: Vec<Self> = table
 			.select(Self::as_select())
 			.filter(
-				version_id
-					.eq(Some(target_version_id))
-					.or(version_range_pattern.is_not_null()),
+				version_id.eq_any(ids).or(version_range_pattern
+					.is_not_null()
+					.and(
+						version_range_floor.is_null().or(version_str_sort_key(
+							version_range_floor.assume_not_null(),
+						)
+						.le(crate::versions::sort_key_of(highest))),
+					)
+					.and(version_range_ceiling.is_null().or(
+						version_str_sort_key(version_range_ceiling.assume_not_null())
+							.ge(crate::versions::sort_key_of(lowest)),
+					))),
 			)
 			.order_by(artifact_type.asc())
 			.then_order_by(platform.asc())
@@ -58,30 +194,42 @@ impl Artifact {
 			.await
 			.map_err(AppError::from)?;
 
-		// Filter out range artifacts that don't match the version
-		artifacts.retain(|artifact| {
-			if artifact.version_id == Some(target_version_id) {
-				// Exact match, always keep
-				true
-			} else if let Some(pattern) = &artifact.version_range_pattern {
-				// Range match, check if version satisfies the pattern
-				match node_semver::Range::parse(pattern) {
-					Ok(range) => range.satisfies(&semver),
-					Err(_) => false, // Invalid pattern, skip this artifact
-				}
-			} else {
-				// Should not happen due to DB constraint, but be safe
-				false
-			}
-		});
+		Ok(versions
+			.iter()
+			.zip(&semvers)
+			.map(|(version, semver)| {
+				let mut artifacts: Vec<Self> = candidates
+					.iter()
+					.filter(|artifact| artifact.applies_to(version.id, semver))
+					.cloned()
+					.collect();
 
-		// Sort by specificity to handle conflicts
-		Self::sort_by_specificity(&mut artifacts);
+				// Sort by specificity to handle conflicts
+				Self::sort_by_specificity(&mut artifacts);
 
-		// Remove duplicates by platform+artifact_type, keeping the most specific one
-		artifacts.dedup_by_key(|a| (a.artifact_type.clone(), a.platform.clone()));
+				// Remove duplicates by platform+artifact_type, keeping the most specific one
+				artifacts.dedup_by_key(|a| (a.artifact_type.clone(), a.platform.clone()));
 
-		Ok(artifacts)
+				(version.id, artifacts)
+			})
+			.collect())
+	}
+
+	/// Whether the artifact is for the version, exactly or through its range pattern.
+	fn applies_to(&self, target_version_id: Uuid, semver: &node_semver::Version) -> bool {
+		if self.version_id == Some(target_version_id) {
+			// Exact match, always keep
+			true
+		} else if let Some(pattern) = &self.version_range_pattern {
+			// Range match, check if version satisfies the pattern
+			match node_semver::Range::parse(pattern) {
+				Ok(range) => range.satisfies(semver),
+				Err(_) => false, // Invalid pattern, skip this artifact
+			}
+		} else {
+			// Should not happen due to DB constraint, but be safe
+			false
+		}
 	}
 
 	/// Get all artifacts for a version including duplicates (by platform+artifact_type).
@@ -97,13 +245,24 @@ impl Artifact {
 		let version = crate::versions::Version::get_by_id(db, target_version_id).await?;
 		let semver = version.as_semver();
 
-		// Query all artifacts (both exact match and range-based)
+		// Query exact matches, and range-based artifacts whose range could include the version
+		let key = crate::versions::sort_key_of(&semver);
 		let mut artifacts: Vec<Self> = table
 			.select(Self::as_select())
 			.filter(
 				version_id
 					.eq(Some(target_version_id))
-					.or(version_range_pattern.is_not_null()),
+					.or(version_range_pattern
+						.is_not_null()
+						.and(
+							version_range_floor
+								.is_null()
+								.or(version_str_sort_key(version_range_floor.assume_not_null())
+									.le(key.clone())),
+						)
+						.and(version_range_ceiling.is_null().or(
+							version_str_sort_key(version_range_ceiling.assume_not_null()).ge(key),
+						))),
 			)
 			.order_by(artifact_type.asc())
 			.then_order_by(platform.asc())
@@ -112,21 +271,7 @@ impl Artifact {
 			.map_err(AppError::from)?;
 
 		// Filter out range artifacts that don't match the version
-		artifacts.retain(|artifact| {
-			if artifact.version_id == Some(target_version_id) {
-				// Exact match, always keep
-				true
-			} else if let Some(pattern) = &artifact.version_range_pattern {
-				// Range match, check if version satisfies the pattern
-				match node_semver::Range::parse(pattern) {
-					Ok(range) => range.satisfies(&semver),
-					Err(_) => false, // Invalid pattern, skip this artifact
-				}
-			} else {
-				// Should not happen due to DB constraint, but be safe
-				false
-			}
-		});
+		artifacts.retain(|artifact| artifact.applies_to(target_version_id, &semver));
 
 		// Sort by specificity but DON'T deduplicate - we want to see all of them
 		Self::sort_by_specificity(&mut artifacts);
@@ -209,16 +354,141 @@ impl Artifact {
 	) -> Result<()> {
 		use crate::schema::artifacts::dsl::*;
 
-		diesel::update(artifacts.filter(id.eq(artifact_id)))
-			.set((
-				artifact_type.eq(new_type),
-				platform.eq(new_platform),
-				download_url.eq(new_url),
-			))
-			.execute(db)
+		db.transaction(|db| {
+			async move {
+				diesel::update(artifacts.filter(id.eq(artifact_id)))
+					.set((artifact_type.eq(new_type), platform.eq(new_platform)))
+					.execute(db)
+					.await?;
+
+				// A different file may be behind the new URL, so what we knew about it no longer holds
+				diesel::update(
+					artifacts
+						.filter(id.eq(artifact_id))
+						.filter(download_url.ne(&new_url)),
+				)
+				.set((
+					download_url.eq(&new_url),
+					sha256.eq(None::<String>),
+					size_bytes.eq(None::<i64>),
+					version_code.eq(None::<i64>),
+					verified_at.eq(None::<jiff_diesel::Timestamp>),
+					verification_error.eq(None::<String>),
+				))
+				.execute(db)
+				.await?;
+
+				Ok::<_, AppError>(())
+			}
+			.scope_boxed()
+		})
+		.await
+	}
+
+	/// Artifacts which haven't been downloaded and checked yet, oldest first.
+	pub async fn get_unverified(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::artifacts::dsl::*;
+
+		artifacts
+			.filter(verified_at.is_null())
+			.order_by(created_at.asc())
+			.select(Self::as_select())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Compare what was downloaded with the declared checksum and size, if any.
+	pub fn mismatch(&self, digest: &ArtifactDigest) -> Option<String> {
+		let mut mismatches = Vec::new();
+		if let Some(declared) = &self.sha256
+			&& *declared != digest.sha256
+		{
+			mismatches.push(format!(
+				"sha256 is {} but {declared} was declared",
+				digest.sha256
+			));
+		}
+		if let Some(declared) = self.size_bytes
+			&& declared != digest.size_bytes
+		{
+			mismatches.push(format!(
+				"size is {} bytes but {declared} was declared",
+				digest.size_bytes
+			));
+		}
+
+		(!mismatches.is_empty()).then(|| mismatches.join("; "))
+	}
+
+	/// Download every artifact which hasn't been verified yet and check it.
+	///
+	/// Checksums and sizes which weren't declared are filled in, and mismatches are recorded as a
+	/// verification error. Artifacts which fail to download are left unverified, so that they're
+	/// tried again on the next run, with the failure recorded in the meantime.
+	///
+	/// Artifacts in the manifest of a version which releasers have signed are checked but not
+	/// filled in, as that would change the manifest out from under their signatures.
+	pub async fn verify_unverified(
+		db: &mut AsyncPgConnection,
+		client: &reqwest::Client,
+	) -> Result<Vec<Self>> {
+		use crate::schema::{artifacts::dsl::*, version_signatures};
+
+		let signed_ids: Vec<Uuid> = version_signatures::table
+			.select(version_signatures::version_id)
+			.distinct()
+			.load(db)
 			.await?;
+		let signed: Vec<(Uuid, node_semver::Version)> = Version::get_by_ids(db, &signed_ids)
+			.await?
+			.into_iter()
+			.map(|version| (version.id, version.as_semver()))
+			.collect();
 
-		Ok(())
+		let mut checked = Vec::new();
+		for artifact in Self::get_unverified(db).await? {
+			let is_signed = signed
+				.iter()
+				.any(|(signed_id, semver)| artifact.applies_to(*signed_id, semver));
+			let update = diesel::update(artifacts.filter(id.eq(artifact.id)));
+			let artifact = match ArtifactDigest::fetch(client, &artifact.download_url).await {
+				Ok(digest) => {
+					let mismatch = artifact.mismatch(&digest);
+					if let Some(mismatch) = &mismatch {
+						warn!(id=%artifact.id, url=artifact.download_url, "artifact does not match: {mismatch}");
+					}
+
+					let (found_sha256, found_size_bytes) = if is_signed {
+						(None, None)
+					} else {
+						(Some(digest.sha256), Some(digest.size_bytes))
+					};
+
+					update
+						.set((
+							sha256.eq(artifact.sha256.clone().or(found_sha256)),
+							size_bytes.eq(artifact.size_bytes.or(found_size_bytes)),
+							verified_at.eq(jiff_diesel::Timestamp::from(Timestamp::now())),
+							verification_error.eq(mismatch),
+						))
+						.returning(Self::as_select())
+						.get_result(db)
+						.await?
+				}
+				Err(err) => {
+					warn!(id=%artifact.id, url=artifact.download_url, "could not verify artifact: {err}");
+					update
+						.set(verification_error.eq(err.to_string()))
+						.returning(Self::as_select())
+						.get_result(db)
+						.await?
+				}
+			};
+			checked.push(artifact);
+		}
+
+		Ok(checked)
 	}
 
 	pub async fn create(
@@ -237,6 +507,12 @@ impl Artifact {
 			download_url: url,
 			device_id: None,
 			version_range_pattern: None,
+			version_range_floor: None,
+			version_range_ceiling: None,
+			sha256: None,
+			size_bytes: None,
+			bundle_id: None,
+			version_code: None,
 		};
 
 		diesel::insert_into(artifacts)
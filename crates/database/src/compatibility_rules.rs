@@ -0,0 +1,49 @@
+use commons_errors::{AppError, Result};
+use commons_types::server::compat::CompatibilityRule;
+use diesel::prelude::*;
+use diesel_async::{
+	AsyncConnection as _, AsyncPgConnection, RunQueryDsl, scoped_futures::ScopedFutureExt as _,
+};
+
+/// Which compatibility rules facilities are checked against relative to their central.
+pub struct CompatibilityRules;
+
+impl CompatibilityRules {
+	/// The rules that are currently checked, in the order of [`CompatibilityRule::ALL`].
+	pub async fn enabled(db: &mut AsyncPgConnection) -> Result<Vec<CompatibilityRule>> {
+		use crate::schema::compatibility_rules::dsl::*;
+
+		let enabled: Vec<CompatibilityRule> = compatibility_rules.select(rule).load(db).await?;
+		Ok(CompatibilityRule::ALL
+			.into_iter()
+			.filter(|known| enabled.contains(known))
+			.collect())
+	}
+
+	/// Replace the set of rules that are checked.
+	pub async fn set(
+		db: &mut AsyncPgConnection,
+		rules: Vec<CompatibilityRule>,
+	) -> Result<Vec<CompatibilityRule>> {
+		db.transaction(|db| {
+			async move {
+				use crate::schema::compatibility_rules::dsl::*;
+
+				diesel::delete(compatibility_rules).execute(db).await?;
+				if !rules.is_empty() {
+					diesel::insert_into(compatibility_rules)
+						.values(rules.iter().map(|r| rule.eq(*r)).collect::<Vec<_>>())
+						.on_conflict_do_nothing()
+						.execute(db)
+						.await?;
+				}
+
+				Ok::<_, AppError>(())
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+		Self::enabled(db).await
+	}
+}
@@ -0,0 +1,167 @@
+use std::collections::{BTreeSet, HashMap};
+
+use commons_errors::{AppError, Result};
+use commons_types::{
+	server::{kind::ServerKind, rank::ServerRank},
+	version::VersionStr,
+};
+use diesel::{
+	QueryableByName,
+	sql_types::{Array, Text, Timestamptz},
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{servers::Server, versions::Version};
+
+/// How far behind the latest release a server is, and how long it has been on its current minor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerDrift {
+	pub server: Server,
+	pub version: VersionStr,
+
+	/// How many published minor releases are newer than the one the server is on.
+	pub minors_behind: u64,
+
+	/// When the server first reported its current minor, going back through its status history.
+	pub on_minor_since: Timestamp,
+}
+
+#[derive(Debug, QueryableByName)]
+struct DriftRow {
+	#[diesel(sql_type = diesel::sql_types::Uuid)]
+	server_id: Uuid,
+	#[diesel(sql_type = Text)]
+	version: String,
+	#[diesel(sql_type = Timestamptz, deserialize_as = jiff_diesel::Timestamp)]
+	on_minor_since: Timestamp,
+}
+
+impl ServerDrift {
+	/// Compute the drift of every server which has reported a version in the last week.
+	///
+	/// Sorted with the servers furthest behind first, then by how long they've been there.
+	pub async fn for_fleet(
+		db: &mut AsyncPgConnection,
+		rank: Option<ServerRank>,
+		kind: Option<ServerKind>,
+	) -> Result<Vec<Self>> {
+		let mut servers: HashMap<Uuid, Server> = Server::get_all(db, 0, None)
+			.await?
+			.into_iter()
+			.filter(|server| rank.is_none_or(|rank| server.rank == Some(rank)))
+			.filter(|server| kind.is_none_or(|kind| server.kind == kind))
+			.map(|server| (server.id, server))
+			.collect();
+		if servers.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let minors: BTreeSet<(u64, u64)> = Version::get_all(db)
+			.await?
+			.into_iter()
+			.map(|version| (version.major as u64, version.minor as u64))
+			.collect();
+
+		// The current minor run starts after the last status which reported a different minor
+		let rows: Vec<DriftRow> = diesel::sql_query(
+			"WITH latest AS (
+				SELECT DISTINCT ON (server_id) server_id, version,
+					split_part(version, '.', 1) || '.' || split_part(version, '.', 2) AS minor
+				FROM statuses
+				WHERE server_id = ANY($1)
+				AND version IS NOT NULL
+				AND created_at >= NOW() - INTERVAL '7 days'
+				AND id != '00000000-0000-0000-0000-000000000000'
+				ORDER BY server_id, created_at DESC
+			)
+			SELECT latest.server_id, latest.version, (
+				SELECT MIN(s.created_at) FROM statuses s
+				WHERE s.server_id = latest.server_id
+				AND split_part(s.version, '.', 1) || '.' || split_part(s.version, '.', 2) = latest.minor
+				AND s.created_at > COALESCE((
+					SELECT MAX(o.created_at) FROM statuses o
+					WHERE o.server_id = latest.server_id
+					AND o.version IS NOT NULL
+					AND split_part(o.version, '.', 1) || '.' || split_part(o.version, '.', 2) <> latest.minor
+				), '-infinity')
+			) AS on_minor_since
+			FROM latest",
+		)
+		.bind::<Array<diesel::sql_types::Uuid>, _>(servers.keys().copied().collect::<Vec<_>>())
+		.load(db)
+		.await
+		.map_err(AppError::from)?;
+
+		let mut drift: Vec<Self> = rows
+			.into_iter()
+			.filter_map(|row| {
+				let version: VersionStr = row.version.parse().ok()?;
+				let current = (version.0.major, version.0.minor);
+				Some(Self {
+					server: servers.remove(&row.server_id)?,
+					minors_behind: minors.iter().filter(|minor| **minor > current).count() as _,
+					on_minor_since: row.on_minor_since,
+					version,
+				})
+			})
+			.collect();
+		drift.sort_by(|a, b| {
+			b.minors_behind
+				.cmp(&a.minors_behind)
+				.then(a.on_minor_since.cmp(&b.on_minor_since))
+		});
+		Ok(drift)
+	}
+
+	/// How long the server has been on its current minor.
+	pub fn time_on_minor(&self) -> SignedDuration {
+		Timestamp::now().duration_since(self.on_minor_since)
+	}
+
+	/// Render a drift report as CSV, one row per server.
+	pub fn to_csv(drift: &[Self]) -> String {
+		let mut csv = String::from(
+			"server_id,name,kind,rank,host,version,minors_behind,on_minor_since,days_on_minor\r\n",
+		);
+		for entry in drift {
+			let fields = [
+				entry.server.id.to_string(),
+				entry.server.name.clone().unwrap_or_default(),
+				entry.server.kind.to_string(),
+				entry.server.rank.map(|r| r.to_string()).unwrap_or_default(),
+				entry.server.host.0.to_string(),
+				entry.version.to_string(),
+				entry.minors_behind.to_string(),
+				entry.on_minor_since.to_string(),
+				(entry.time_on_minor().as_hours() / 24).to_string(),
+			];
+			csv.push_str(
+				&fields
+					.iter()
+					.map(|field| csv_field(field))
+					.collect::<Vec<_>>()
+					.join(","),
+			);
+			csv.push_str("\r\n");
+		}
+		csv
+	}
+}
+
+fn csv_field(field: &str) -> String {
+	// spreadsheets treat values starting with these as formulas, so make them plain text
+	let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+		format!("'{field}")
+	} else {
+		field.to_string()
+	};
+
+	if field.contains([',', '"', '\r', '\n']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field
+	}
+}
@@ -4,18 +4,32 @@ use diesel_async::{
 };
 
 pub mod admins;
+pub mod artifact_downloads;
 pub mod artifacts;
 pub mod bestool_snippets;
 pub mod chrome_releases;
+pub mod compatibility_rules;
 pub mod devices;
+pub mod drift;
+pub mod link_checks;
+pub mod manifests;
 pub mod pg_duration;
+pub mod platform_requirements;
+pub mod probes;
+pub mod releaser_keys;
+pub mod releases;
+pub mod rollouts;
 pub mod schema;
 pub mod servers;
 pub mod sql_playground_history;
 pub mod statuses;
+pub mod support_policies;
+pub mod update_advice;
+pub mod upgrade_plans;
 pub mod url_field;
+pub mod version_ranges;
 pub mod versions;
-pub mod views;
+pub mod yanks;
 
 pub use bestool_snippets::{BestoolSnippet, NewBestoolSnippet};
 pub use devices::{Device, DeviceConnection, DeviceKey, DeviceWithInfo};
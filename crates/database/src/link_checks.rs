@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+
+use commons_errors::{AppError, Result};
+use commons_types::version::VersionStatus;
+use diesel::{dsl::exists, prelude::*, upsert::excluded};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures::{StreamExt as _, stream};
+use jiff::Timestamp;
+use reqwest::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+	artifacts::Artifact,
+	version_ranges,
+	versions::{Version, version_sort_key},
+};
+
+/// The latest result of checking that an artifact's download URL still works.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::artifact_link_checks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ArtifactLinkCheck {
+	pub artifact_id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub checked_at: Timestamp,
+
+	pub healthy: bool,
+
+	/// HTTP status of the last request made, if it got a response.
+	pub status_code: Option<i32>,
+
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::artifact_link_checks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct NewArtifactLinkCheck {
+	artifact_id: Uuid,
+	healthy: bool,
+	status_code: Option<i32>,
+	error: Option<String>,
+}
+
+impl NewArtifactLinkCheck {
+	/// Send a HEAD request, then fall back to fetching the first byte for servers which don't
+	/// answer HEAD requests properly.
+	async fn check(client: &reqwest::Client, artifact_id: Uuid, url: &str) -> Self {
+		let head = client.head(url).send().await;
+		if let Ok(response) = &head
+			&& response.status().is_success()
+		{
+			return Self::from_status(artifact_id, response.status());
+		}
+
+		match client
+			.get(url)
+			.header(header::RANGE, "bytes=0-0")
+			.send()
+			.await
+		{
+			Ok(response) => Self::from_status(artifact_id, response.status()),
+			Err(err) => Self {
+				artifact_id,
+				healthy: false,
+				status_code: None,
+				error: Some(err.to_string()),
+			},
+		}
+	}
+
+	fn from_status(artifact_id: Uuid, status: StatusCode) -> Self {
+		Self {
+			artifact_id,
+			healthy: status.is_success(),
+			status_code: Some(status.as_u16().into()),
+			error: (!status.is_success()).then(|| format!("responded with {status}")),
+		}
+	}
+}
+
+impl ArtifactLinkCheck {
+	/// Artifacts of published versions, including range artifacts which match any of them.
+	pub async fn artifacts_to_check(db: &mut AsyncPgConnection) -> Result<Vec<Artifact>> {
+		use crate::schema::{artifacts, versions};
+
+		let mut to_check: Vec<Artifact> = artifacts::table
+			.inner_join(versions::table)
+			.filter(versions::status.eq(VersionStatus::Published))
+			.select(Artifact::as_select())
+			.order_by(artifacts::created_at.asc())
+			.load(db)
+			.await?;
+
+		// Range artifacts are few, and each is resolved against the published versions in SQL
+		let ranged: Vec<Artifact> = artifacts::table
+			.filter(artifacts::version_range_pattern.is_not_null())
+			.select(Artifact::as_select())
+			.order_by(artifacts::created_at.asc())
+			.load(db)
+			.await?;
+
+		for artifact in ranged {
+			let Some(range) = artifact
+				.version_range_pattern
+				.as_deref()
+				.and_then(|pattern| node_semver::Range::parse(pattern).ok())
+			else {
+				continue;
+			};
+
+			let matches_published: bool = diesel::select(exists(
+				versions::table
+					.filter(versions::status.eq(VersionStatus::Published))
+					.filter(version_ranges::matching(&range)),
+			))
+			.get_result(db)
+			.await?;
+			if matches_published {
+				to_check.push(artifact);
+			}
+		}
+
+		Ok(to_check)
+	}
+
+	/// Check the links of every artifact of a published version, and record the results.
+	pub async fn check_all(
+		db: &mut AsyncPgConnection,
+		client: &reqwest::Client,
+		concurrency: usize,
+	) -> Result<Vec<Self>> {
+		use crate::schema::artifact_link_checks::dsl::*;
+
+		let artifacts = Self::artifacts_to_check(db).await?;
+		let checks: Vec<NewArtifactLinkCheck> = stream::iter(artifacts)
+			.map(|artifact| async move {
+				NewArtifactLinkCheck::check(client, artifact.id, &artifact.download_url).await
+			})
+			.buffer_unordered(concurrency.max(1))
+			.collect()
+			.await;
+
+		if checks.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		diesel::insert_into(artifact_link_checks)
+			.values(&checks)
+			.on_conflict(artifact_id)
+			.do_update()
+			.set((
+				checked_at.eq(diesel::dsl::now),
+				healthy.eq(excluded(healthy)),
+				status_code.eq(excluded(status_code)),
+				error.eq(excluded(error)),
+			))
+			.returning(Self::as_select())
+			.get_results(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn get_for_artifacts(
+		db: &mut AsyncPgConnection,
+		artifacts: &[Uuid],
+	) -> Result<Vec<Self>> {
+		use crate::schema::artifact_link_checks::dsl::*;
+
+		artifact_link_checks
+			.filter(artifact_id.eq_any(artifacts))
+			.select(Self::as_select())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+}
+
+/// An artifact type and platform, like `server` on `windows`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ArtifactKind {
+	pub artifact_type: String,
+	pub platform: String,
+}
+
+impl From<&Artifact> for ArtifactKind {
+	fn from(artifact: &Artifact) -> Self {
+		Self {
+			artifact_type: artifact.artifact_type.clone(),
+			platform: artifact.platform.clone(),
+		}
+	}
+}
+
+/// Whether a version has everything a release should: the same kinds of artifacts as the
+/// previous release, with links that work.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseCompleteness {
+	/// The release the version is compared with, if there is one.
+	pub previous: Option<String>,
+
+	/// Kinds of artifacts the previous release had, which this version doesn't.
+	pub missing: Vec<ArtifactKind>,
+
+	/// Artifacts whose link failed its last check.
+	pub broken: usize,
+
+	/// Artifacts whose link hasn't been checked yet.
+	pub unchecked: usize,
+}
+
+impl ReleaseCompleteness {
+	pub fn is_complete(&self) -> bool {
+		self.missing.is_empty() && self.broken == 0
+	}
+
+	pub async fn for_version(db: &mut AsyncPgConnection, version_id: Uuid) -> Result<Self> {
+		let version = Version::get_by_id(db, version_id).await?;
+
+		let previous = {
+			use crate::schema::versions::dsl::*;
+			versions
+				.filter(status.eq(VersionStatus::Published))
+				.filter(prerelease.eq(""))
+				.filter(
+					version_sort_key(major, minor, patch, prerelease).lt(version_sort_key(
+						version.major,
+						version.minor,
+						version.patch,
+						version.prerelease.clone(),
+					)),
+				)
+				.order_by(version_sort_key(major, minor, patch, prerelease).desc())
+				.select(Version::as_select())
+				.first(db)
+				.await
+				.optional()?
+		};
+
+		let artifacts = Artifact::get_for_version(db, version.id).await?;
+		let present: BTreeSet<ArtifactKind> = artifacts.iter().map(ArtifactKind::from).collect();
+		let missing = match &previous {
+			Some(previous) => Artifact::get_for_version(db, previous.id)
+				.await?
+				.iter()
+				.map(ArtifactKind::from)
+				.filter(|kind| !present.contains(kind))
+				.collect::<BTreeSet<_>>()
+				.into_iter()
+				.collect(),
+			None => Vec::new(),
+		};
+
+		let ids: Vec<Uuid> = artifacts.iter().map(|artifact| artifact.id).collect();
+		let checks = ArtifactLinkCheck::get_for_artifacts(db, &ids).await?;
+
+		Ok(Self {
+			previous: previous.map(|previous| previous.as_semver().to_string()),
+			missing,
+			broken: checks.iter().filter(|check| !check.healthy).count(),
+			unchecked: ids.len() - checks.len(),
+		})
+	}
+}
@@ -0,0 +1,128 @@
+use commons_errors::{AppError, Result};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use uuid::Uuid;
+
+use crate::{artifacts::Artifact, releaser_keys::ReleaserKey, versions::Version};
+
+/// What a version is made of, in a form that can be signed and checked by Tamanu servers.
+///
+/// The payload is the compact JSON serialisation of this struct, which is what signatures are made
+/// over. Artifacts are sorted so that the payload only changes when the version or its artifacts do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+	pub version: String,
+
+	/// Hex-encoded SHA-256 of the changelog markdown.
+	pub changelog_sha256: String,
+
+	pub artifacts: Vec<ManifestArtifact>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ManifestArtifact {
+	pub artifact_type: String,
+	pub platform: String,
+	pub download_url: String,
+	pub sha256: Option<String>,
+	pub size_bytes: Option<i64>,
+}
+
+impl ReleaseManifest {
+	pub fn new(version: &Version, artifacts: &[Artifact]) -> Self {
+		let mut artifacts: Vec<ManifestArtifact> = artifacts
+			.iter()
+			.map(|artifact| ManifestArtifact {
+				artifact_type: artifact.artifact_type.clone(),
+				platform: artifact.platform.clone(),
+				download_url: artifact.download_url.clone(),
+				sha256: artifact.sha256.clone(),
+				size_bytes: artifact.size_bytes,
+			})
+			.collect();
+		artifacts.sort();
+
+		Self {
+			version: version.as_semver().to_string(),
+			changelog_sha256: hex::encode(Sha256::digest(version.changelog.as_bytes())),
+			artifacts,
+		}
+	}
+
+	pub async fn for_version(db: &mut AsyncPgConnection, version: &Version) -> Result<Self> {
+		let artifacts = Artifact::get_for_version(db, version.id).await?;
+		Ok(Self::new(version, &artifacts))
+	}
+
+	/// The exact bytes which are signed.
+	pub fn payload(&self) -> String {
+		serde_json::to_string(self).expect("manifest serialises to JSON")
+	}
+}
+
+/// A releaser's detached signature over a version's manifest payload.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::version_signatures)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct VersionSignature {
+	pub id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	pub version_id: Uuid,
+	pub releaser_key_id: Uuid,
+
+	/// The manifest payload as it was when signed.
+	pub payload: String,
+
+	/// Raw 64-byte Ed25519 signature.
+	pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::version_signatures)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewVersionSignature {
+	pub version_id: Uuid,
+	pub releaser_key_id: Uuid,
+	pub payload: String,
+	pub signature: Vec<u8>,
+}
+
+impl VersionSignature {
+	pub async fn create(db: &mut AsyncPgConnection, new: NewVersionSignature) -> Result<Self> {
+		use crate::schema::version_signatures::dsl::*;
+
+		diesel::insert_into(version_signatures)
+			.values(new)
+			.returning(Self::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Signatures over each of the versions' manifests, with the key each was made with, newest
+	/// first.
+	///
+	/// This includes signatures over older manifests of the versions, so compare the payload with
+	/// the current manifest before using one.
+	pub async fn get_for_versions(
+		db: &mut AsyncPgConnection,
+		versions: &[Uuid],
+	) -> Result<Vec<(Self, ReleaserKey)>> {
+		use crate::schema::{releaser_keys, version_signatures::dsl::*};
+
+		version_signatures
+			.inner_join(releaser_keys::table)
+			.filter(version_id.eq_any(versions))
+			.order_by(created_at.desc())
+			.select((Self::as_select(), ReleaserKey::as_select()))
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+}
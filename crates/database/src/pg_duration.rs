@@ -1,5 +1,9 @@
 use diesel::{
-	data_types::PgInterval, deserialize, expression::AsExpression, pg::Pg, serialize,
+	data_types::PgInterval,
+	deserialize::{self, FromSqlRow},
+	expression::AsExpression,
+	pg::Pg,
+	serialize,
 	sql_types::Interval,
 };
 use jiff::SignedDuration;
@@ -11,7 +15,17 @@ const SECONDS_PER_DAY: i128 = 60 * 60 * 24;
 const MICROSECONDS_PER_SECOND: i128 = 1_000_000;
 
 #[derive(
-	Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, AsExpression, Serialize, Deserialize,
+	Debug,
+	Clone,
+	Eq,
+	PartialEq,
+	Ord,
+	PartialOrd,
+	Hash,
+	AsExpression,
+	FromSqlRow,
+	Serialize,
+	Deserialize,
 )]
 #[diesel(sql_type = Interval)]
 pub struct PgDuration(pub SignedDuration);
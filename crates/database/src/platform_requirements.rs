@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use commons_errors::{AppError, Result};
+use commons_types::{
+	requirements::{Platform, RequirementViolation, is_version_bound},
+	version::VersionStr,
+};
+use diesel::prelude::*;
+use diesel_async::{
+	AsyncConnection as _, AsyncPgConnection, RunQueryDsl, scoped_futures::ScopedFutureExt as _,
+};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{devices::DeviceConnection, servers::Server, statuses::Status};
+
+/// The versions of a platform that a minor release supports.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::platform_requirements)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PlatformRequirement {
+	pub major: i32,
+	pub minor: i32,
+	pub platform: Platform,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub updated_at: Timestamp,
+
+	/// Oldest supported version, like `20` or `20.11`.
+	pub min_version: Option<String>,
+
+	/// Newest supported version, compared only as precisely as it's written: `16` includes `16.4`.
+	pub max_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::platform_requirements)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewPlatformRequirement {
+	pub major: i32,
+	pub minor: i32,
+	pub platform: Platform,
+	pub min_version: Option<String>,
+	pub max_version: Option<String>,
+}
+
+/// A server whose latest status reports a stack its release doesn't support.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsupportedServer {
+	pub server: Server,
+	pub version: VersionStr,
+	pub violations: Vec<RequirementViolation>,
+}
+
+impl NewPlatformRequirement {
+	/// Problems with the bounds, if any.
+	fn problem(&self) -> Option<String> {
+		let platform = self.platform;
+		for (name, bound) in [("minimum", &self.min_version), ("maximum", &self.max_version)] {
+			if let Some(bound) = bound
+				&& !is_version_bound(bound)
+			{
+				return Some(format!(
+					"{platform} {name} must be dot-separated numbers, like 16 or 20.11"
+				));
+			}
+		}
+
+		if let (Some(min), Some(max)) = (&self.min_version, &self.max_version)
+			&& RequirementViolation::check(platform, min, None, Some(max)).is_some()
+		{
+			return Some(format!("{platform} minimum {min} is newer than its maximum {max}"));
+		}
+
+		None
+	}
+}
+
+impl PlatformRequirement {
+	pub async fn get_all(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::platform_requirements::dsl::*;
+
+		let mut requirements: Vec<Self> = platform_requirements
+			.select(Self::as_select())
+			.load(db)
+			.await?;
+		requirements.sort_by_key(|requirement| {
+			(
+				std::cmp::Reverse((requirement.major, requirement.minor)),
+				requirement.platform,
+			)
+		});
+		Ok(requirements)
+	}
+
+	/// The requirements of a minor, in the order of [`Platform::ALL`].
+	pub async fn get_for_minor(
+		db: &mut AsyncPgConnection,
+		target_major: i32,
+		target_minor: i32,
+	) -> Result<Vec<Self>> {
+		use crate::schema::platform_requirements::dsl::*;
+
+		let mut requirements: Vec<Self> = platform_requirements
+			.filter(major.eq(target_major).and(minor.eq(target_minor)))
+			.select(Self::as_select())
+			.load(db)
+			.await?;
+		requirements.sort_by_key(|requirement| requirement.platform);
+		Ok(requirements)
+	}
+
+	/// Replace the requirements of a minor.
+	///
+	/// Platforms without either bound are left without requirements.
+	pub async fn set_for_minor(
+		db: &mut AsyncPgConnection,
+		in_major: i32,
+		in_minor: i32,
+		requirements: Vec<NewPlatformRequirement>,
+	) -> Result<Vec<Self>> {
+		let requirements: Vec<NewPlatformRequirement> = requirements
+			.into_iter()
+			.filter(|requirement| {
+				requirement.min_version.is_some() || requirement.max_version.is_some()
+			})
+			.map(|requirement| NewPlatformRequirement {
+				major: in_major,
+				minor: in_minor,
+				..requirement
+			})
+			.collect();
+
+		let problems: Vec<String> = requirements
+			.iter()
+			.filter_map(NewPlatformRequirement::problem)
+			.collect();
+		if !problems.is_empty() {
+			return Err(AppError::custom(problems.join("; ")));
+		}
+
+		db.transaction(|db| {
+			async move {
+				use crate::schema::platform_requirements::dsl::*;
+
+				diesel::delete(
+					platform_requirements.filter(major.eq(in_major).and(minor.eq(in_minor))),
+				)
+				.execute(db)
+				.await?;
+
+				if !requirements.is_empty() {
+					diesel::insert_into(platform_requirements)
+						.values(&requirements)
+						.execute(db)
+						.await?;
+				}
+
+				Ok::<_, AppError>(())
+			}
+			.scope_boxed()
+		})
+		.await?;
+
+		Self::get_for_minor(db, in_major, in_minor).await
+	}
+
+	/// Check a reported version of this platform, if it can be read.
+	pub fn check(&self, version: &str) -> Option<RequirementViolation> {
+		RequirementViolation::check(
+			self.platform,
+			version,
+			self.min_version.as_deref(),
+			self.max_version.as_deref(),
+		)
+	}
+
+	/// Check the versions of the server stack against the requirements of a minor.
+	pub fn check_stack(
+		requirements: &[Self],
+		postgres: Option<&str>,
+		nodejs: Option<&str>,
+	) -> Vec<RequirementViolation> {
+		requirements
+			.iter()
+			.filter_map(|requirement| match requirement.platform {
+				Platform::Postgres => requirement.check(postgres?),
+				Platform::Nodejs => requirement.check(nodejs?),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+impl UnsupportedServer {
+	/// Every server whose latest status, in the last week, reports a PostgreSQL or Node.js
+	/// version outside of what its release supports.
+	///
+	/// Sorted by server name.
+	pub async fn for_fleet(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		let mut requirements: HashMap<(i32, i32), Vec<PlatformRequirement>> = HashMap::new();
+		for requirement in PlatformRequirement::get_all(db).await? {
+			requirements
+				.entry((requirement.major, requirement.minor))
+				.or_default()
+				.push(requirement);
+		}
+		if requirements.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let mut servers: HashMap<Uuid, Server> = Server::get_all(db, 0, None)
+			.await?
+			.into_iter()
+			.map(|server| (server.id, server))
+			.collect();
+		let server_ids: Vec<Uuid> = servers.keys().copied().collect();
+		let statuses = Status::latest_for_servers(db, &server_ids).await?;
+
+		// Node.js is only told by the user agent of the server's connections
+		let nodejs: HashMap<Uuid, String> = DeviceConnection::get_latest_from_device_ids(
+			db,
+			statuses.iter().filter_map(|status| status.device_id),
+		)
+		.await?
+		.into_iter()
+		.filter_map(|connection| Some((connection.device_id, connection.nodejs_version()?)))
+		.collect();
+
+		let mut unsupported: Vec<Self> = statuses
+			.into_iter()
+			.filter_map(|status| {
+				let version = status.version.clone()?;
+				let requirements =
+					requirements.get(&(version.0.major as i32, version.0.minor as i32))?;
+				let violations = PlatformRequirement::check_stack(
+					requirements,
+					status.postgres_version().as_deref(),
+					status
+						.device_id
+						.and_then(|device| nodejs.get(&device))
+						.map(String::as_str),
+				);
+				if violations.is_empty() {
+					return None;
+				}
+
+				Some(Self {
+					server: servers.remove(&status.server_id)?,
+					version,
+					violations,
+				})
+			})
+			.collect();
+		unsupported.sort_by(|a, b| a.server.name.cmp(&b.server.name));
+		Ok(unsupported)
+	}
+}
@@ -0,0 +1,457 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use commons_errors::{AppError, Result};
+use commons_types::server::{kind::ServerKind, probe::ProbeType};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures::stream::{self, StreamExt};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{pg_duration::PgDuration, servers::Server};
+
+/// Probes that are due within this much of their interval are run early, so
+/// that the one-minute ping round doesn't make them drift by a whole round.
+const DUE_SLACK: SignedDuration = SignedDuration::from_secs(5);
+
+/// The ping task runs once a minute, so probes can't run more often than that.
+pub const MIN_PROBE_INTERVAL: SignedDuration = SignedDuration::from_mins(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::server_probes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ServerProbe {
+	pub id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	/// The server this probe applies to, if it's for a single server.
+	pub server_id: Option<Uuid>,
+
+	/// The kind of server this probe applies to, if it's for all servers of a kind.
+	pub server_kind: Option<ServerKind>,
+
+	pub name: String,
+
+	#[diesel(deserialize_as = String, serialize_as = String)]
+	pub probe_type: ProbeType,
+
+	pub enabled: bool,
+
+	/// How often the probe should run.
+	pub check_interval: PgDuration,
+
+	/// How long the probe may take before it's considered failed.
+	pub timeout: PgDuration,
+
+	/// For HTTP probes, the path to request, relative to the server's host.
+	pub path: Option<String>,
+
+	/// For HTTP probes, the status the response must have (any 2xx if unset).
+	pub expected_status: Option<i32>,
+
+	/// For HTTP probes, text the response body must contain.
+	pub expected_body: Option<String>,
+
+	/// For TCP probes, the port to connect to on the server's host.
+	pub port: Option<i32>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::server_probes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewServerProbe {
+	pub server_id: Option<Uuid>,
+	pub server_kind: Option<ServerKind>,
+	pub name: String,
+	#[diesel(serialize_as = String)]
+	pub probe_type: ProbeType,
+	pub check_interval: PgDuration,
+	pub timeout: PgDuration,
+	pub path: Option<String>,
+	pub expected_status: Option<i32>,
+	pub expected_body: Option<String>,
+	pub port: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::server_probe_results)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ServerProbeResult {
+	pub id: Uuid,
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+	pub probe_id: Uuid,
+	pub server_id: Uuid,
+	pub success: bool,
+	pub latency_ms: i32,
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = crate::schema::server_probe_results)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewServerProbeResult {
+	pub probe_id: Uuid,
+	pub server_id: Uuid,
+	pub success: bool,
+	pub latency_ms: i32,
+	pub error: Option<String>,
+}
+
+impl NewServerProbe {
+	/// Check that the probe definition makes sense for its type.
+	pub fn validate(&self) -> Result<()> {
+		if self.server_id.is_some() == self.server_kind.is_some() {
+			return Err(AppError::custom(
+				"a probe must apply to either a server or a kind of server",
+			));
+		}
+
+		if self.name.trim().is_empty() {
+			return Err(AppError::custom("probe name cannot be empty"));
+		}
+
+		if self.check_interval.0 < MIN_PROBE_INTERVAL {
+			return Err(AppError::custom(format!(
+				"probe interval must be at least {MIN_PROBE_INTERVAL:#}"
+			)));
+		}
+
+		if self.timeout.0 <= SignedDuration::ZERO || self.timeout.0 > self.check_interval.0 {
+			return Err(AppError::custom(
+				"probe timeout must be positive and no longer than its interval",
+			));
+		}
+
+		match self.probe_type {
+			ProbeType::Http => {
+				if !self
+					.path
+					.as_deref()
+					.is_some_and(|path| path.starts_with('/'))
+				{
+					return Err(AppError::custom("HTTP probes need a path starting with /"));
+				}
+				if let Some(status) = self.expected_status
+					&& !(100..=599).contains(&status)
+				{
+					return Err(AppError::custom(format!(
+						"invalid expected HTTP status: {status}"
+					)));
+				}
+			}
+			ProbeType::Tcp => {
+				if !self.port.is_some_and(|port| (1..=65535).contains(&port)) {
+					return Err(AppError::custom(
+						"TCP probes need a port between 1 and 65535",
+					));
+				}
+			}
+			ProbeType::Dns => {}
+		}
+
+		Ok(())
+	}
+
+	pub async fn save(self, db: &mut AsyncPgConnection) -> Result<ServerProbe> {
+		self.validate()?;
+		diesel::insert_into(crate::schema::server_probes::table)
+			.values(self)
+			.returning(ServerProbe::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+}
+
+impl ServerProbe {
+	pub async fn get_by_id(db: &mut AsyncPgConnection, probe_id: Uuid) -> Result<Self> {
+		use crate::schema::server_probes::dsl::*;
+		server_probes
+			.select(Self::as_select())
+			.filter(id.eq(probe_id))
+			.first(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// All probes that apply to a server, whether defined for it or for its kind.
+	pub async fn for_server(db: &mut AsyncPgConnection, server: &Server) -> Result<Vec<Self>> {
+		use crate::schema::server_probes::dsl::*;
+		server_probes
+			.select(Self::as_select())
+			.filter(server_id.eq(server.id).or(server_kind.eq(server.kind)))
+			.order_by((server_kind.is_not_null(), name.asc()))
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn all_enabled(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::server_probes::dsl::*;
+		server_probes
+			.select(Self::as_select())
+			.filter(enabled.eq(true))
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn set_enabled(db: &mut AsyncPgConnection, probe_id: Uuid, on: bool) -> Result<()> {
+		use crate::schema::server_probes::dsl::*;
+		diesel::update(server_probes.filter(id.eq(probe_id)))
+			.set(enabled.eq(on))
+			.execute(db)
+			.await
+			.map_err(AppError::from)?;
+		Ok(())
+	}
+
+	pub async fn delete(db: &mut AsyncPgConnection, probe_id: Uuid) -> Result<()> {
+		use crate::schema::server_probes::dsl::*;
+		diesel::delete(server_probes.filter(id.eq(probe_id)))
+			.execute(db)
+			.await
+			.map_err(AppError::from)?;
+		Ok(())
+	}
+
+	pub fn applies_to(&self, server: &Server) -> bool {
+		self.server_id == Some(server.id) || self.server_kind == Some(server.kind)
+	}
+
+	fn is_due(&self, last_run: Option<Timestamp>, now: Timestamp) -> bool {
+		last_run.is_none_or(|last| now.duration_since(last) + DUE_SLACK >= self.check_interval.0)
+	}
+
+	/// Run this probe against a server.
+	///
+	/// This never fails: errors are recorded in the result instead.
+	pub async fn run(&self, client: &reqwest::Client, server: &Server) -> NewServerProbeResult {
+		let start = Instant::now();
+		let timeout = self.timeout.0.unsigned_abs();
+		debug!(probe=%self.id, server=%server.id, kind=%self.probe_type, "probing");
+		let outcome = match tokio::time::timeout(timeout, self.check(client, server, timeout)).await
+		{
+			Ok(outcome) => outcome,
+			Err(_) => Err(format!("timed out after {:#}", self.timeout.0)),
+		};
+
+		let latency_ms = start.elapsed().as_millis().try_into().unwrap_or(i32::MAX);
+		if let Err(err) = &outcome {
+			warn!(probe=%self.id, server=%server.id, host=%server.host.0, "probe failure: {err}");
+		}
+
+		NewServerProbeResult {
+			probe_id: self.id,
+			server_id: server.id,
+			success: outcome.is_ok(),
+			latency_ms,
+			error: outcome.err(),
+		}
+	}
+
+	async fn check(
+		&self,
+		client: &reqwest::Client,
+		server: &Server,
+		timeout: Duration,
+	) -> std::result::Result<(), String> {
+		match self.probe_type {
+			ProbeType::Http => {
+				let url = server
+					.host
+					.0
+					.join(self.path.as_deref().unwrap_or("/"))
+					.map_err(|err| format!("invalid probe path: {err}"))?;
+				let res = client
+					.get(url)
+					.timeout(timeout)
+					.send()
+					.await
+					.map_err(|err| err.to_string())?;
+
+				let status = res.status();
+				let status_ok = match self.expected_status {
+					Some(expected) => i32::from(status.as_u16()) == expected,
+					None => status.is_success(),
+				};
+				if !status_ok {
+					return Err(format!("unexpected status: {status}"));
+				}
+
+				if let Some(expected) = &self.expected_body {
+					let body = res.text().await.map_err(|err| err.to_string())?;
+					if !body.contains(expected.as_str()) {
+						return Err("response body did not contain the expected text".into());
+					}
+				}
+
+				Ok(())
+			}
+			ProbeType::Tcp => {
+				let host = host_of(server)?;
+				let port = self
+					.port
+					.and_then(|port| u16::try_from(port).ok())
+					.ok_or_else(|| "probe has no valid port".to_string())?;
+				tokio::net::TcpStream::connect((host, port))
+					.await
+					.map(drop)
+					.map_err(|err| err.to_string())
+			}
+			ProbeType::Dns => {
+				let host = host_of(server)?;
+				let mut addrs = tokio::net::lookup_host((host, 0))
+					.await
+					.map_err(|err| err.to_string())?;
+				if addrs.next().is_some() {
+					Ok(())
+				} else {
+					Err(format!("{host} did not resolve to any address"))
+				}
+			}
+		}
+	}
+
+	/// Run every enabled probe that is due, against every server it applies to,
+	/// and save the results.
+	pub async fn run_due(
+		db: &mut AsyncPgConnection,
+		client: &reqwest::Client,
+		concurrency: usize,
+	) -> Result<Vec<ServerProbeResult>> {
+		let probes = Self::all_enabled(db).await?;
+		if probes.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let servers = Server::get_all(db, 0, None).await?;
+		let last_runs = ServerProbeResult::last_runs(db).await?;
+		let now = Timestamp::now();
+
+		let checks: Vec<_> = probes
+			.iter()
+			.flat_map(|probe| {
+				servers
+					.iter()
+					.filter(|server| probe.applies_to(server))
+					.map(move |server| (probe, server))
+			})
+			.filter(|(probe, server)| {
+				probe.is_due(last_runs.get(&(probe.id, server.id)).copied(), now)
+			})
+			.map(|(probe, server)| probe.run(client, server))
+			.collect();
+
+		let results = stream::iter(checks)
+			.buffer_unordered(concurrency.max(1))
+			.collect::<Vec<_>>()
+			.await;
+
+		ServerProbeResult::save_all(db, results).await
+	}
+}
+
+fn host_of(server: &Server) -> std::result::Result<&str, String> {
+	server
+		.host
+		.0
+		.host_str()
+		.map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+		.ok_or_else(|| "server URL has no host".into())
+}
+
+impl ServerProbeResult {
+	pub async fn save_all(
+		db: &mut AsyncPgConnection,
+		results: Vec<NewServerProbeResult>,
+	) -> Result<Vec<Self>> {
+		if results.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		diesel::insert_into(crate::schema::server_probe_results::table)
+			.values(results)
+			.returning(Self::as_select())
+			.get_results(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// When each probe last ran against each server.
+	pub async fn last_runs(db: &mut AsyncPgConnection) -> Result<HashMap<(Uuid, Uuid), Timestamp>> {
+		use crate::schema::server_probe_results::dsl::*;
+		server_probe_results
+			.group_by((probe_id, server_id))
+			.select((
+				probe_id,
+				server_id,
+				diesel::dsl::max(created_at).assume_not_null(),
+			))
+			.load::<(Uuid, Uuid, jiff_diesel::Timestamp)>(db)
+			.await
+			.map(|rows| {
+				rows.into_iter()
+					.map(|(probe, server, last)| ((probe, server), last.to_jiff()))
+					.collect()
+			})
+			.map_err(AppError::from)
+	}
+
+	/// The latest result of each probe that ran against a server.
+	pub async fn latest_for_server(db: &mut AsyncPgConnection, server: Uuid) -> Result<Vec<Self>> {
+		use crate::schema::server_probe_results::dsl::*;
+		server_probe_results
+			.select(Self::as_select())
+			.filter(server_id.eq(server))
+			.order((probe_id, created_at.desc()))
+			.distinct_on(probe_id)
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// The latest result of each enabled probe against each of the servers, by server.
+	pub async fn latest_for_servers(
+		db: &mut AsyncPgConnection,
+		servers: &[Uuid],
+	) -> Result<HashMap<Uuid, Vec<Self>>> {
+		use crate::schema::{server_probe_results::dsl::*, server_probes};
+		let results: Vec<Self> = server_probe_results
+			.inner_join(server_probes::table)
+			.select(Self::as_select())
+			.filter(server_id.eq_any(servers))
+			.filter(server_probes::enabled.eq(true))
+			.order((server_id, probe_id, created_at.desc()))
+			.distinct_on((server_id, probe_id))
+			.load(db)
+			.await
+			.map_err(AppError::from)?;
+
+		let mut by_server: HashMap<Uuid, Vec<Self>> = HashMap::new();
+		for result in results {
+			by_server.entry(result.server_id).or_default().push(result);
+		}
+		Ok(by_server)
+	}
+
+	/// Delete results older than the given age.
+	pub async fn prune(db: &mut AsyncPgConnection, older_than: SignedDuration) -> Result<usize> {
+		use crate::schema::server_probe_results::dsl::*;
+		let cutoff = Timestamp::now() - older_than;
+		diesel::delete(
+			server_probe_results.filter(created_at.lt(jiff_diesel::Timestamp::from(cutoff))),
+		)
+		.execute(db)
+		.await
+		.map_err(AppError::from)
+	}
+}
@@ -0,0 +1,70 @@
+use commons_errors::{AppError, Result};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An Ed25519 public key which a releaser signs version manifests with.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::releaser_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ReleaserKey {
+	pub id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	/// The releaser device which registered the key.
+	pub device_id: Uuid,
+
+	pub name: Option<String>,
+
+	/// Raw 32-byte Ed25519 public key.
+	pub public_key: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::releaser_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewReleaserKey {
+	pub device_id: Uuid,
+	pub name: Option<String>,
+	pub public_key: Vec<u8>,
+}
+
+impl ReleaserKey {
+	pub async fn create(db: &mut AsyncPgConnection, key: NewReleaserKey) -> Result<Self> {
+		use crate::schema::releaser_keys::dsl::*;
+
+		diesel::insert_into(releaser_keys)
+			.values(key)
+			.returning(Self::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn get_all(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::releaser_keys::dsl::*;
+
+		releaser_keys
+			.select(Self::as_select())
+			.order_by(created_at.asc())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn get_by_public_key(db: &mut AsyncPgConnection, key: &[u8]) -> Result<Option<Self>> {
+		use crate::schema::releaser_keys::dsl::*;
+
+		releaser_keys
+			.filter(public_key.eq(key))
+			.select(Self::as_select())
+			.first(db)
+			.await
+			.optional()
+			.map_err(AppError::from)
+	}
+}
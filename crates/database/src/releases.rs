@@ -0,0 +1,320 @@
+use std::{collections::BTreeSet, str::FromStr as _};
+
+use commons_errors::{AppError, Result};
+use commons_types::version::{ReleaseChannel, UpdateUrgency, VersionStatus, VersionStr};
+use diesel::prelude::*;
+use diesel_async::{
+	AsyncConnection as _, AsyncPgConnection, RunQueryDsl, scoped_futures::ScopedFutureExt as _,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+	artifacts::{Artifact, NewArtifact, is_bundle_id, is_sha256},
+	versions::{NewVersion, Version},
+};
+
+/// Everything that makes up a release, to be published in one go.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewRelease {
+	pub version: String,
+
+	#[serde(default)]
+	pub changelog: String,
+
+	/// Channels to release to.
+	///
+	/// Defaults to the draft's channels, or to stable, or beta for pre-releases.
+	pub channels: Option<Vec<String>>,
+
+	/// Defaults to the draft's urgency, or to the default urgency.
+	pub urgency: Option<UpdateUrgency>,
+
+	/// Whether servers must upgrade through this version before moving on to anything newer.
+	///
+	/// Defaults to the draft's setting, or to false.
+	pub stepping_stone: Option<bool>,
+
+	#[serde(default)]
+	pub artifacts: Vec<NewReleaseArtifact>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewReleaseArtifact {
+	pub artifact_type: String,
+	pub platform: String,
+	pub download_url: String,
+
+	/// Hex-encoded SHA-256 of the artifact.
+	pub sha256: Option<String>,
+	pub size_bytes: Option<i64>,
+
+	/// For mobile apps, see [`Artifact::bundle_id`].
+	pub bundle_id: Option<String>,
+	/// For mobile apps, see [`Artifact::version_code`].
+	pub version_code: Option<i64>,
+}
+
+/// A release as it was, or would be, published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedRelease {
+	/// Nothing was changed, this is what would have been published.
+	pub dry_run: bool,
+
+	/// Whether the release replaced a draft of the same version, and its artifacts.
+	pub replaced_draft: bool,
+
+	pub version: Version,
+	pub artifacts: Vec<Artifact>,
+}
+
+/// A release which is valid, ready to be applied.
+struct CheckedRelease {
+	version: VersionStr,
+	changelog: String,
+	/// When unset, the draft's channels, or the default channels for the version.
+	channels: Option<Vec<ReleaseChannel>>,
+	urgency: Option<UpdateUrgency>,
+	stepping_stone: Option<bool>,
+	artifacts: Vec<NewReleaseArtifact>,
+}
+
+/// Why a release transaction didn't commit.
+enum Aborted {
+	DryRun(PublishedRelease),
+	Failed(AppError),
+}
+
+impl From<AppError> for Aborted {
+	fn from(err: AppError) -> Self {
+		Self::Failed(err)
+	}
+}
+
+impl From<diesel::result::Error> for Aborted {
+	fn from(err: diesel::result::Error) -> Self {
+		Self::Failed(err.into())
+	}
+}
+
+impl NewRelease {
+	/// Check the release on its own, returning every problem found.
+	fn check(self) -> std::result::Result<CheckedRelease, Vec<String>> {
+		let mut problems = Vec::new();
+
+		let version = VersionStr::from_str(&self.version)
+			.map_err(|err| problems.push(format!("version: {err}")))
+			.ok();
+
+		let channels = match &self.channels {
+			Some(channels) if channels.is_empty() => {
+				problems.push("channels: must not be empty".into());
+				None
+			}
+			Some(channels) => Some(
+				channels
+					.iter()
+					.filter_map(|channel| {
+						ReleaseChannel::from_str(channel.trim())
+							.map_err(|err| problems.push(format!("channels: {err}")))
+							.ok()
+					})
+					.collect(),
+			),
+			None => None,
+		};
+
+		let mut kinds = BTreeSet::new();
+		let mut artifacts = self.artifacts;
+		for (n, artifact) in artifacts.iter_mut().enumerate() {
+			let name = format!(
+				"artifacts[{n}] ({}/{})",
+				artifact.artifact_type, artifact.platform
+			);
+			if artifact.artifact_type.trim().is_empty() || artifact.platform.trim().is_empty() {
+				problems.push(format!("{name}: artifact_type and platform are required"));
+			}
+			if !kinds.insert((artifact.artifact_type.clone(), artifact.platform.clone())) {
+				problems.push(format!("{name}: appears more than once"));
+			}
+			match url::Url::parse(&artifact.download_url) {
+				Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+				Ok(_) => problems.push(format!("{name}: download_url must be http or https")),
+				Err(err) => problems.push(format!("{name}: download_url: {err}")),
+			}
+			if let Some(sha256) = &mut artifact.sha256 {
+				sha256.make_ascii_lowercase();
+				if !is_sha256(sha256) {
+					problems.push(format!("{name}: sha256 must be 64 hex digits"));
+				}
+			}
+			if artifact.size_bytes.is_some_and(|size| size < 0) {
+				problems.push(format!("{name}: size_bytes must not be negative"));
+			}
+			if let Some(bundle_id) = &artifact.bundle_id
+				&& !is_bundle_id(bundle_id)
+			{
+				problems.push(format!(
+					"{name}: bundle_id must be in reverse-DNS form, like com.example.app"
+				));
+			}
+			if artifact.version_code.is_some_and(|code| code < 0) {
+				problems.push(format!("{name}: version_code must not be negative"));
+			}
+		}
+
+		match version {
+			Some(version) if problems.is_empty() => Ok(CheckedRelease {
+				version,
+				changelog: self.changelog,
+				channels,
+				urgency: self.urgency,
+				stepping_stone: self.stepping_stone,
+				artifacts,
+			}),
+			_ => Err(problems),
+		}
+	}
+
+	/// Validate and publish the release, with its artifacts, in a single transaction.
+	///
+	/// A draft of the same version is replaced, along with the artifacts it was created with.
+	/// With `dry_run`, everything is checked and applied as normal, and then rolled back.
+	pub async fn publish(
+		self,
+		db: &mut AsyncPgConnection,
+		device_id: Uuid,
+		dry_run: bool,
+	) -> Result<PublishedRelease> {
+		let release = self.check().map_err(|problems| {
+			AppError::custom(format!("Invalid release: {}", problems.join("; ")))
+		})?;
+
+		let outcome = db
+			.transaction::<_, Aborted, _>(|db| {
+				async move {
+					let published = release.apply(db, device_id).await?;
+					if dry_run {
+						Err(Aborted::DryRun(published))
+					} else {
+						Ok(published)
+					}
+				}
+				.scope_boxed()
+			})
+			.await;
+
+		match outcome {
+			Ok(published) => Ok(published),
+			Err(Aborted::DryRun(published)) => Ok(PublishedRelease {
+				dry_run: true,
+				..published
+			}),
+			Err(Aborted::Failed(err)) => Err(err),
+		}
+	}
+}
+
+impl CheckedRelease {
+	async fn apply(self, db: &mut AsyncPgConnection, publisher: Uuid) -> Result<PublishedRelease> {
+		let existing = {
+			use crate::schema::versions::*;
+			table
+				.filter(crate::predicate_version!(self.version.0.clone()))
+				.select(Version::as_select())
+				.for_update()
+				.first(db)
+				.await
+				.optional()?
+		};
+
+		let (version, replaced_draft) = match existing {
+			Some(existing) if existing.status != VersionStatus::Draft => {
+				return Err(AppError::custom(format!(
+					"Invalid release: version {} is already {}",
+					self.version.0, existing.status
+				)));
+			}
+			Some(draft) => {
+				{
+					use crate::schema::artifacts::dsl::*;
+					diesel::delete(artifacts.filter(version_id.eq(draft.id)))
+						.execute(db)
+						.await?;
+				}
+
+				use crate::schema::versions::dsl::*;
+				let version = diesel::update(versions.find(draft.id))
+					.set((
+						status.eq(VersionStatus::Published),
+						changelog.eq(self.changelog),
+						channels.eq(self.channels.unwrap_or(draft.channels)),
+						urgency.eq(self.urgency.unwrap_or(draft.urgency)),
+						stepping_stone.eq(self.stepping_stone.unwrap_or(draft.stepping_stone)),
+						device_id.eq(Some(publisher)),
+					))
+					.returning(Version::as_select())
+					.get_result(db)
+					.await?;
+				(version, true)
+			}
+			None => {
+				let version = diesel::insert_into(crate::schema::versions::table)
+					.values(NewVersion {
+						urgency: self.urgency.unwrap_or_default(),
+						stepping_stone: self.stepping_stone.unwrap_or_default(),
+						..NewVersion::new(
+							&self.version,
+							self.changelog,
+							VersionStatus::Published,
+							Some(publisher),
+							self.channels.unwrap_or_else(|| {
+								vec![ReleaseChannel::default_for(&self.version)]
+							}),
+						)
+					})
+					.returning(Version::as_select())
+					.get_result(db)
+					.await?;
+				(version, false)
+			}
+		};
+
+		let new_artifacts: Vec<NewArtifact> = self
+			.artifacts
+			.into_iter()
+			.map(|artifact| NewArtifact {
+				version_id: Some(version.id),
+				artifact_type: artifact.artifact_type,
+				platform: artifact.platform,
+				download_url: artifact.download_url,
+				device_id: Some(publisher),
+				version_range_pattern: None,
+				version_range_floor: None,
+				version_range_ceiling: None,
+				sha256: artifact.sha256,
+				size_bytes: artifact.size_bytes,
+				bundle_id: artifact.bundle_id,
+				version_code: artifact.version_code,
+			})
+			.collect();
+		let artifacts = if new_artifacts.is_empty() {
+			Vec::new()
+		} else {
+			diesel::insert_into(crate::schema::artifacts::table)
+				.values(&new_artifacts)
+				.returning(Artifact::as_select())
+				.get_results(db)
+				.await?
+		};
+
+		Ok(PublishedRelease {
+			dry_run: false,
+			replaced_draft,
+			version,
+			artifacts,
+		})
+	}
+}
@@ -0,0 +1,150 @@
+use commons_errors::{AppError, Result};
+use diesel::{QueryableByName, prelude::*, sql_types::BigInt, upsert::excluded};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A staged rollout of a version.
+///
+/// While a version has a rollout, update checks only offer it to servers in the allowlist, then
+/// to the given percentage of servers, chosen by stable hashing so that a server stays in as the
+/// percentage grows. At 100% it's offered to everyone. Versions without a rollout are offered to
+/// everyone as soon as they're published.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::version_rollouts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct VersionRollout {
+	pub version_id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub updated_at: Timestamp,
+
+	/// Servers which are offered the version regardless of the percentage.
+	pub allowlist: Vec<Uuid>,
+
+	/// Share of servers which are offered the version, from 0 to 100.
+	pub percentage: i32,
+
+	/// While paused, the version isn't offered to any server, including those in the allowlist.
+	pub paused: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::version_rollouts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewVersionRollout {
+	pub version_id: Uuid,
+	pub allowlist: Vec<Uuid>,
+	pub percentage: i32,
+	pub paused: bool,
+}
+
+#[derive(Debug, QueryableByName)]
+struct EligibleCount {
+	#[diesel(sql_type = BigInt)]
+	eligible: i64,
+	#[diesel(sql_type = BigInt)]
+	total: i64,
+}
+
+impl VersionRollout {
+	pub async fn get_for_version(
+		db: &mut AsyncPgConnection,
+		version: Uuid,
+	) -> Result<Option<Self>> {
+		use crate::schema::version_rollouts::dsl::*;
+
+		version_rollouts
+			.filter(version_id.eq(version))
+			.select(Self::as_select())
+			.first(db)
+			.await
+			.optional()
+			.map_err(AppError::from)
+	}
+
+	/// Start a rollout for a version, or change the one it has.
+	pub async fn upsert(db: &mut AsyncPgConnection, rollout: NewVersionRollout) -> Result<Self> {
+		use crate::schema::version_rollouts::dsl::*;
+
+		if !(0..=100).contains(&rollout.percentage) {
+			return Err(AppError::custom(
+				"Rollout percentage must be between 0 and 100",
+			));
+		}
+
+		let known: Vec<Uuid> = {
+			use crate::schema::servers::dsl::*;
+			servers
+				.select(id)
+				.filter(id.eq_any(&rollout.allowlist))
+				.load(db)
+				.await?
+		};
+		let unknown: Vec<String> = rollout
+			.allowlist
+			.iter()
+			.filter(|server| !known.contains(server))
+			.map(|server| server.to_string())
+			.collect();
+		if !unknown.is_empty() {
+			return Err(AppError::custom(format!(
+				"Allowlist entries must be server IDs, these aren't: {}",
+				unknown.join(", ")
+			)));
+		}
+
+		diesel::insert_into(version_rollouts)
+			.values(&rollout)
+			.on_conflict(version_id)
+			.do_update()
+			.set((
+				allowlist.eq(excluded(allowlist)),
+				percentage.eq(excluded(percentage)),
+				paused.eq(excluded(paused)),
+			))
+			.returning(Self::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Remove the rollout, so the version is offered to everyone.
+	pub async fn remove(db: &mut AsyncPgConnection, version: Uuid) -> Result<()> {
+		use crate::schema::version_rollouts::dsl::*;
+
+		diesel::delete(version_rollouts.filter(version_id.eq(version)))
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// How many servers the version is currently offered to, out of all the servers which have
+	/// a device to identify themselves with.
+	pub async fn eligible_servers(&self, db: &mut AsyncPgConnection) -> Result<(u64, u64)> {
+		let count: EligibleCount = diesel::sql_query(
+			"SELECT
+				count(*) FILTER (WHERE NOT $1 AND (
+					$2 >= 100
+					OR id = ANY($3)
+					OR rollout_bucket(id, $4) < $2
+				)) AS eligible,
+				count(*) AS total
+			FROM servers
+			WHERE device_id IS NOT NULL",
+		)
+		.bind::<diesel::sql_types::Bool, _>(self.paused)
+		.bind::<diesel::sql_types::Integer, _>(self.percentage)
+		.bind::<diesel::sql_types::Array<diesel::sql_types::Uuid>, _>(&self.allowlist)
+		.bind::<diesel::sql_types::Uuid, _>(self.version_id)
+		.get_result(db)
+		.await?;
+
+		Ok((count.eligible as u64, count.total as u64))
+	}
+}
@@ -7,6 +7,30 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	artifact_downloads (id) {
+		id -> Uuid,
+		created_at -> Timestamptz,
+		artifact_id -> Uuid,
+		version_id -> Uuid,
+		redirected -> Bool,
+		client_network -> Nullable<Inet>,
+		country -> Nullable<Text>,
+		user_agent -> Nullable<Text>,
+		device_id -> Nullable<Uuid>,
+	}
+}
+
+diesel::table! {
+	artifact_link_checks (artifact_id) {
+		artifact_id -> Uuid,
+		checked_at -> Timestamptz,
+		healthy -> Bool,
+		status_code -> Nullable<Int4>,
+		error -> Nullable<Text>,
+	}
+}
+
 diesel::table! {
 	artifacts (id) {
 		id -> Uuid,
@@ -18,6 +42,14 @@ diesel::table! {
 		download_url -> Text,
 		device_id -> Nullable<Uuid>,
 		version_range_pattern -> Nullable<Text>,
+		version_range_floor -> Nullable<Text>,
+		version_range_ceiling -> Nullable<Text>,
+		sha256 -> Nullable<Text>,
+		size_bytes -> Nullable<Int8>,
+		verified_at -> Nullable<Timestamptz>,
+		verification_error -> Nullable<Text>,
+		bundle_id -> Nullable<Text>,
+		version_code -> Nullable<Int8>,
 	}
 }
 
@@ -45,6 +77,13 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	compatibility_rules (rule) {
+		rule -> Text,
+		created_at -> Timestamptz,
+	}
+}
+
 diesel::table! {
 	device_connections (id, created_at) {
 		id -> Uuid,
@@ -76,6 +115,59 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	platform_requirements (major, minor, platform) {
+		major -> Int4,
+		minor -> Int4,
+		platform -> Text,
+		created_at -> Timestamptz,
+		updated_at -> Timestamptz,
+		min_version -> Nullable<Text>,
+		max_version -> Nullable<Text>,
+	}
+}
+
+diesel::table! {
+	releaser_keys (id) {
+		id -> Uuid,
+		created_at -> Timestamptz,
+		device_id -> Uuid,
+		name -> Nullable<Text>,
+		public_key -> Bytea,
+	}
+}
+
+diesel::table! {
+	server_probe_results (id) {
+		id -> Uuid,
+		created_at -> Timestamptz,
+		probe_id -> Uuid,
+		server_id -> Uuid,
+		success -> Bool,
+		latency_ms -> Int4,
+		error -> Nullable<Text>,
+	}
+}
+
+diesel::table! {
+	server_probes (id) {
+		id -> Uuid,
+		created_at -> Timestamptz,
+		updated_at -> Timestamptz,
+		server_id -> Nullable<Uuid>,
+		server_kind -> Nullable<Text>,
+		name -> Text,
+		probe_type -> Text,
+		enabled -> Bool,
+		check_interval -> Interval,
+		timeout -> Interval,
+		path -> Nullable<Text>,
+		expected_status -> Nullable<Int4>,
+		expected_body -> Nullable<Text>,
+		port -> Nullable<Int4>,
+	}
+}
+
 diesel::table! {
 	servers (id) {
 		id -> Uuid,
@@ -90,6 +182,8 @@ diesel::table! {
 		listed -> Bool,
 		cloud -> Nullable<Bool>,
 		geolocation -> Nullable<Array<Nullable<Float8>>>,
+		default_channel -> Text,
+		ping_timeout -> Nullable<Interval>,
 	}
 }
 
@@ -113,6 +207,51 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	support_policies (major, minor) {
+		major -> Int4,
+		minor -> Int4,
+		created_at -> Timestamptz,
+		updated_at -> Timestamptz,
+		active_support_until -> Nullable<Date>,
+		security_support_until -> Nullable<Date>,
+	}
+}
+
+diesel::table! {
+	version_rollouts (version_id) {
+		version_id -> Uuid,
+		created_at -> Timestamptz,
+		updated_at -> Timestamptz,
+		allowlist -> Array<Uuid>,
+		percentage -> Int4,
+		paused -> Bool,
+	}
+}
+
+diesel::table! {
+	version_signatures (id) {
+		id -> Uuid,
+		created_at -> Timestamptz,
+		version_id -> Uuid,
+		releaser_key_id -> Uuid,
+		payload -> Text,
+		signature -> Bytea,
+	}
+}
+
+diesel::table! {
+	version_yanks (version_id) {
+		version_id -> Uuid,
+		created_at -> Timestamptz,
+		updated_at -> Timestamptz,
+		reason -> Text,
+		severity -> Text,
+		replacement_version_id -> Nullable<Uuid>,
+		device_id -> Nullable<Uuid>,
+	}
+}
+
 diesel::table! {
 	versions (id) {
 		id -> Uuid,
@@ -124,28 +263,58 @@ diesel::table! {
 		changelog -> Text,
 		status -> Text,
 		device_id -> Nullable<Uuid>,
+		channels -> Array<Text>,
+		prerelease -> Text,
+		build -> Text,
+		urgency -> Text,
+		stepping_stone -> Bool,
+		published_at -> Nullable<Timestamptz>,
+		yanked_at -> Nullable<Timestamptz>,
 	}
 }
 
+diesel::joinable!(artifact_downloads -> artifacts (artifact_id));
+diesel::joinable!(artifact_downloads -> devices (device_id));
+diesel::joinable!(artifact_downloads -> versions (version_id));
+diesel::joinable!(artifact_link_checks -> artifacts (artifact_id));
 diesel::joinable!(artifacts -> devices (device_id));
 diesel::joinable!(artifacts -> versions (version_id));
 diesel::joinable!(device_connections -> devices (device_id));
 diesel::joinable!(device_keys -> devices (device_id));
+diesel::joinable!(releaser_keys -> devices (device_id));
+diesel::joinable!(server_probe_results -> server_probes (probe_id));
+diesel::joinable!(server_probe_results -> servers (server_id));
+diesel::joinable!(server_probes -> servers (server_id));
 diesel::joinable!(servers -> devices (device_id));
 diesel::joinable!(statuses -> devices (device_id));
 diesel::joinable!(statuses -> servers (server_id));
+diesel::joinable!(version_rollouts -> versions (version_id));
+diesel::joinable!(version_signatures -> releaser_keys (releaser_key_id));
+diesel::joinable!(version_signatures -> versions (version_id));
+diesel::joinable!(version_yanks -> devices (device_id));
 diesel::joinable!(versions -> devices (device_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
 	admins,
+	artifact_downloads,
+	artifact_link_checks,
 	artifacts,
 	bestool_snippets,
 	chrome_releases,
+	compatibility_rules,
 	device_connections,
 	device_keys,
 	devices,
+	platform_requirements,
+	releaser_keys,
+	server_probe_results,
+	server_probes,
 	servers,
 	sql_playground_history,
 	statuses,
+	support_policies,
+	version_rollouts,
+	version_signatures,
+	version_yanks,
 	versions,
 );
@@ -2,13 +2,14 @@ use commons_errors::{AppError, Result};
 use commons_types::{
 	geo::GeoPoint,
 	server::{kind::ServerKind, rank::ServerRank, ticket::MetaTicket},
+	version::ReleaseChannel,
 };
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::url_field::UrlField;
+use super::{pg_duration::PgDuration, url_field::UrlField};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, AsChangeset)]
 #[diesel(table_name = crate::schema::servers)]
@@ -34,6 +35,14 @@ pub struct Server {
 	pub cloud: Option<bool>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub geolocation: Option<GeoPoint>,
+
+	/// The release channel this server's update checks use.
+	#[diesel(deserialize_as = String, serialize_as = String)]
+	pub default_channel: ReleaseChannel,
+
+	/// Overrides the global ping timeout for this server.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ping_timeout: Option<PgDuration>,
 }
 
 impl Server {
@@ -223,6 +232,8 @@ impl Server {
 			listed: false,
 			cloud,
 			geolocation: None,
+			default_channel: ReleaseChannel::default(),
+			ping_timeout: None,
 		};
 
 		let host_str = server_value.host.0.to_string();
@@ -387,6 +398,8 @@ fn test_server_serialization() {
 		listed: true,
 		cloud: None,
 		geolocation: None,
+		default_channel: ReleaseChannel::Stable,
+		ping_timeout: None,
 	};
 
 	let serialized = serde_json::to_string_pretty(&server).unwrap();
@@ -399,7 +412,8 @@ fn test_server_serialization() {
   "kind": "central",
   "rank": "production",
   "device_id": "00000000-0000-0000-0000-000000000000",
-  "listed": true
+  "listed": true,
+  "default_channel": "stable"
 }"#
 	);
 }
@@ -426,6 +440,8 @@ impl From<NewServer> for Server {
 			listed: false,
 			cloud: None,
 			geolocation: None,
+			default_channel: ReleaseChannel::default(),
+			ping_timeout: None,
 		}
 	}
 }
@@ -446,4 +462,6 @@ pub struct PartialServer {
 	pub listed: Option<bool>,
 	pub cloud: Option<Option<bool>>,
 	pub geolocation: Option<Option<GeoPoint>>,
+	pub default_channel: Option<ReleaseChannel>,
+	pub ping_timeout: Option<Option<PgDuration>>,
 }
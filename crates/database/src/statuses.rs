@@ -1,20 +1,78 @@
 use std::{
+	collections::HashMap,
 	str::FromStr as _,
+	sync::Arc,
 	time::{Duration, Instant},
 };
 
 use commons_errors::{AppError, Result};
-use commons_types::{server::rank::ServerRank, status::ShortStatus, version::VersionStr};
+use commons_types::{
+	server::{
+		compat::{CompatibilityRule, VersionMismatch},
+		kind::ServerKind,
+		rank::ServerRank,
+	},
+	status::ShortStatus,
+	version::VersionStr,
+};
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use futures::stream::{FuturesOrdered, StreamExt};
+use futures::stream::{FuturesUnordered, StreamExt};
 use jiff::{SignedDuration, Timestamp};
 use node_semver::Version;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info, warn};
+use tokio::{sync::Semaphore, time::sleep};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::servers::Server;
+use crate::{
+	compatibility_rules::CompatibilityRules,
+	probes::{ServerProbe, ServerProbeResult},
+	servers::Server,
+};
+
+/// How a round of pings is spread out and how hard each server is tried.
+#[derive(Debug, Clone)]
+pub struct PingOptions {
+	/// How many servers are pinged at the same time.
+	pub concurrency: usize,
+
+	/// Timeout for each individual attempt, unless the server sets its own.
+	pub timeout: Duration,
+
+	/// How many times a failed ping is retried before the server is considered unreachable.
+	pub retries: u32,
+
+	/// Delay before the first retry, doubled for each subsequent retry.
+	pub backoff: Duration,
+
+	/// Pings are started at a stable per-server offset within this window.
+	pub spread: Duration,
+}
+
+impl Default for PingOptions {
+	fn default() -> Self {
+		Self {
+			concurrency: 16,
+			timeout: Duration::from_secs(10),
+			retries: 2,
+			backoff: Duration::from_secs(1),
+			spread: Duration::from_secs(20),
+		}
+	}
+}
+
+/// A delay in `0..max` derived from the server ID, so each server keeps its slot between rounds.
+fn jitter(server: Uuid, max: Duration) -> Duration {
+	let max = max.as_millis() as u64;
+	if max == 0 {
+		return Duration::ZERO;
+	}
+
+	// derived from the ID bytes rather than a Hasher, whose output may change between releases
+	let (high, low) = server.as_u64_pair();
+	Duration::from_millis((high ^ low) % max)
+}
 
 #[derive(
 	Debug,
@@ -78,53 +136,81 @@ impl Status {
 		self.extra.as_object().and_then(|obj| obj.get(key))
 	}
 
-	pub async fn ping_server(client: &reqwest::Client, server: &Server) -> Option<Self> {
-		let start = Instant::now();
+	pub async fn ping_server(
+		client: &reqwest::Client,
+		server: &Server,
+		options: &PingOptions,
+	) -> Option<Self> {
 		let url = server.host.0.join("/api/public/ping").unwrap();
-		debug!(%url, "pinging");
-		match client.get(url).send().await.map(|res| {
-			res.headers()
-				.get("X-Version")
-				.and_then(|value| value.to_str().ok())
-				.and_then(|value| VersionStr::from_str(value).ok())
-		}) {
-			Ok(version) => {
-				let latency = start.elapsed().as_millis().try_into().unwrap_or(i32::MAX);
-				info!(server=%server.id, host=%server.host.0, %latency, "ping success");
-				Some(Self {
-					id: Uuid::new_v4(),
-					server_id: server.id,
-					device_id: None,
-					created_at: Timestamp::now(),
-					version,
-
-					extra: Default::default(),
-				})
-			}
-			Err(err) => {
-				warn!(server=%server.id, host=%server.host.0, "ping failure: {err}");
-				None
+		let timeout = server
+			.ping_timeout
+			.as_ref()
+			.and_then(|timeout| Duration::try_from(timeout.0).ok())
+			.unwrap_or(options.timeout);
+		let mut backoff = options.backoff;
+		for attempt in 0..=options.retries {
+			let start = Instant::now();
+			debug!(%url, attempt, "pinging");
+			match client
+				.get(url.clone())
+				.timeout(timeout)
+				.send()
+				.await
+				.map(|res| {
+					res.headers()
+						.get("X-Version")
+						.and_then(|value| value.to_str().ok())
+						.and_then(|value| VersionStr::from_str(value).ok())
+				}) {
+				Ok(version) => {
+					let latency = start.elapsed().as_millis().try_into().unwrap_or(i32::MAX);
+					info!(server=%server.id, host=%server.host.0, %latency, attempt, "ping success");
+					return Some(Self {
+						id: Uuid::new_v4(),
+						server_id: server.id,
+						device_id: None,
+						created_at: Timestamp::now(),
+						version,
+
+						extra: Default::default(),
+					});
+				}
+				Err(err) if attempt < options.retries => {
+					debug!(server=%server.id, host=%server.host.0, attempt, "ping failure, retrying: {err}");
+					sleep(backoff + jitter(server.id, backoff / 2)).await;
+					backoff *= 2;
+				}
+				Err(err) => {
+					warn!(server=%server.id, host=%server.host.0, attempts=attempt + 1, "ping failure: {err}");
+				}
 			}
 		}
+
+		None
 	}
 
-	pub async fn ping_servers(db: &mut AsyncPgConnection) -> Result<Vec<(Self, Server)>> {
+	pub async fn ping_servers(
+		db: &mut AsyncPgConnection,
+		options: &PingOptions,
+	) -> Result<Vec<(Self, Server)>> {
 		let client = reqwest::ClientBuilder::new()
-			.timeout(Duration::from_secs(10))
 			.build()
-			.unwrap();
-		let statuses =
-			FuturesOrdered::from_iter(Server::all_pingable(db).await?.into_iter().map({
+			.map_err(AppError::custom)?;
+		let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+		let statuses = FuturesUnordered::from_iter(
+			Server::all_pingable(db).await?.into_iter().map(|server| {
 				let client = client.clone();
-				move |server| {
-					let client = client.clone();
-					async move {
-						Self::ping_server(&client, &server)
-							.await
-							.map(|ping| (ping, server))
-					}
+				let permits = permits.clone();
+				async move {
+					// spread the pings over the round so they don't all land at once
+					sleep(jitter(server.id, options.spread)).await;
+					let _permit = permits.acquire_owned().await.ok()?;
+					Self::ping_server(&client, &server, options)
+						.await
+						.map(|ping| (ping, server))
 				}
-			}));
+			}),
+		);
 
 		Ok(statuses
 			.collect::<Vec<Option<_>>>()
@@ -134,10 +220,21 @@ impl Status {
 			.collect())
 	}
 
-	pub async fn ping_servers_and_save(db: &mut AsyncPgConnection) -> Result<()> {
+	pub async fn ping_servers_and_save(
+		db: &mut AsyncPgConnection,
+		options: &PingOptions,
+	) -> Result<Vec<(Self, Server)>> {
 		use crate::schema::statuses::dsl::*;
 
-		let servers = Self::ping_servers(db).await?;
+		let probe_client = reqwest::ClientBuilder::new()
+			.build()
+			.map_err(AppError::custom)?;
+		// probes are extra checks: failing to run them shouldn't stop the ping round
+		if let Err(err) = ServerProbe::run_due(db, &probe_client, options.concurrency).await {
+			error!("failed to run probes: {err}");
+		}
+
+		let servers = Self::ping_servers(db, options).await?;
 		diesel::insert_into(statuses)
 			.values(
 				servers
@@ -149,7 +246,7 @@ impl Status {
 			.await
 			.map_err(AppError::from)?;
 
-		Ok(())
+		Ok(servers)
 	}
 
 	pub async fn latest_for_server(
@@ -195,6 +292,83 @@ impl Status {
 		query.load::<Status>(db).await.map_err(AppError::from)
 	}
 
+	/// Check every facility against its central using the latest version each reported.
+	///
+	/// Servers which haven't reported a version recently are skipped.
+	pub async fn version_mismatches(db: &mut AsyncPgConnection) -> Result<Vec<VersionMismatch>> {
+		let centrals = Server::list_by_kind(db, ServerKind::Central, 0, None).await?;
+		let facilities = Server::list_by_kind(db, ServerKind::Facility, 0, None).await?;
+		Self::mismatches_between(db, centrals, facilities).await
+	}
+
+	/// Check the facilities of one central against it, like [`Self::version_mismatches`].
+	pub async fn version_mismatches_for_central(
+		db: &mut AsyncPgConnection,
+		central: &Server,
+	) -> Result<Vec<VersionMismatch>> {
+		let facilities = central.get_children(db).await?;
+		Self::mismatches_between(db, vec![central.clone()], facilities).await
+	}
+
+	async fn mismatches_between(
+		db: &mut AsyncPgConnection,
+		centrals: Vec<Server>,
+		facilities: Vec<Server>,
+	) -> Result<Vec<VersionMismatch>> {
+		let centrals: HashMap<Uuid, Server> = centrals
+			.into_iter()
+			.map(|server| (server.id, server))
+			.collect();
+		let facilities: Vec<Server> = facilities
+			.into_iter()
+			.filter(|server| {
+				server.kind == ServerKind::Facility
+					&& server
+						.parent_server_id
+						.is_some_and(|parent| centrals.contains_key(&parent))
+			})
+			.collect();
+		if facilities.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let rules = CompatibilityRules::enabled(db).await?;
+		let server_ids: Vec<Uuid> = centrals
+			.keys()
+			.copied()
+			.chain(facilities.iter().map(|server| server.id))
+			.collect();
+		let versions: HashMap<Uuid, VersionStr> = Self::latest_for_servers(db, &server_ids)
+			.await?
+			.into_iter()
+			.filter_map(|status| Some((status.server_id, status.version?)))
+			.collect();
+
+		let mut mismatches: Vec<_> = facilities
+			.into_iter()
+			.filter_map(|facility| {
+				let central = &centrals[&facility.parent_server_id?];
+				let central_version = versions.get(&central.id)?;
+				let facility_version = versions.get(&facility.id)?;
+				let violations =
+					CompatibilityRule::violations(&rules, central_version, facility_version);
+				(!violations.is_empty()).then(|| VersionMismatch {
+					central_id: central.id,
+					central_name: central.name.clone().unwrap_or_default(),
+					central_version: central_version.clone(),
+					facility_id: facility.id,
+					facility_name: facility.name.unwrap_or_default(),
+					facility_version: facility_version.clone(),
+					violations,
+				})
+			})
+			.collect();
+		mismatches.sort_by(|a, b| {
+			(&a.central_name, &a.facility_name).cmp(&(&b.central_name, &b.facility_name))
+		});
+		Ok(mismatches)
+	}
+
 	pub async fn production_versions(db: &mut AsyncPgConnection) -> Result<Vec<VersionStr>> {
 		use crate::schema::servers::dsl as servers_dsl;
 		use crate::schema::statuses::dsl as statuses_dsl;
@@ -246,6 +420,19 @@ impl Status {
 			.map(|vers| vers.trim_end_matches(',').into())
 	}
 
+	/// The short status, taking into account the latest results of the server's probes.
+	///
+	/// A server which answers pings but fails any of its probes is degraded. Probes don't make
+	/// a server which isn't answering look any better or worse.
+	pub fn short_status_with_probes(&self, probes: &[ServerProbeResult]) -> ShortStatus {
+		match self.short_status() {
+			ShortStatus::Up | ShortStatus::Blip if probes.iter().any(|probe| !probe.success) => {
+				ShortStatus::Degraded
+			}
+			status => status,
+		}
+	}
+
 	pub fn short_status(&self) -> ShortStatus {
 		let since = self.created_at.duration_since(Timestamp::now()).abs();
 		if since > SignedDuration::from_mins(30) {
@@ -0,0 +1,251 @@
+use std::collections::{BTreeSet, HashMap};
+
+use commons_errors::{AppError, Result};
+use commons_types::version::{SupportStatus, VersionStatus, VersionStr};
+use diesel::{prelude::*, upsert::excluded};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jiff::{Timestamp, civil::Date, tz::TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::versions::Version;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::support_policies)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SupportPolicy {
+	pub major: i32,
+	pub minor: i32,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub updated_at: Timestamp,
+
+	/// Last day of bug fixes and security fixes.
+	#[diesel(deserialize_as = jiff_diesel::NullableDate, serialize_as = jiff_diesel::NullableDate)]
+	pub active_support_until: Option<Date>,
+
+	/// Last day of security fixes only.
+	#[diesel(deserialize_as = jiff_diesel::NullableDate, serialize_as = jiff_diesel::NullableDate)]
+	pub security_support_until: Option<Date>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::support_policies)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewSupportPolicy {
+	pub major: i32,
+	pub minor: i32,
+	#[diesel(serialize_as = jiff_diesel::NullableDate)]
+	pub active_support_until: Option<Date>,
+	#[diesel(serialize_as = jiff_diesel::NullableDate)]
+	pub security_support_until: Option<Date>,
+}
+
+/// The support lifecycle of a minor release, as shown to customers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinorSupport {
+	pub major: i32,
+	pub minor: i32,
+
+	/// When the first patch of the minor was created.
+	pub released_at: Option<Timestamp>,
+
+	pub active_support_until: Option<Date>,
+	pub security_support_until: Option<Date>,
+
+	/// Where the minor is in its lifecycle today.
+	pub status: SupportStatus,
+}
+
+impl SupportPolicy {
+	pub async fn get_all(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::support_policies::dsl::*;
+
+		support_policies
+			.select(Self::as_select())
+			.order_by(major.desc())
+			.then_order_by(minor.desc())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	pub async fn get_for_minor(
+		db: &mut AsyncPgConnection,
+		target_major: i32,
+		target_minor: i32,
+	) -> Result<Option<Self>> {
+		use crate::schema::support_policies::dsl::*;
+
+		support_policies
+			.filter(major.eq(target_major).and(minor.eq(target_minor)))
+			.select(Self::as_select())
+			.first(db)
+			.await
+			.optional()
+			.map_err(AppError::from)
+	}
+
+	pub async fn upsert(db: &mut AsyncPgConnection, policy: NewSupportPolicy) -> Result<Self> {
+		use crate::schema::support_policies::dsl::*;
+
+		if let (Some(active), Some(security)) =
+			(policy.active_support_until, policy.security_support_until)
+			&& security < active
+		{
+			return Err(AppError::custom(
+				"Security support can't end before active support",
+			));
+		}
+
+		diesel::insert_into(support_policies)
+			.values(policy)
+			.on_conflict((major, minor))
+			.do_update()
+			.set((
+				active_support_until.eq(excluded(active_support_until)),
+				security_support_until.eq(excluded(security_support_until)),
+			))
+			.returning(Self::as_select())
+			.get_result(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Where a minor with this policy is in its lifecycle on the given day.
+	pub fn status_on(&self, date: Date) -> SupportStatus {
+		status_on(self.active_support_until, self.security_support_until, date)
+	}
+
+	pub async fn remove(
+		db: &mut AsyncPgConnection,
+		target_major: i32,
+		target_minor: i32,
+	) -> Result<()> {
+		use crate::schema::support_policies::dsl::*;
+
+		diesel::delete(support_policies.filter(major.eq(target_major).and(minor.eq(target_minor))))
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+}
+
+fn status_on(active: Option<Date>, security: Option<Date>, date: Date) -> SupportStatus {
+	match (active, security) {
+		(_, Some(security)) if date > security => SupportStatus::EndOfLife,
+		(Some(active), _) if date <= active => SupportStatus::Active,
+		(Some(_), _) => SupportStatus::SecurityOnly,
+		(None, _) => SupportStatus::Unknown,
+	}
+}
+
+fn today() -> Date {
+	Timestamp::now().to_zoned(TimeZone::UTC).date()
+}
+
+impl MinorSupport {
+	/// The support lifecycle of the minor that a version belongs to.
+	pub async fn for_version(db: &mut AsyncPgConnection, version: VersionStr) -> Result<Self> {
+		let (major, minor) = (version.0.major as i32, version.0.minor as i32);
+		let released_at = Version::get_head_release_date(db, version).await.ok();
+		let policy = SupportPolicy::get_for_minor(db, major, minor).await?;
+
+		Ok(Self::new(major, minor, released_at, policy.as_ref()))
+	}
+
+	/// The support lifecycle of every minor that has a published release, newest first.
+	pub async fn for_all_minors(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		let policies: HashMap<(i32, i32), SupportPolicy> = SupportPolicy::get_all(db)
+			.await?
+			.into_iter()
+			.map(|policy| ((policy.major, policy.minor), policy))
+			.collect();
+
+		let released_at = Version::get_head_release_dates(db).await?;
+
+		let minors: BTreeSet<(i32, i32)> = Version::get_all_including_drafts(db)
+			.await?
+			.into_iter()
+			.filter(|version| !version.is_prerelease())
+			.filter(|version| version.status == VersionStatus::Published)
+			.map(|version| (version.major, version.minor))
+			.collect();
+
+		Ok(minors
+			.into_iter()
+			.rev()
+			.map(|(major, minor)| {
+				Self::new(
+					major,
+					minor,
+					released_at.get(&(major, minor)).copied(),
+					policies.get(&(major, minor)),
+				)
+			})
+			.collect())
+	}
+
+	fn new(
+		major: i32,
+		minor: i32,
+		released_at: Option<Timestamp>,
+		policy: Option<&SupportPolicy>,
+	) -> Self {
+		let active_support_until = policy.and_then(|p| p.active_support_until);
+		let security_support_until = policy.and_then(|p| p.security_support_until);
+		Self {
+			major,
+			minor,
+			released_at,
+			active_support_until,
+			security_support_until,
+			status: status_on(active_support_until, security_support_until, today()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jiff::civil::date;
+
+	use super::*;
+
+	#[test]
+	fn status_through_the_lifecycle() {
+		let active = Some(date(2026, 6, 30));
+		let security = Some(date(2026, 12, 31));
+
+		assert_eq!(
+			status_on(active, security, date(2026, 1, 1)),
+			SupportStatus::Active
+		);
+		assert_eq!(
+			status_on(active, security, date(2026, 6, 30)),
+			SupportStatus::Active
+		);
+		assert_eq!(
+			status_on(active, security, date(2026, 7, 1)),
+			SupportStatus::SecurityOnly
+		);
+		assert_eq!(
+			status_on(active, security, date(2027, 1, 1)),
+			SupportStatus::EndOfLife
+		);
+		assert_eq!(
+			status_on(active, None, date(2030, 1, 1)),
+			SupportStatus::SecurityOnly
+		);
+		assert_eq!(
+			status_on(None, None, date(2026, 1, 1)),
+			SupportStatus::Unknown
+		);
+		assert_eq!(
+			status_on(None, security, date(2027, 1, 1)),
+			SupportStatus::EndOfLife
+		);
+	}
+}
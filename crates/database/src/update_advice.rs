@@ -0,0 +1,167 @@
+use commons_errors::Result;
+use commons_types::version::{ReleaseChannel, UpdateUrgency, VersionStr};
+use diesel_async::AsyncPgConnection;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::versions::{Version, ViewVersion};
+
+/// A version newer than the one a server is on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisedVersion {
+	pub id: Uuid,
+	pub version: VersionStr,
+	pub urgency: UpdateUrgency,
+	pub created_at: Timestamp,
+}
+
+impl From<&Version> for AdvisedVersion {
+	fn from(version: &Version) -> Self {
+		Self {
+			id: version.id,
+			version: VersionStr(version.as_semver()),
+			urgency: version.urgency,
+			created_at: version.created_at,
+		}
+	}
+}
+
+/// One step of an upgrade path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeHop {
+	#[serde(flatten)]
+	pub version: ViewVersion,
+
+	/// Whether the hop can't be skipped, because it's through a stepping-stone version.
+	pub required: bool,
+}
+
+/// What a server on a given version should do about updating.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAdvice {
+	pub current: VersionStr,
+
+	/// The newest version offered to the server, if there's one newer than what it's on.
+	pub latest: Option<ViewVersion>,
+
+	/// How urgently the server should update, from the versions it's behind.
+	pub urgency: UpdateUrgency,
+
+	/// Whether the server is below the minimum version required for its minor.
+	pub mandatory: bool,
+
+	/// The newest mandatory version of the server's minor, if it's newer than the current one.
+	pub minimum_version: Option<VersionStr>,
+
+	/// Security fixes released after the current version, which staying put misses out on.
+	pub security_fixes: Vec<AdvisedVersion>,
+
+	/// The newest patch of each newer minor, across majors.
+	pub updates: Vec<ViewVersion>,
+
+	/// The versions to upgrade to, in order, to get from the current version to the latest.
+	pub path: Vec<UpgradeHop>,
+}
+
+impl UpdateAdvice {
+	/// Advise a server on `current` about the versions it's behind in its channel.
+	///
+	/// A mandatory version only makes updating mandatory for servers on the same minor: for
+	/// servers on older minors it counts as recommended.
+	///
+	/// The path to the latest version goes through every stepping stone on the way.
+	pub async fn for_version(
+		db: &mut AsyncPgConnection,
+		current: VersionStr,
+		channel: ReleaseChannel,
+		server: Option<Uuid>,
+	) -> Result<Self> {
+		let updates =
+			Version::get_updates_across_majors(db, current.clone(), channel, server).await?;
+
+		let newer = Version::get_newer_offered(db, current.clone(), channel, server).await?;
+
+		let same_minor = |version: &Version| {
+			version.major as u64 == current.0.major && version.minor as u64 == current.0.minor
+		};
+
+		let minimum_version = newer
+			.iter()
+			.rev()
+			.find(|version| version.urgency == UpdateUrgency::Mandatory && same_minor(version))
+			.map(|version| VersionStr(version.as_semver()));
+
+		let urgency = newer
+			.iter()
+			.map(|version| match version.urgency {
+				UpdateUrgency::Mandatory if !same_minor(version) => UpdateUrgency::Recommended,
+				urgency => urgency,
+			})
+			.max()
+			.unwrap_or_default();
+
+		let security_fixes = newer
+			.iter()
+			.filter(|version| version.urgency == UpdateUrgency::Security)
+			.map(AdvisedVersion::from)
+			.collect();
+
+		let path = upgrade_path(&updates, &newer, channel);
+
+		Ok(Self {
+			current,
+			latest: updates.last().cloned(),
+			urgency,
+			mandatory: minimum_version.is_some(),
+			minimum_version,
+			security_fixes,
+			updates,
+			path,
+		})
+	}
+}
+
+/// The hops from a version to the latest of `updates`, stopping at each stepping stone in `newer`.
+///
+/// A stepping stone is reached by upgrading to the newest patch of its minor which the server is
+/// offered, as any later patch runs its migrations too. Stepping stones in the latest version's minor
+/// are passed through by upgrading to it, and those past it are still held back by a rollout.
+pub(crate) fn upgrade_path(
+	updates: &[ViewVersion],
+	newer: &[Version],
+	channel: ReleaseChannel,
+) -> Vec<UpgradeHop> {
+	let Some(latest) = updates.last() else {
+		return Vec::new();
+	};
+
+	let mut path: Vec<UpgradeHop> = Vec::new();
+	for stone in newer.iter().filter(|version| version.stepping_stone) {
+		if (stone.major, stone.minor) >= (latest.major, latest.minor) {
+			break;
+		}
+		if path
+			.last()
+			.is_some_and(|hop| hop.version.major == stone.major && hop.version.minor == stone.minor)
+		{
+			continue;
+		}
+
+		let version = updates
+			.iter()
+			.find(|update| update.major == stone.major && update.minor == stone.minor)
+			.cloned()
+			.unwrap_or_else(|| ViewVersion::of(stone, channel));
+		path.push(UpgradeHop {
+			version,
+			required: true,
+		});
+	}
+
+	path.push(UpgradeHop {
+		version: latest.clone(),
+		required: latest.stepping_stone,
+	});
+	path
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use commons_errors::{AppError, Result};
+use commons_types::{
+	server::{compat::CompatibilityRule, kind::ServerKind},
+	version::{UpdateUrgency, VersionStatus, VersionStr},
+};
+use diesel_async::AsyncPgConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+	compatibility_rules::CompatibilityRules,
+	servers::Server,
+	statuses::Status,
+	update_advice::{UpgradeHop, upgrade_path},
+	versions::{Version, ViewVersion},
+};
+
+/// A version to stop at on the way to the target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStop {
+	pub version: VersionStr,
+	pub urgency: UpdateUrgency,
+
+	/// Whether the stop can't be skipped, because it's through a stepping-stone version.
+	pub required: bool,
+}
+
+/// A server in the plan, and the stops it makes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedServer {
+	pub server: Server,
+
+	/// The version the server last reported in the past week, if any.
+	pub version: Option<VersionStr>,
+
+	/// The stops from the server's version to the target, empty if it's already there.
+	pub stops: Vec<PlanStop>,
+}
+
+/// A facility whose central is in the plan, but which isn't itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacilityMove {
+	pub facility: Server,
+	pub central_id: Uuid,
+	pub version: Option<VersionStr>,
+
+	/// The rules its version would break once its central is on the target.
+	///
+	/// Empty for facilities which haven't reported a version, whose move can't be estimated.
+	pub violations: Vec<CompatibilityRule>,
+}
+
+/// How to get a group of servers onto a target version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradePlan {
+	pub target: VersionStr,
+	pub servers: Vec<PlannedServer>,
+
+	/// The stops from the oldest version in the plan to the target.
+	pub stops: Vec<PlanStop>,
+
+	/// Every published version the oldest server goes through, oldest first.
+	pub changelog: Vec<Version>,
+
+	/// Facilities which need to move with their central, or may need to.
+	pub facilities: Vec<FacilityMove>,
+}
+
+impl From<UpgradeHop> for PlanStop {
+	fn from(hop: UpgradeHop) -> Self {
+		Self {
+			version: VersionStr(hop.version.as_semver()),
+			urgency: hop.version.urgency,
+			required: hop.required,
+		}
+	}
+}
+
+/// The stops a server on `from` makes to get to `target`, going through every stepping stone in
+/// between, as update checks would lead it.
+///
+/// The server's channel and rollouts decide which patch of each stepping stone's minor it stops at.
+async fn stops_between(
+	db: &mut AsyncPgConnection,
+	from: &VersionStr,
+	target: &Version,
+	server: &Server,
+) -> Result<Vec<PlanStop>> {
+	if *from >= VersionStr(target.as_semver()) {
+		return Ok(Vec::new());
+	}
+
+	let channel = server.default_channel;
+	let mut updates: Vec<ViewVersion> =
+		Version::get_updates_across_majors(db, from.clone(), channel, Some(server.id))
+			.await?
+			.into_iter()
+			.filter(|update| (update.major, update.minor) < (target.major, target.minor))
+			.collect();
+	updates.push(ViewVersion::of(target, channel));
+	let newer = Version::get_newer_offered(db, from.clone(), channel, Some(server.id)).await?;
+
+	Ok(upgrade_path(&updates, &newer, channel)
+		.into_iter()
+		.map(PlanStop::from)
+		.collect())
+}
+
+impl UpgradePlan {
+	/// Plan the upgrade of the servers to `target`, from the versions they last reported.
+	///
+	/// Facilities of the centrals in the plan are checked against the target with the version
+	/// compatibility rules: those which would break one need to be upgraded alongside.
+	pub async fn for_servers(
+		db: &mut AsyncPgConnection,
+		server_ids: &[Uuid],
+		target: VersionStr,
+	) -> Result<Self> {
+		let target = Version::get_by_version(db, target).await?;
+		if target.status != VersionStatus::Published {
+			return Err(AppError::custom(format!(
+				"Target version {} is {}",
+				target.as_semver(),
+				target.status
+			)));
+		}
+		if target.is_prerelease() {
+			return Err(AppError::custom(format!(
+				"Target version {} is a pre-release",
+				target.as_semver()
+			)));
+		}
+		let target_str = VersionStr(target.as_semver());
+
+		let servers = Server::get_by_ids(db, server_ids).await?;
+		if servers.is_empty() {
+			return Err(AppError::custom("No servers to plan an upgrade for"));
+		}
+		let planned: HashSet<Uuid> = servers.iter().map(|server| server.id).collect();
+
+		let mut facilities: Vec<(Uuid, Server)> = Vec::new();
+		for central in servers
+			.iter()
+			.filter(|server| server.kind == ServerKind::Central)
+		{
+			for facility in central.get_children(db).await? {
+				if !planned.contains(&facility.id) {
+					facilities.push((central.id, facility));
+				}
+			}
+		}
+
+		let status_ids: Vec<Uuid> = servers
+			.iter()
+			.map(|server| server.id)
+			.chain(facilities.iter().map(|(_, facility)| facility.id))
+			.collect();
+		let versions: HashMap<Uuid, VersionStr> = Status::latest_for_servers(db, &status_ids)
+			.await?
+			.into_iter()
+			.filter_map(|status| Some((status.server_id, status.version?)))
+			.collect();
+
+		let mut with_stops: Vec<PlannedServer> = Vec::with_capacity(servers.len());
+		for server in servers {
+			let version = versions.get(&server.id).cloned();
+			let stops = match &version {
+				Some(version) => stops_between(db, version, &target, &server).await?,
+				None => Vec::new(),
+			};
+			with_stops.push(PlannedServer {
+				server,
+				version,
+				stops,
+			});
+		}
+		let mut servers = with_stops;
+		// Centrals first, as their facilities follow them
+		servers.sort_by_key(|planned| {
+			(
+				planned.server.kind != ServerKind::Central,
+				planned.server.name.clone(),
+			)
+		});
+
+		let oldest = servers
+			.iter()
+			.filter_map(|planned| Some((planned.version.clone()?, planned)))
+			.filter(|(version, _)| *version < target_str)
+			.min_by(|(a, _), (b, _)| a.cmp(b));
+		let (stops, changelog) = match oldest {
+			Some((oldest, planned)) => (
+				planned.stops.clone(),
+				Version::get_between(db, oldest, target_str.clone()).await?,
+			),
+			None => (Vec::new(), Vec::new()),
+		};
+
+		let rules = CompatibilityRules::enabled(db).await?;
+		let mut facilities: Vec<FacilityMove> = facilities
+			.into_iter()
+			.filter_map(|(central_id, facility)| {
+				let version = versions.get(&facility.id).cloned();
+				let violations = version
+					.as_ref()
+					.map(|version| CompatibilityRule::violations(&rules, &target_str, version))
+					.unwrap_or_default();
+				(version.is_none() || !violations.is_empty()).then_some(FacilityMove {
+					facility,
+					central_id,
+					version,
+					violations,
+				})
+			})
+			.collect();
+		facilities.sort_by(|a, b| a.facility.name.cmp(&b.facility.name));
+
+		Ok(Self {
+			target: target_str,
+			servers,
+			stops,
+			changelog,
+			facilities,
+		})
+	}
+
+	/// The plan as a markdown document, to send to the customer.
+	pub fn to_markdown(&self) -> String {
+		let name = |server: &Server| {
+			server
+				.name
+				.clone()
+				.unwrap_or_else(|| server.host.0.to_string())
+		};
+		let mut doc = String::new();
+
+		// Writing to a String can't fail
+		let _ = writeln!(doc, "# Upgrade plan to Tamanu {}\n", self.target);
+
+		let _ = writeln!(doc, "## Servers\n");
+		let _ = writeln!(doc, "| Server | Kind | Current version | Stops |");
+		let _ = writeln!(doc, "| --- | --- | --- | --- |");
+		for planned in &self.servers {
+			let stops = if planned.version.is_none() {
+				"unknown".to_string()
+			} else if planned.stops.is_empty() {
+				"up to date".to_string()
+			} else {
+				planned
+					.stops
+					.iter()
+					.map(|stop| stop.version.to_string())
+					.collect::<Vec<_>>()
+					.join(" → ")
+			};
+			let _ = writeln!(
+				doc,
+				"| {} | {} | {} | {} |",
+				table_cell(&name(&planned.server)),
+				planned.server.kind,
+				planned
+					.version
+					.as_ref()
+					.map_or("unknown".to_string(), |version| version.to_string()),
+				stops,
+			);
+		}
+
+		if !self.stops.is_empty() {
+			let _ = writeln!(doc, "\n## Upgrade steps\n");
+			for (n, stop) in self.stops.iter().enumerate() {
+				let _ = writeln!(
+					doc,
+					"{}. Upgrade to {}{}",
+					n + 1,
+					stop.version,
+					if stop.required {
+						" (required: its migrations must run before going further)"
+					} else {
+						""
+					}
+				);
+			}
+		}
+
+		if !self.facilities.is_empty() {
+			let _ = writeln!(doc, "\n## Facilities to upgrade with their central\n");
+			for moving in &self.facilities {
+				let reason = match &moving.version {
+					None => "hasn't reported its version recently, check it".to_string(),
+					Some(version) => format!(
+						"on {version}, would be {}",
+						moving
+							.violations
+							.iter()
+							.map(|rule| rule.to_string())
+							.collect::<Vec<_>>()
+							.join(", ")
+					),
+				};
+				let _ = writeln!(doc, "- {}: {reason}", name(&moving.facility));
+			}
+		}
+
+		if !self.changelog.is_empty() {
+			let _ = writeln!(doc, "\n## Changes\n");
+			for version in &self.changelog {
+				let _ = writeln!(doc, "### {}\n", version.as_semver());
+				let _ = writeln!(doc, "{}\n", version.changelog.trim());
+			}
+		}
+
+		doc
+	}
+}
+
+/// Text made safe to put in a markdown table cell, which a `|` or a line break would end early.
+fn table_cell(text: &str) -> String {
+	text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
@@ -0,0 +1,151 @@
+//! Resolving semver ranges against versions in the database.
+//!
+//! A range is made of one or more spans of versions, each between a lower and an upper bound.
+//! Versions are compared by their sort key, and as with [`Range::satisfies`], pre-releases are
+//! only matched by a span which has a pre-release of the same version as one of its bounds.
+
+use diesel::{dsl::sql, pg::Pg, prelude::*, sql_types::Bool};
+use node_semver::{Range, Version as Semver};
+
+use crate::{
+	schema::versions,
+	versions::{sort_key_of, version_sort_key},
+};
+
+/// A boolean SQL expression over the versions table.
+pub type VersionPredicate = Box<dyn BoxableExpression<versions::table, Pg, SqlType = Bool>>;
+
+/// One end of a span of versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bound {
+	Unbounded,
+	Including(Semver),
+	Excluding(Semver),
+}
+
+impl Bound {
+	fn version(&self) -> Option<&Semver> {
+		match self {
+			Self::Unbounded => None,
+			Self::Including(version) | Self::Excluding(version) => Some(version),
+		}
+	}
+}
+
+/// A contiguous span of versions, one of the alternatives a range is made of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+	pub lower: Bound,
+	pub upper: Bound,
+}
+
+/// The spans a range is made of.
+pub fn spans(range: &Range) -> Vec<Span> {
+	// node-semver doesn't expose its bounds, but writes ranges out in a normalised form, with
+	// spans separated by `||` and each one either `*`, an exact version, or comparators.
+	range
+		.to_string()
+		.split("||")
+		.map(str::trim)
+		.filter(|span| !span.is_empty())
+		.map(|span| {
+			let mut lower = Bound::Unbounded;
+			let mut upper = Bound::Unbounded;
+			for comparator in span.split_whitespace().filter(|c| *c != "*") {
+				let parse = |version: &str| {
+					Semver::parse(version).expect("node-semver writes out valid versions")
+				};
+				if let Some(version) = comparator.strip_prefix(">=") {
+					lower = Bound::Including(parse(version));
+				} else if let Some(version) = comparator.strip_prefix('>') {
+					lower = Bound::Excluding(parse(version));
+				} else if let Some(version) = comparator.strip_prefix("<=") {
+					upper = Bound::Including(parse(version));
+				} else if let Some(version) = comparator.strip_prefix('<') {
+					upper = Bound::Excluding(parse(version));
+				} else {
+					lower = Bound::Including(parse(comparator));
+					upper = Bound::Including(parse(comparator));
+				}
+			}
+			Span { lower, upper }
+		})
+		.collect()
+}
+
+/// The lowest and highest versions the range can match, both inclusive, or `None` where the
+/// range is unbounded.
+pub fn hull(range: &Range) -> (Option<Semver>, Option<Semver>) {
+	let spans = spans(range);
+	let floor = spans
+		.iter()
+		.map(|span| span.lower.version())
+		.collect::<Option<Vec<_>>>()
+		.and_then(|versions| versions.into_iter().min().cloned());
+	let ceiling = spans
+		.iter()
+		.map(|span| span.upper.version())
+		.collect::<Option<Vec<_>>>()
+		.and_then(|versions| versions.into_iter().max().cloned());
+	(floor, ceiling)
+}
+
+/// A predicate over the versions table which matches the same versions as the range.
+pub fn matching(range: &Range) -> VersionPredicate {
+	spans(range)
+		.iter()
+		.map(Span::matching)
+		.reduce(|predicate, span| Box::new(predicate.or(span)))
+		.unwrap_or_else(|| Box::new(sql::<Bool>("FALSE")))
+}
+
+impl Span {
+	fn matching(&self) -> VersionPredicate {
+		use crate::schema::versions::dsl::*;
+
+		let mut predicate: VersionPredicate = Box::new(sql::<Bool>("TRUE"));
+
+		match &self.lower {
+			Bound::Unbounded => {}
+			Bound::Including(version) => {
+				predicate = Box::new(predicate.and(
+					version_sort_key(major, minor, patch, prerelease).ge(sort_key_of(version)),
+				));
+			}
+			Bound::Excluding(version) => {
+				predicate = Box::new(predicate.and(
+					version_sort_key(major, minor, patch, prerelease).gt(sort_key_of(version)),
+				));
+			}
+		}
+
+		match &self.upper {
+			Bound::Unbounded => {}
+			Bound::Including(version) => {
+				predicate = Box::new(predicate.and(
+					version_sort_key(major, minor, patch, prerelease).le(sort_key_of(version)),
+				));
+			}
+			Bound::Excluding(version) => {
+				predicate = Box::new(predicate.and(
+					version_sort_key(major, minor, patch, prerelease).lt(sort_key_of(version)),
+				));
+			}
+		}
+
+		// Pre-releases only match when a bound opts into the pre-releases of its version
+		let mut prereleases: VersionPredicate = Box::new(prerelease.eq(""));
+		for bound in [&self.lower, &self.upper] {
+			if let Some(version) = bound.version().filter(|v| v.is_prerelease()) {
+				prereleases = Box::new(
+					prereleases.or(major
+						.eq(version.major as i32)
+						.and(minor.eq(version.minor as i32))
+						.and(patch.eq(version.patch as i32))),
+				);
+			}
+		}
+
+		Box::new(predicate.and(prereleases))
+	}
+}
@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
 use commons_errors::{AppError, Result};
-use commons_types::version::{VersionStatus, VersionStr};
+use commons_types::version::{
+	ReleaseChannel, UpdateUrgency, VersionStatus, VersionStr, parse_identifiers,
+};
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use jiff::Timestamp;
@@ -15,6 +19,7 @@ macro_rules! predicate_version {
 			major: target_major,
 			minor: target_minor,
 			patch: target_patch,
+			pre_release: target_pre_release,
 			..
 		} = $version;
 
@@ -22,10 +27,27 @@ macro_rules! predicate_version {
 			.eq(target_major as i32)
 			.and(minor.eq(target_minor as i32))
 			.and(patch.eq(target_patch as i32))
+			.and(prerelease.eq(::commons_types::version::format_identifiers(
+				&target_pre_release,
+			)))
 	}};
 }
 pub use predicate_version;
 
+/// Whether a version in the `versions` query, joined with its rollout, is offered to server `$5`.
+///
+/// Only published versions are held back by a rollout, and servers which can't be identified only
+/// get versions whose rollout has reached everyone.
+const OFFERED_TO_SERVER: &str = "(
+	version_rollouts.version_id IS NULL
+	OR status <> 'published'
+	OR (NOT paused AND (
+		percentage >= 100
+		OR $5 = ANY(allowlist)
+		OR rollout_bucket($5, versions.id) < percentage
+	))
+)";
+
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, QueryableByName)]
 #[diesel(table_name = crate::schema::versions)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -42,19 +64,75 @@ pub struct Version {
 	pub status: VersionStatus,
 	pub changelog: String,
 	pub device_id: Option<Uuid>,
+	pub channels: Vec<ReleaseChannel>,
+	/// Pre-release identifiers, like `rc.3`, or empty for a release.
+	pub prerelease: String,
+	/// Build metadata, which doesn't affect precedence.
+	pub build: String,
+	pub urgency: UpdateUrgency,
+	/// Whether servers must upgrade through this version before moving on to anything newer.
+	pub stepping_stone: bool,
+	/// When the version was last published, set as its status changes.
+	#[diesel(deserialize_as = jiff_diesel::NullableTimestamp, serialize_as = jiff_diesel::NullableTimestamp)]
+	pub published_at: Option<Timestamp>,
+	/// When the version was yanked, if it is.
+	#[diesel(deserialize_as = jiff_diesel::NullableTimestamp, serialize_as = jiff_diesel::NullableTimestamp)]
+	pub yanked_at: Option<Timestamp>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, QueryableByName)]
-#[diesel(table_name = crate::views::version_updates)]
+/// A version as offered in a channel, as returned by update queries.
+#[derive(Debug, Clone, Serialize, Deserialize, QueryableByName)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct ViewVersion {
+	#[diesel(sql_type = diesel::sql_types::Uuid)]
 	pub id: Uuid,
+	#[diesel(sql_type = diesel::sql_types::Integer)]
 	pub major: i32,
+	#[diesel(sql_type = diesel::sql_types::Integer)]
 	pub minor: i32,
+	#[diesel(sql_type = diesel::sql_types::Integer)]
 	pub patch: i32,
-	#[diesel(deserialize_as = String, serialize_as = String)]
+	#[diesel(sql_type = diesel::sql_types::Text)]
+	pub prerelease: String,
+	#[diesel(sql_type = diesel::sql_types::Text)]
+	pub build: String,
+	#[diesel(sql_type = diesel::sql_types::Text, deserialize_as = String)]
 	pub status: VersionStatus,
+	#[diesel(sql_type = diesel::sql_types::Text)]
 	pub changelog: String,
+	#[diesel(sql_type = diesel::sql_types::Text)]
+	pub channel: ReleaseChannel,
+	#[diesel(sql_type = diesel::sql_types::Text)]
+	pub urgency: UpdateUrgency,
+	#[diesel(sql_type = diesel::sql_types::Bool)]
+	pub stepping_stone: bool,
+}
+
+impl ViewVersion {
+	pub fn as_semver(&self) -> node_semver::Version {
+		let mut version =
+			node_semver::Version::new(self.major as _, self.minor as _, self.patch as _);
+		version.pre_release = parse_identifiers(&self.prerelease);
+		version.build = parse_identifiers(&self.build);
+		version
+	}
+
+	/// The version as listed in a channel.
+	pub fn of(version: &Version, channel: ReleaseChannel) -> Self {
+		Self {
+			id: version.id,
+			major: version.major,
+			minor: version.minor,
+			patch: version.patch,
+			prerelease: version.prerelease.clone(),
+			build: version.build.clone(),
+			status: version.status,
+			changelog: version.changelog.clone(),
+			channel,
+			urgency: version.urgency,
+			stepping_stone: version.stepping_stone,
+		}
+	}
 }
 
 #[derive(Debug, Deserialize, Insertable)]
@@ -67,22 +145,99 @@ pub struct NewVersion {
 	pub changelog: String,
 	pub status: VersionStatus,
 	pub device_id: Option<Uuid>,
+	pub channels: Vec<ReleaseChannel>,
+	pub prerelease: String,
+	pub build: String,
+	pub urgency: UpdateUrgency,
+	pub stepping_stone: bool,
+}
+
+impl NewVersion {
+	pub fn new(
+		version: &VersionStr,
+		changelog: String,
+		status: VersionStatus,
+		device_id: Option<Uuid>,
+		channels: Vec<ReleaseChannel>,
+	) -> Self {
+		Self {
+			major: version.0.major as _,
+			minor: version.0.minor as _,
+			patch: version.0.patch as _,
+			changelog,
+			status,
+			device_id,
+			channels,
+			prerelease: version.prerelease(),
+			build: version.build(),
+			urgency: UpdateUrgency::default(),
+			stepping_stone: false,
+		}
+	}
+}
+
+diesel::define_sql_function! {
+	/// Sorts versions in semver precedence order, including pre-releases.
+	fn version_sort_key(
+		major: diesel::sql_types::Integer,
+		minor: diesel::sql_types::Integer,
+		patch: diesel::sql_types::Integer,
+		prerelease: diesel::sql_types::Text,
+	) -> diesel::sql_types::Binary;
+}
+
+diesel::define_sql_function! {
+	/// The sort key of a version written out as a string.
+	#[sql_name = "version_sort_key"]
+	fn version_str_sort_key(version: diesel::sql_types::Text) -> diesel::sql_types::Binary;
+}
+
+/// The sort key of a given version, to compare against versions in the database.
+pub fn sort_key_of(version: &node_semver::Version) -> version_sort_key<i32, i32, i32, String> {
+	version_sort_key(
+		version.major as i32,
+		version.minor as i32,
+		version.patch as i32,
+		commons_types::version::format_identifiers(&version.pre_release),
+	)
 }
 
 impl Version {
 	pub fn as_semver(&self) -> node_semver::Version {
-		node_semver::Version::new(self.major as _, self.minor as _, self.patch as _)
+		let mut version =
+			node_semver::Version::new(self.major as _, self.minor as _, self.patch as _);
+		version.pre_release = parse_identifiers(&self.prerelease);
+		version.build = parse_identifiers(&self.build);
+		version
 	}
 
+	/// Whether this is a pre-release, like a release candidate.
+	pub fn is_prerelease(&self) -> bool {
+		!self.prerelease.is_empty()
+	}
+
+	/// All published releases, newest first. Pre-releases are left out.
 	pub async fn get_all(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
 		use crate::schema::versions::*;
 
 		table
 			.select(Version::as_select())
 			.filter(status.eq(VersionStatus::Published))
-			.order_by(major.desc())
-			.then_order_by(minor.desc())
-			.then_order_by(patch.desc())
+			.filter(prerelease.eq(""))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// All published versions, pre-releases included, newest first.
+	pub async fn get_all_with_prereleases(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		use crate::schema::versions::*;
+
+		table
+			.select(Version::as_select())
+			.filter(status.eq(VersionStatus::Published))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
 			.load(db)
 			.await
 			.map_err(AppError::from)
@@ -93,9 +248,7 @@ impl Version {
 
 		table
 			.select(Version::as_select())
-			.order_by(major.desc())
-			.then_order_by(minor.desc())
-			.then_order_by(patch.desc())
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
 			.load(db)
 			.await
 			.map_err(AppError::from)
@@ -123,73 +276,231 @@ impl Version {
 			.map_err(AppError::from)
 	}
 
+	/// The versions with these IDs, in no particular order.
+	pub async fn get_by_ids(db: &mut AsyncPgConnection, version_ids: &[Uuid]) -> Result<Vec<Self>> {
+		use crate::schema::versions::dsl::*;
+
+		versions
+			.filter(id.eq_any(version_ids))
+			.select(Version::as_select())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Published versions which have been released to the given channel.
+	pub async fn get_all_in_channel(
+		db: &mut AsyncPgConnection,
+		channel: ReleaseChannel,
+	) -> Result<Vec<Self>> {
+		use crate::schema::versions::*;
+
+		table
+			.select(Version::as_select())
+			.filter(status.eq(VersionStatus::Published))
+			.filter(channels.contains(vec![channel]))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// Published and yanked versions in the channel, most recently published or yanked first.
+	///
+	/// Yanking a version brings it back up to the top; other edits don't move it.
+	pub async fn get_for_feed(
+		db: &mut AsyncPgConnection,
+		channel: ReleaseChannel,
+		in_major: Option<i32>,
+		in_minor: Option<i32>,
+		limit: i64,
+	) -> Result<Vec<Self>> {
+		use crate::schema::versions::*;
+
+		let mut query = table
+			.select(Version::as_select())
+			.filter(status.eq_any(vec![VersionStatus::Published, VersionStatus::Yanked]))
+			.filter(channels.contains(vec![channel]))
+			.into_boxed();
+		if let Some(in_major) = in_major {
+			query = query.filter(major.eq(in_major));
+		}
+		if let Some(in_minor) = in_minor {
+			query = query.filter(minor.eq(in_minor));
+		}
+
+		query
+			.order_by(
+				diesel::dsl::sql::<diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>>(
+					"COALESCE(yanked_at, published_at)",
+				)
+				.desc(),
+			)
+			.limit(limit)
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
+	/// The newest patch of each minor in the same major which is newer than `version`.
+	///
+	/// Versions in a staged rollout are only offered to `server` once it's included in the
+	/// rollout; until then it gets the latest patch it's eligible for. Callers which can't be
+	/// identified only get versions whose rollout has reached everyone.
+	///
+	/// Pre-releases are ordered before their release, and are only offered in the channels
+	/// they've been released to, which is beta unless chosen otherwise.
 	pub async fn get_updates_for_version(
 		db: &mut AsyncPgConnection,
 		version: VersionStr,
+		release_channel: ReleaseChannel,
+		server: Option<Uuid>,
 	) -> Result<Vec<ViewVersion>> {
-		use crate::views::version_updates::dsl::*;
+		Self::updates_for_version(db, version, release_channel, server, false).await
+	}
+
+	/// Like [`get_updates_for_version`](Self::get_updates_for_version), but across all majors.
+	pub async fn get_updates_across_majors(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		release_channel: ReleaseChannel,
+		server: Option<Uuid>,
+	) -> Result<Vec<ViewVersion>> {
+		Self::updates_for_version(db, version, release_channel, server, true).await
+	}
+
+	async fn updates_for_version(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		release_channel: ReleaseChannel,
+		server: Option<Uuid>,
+		across_majors: bool,
+	) -> Result<Vec<ViewVersion>> {
+		use diesel::sql_types::{Bool, Integer, Nullable, Text, Uuid as SqlUuid};
+
+		let target_prerelease = version.prerelease();
 		let node_semver::Version {
 			major: target_major,
 			minor: target_minor,
 			patch: target_patch,
 			..
 		} = version.0;
-		version_updates
-			.filter(
-				major
-					.eq(target_major as i32)
-					.and(status.eq(VersionStatus::Published))
-					.and(
-						minor.gt(target_minor as i32).or(minor
-							.eq(target_minor as i32)
-							.and(patch.gt(target_patch as i32))),
-					),
+
+		diesel::sql_query(format!(
+			"WITH ranked_versions AS (
+				SELECT versions.id, major, minor, patch, prerelease, build, status, changelog,
+					$4 AS channel, urgency, stepping_stone,
+					ROW_NUMBER() OVER (
+						PARTITION BY major, minor
+						ORDER BY version_sort_key(major, minor, patch, prerelease) DESC
+					) AS rn
+				FROM versions
+				LEFT JOIN version_rollouts ON version_rollouts.version_id = versions.id
+				WHERE $4 = ANY(channels)
+					AND ($7 OR major = $1)
+					AND version_sort_key(major, minor, patch, prerelease)
+						> version_sort_key($1, $2, $3, $6)
+					AND {OFFERED_TO_SERVER}
 			)
-			.order_by(minor)
-			.select(version_updates::all_columns())
-			.load(db)
-			.await
-			.map_err(AppError::from)
+			SELECT id, major, minor, patch, prerelease, build, status, changelog, channel, urgency,
+				stepping_stone
+			FROM ranked_versions
+			WHERE rn = 1 AND status = 'published'
+			ORDER BY major, minor"
+		))
+		.bind::<Integer, _>(target_major as i32)
+		.bind::<Integer, _>(target_minor as i32)
+		.bind::<Integer, _>(target_patch as i32)
+		.bind::<Text, _>(release_channel)
+		.bind::<Nullable<SqlUuid>, _>(server)
+		.bind::<Text, _>(target_prerelease)
+		.bind::<Bool, _>(across_majors)
+		.load(db)
+		.await
+		.map_err(AppError::from)
 	}
 
-	pub async fn get_latest_matching(
+	/// Every published version newer than `version` in the channel, oldest first.
+	///
+	/// Like [`get_updates_for_version`](Self::get_updates_for_version), versions in a staged
+	/// rollout are left out until `server` is included in it.
+	pub async fn get_newer_offered(
 		db: &mut AsyncPgConnection,
-		range: node_semver::Range,
-	) -> Result<Self> {
-		use crate::schema::versions::*;
+		version: VersionStr,
+		release_channel: ReleaseChannel,
+		server: Option<Uuid>,
+	) -> Result<Vec<Self>> {
+		use diesel::sql_types::{Integer, Nullable, Text, Uuid as SqlUuid};
 
+		let target_prerelease = version.prerelease();
 		let node_semver::Version {
 			major: target_major,
 			minor: target_minor,
 			patch: target_patch,
 			..
-		} = range.min_version().ok_or(AppError::UnusableRange)?;
+		} = version.0;
+
+		diesel::sql_query(format!(
+			"SELECT versions.*
+			FROM versions
+			LEFT JOIN version_rollouts ON version_rollouts.version_id = versions.id
+			WHERE status = 'published'
+				AND $4 = ANY(channels)
+				AND version_sort_key(major, minor, patch, prerelease)
+					> version_sort_key($1, $2, $3, $6)
+				AND {OFFERED_TO_SERVER}
+			ORDER BY version_sort_key(major, minor, patch, prerelease)"
+		))
+		.bind::<Integer, _>(target_major as i32)
+		.bind::<Integer, _>(target_minor as i32)
+		.bind::<Integer, _>(target_patch as i32)
+		.bind::<Text, _>(release_channel)
+		.bind::<Nullable<SqlUuid>, _>(server)
+		.bind::<Text, _>(target_prerelease)
+		.load(db)
+		.await
+		.map_err(AppError::from)
+	}
+
+	/// The newest published version which satisfies the range.
+	pub async fn get_latest_matching(
+		db: &mut AsyncPgConnection,
+		range: node_semver::Range,
+	) -> Result<Self> {
+		use crate::schema::versions::*;
+
+		range.min_version().ok_or(AppError::UnusableRange)?;
 
 		table
 			.select(Version::as_select())
-			.filter(
-				status
-					.eq(VersionStatus::Published)
-					.and(major.ge(target_major as i32))
-					.and(minor.ge(target_minor as i32))
-					.and(patch.ge(target_patch as i32)),
-			)
-			.order_by(major.desc())
-			.then_order_by(minor.desc())
-			.then_order_by(patch.desc())
-			.load(db)
+			.filter(status.eq(VersionStatus::Published))
+			.filter(crate::version_ranges::matching(&range))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
+			.first(db)
 			.await
-			.map_err(AppError::from)?
-			.into_iter()
-			.find(|v| range.satisfies(&v.as_semver()))
+			.optional()?
 			.ok_or(AppError::NoMatchingVersions)
 	}
 
+	/// The `.0` releases which date their minor; drafts haven't been released yet.
+	fn head_releases() -> crate::schema::versions::BoxedQuery<'static, diesel::pg::Pg> {
+		use crate::schema::versions::dsl::*;
+
+		versions
+			.filter(
+				patch
+					.eq(0)
+					.and(prerelease.eq(""))
+					.and(status.ne(VersionStatus::Draft)),
+			)
+			.into_boxed()
+	}
+
 	pub async fn get_head_release_date(
 		db: &mut AsyncPgConnection,
 		version: VersionStr,
 	) -> Result<Timestamp> {
-		use crate::schema::versions::*;
+		use crate::schema::versions::dsl::*;
 
 		let node_semver::Version {
 			major: target_major,
@@ -197,20 +508,34 @@ impl Version {
 			..
 		} = version.0;
 
-		table
-			.select(Version::as_select())
+		Self::head_releases()
+			.select(created_at)
 			.filter(
 				major
 					.eq(target_major as i32)
-					.and(minor.eq(target_minor as i32))
-					.and(patch.eq(0)),
+					.and(minor.eq(target_minor as i32)),
 			)
-			.first(db)
+			.first::<jiff_diesel::Timestamp>(db)
 			.await
-			.map(|v: Version| v.created_at)
+			.map(|date| date.to_jiff())
 			.map_err(AppError::from)
 	}
 
+	/// The release date of every minor, as in [`Self::get_head_release_date`].
+	pub async fn get_head_release_dates(
+		db: &mut AsyncPgConnection,
+	) -> Result<HashMap<(i32, i32), Timestamp>> {
+		use crate::schema::versions::dsl::*;
+
+		Ok(Self::head_releases()
+			.select((major, minor, created_at))
+			.load::<(i32, i32, jiff_diesel::Timestamp)>(db)
+			.await?
+			.into_iter()
+			.map(|(head_major, head_minor, date)| ((head_major, head_minor), date.to_jiff()))
+			.collect())
+	}
+
 	pub async fn update_status(
 		db: &mut AsyncPgConnection,
 		version: VersionStr,
@@ -259,6 +584,54 @@ impl Version {
 		Ok(())
 	}
 
+	pub async fn update_channels(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		new_channels: Vec<ReleaseChannel>,
+	) -> Result<()> {
+		use crate::schema::versions::dsl::*;
+
+		diesel::update(versions)
+			.filter(predicate_version!(version.0))
+			.set(channels.eq(new_channels))
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	pub async fn update_urgency(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		new_urgency: UpdateUrgency,
+	) -> Result<()> {
+		use crate::schema::versions::dsl::*;
+
+		diesel::update(versions)
+			.filter(predicate_version!(version.0))
+			.set(urgency.eq(new_urgency))
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	pub async fn update_stepping_stone(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		new_stepping_stone: bool,
+	) -> Result<()> {
+		use crate::schema::versions::dsl::*;
+
+		diesel::update(versions)
+			.filter(predicate_version!(version.0))
+			.set(stepping_stone.eq(new_stepping_stone))
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
 	pub async fn is_latest_in_minor(
 		db: &mut AsyncPgConnection,
 		version: VersionStr,
@@ -271,7 +644,8 @@ impl Version {
 			.filter(major.eq(version_record.major))
 			.filter(minor.eq(version_record.minor))
 			.filter(status.eq(VersionStatus::Published))
-			.order_by(patch.desc())
+			.filter(prerelease.eq(""))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
 			.select(Version::as_select())
 			.first(db)
 			.await
@@ -279,16 +653,49 @@ impl Version {
 
 		Ok(latest_in_minor
 			.as_ref()
-			.map(|v| v.patch == version_record.patch)
+			.map(|v| v.as_semver() == version_record.as_semver())
 			.unwrap_or(true))
 	}
 
+	/// Published versions after `from` up to and including `to`, oldest first.
+	///
+	/// This is what a server upgrading from `from` to `to` goes through, for collecting changelogs.
+	/// Pre-releases are only included when upgrading to a pre-release.
+	pub async fn get_between(
+		db: &mut AsyncPgConnection,
+		from: VersionStr,
+		to: VersionStr,
+	) -> Result<Vec<Self>> {
+		use crate::schema::versions::dsl::*;
+
+		if from.0 > to.0 {
+			return Err(AppError::BackwardsRange);
+		}
+
+		let mut query = versions
+			.filter(status.eq(VersionStatus::Published))
+			.filter(version_sort_key(major, minor, patch, prerelease).gt(sort_key_of(&from.0)))
+			.filter(version_sort_key(major, minor, patch, prerelease).le(sort_key_of(&to.0)))
+			.into_boxed();
+		if !to.0.is_prerelease() {
+			query = query.filter(prerelease.eq(""));
+		}
+
+		query
+			.order_by(version_sort_key(major, minor, patch, prerelease).asc())
+			.select(Version::as_select())
+			.load(db)
+			.await
+			.map_err(AppError::from)
+	}
+
 	pub async fn get_all_in_minor(
 		db: &mut AsyncPgConnection,
 		version: VersionStr,
 	) -> Result<Vec<Self>> {
 		use crate::schema::versions::dsl::*;
 
+		let target_prerelease = version.prerelease();
 		let node_semver::Version {
 			major: target_major,
 			minor: target_minor,
@@ -299,9 +706,16 @@ impl Version {
 		versions
 			.filter(major.eq(target_major as i32))
 			.filter(minor.eq(target_minor as i32))
-			.filter(patch.lt(target_patch as i32))
+			.filter(
+				version_sort_key(major, minor, patch, prerelease).lt(version_sort_key(
+					target_major as i32,
+					target_minor as i32,
+					target_patch as i32,
+					target_prerelease,
+				)),
+			)
 			.filter(status.ne(VersionStatus::Draft))
-			.order_by(patch.desc())
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
 			.select(Version::as_select())
 			.load(db)
 			.await
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use commons_errors::{AppError, Result};
+use commons_types::version::{VersionStatus, VersionStr, YankSeverity};
+use diesel::{prelude::*, upsert::excluded};
+use diesel_async::{
+	AsyncConnection as _, AsyncPgConnection, RunQueryDsl, scoped_futures::ScopedFutureExt as _,
+};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{servers::Server, statuses::Status, versions::Version};
+
+/// Why a version was yanked, and what to move to instead.
+///
+/// The version's status is what makes it yanked: this is only read while it is.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::version_yanks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct VersionYank {
+	pub version_id: Uuid,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub created_at: Timestamp,
+
+	#[diesel(deserialize_as = jiff_diesel::Timestamp, serialize_as = jiff_diesel::Timestamp)]
+	pub updated_at: Timestamp,
+
+	pub reason: String,
+	pub severity: YankSeverity,
+
+	/// The version servers should move to, if there's a particular one.
+	pub replacement_version_id: Option<Uuid>,
+
+	/// The device which yanked the version.
+	pub device_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::version_yanks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewVersionYank {
+	pub version_id: Uuid,
+	pub reason: String,
+	pub severity: YankSeverity,
+	pub replacement_version_id: Option<Uuid>,
+	pub device_id: Option<Uuid>,
+}
+
+/// What servers on a yanked version are told.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YankNotice {
+	pub version: VersionStr,
+	pub reason: String,
+	pub severity: YankSeverity,
+	pub replacement: Option<VersionStr>,
+
+	/// When the reason was given, if it was. Versions yanked without one have none.
+	pub yanked_at: Option<Timestamp>,
+}
+
+/// A server which last reported running a yanked version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YankedServer {
+	pub server: Server,
+	pub notice: YankNotice,
+}
+
+impl VersionYank {
+	pub async fn get_for_version(
+		db: &mut AsyncPgConnection,
+		version: Uuid,
+	) -> Result<Option<Self>> {
+		use crate::schema::version_yanks::dsl::*;
+
+		version_yanks
+			.filter(version_id.eq(version))
+			.select(Self::as_select())
+			.first(db)
+			.await
+			.optional()
+			.map_err(AppError::from)
+	}
+
+	/// Yank a version, recording why and what to replace it with.
+	///
+	/// The replacement must be a different, published version. Yanking a version again replaces
+	/// the reason it was yanked with.
+	pub async fn yank(
+		db: &mut AsyncPgConnection,
+		version: VersionStr,
+		reason: String,
+		severity: YankSeverity,
+		replacement: Option<VersionStr>,
+		device: Option<Uuid>,
+	) -> Result<YankNotice> {
+		db.transaction(|db| {
+			async move {
+				let target = {
+					use crate::schema::versions::*;
+					table
+						.filter(crate::predicate_version!(version.0.clone()))
+						.select(Version::as_select())
+						.for_update()
+						.first(db)
+						.await?
+				};
+
+				let replacement = match replacement {
+					Some(replacement) => {
+						let replacement = Version::get_by_version(db, replacement).await?;
+						if replacement.id == target.id {
+							return Err(AppError::custom("A version can't replace itself"));
+						}
+						if replacement.status != VersionStatus::Published {
+							return Err(AppError::custom(format!(
+								"Replacement version {} is {}",
+								replacement.as_semver(),
+								replacement.status
+							)));
+						}
+						Some(replacement)
+					}
+					None => None,
+				};
+
+				let new_yank = NewVersionYank {
+					version_id: target.id,
+					reason,
+					severity,
+					replacement_version_id: replacement.as_ref().map(|version| version.id),
+					device_id: device,
+				};
+
+				{
+					use crate::schema::versions::dsl::*;
+					diesel::update(versions.find(target.id))
+						.set(status.eq(VersionStatus::Yanked))
+						.execute(db)
+						.await?;
+				}
+
+				// Parameters shadow the column names, so they're qualified here
+				let yank = {
+					use crate::schema::version_yanks::dsl as yanks_dsl;
+					diesel::insert_into(yanks_dsl::version_yanks)
+						.values(new_yank)
+						.on_conflict(yanks_dsl::version_id)
+						.do_update()
+						.set((
+							yanks_dsl::reason.eq(excluded(yanks_dsl::reason)),
+							yanks_dsl::severity.eq(excluded(yanks_dsl::severity)),
+							yanks_dsl::replacement_version_id
+								.eq(excluded(yanks_dsl::replacement_version_id)),
+							yanks_dsl::device_id.eq(excluded(yanks_dsl::device_id)),
+						))
+						.returning(VersionYank::as_select())
+						.get_result(db)
+						.await?
+				};
+
+				Ok(YankNotice::new(&target, Some(yank), replacement.as_ref()))
+			}
+			.scope_boxed()
+		})
+		.await
+	}
+}
+
+impl YankNotice {
+	fn new(version: &Version, yank: Option<VersionYank>, replacement: Option<&Version>) -> Self {
+		let (reason, severity, yanked_at) = match yank {
+			Some(yank) => (yank.reason, yank.severity, Some(yank.updated_at)),
+			None => (String::new(), YankSeverity::default(), None),
+		};
+		Self {
+			version: VersionStr(version.as_semver()),
+			reason,
+			severity,
+			replacement: replacement.map(|version| VersionStr(version.as_semver())),
+			yanked_at,
+		}
+	}
+
+	/// The notice for a version, if it's yanked.
+	pub async fn for_version(db: &mut AsyncPgConnection, version: &Version) -> Result<Option<Self>> {
+		let mut notices = Self::for_versions(db, std::slice::from_ref(version)).await?;
+		Ok(notices.remove(&version.id))
+	}
+
+	/// Every yanked version's notice, by version id.
+	pub async fn for_all(db: &mut AsyncPgConnection) -> Result<HashMap<Uuid, Self>> {
+		let yanked: Vec<Version> = {
+			use crate::schema::versions::dsl::*;
+			versions
+				.filter(status.eq(VersionStatus::Yanked))
+				.select(Version::as_select())
+				.load(db)
+				.await?
+		};
+
+		Self::for_versions(db, &yanked).await
+	}
+
+	/// The notices of those of the versions which are yanked, by version id.
+	pub async fn for_versions(
+		db: &mut AsyncPgConnection,
+		versions: &[Version],
+	) -> Result<HashMap<Uuid, Self>> {
+		let yanked: Vec<&Version> = versions
+			.iter()
+			.filter(|version| version.status == VersionStatus::Yanked)
+			.collect();
+		if yanked.is_empty() {
+			return Ok(HashMap::new());
+		}
+
+		let mut yanks: HashMap<Uuid, VersionYank> = {
+			use crate::schema::version_yanks::dsl::*;
+			version_yanks
+				.filter(version_id.eq_any(yanked.iter().map(|version| version.id).collect::<Vec<_>>()))
+				.select(VersionYank::as_select())
+				.load(db)
+				.await?
+				.into_iter()
+				.map(|yank| (yank.version_id, yank))
+				.collect()
+		};
+
+		let replacement_ids: Vec<Uuid> = yanks
+			.values()
+			.filter_map(|yank| yank.replacement_version_id)
+			.collect();
+		let replacements: HashMap<Uuid, Version> = if replacement_ids.is_empty() {
+			HashMap::new()
+		} else {
+			Version::get_by_ids(db, &replacement_ids)
+				.await?
+				.into_iter()
+				.map(|version| (version.id, version))
+				.collect()
+		};
+
+		Ok(yanked
+			.into_iter()
+			.map(|version| {
+				let yank = yanks.remove(&version.id);
+				let replacement = yank
+					.as_ref()
+					.and_then(|yank| yank.replacement_version_id)
+					.and_then(|replacement| replacements.get(&replacement));
+				(version.id, Self::new(version, yank, replacement))
+			})
+			.collect())
+	}
+}
+
+/// The parts of a version which identify it: build metadata doesn't.
+fn precedence_key(version: &VersionStr) -> (u64, u64, u64, String) {
+	(
+		version.0.major,
+		version.0.minor,
+		version.0.patch,
+		version.prerelease(),
+	)
+}
+
+impl YankedServer {
+	/// Every server whose latest reported version, in the last week, has been yanked.
+	///
+	/// Sorted by severity, most severe first, then by server name.
+	pub async fn for_fleet(db: &mut AsyncPgConnection) -> Result<Vec<Self>> {
+		let notices = YankNotice::for_all(db).await?;
+		if notices.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let notices: HashMap<(u64, u64, u64, String), YankNotice> = notices
+			.into_values()
+			.map(|notice| (precedence_key(&notice.version), notice))
+			.collect();
+
+		let mut servers: HashMap<Uuid, Server> = Server::get_all(db, 0, None)
+			.await?
+			.into_iter()
+			.map(|server| (server.id, server))
+			.collect();
+		let server_ids: Vec<Uuid> = servers.keys().copied().collect();
+
+		let mut yanked: Vec<Self> = Status::latest_for_servers(db, &server_ids)
+			.await?
+			.into_iter()
+			.filter_map(|status| {
+				let notice = notices.get(&precedence_key(&status.version?))?.clone();
+				Some(Self {
+					server: servers.remove(&status.server_id)?,
+					notice,
+				})
+			})
+			.collect();
+		yanked.sort_by(|a, b| {
+			b.notice
+				.severity
+				.cmp(&a.notice.severity)
+				.then_with(|| a.server.name.cmp(&b.server.name))
+		});
+		Ok(yanked)
+	}
+}
@@ -0,0 +1,185 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use database::artifacts::Artifact;
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+use uuid::Uuid;
+
+const BODY: &str = "tamanu installer bytes";
+const BODY_SHA256: &str = "e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0";
+
+/// A tiny HTTP server that serves the same file for every path except `/missing`.
+async fn stand_in() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let read = stream.read(&mut request).await.unwrap_or(0);
+			let request = String::from_utf8_lossy(&request[..read]);
+			let (status, body) = if request.starts_with("GET /missing ") {
+				("404 Not Found", "")
+			} else {
+				("200 OK", BODY)
+			};
+			let response = format!(
+				"HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+				body.len()
+			);
+			let _ = stream.write_all(response.as_bytes()).await;
+		}
+	});
+	port
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verification_fills_in_checks_and_flags_artifacts() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+			INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url, sha256, size_bytes) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'server', 'windows', 'http://127.0.0.1:{port}/undeclared', NULL, NULL),
+			('bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', '00000000-0000-0000-0000-000000000001', 'server', 'linux', 'http://127.0.0.1:{port}/declared', '{BODY_SHA256}', 22),
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', '00000000-0000-0000-0000-000000000001', 'web', 'linux', 'http://127.0.0.1:{port}/tampered', '{zeroes}', 22),
+			('dddddddd-dddd-dddd-dddd-dddddddddddd', '00000000-0000-0000-0000-000000000001', 'web', 'windows', 'http://127.0.0.1:{port}/missing', NULL, NULL)",
+			zeroes = "0".repeat(64),
+		))
+		.await
+		.unwrap();
+
+		let client = reqwest::Client::new();
+		let mut checked = Artifact::verify_unverified(&mut conn, &client).await.unwrap();
+		checked.sort_by_key(|artifact| artifact.id);
+		assert_eq!(checked.len(), 4);
+		let [undeclared, declared, tampered, missing] = &checked[..] else {
+			unreachable!()
+		};
+
+		// Missing checksums and sizes are filled in
+		assert_eq!(undeclared.sha256.as_deref(), Some(BODY_SHA256));
+		assert_eq!(undeclared.size_bytes, Some(BODY.len() as i64));
+		assert!(undeclared.verified_at.is_some());
+		assert_eq!(undeclared.verification_error, None);
+
+		assert!(declared.verified_at.is_some());
+		assert_eq!(declared.verification_error, None);
+
+		// Mismatches keep what was declared, and are flagged
+		assert_eq!(tampered.sha256.as_deref(), Some("0".repeat(64).as_str()));
+		assert!(tampered.verified_at.is_some());
+		assert!(
+			tampered
+				.verification_error
+				.as_deref()
+				.unwrap()
+				.contains(BODY_SHA256)
+		);
+
+		// Failed downloads are retried next time
+		assert_eq!(missing.verified_at, None);
+		assert!(
+			missing
+				.verification_error
+				.as_deref()
+				.unwrap()
+				.starts_with("download failed")
+		);
+
+		let again = Artifact::verify_unverified(&mut conn, &client).await.unwrap();
+		let again: Vec<Uuid> = again.iter().map(|artifact| artifact.id).collect();
+		assert_eq!(again, vec![missing.id]);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn changing_the_url_clears_verification() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+			INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'server', 'windows', 'http://127.0.0.1:{port}/first')",
+		))
+		.await
+		.unwrap();
+
+		let client = reqwest::Client::new();
+		let checked = Artifact::verify_unverified(&mut conn, &client).await.unwrap();
+		let id = checked[0].id;
+
+		// Renaming the artifact keeps its verification
+		Artifact::update(
+			&mut conn,
+			id,
+			"server".into(),
+			"linux".into(),
+			format!("http://127.0.0.1:{port}/first"),
+		)
+		.await
+		.unwrap();
+		assert!(Artifact::get_unverified(&mut conn).await.unwrap().is_empty());
+
+		Artifact::update(
+			&mut conn,
+			id,
+			"server".into(),
+			"linux".into(),
+			format!("http://127.0.0.1:{port}/second"),
+		)
+		.await
+		.unwrap();
+		let unverified = Artifact::get_unverified(&mut conn).await.unwrap();
+		assert_eq!(unverified.len(), 1);
+		assert_eq!(unverified[0].sha256, None);
+		assert_eq!(unverified[0].platform, "linux");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verification_leaves_signed_manifests_alone() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published'),
+			('00000000-0000-0000-0000-000000000002', 2, 0, 0, '', 'published');
+			INSERT INTO devices (id) VALUES ('11111111-1111-1111-1111-111111111111');
+			INSERT INTO releaser_keys (id, device_id, public_key) VALUES
+			('22222222-2222-2222-2222-222222222222', '11111111-1111-1111-1111-111111111111', decode(repeat('00', 32), 'hex'));
+			INSERT INTO version_signatures (version_id, releaser_key_id, payload, signature) VALUES
+			('00000000-0000-0000-0000-000000000001', '22222222-2222-2222-2222-222222222222', '{{}}', decode(repeat('00', 64), 'hex'));
+			INSERT INTO artifacts (id, version_id, version_range_pattern, artifact_type, platform, download_url) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', NULL, 'server', 'windows', 'http://127.0.0.1:{port}/signed'),
+			('bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', NULL, '^1.0.0', 'server', 'linux', 'http://127.0.0.1:{port}/ranged'),
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', '00000000-0000-0000-0000-000000000002', NULL, 'server', 'windows', 'http://127.0.0.1:{port}/unsigned')",
+		))
+		.await
+		.unwrap();
+
+		let client = reqwest::Client::new();
+		let mut checked = Artifact::verify_unverified(&mut conn, &client).await.unwrap();
+		checked.sort_by_key(|artifact| artifact.id);
+		let [signed, ranged, unsigned] = &checked[..] else {
+			panic!("expected three artifacts, got {checked:?}")
+		};
+
+		// Artifacts in a signed manifest are checked, but not filled in
+		for artifact in [signed, ranged] {
+			assert!(artifact.verified_at.is_some());
+			assert_eq!(artifact.sha256, None);
+			assert_eq!(artifact.size_bytes, None);
+		}
+
+		assert_eq!(unsigned.sha256.as_deref(), Some(BODY_SHA256));
+	})
+	.await
+}
@@ -0,0 +1,117 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use commons_types::server::{kind::ServerKind, rank::ServerRank};
+use database::drift::ServerDrift;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drift_counts_minors_behind_and_time_on_minor() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		// status partitions only start from the current week, so make room for some history
+		conn.batch_execute(
+			"CREATE TABLE statuses_history PARTITION OF statuses
+				FOR VALUES FROM (MINVALUE) TO (date_trunc('week', NOW()))",
+		)
+		.await
+		.unwrap();
+
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES
+			(2, 1, 0, 'published', ''), (2, 2, 0, 'published', ''),
+			(2, 3, 0, 'published', ''), (2, 4, 0, 'draft', '');
+			INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Current', 'https://current.example.com', 'production', 'central'),
+			('22222222-2222-2222-2222-222222222222', 'Lagging', 'https://lagging.example.com', 'production', 'central'),
+			('33333333-3333-3333-3333-333333333333', 'Lagging, too', 'https://lagging-too.example.com', 'production', 'facility'),
+			('44444444-4444-4444-4444-444444444444', 'Test', 'https://test.example.com', 'test', 'central');
+			INSERT INTO statuses (server_id, version, created_at) VALUES
+			('11111111-1111-1111-1111-111111111111', '2.3.1', NOW() - INTERVAL '1 day'),
+			('22222222-2222-2222-2222-222222222222', '2.1.0', NOW() - INTERVAL '60 days'),
+			('22222222-2222-2222-2222-222222222222', '2.2.0', NOW() - INTERVAL '40 days'),
+			('22222222-2222-2222-2222-222222222222', '2.1.5', NOW() - INTERVAL '30 days'),
+			('22222222-2222-2222-2222-222222222222', '2.1.6', NOW() - INTERVAL '1 hour'),
+			('33333333-3333-3333-3333-333333333333', '2.1.2', NOW() - INTERVAL '10 days'),
+			('33333333-3333-3333-3333-333333333333', '2.1.3', NOW() - INTERVAL '1 hour'),
+			('44444444-4444-4444-4444-444444444444', '2.2.0', NOW() - INTERVAL '1 hour')",
+		)
+		.await
+		.unwrap();
+
+		let drift = ServerDrift::for_fleet(&mut conn, Some(ServerRank::Production), None)
+			.await
+			.unwrap();
+		let summary: Vec<_> = drift
+			.iter()
+			.map(|entry| {
+				(
+					entry.server.name.clone().unwrap(),
+					entry.minors_behind,
+					entry.time_on_minor().as_hours() / 24,
+				)
+			})
+			.collect();
+		assert_eq!(
+			summary,
+			vec![
+				// rolled back from 2.2 to 2.1 thirty days ago
+				("Lagging".to_string(), 2, 30),
+				("Lagging, too".to_string(), 2, 10),
+				("Current".to_string(), 0, 1),
+			]
+		);
+
+		let centrals =
+			ServerDrift::for_fleet(&mut conn, Some(ServerRank::Production), Some(ServerKind::Central))
+				.await
+				.unwrap();
+		assert_eq!(centrals.len(), 2);
+
+		let csv = ServerDrift::to_csv(&drift);
+		let mut lines = csv.lines();
+		assert_eq!(
+			lines.next(),
+			Some("server_id,name,kind,rank,host,version,minors_behind,on_minor_since,days_on_minor")
+		);
+		let lagging_too = lines.nth(1).unwrap();
+		assert!(
+			lagging_too.starts_with(
+				"33333333-3333-3333-3333-333333333333,\"Lagging, too\",facility,production,https://lagging-too.example.com/,2.1.3,2,"
+			),
+			"{lagging_too}"
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drift_csv_doesnt_start_fields_with_formulas() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES
+			(2, 1, 0, 'published', '');
+			INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', '=HYPERLINK(\"https://evil.example.com\", \"Click\")', 'https://one.example.com', 'production', 'central'),
+			('22222222-2222-2222-2222-222222222222', '@SUM(1+1)', 'https://two.example.com', 'production', 'central');
+			INSERT INTO statuses (server_id, version, created_at) VALUES
+			('11111111-1111-1111-1111-111111111111', '2.1.0', NOW() - INTERVAL '1 hour'),
+			('22222222-2222-2222-2222-222222222222', '2.1.0', NOW() - INTERVAL '2 hours')",
+		)
+		.await
+		.unwrap();
+
+		let drift = ServerDrift::for_fleet(&mut conn, None, None).await.unwrap();
+		let csv = ServerDrift::to_csv(&drift);
+		let mut lines = csv.lines().skip(1);
+		let at = lines.next().unwrap();
+		assert!(
+			at.starts_with("22222222-2222-2222-2222-222222222222,'@SUM(1+1),central,"),
+			"{at}"
+		);
+		let equals = lines.next().unwrap();
+		assert!(
+			equals.starts_with(
+				"11111111-1111-1111-1111-111111111111,\"'=HYPERLINK(\"\"https://evil.example.com\"\", \"\"Click\"\")\",central,"
+			),
+			"{equals}"
+		);
+	})
+	.await
+}
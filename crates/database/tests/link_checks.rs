@@ -0,0 +1,174 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use database::link_checks::{ArtifactKind, ArtifactLinkCheck, ReleaseCompleteness};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+use uuid::Uuid;
+
+/// A tiny HTTP server for artifact links:
+/// - `/ok` answers everything,
+/// - `/no-head` refuses HEAD but serves ranged GETs,
+/// - anything else is not found.
+async fn stand_in() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let read = stream.read(&mut request).await.unwrap_or(0);
+			let request = String::from_utf8_lossy(&request[..read]);
+			let mut line = request.split_whitespace();
+			let (method, path) = (line.next().unwrap_or(""), line.next().unwrap_or(""));
+			let ranged = request.to_ascii_lowercase().contains("range: bytes=0-0");
+			let status = match (method, path) {
+				(_, "/ok") => "200 OK",
+				("HEAD", "/no-head") => "405 Method Not Allowed",
+				("GET", "/no-head") if ranged => "206 Partial Content",
+				_ => "404 Not Found",
+			};
+			let response =
+				format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+			let _ = stream.write_all(response.as_bytes()).await;
+		}
+	});
+	port
+}
+
+/// A port that nothing is listening on.
+async fn closed_port() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	listener.local_addr().unwrap().port()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn checks_links_of_published_versions() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in().await;
+		let closed = closed_port().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published'),
+			('00000000-0000-0000-0000-000000000002', 1, 1, 0, '', 'draft'),
+			('00000000-0000-0000-0000-000000000003', 1, 2, 0, '', 'yanked');
+			INSERT INTO artifacts (id, version_id, version_range_pattern, artifact_type, platform, download_url) VALUES
+			('aaaaaaaa-0000-0000-0000-000000000001', '00000000-0000-0000-0000-000000000001', NULL, 'server', 'windows', 'http://127.0.0.1:{port}/ok'),
+			('aaaaaaaa-0000-0000-0000-000000000002', '00000000-0000-0000-0000-000000000001', NULL, 'server', 'linux', 'http://127.0.0.1:{port}/no-head'),
+			('aaaaaaaa-0000-0000-0000-000000000003', '00000000-0000-0000-0000-000000000001', NULL, 'web', 'linux', 'http://127.0.0.1:{port}/gone'),
+			('aaaaaaaa-0000-0000-0000-000000000004', '00000000-0000-0000-0000-000000000001', NULL, 'web', 'windows', 'http://127.0.0.1:{closed}/ok'),
+			('aaaaaaaa-0000-0000-0000-000000000005', NULL, '1.0.x', 'report', 'all', 'http://127.0.0.1:{port}/gone'),
+			('aaaaaaaa-0000-0000-0000-000000000006', '00000000-0000-0000-0000-000000000002', NULL, 'server', 'windows', 'http://127.0.0.1:{port}/gone'),
+			('aaaaaaaa-0000-0000-0000-000000000007', '00000000-0000-0000-0000-000000000003', NULL, 'server', 'windows', 'http://127.0.0.1:{port}/gone'),
+			('aaaaaaaa-0000-0000-0000-000000000008', NULL, '1.1.x', 'report', 'all', 'http://127.0.0.1:{port}/gone')"
+		))
+		.await
+		.unwrap();
+
+		let client = reqwest::Client::new();
+		let mut checks = ArtifactLinkCheck::check_all(&mut conn, &client, 4)
+			.await
+			.unwrap();
+		checks.sort_by_key(|check| check.artifact_id);
+
+		// Drafts, yanked versions, and ranges which only match those, aren't checked
+		let results: Vec<_> = checks
+			.iter()
+			.map(|check| {
+				(
+					check.artifact_id.to_string().chars().last().unwrap(),
+					check.healthy,
+					check.status_code,
+				)
+			})
+			.collect();
+		assert_eq!(
+			results,
+			vec![
+				('1', true, Some(200)),
+				('2', true, Some(206)),
+				('3', false, Some(404)),
+				('4', false, None),
+				('5', false, Some(404)),
+			]
+		);
+		assert!(checks[3].error.is_some());
+
+		// Re-checking replaces the previous result
+		conn.batch_execute(&format!(
+			"UPDATE artifacts SET download_url = 'http://127.0.0.1:{port}/ok'
+			WHERE id = 'aaaaaaaa-0000-0000-0000-000000000003'"
+		))
+		.await
+		.unwrap();
+		ArtifactLinkCheck::check_all(&mut conn, &client, 4)
+			.await
+			.unwrap();
+		let ids: Vec<Uuid> = vec!["aaaaaaaa-0000-0000-0000-000000000003".parse().unwrap()];
+		let rechecked = ArtifactLinkCheck::get_for_artifacts(&mut conn, &ids)
+			.await
+			.unwrap();
+		assert_eq!(rechecked.len(), 1);
+		assert!(rechecked[0].healthy);
+		assert_eq!(rechecked[0].error, None);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completeness_compares_with_the_previous_release() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, prerelease, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', '', 'published'),
+			('00000000-0000-0000-0000-000000000002', 1, 1, 0, 'rc.1', '', 'published'),
+			('00000000-0000-0000-0000-000000000003', 1, 1, 0, '', '', 'published');
+			INSERT INTO artifacts (version_id, artifact_type, platform, download_url) VALUES
+			('00000000-0000-0000-0000-000000000001', 'server', 'windows', 'http://127.0.0.1:{port}/ok'),
+			('00000000-0000-0000-0000-000000000001', 'web', 'linux', 'http://127.0.0.1:{port}/ok'),
+			('00000000-0000-0000-0000-000000000003', 'server', 'windows', 'http://127.0.0.1:{port}/gone'),
+			('00000000-0000-0000-0000-000000000003', 'mobile', 'android', 'http://127.0.0.1:{port}/ok')"
+		))
+		.await
+		.unwrap();
+
+		let version: Uuid = "00000000-0000-0000-0000-000000000003".parse().unwrap();
+		let before = ReleaseCompleteness::for_version(&mut conn, version)
+			.await
+			.unwrap();
+		assert_eq!(before.previous.as_deref(), Some("1.0.0"));
+		assert_eq!(
+			before.missing,
+			vec![ArtifactKind {
+				artifact_type: "web".into(),
+				platform: "linux".into(),
+			}]
+		);
+		assert_eq!(before.broken, 0);
+		assert_eq!(before.unchecked, 2);
+		assert!(!before.is_complete());
+
+		let client = reqwest::Client::new();
+		ArtifactLinkCheck::check_all(&mut conn, &client, 4)
+			.await
+			.unwrap();
+		let after = ReleaseCompleteness::for_version(&mut conn, version)
+			.await
+			.unwrap();
+		assert_eq!(after.broken, 1);
+		assert_eq!(after.unchecked, 0);
+
+		// The first release has nothing to compare with, and its links work
+		let first = ReleaseCompleteness::for_version(
+			&mut conn,
+			"00000000-0000-0000-0000-000000000001".parse().unwrap(),
+		)
+		.await
+		.unwrap();
+		assert_eq!(first.previous, None);
+		assert!(first.is_complete());
+	})
+	.await
+}
@@ -0,0 +1,195 @@
+use std::{
+	sync::{
+		Arc,
+		atomic::{AtomicUsize, Ordering},
+	},
+	time::Duration,
+};
+
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use database::{
+	probes::ServerProbeResult,
+	servers::Server,
+	statuses::{PingOptions, Status},
+};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+
+/// A stand-in server that hangs up on the first `failures` connections, then answers pings.
+async fn flaky_stand_in(failures: usize) -> (u16, Arc<AtomicUsize>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let connections = Arc::new(AtomicUsize::new(0));
+	tokio::spawn({
+		let connections = connections.clone();
+		async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = [0; 1024];
+				let _ = stream.read(&mut request).await;
+				if connections.fetch_add(1, Ordering::SeqCst) < failures {
+					continue;
+				}
+
+				let _ = stream
+					.write_all(
+						b"HTTP/1.1 200 OK\r\nX-Version: 2.3.4\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+					)
+					.await;
+			}
+		}
+	});
+	(port, connections)
+}
+
+fn quick_options(retries: u32) -> PingOptions {
+	PingOptions {
+		concurrency: 2,
+		timeout: Duration::from_secs(2),
+		retries,
+		backoff: Duration::from_millis(10),
+		spread: Duration::from_millis(50),
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ping_retries_transient_failures() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let (port, connections) = flaky_stand_in(2).await;
+		conn.batch_execute(&format!(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Flaky', 'http://127.0.0.1:{port}/', 'facility')"
+		))
+		.await
+		.unwrap();
+		let server = Server::get_by_id(
+			&mut conn,
+			"11111111-1111-1111-1111-111111111111".parse().unwrap(),
+		)
+		.await
+		.unwrap();
+		let client = reqwest::Client::new();
+
+		assert!(
+			Status::ping_server(&client, &server, &quick_options(1))
+				.await
+				.is_none(),
+			"gave up after a single retry"
+		);
+		assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+		let status = Status::ping_server(&client, &server, &quick_options(1))
+			.await
+			.expect("third attempt succeeds");
+		assert_eq!(status.version.unwrap().0.to_string(), "2.3.4");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ping_servers_reaches_every_server() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let (up, _) = flaky_stand_in(1).await;
+		let (down, _) = flaky_stand_in(usize::MAX).await;
+		conn.batch_execute(&format!(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Up', 'http://127.0.0.1:{up}/', 'facility'),
+			('22222222-2222-2222-2222-222222222222', 'Up too', 'http://localhost:{up}/', 'facility'),
+			('33333333-3333-3333-3333-333333333333', 'Down', 'http://127.0.0.1:{down}/', 'central')"
+		))
+		.await
+		.unwrap();
+
+		let mut pinged: Vec<_> = Status::ping_servers_and_save(&mut conn, &quick_options(2))
+			.await
+			.unwrap()
+			.into_iter()
+			.map(|(_, server)| server.name.unwrap_or_default())
+			.collect();
+		pinged.sort();
+		assert_eq!(pinged, vec!["Up", "Up too"]);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn probes_of_down_servers_are_kept() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let (down, _) = flaky_stand_in(usize::MAX).await;
+		let closed = {
+			let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+			listener.local_addr().unwrap().port()
+		};
+		conn.batch_execute(&format!(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('33333333-3333-3333-3333-333333333333', 'Down', 'http://127.0.0.1:{down}/', 'central');
+			INSERT INTO statuses (server_id, version, created_at) VALUES
+			('33333333-3333-3333-3333-333333333333', '2.3.4', NOW() - INTERVAL '1 hour');
+			INSERT INTO server_probes (server_id, name, probe_type, port) VALUES
+			('33333333-3333-3333-3333-333333333333', 'postgres', 'tcp', {closed})"
+		))
+		.await
+		.unwrap();
+
+		let pinged = Status::ping_servers_and_save(&mut conn, &quick_options(0))
+			.await
+			.unwrap();
+		assert!(pinged.is_empty());
+
+		let server = "33333333-3333-3333-3333-333333333333".parse().unwrap();
+		let status = Status::latest_for_server(&mut conn, server)
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(status.version.as_ref().unwrap().0.to_string(), "2.3.4");
+		assert!(status.extra("probes").is_none());
+
+		let probes = ServerProbeResult::latest_for_servers(&mut conn, &[server])
+			.await
+			.unwrap();
+		assert_eq!(probes[&server].len(), 1);
+		assert!(!probes[&server][0].success);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ping_timeout_can_be_set_per_server() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		// accepts connections but never answers
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut held = Vec::new();
+			while let Ok((stream, _)) = listener.accept().await {
+				held.push(stream);
+			}
+		});
+
+		conn.batch_execute(&format!(
+			"INSERT INTO servers (id, name, host, kind, ping_timeout) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Slow', 'http://127.0.0.1:{port}/', 'facility', '100 milliseconds')"
+		))
+		.await
+		.unwrap();
+		let server = Server::get_by_id(
+			&mut conn,
+			"11111111-1111-1111-1111-111111111111".parse().unwrap(),
+		)
+		.await
+		.unwrap();
+
+		let start = std::time::Instant::now();
+		assert!(
+			Status::ping_server(&reqwest::Client::new(), &server, &quick_options(0))
+				.await
+				.is_none()
+		);
+		assert!(
+			start.elapsed() < Duration::from_secs(1),
+			"gave up after the server's own timeout, not the global one"
+		);
+	})
+	.await
+}
@@ -0,0 +1,115 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use commons_types::server::probe::ProbeType;
+use database::{
+	pg_duration::PgDuration,
+	probes::{NewServerProbe, ServerProbe},
+};
+use jiff::SignedDuration;
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+
+/// A tiny HTTP server that answers every request with the given status line and body.
+async fn stand_in(status: &'static str, body: &'static str) -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let _ = stream.read(&mut request).await;
+			let response = format!(
+				"HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+				body.len()
+			);
+			let _ = stream.write_all(response.as_bytes()).await;
+		}
+	});
+	port
+}
+
+/// A port that nothing is listening on.
+async fn closed_port() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	listener.local_addr().unwrap().port()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn probes_run_when_due_and_record_results() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let port = stand_in("200 OK", "all systems nominal").await;
+		let closed = closed_port().await;
+
+		conn.batch_execute(&format!(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Facility', 'http://127.0.0.1:{port}/', 'facility'),
+			('22222222-2222-2222-2222-222222222222', 'Central', 'http://localhost:{port}/', 'central');
+			INSERT INTO server_probes (id, server_id, server_kind, name, probe_type, path, expected_status, expected_body, port) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '11111111-1111-1111-1111-111111111111', NULL, 'health', 'http', '/health', 200, 'nominal', NULL),
+			('bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', NULL, 'facility', 'postgres', 'tcp', NULL, NULL, NULL, {closed}),
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', NULL, 'central', 'wrong body', 'http', '/', NULL, 'degraded', NULL),
+			('dddddddd-dddd-dddd-dddd-dddddddddddd', NULL, 'central', 'dns', 'dns', NULL, NULL, NULL, NULL)"
+		))
+		.await
+		.unwrap();
+
+		let client = reqwest::Client::new();
+		let mut results = ServerProbe::run_due(&mut conn, &client, 4).await.unwrap();
+		results.sort_by_key(|result| result.probe_id);
+		let outcomes: Vec<_> = results
+			.iter()
+			.map(|result| (result.probe_id.to_string(), result.success))
+			.collect();
+		assert_eq!(
+			outcomes,
+			vec![
+				("aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa".to_string(), true),
+				("bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb".to_string(), false),
+				("cccccccc-cccc-cccc-cccc-cccccccccccc".to_string(), false),
+				("dddddddd-dddd-dddd-dddd-dddddddddddd".to_string(), true),
+			]
+		);
+		assert!(results[1].error.is_some());
+		assert_eq!(
+			results[2].error.as_deref(),
+			Some("response body did not contain the expected text")
+		);
+
+		// Nothing is due again until the interval has passed
+		let again = ServerProbe::run_due(&mut conn, &client, 4).await.unwrap();
+		assert!(again.is_empty(), "probes re-ran too early: {again:?}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn probe_definitions_are_validated() {
+	let probe = NewServerProbe {
+		server_id: Some(uuid::Uuid::new_v4()),
+		server_kind: None,
+		name: "postgres".into(),
+		probe_type: ProbeType::Tcp,
+		check_interval: PgDuration(SignedDuration::from_mins(5)),
+		timeout: PgDuration(SignedDuration::from_secs(10)),
+		path: None,
+		expected_status: None,
+		expected_body: None,
+		port: None,
+	};
+	assert!(probe.validate().is_err(), "tcp probe without a port");
+
+	let probe = NewServerProbe {
+		port: Some(5432),
+		..probe
+	};
+	assert!(probe.validate().is_ok());
+
+	let probe = NewServerProbe {
+		check_interval: PgDuration(SignedDuration::from_secs(10)),
+		..probe
+	};
+	assert!(
+		probe.validate().is_err(),
+		"interval shorter than a ping round"
+	);
+}
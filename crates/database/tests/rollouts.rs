@@ -0,0 +1,131 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use database::rollouts::{NewVersionRollout, VersionRollout};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rollout_percentage_grows_monotonically() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, status, changelog) VALUES
+			('11111111-1111-1111-1111-111111111111', 2, 0, 0, 'published', '');
+			INSERT INTO devices (id, role)
+				SELECT gen_random_uuid(), 'server' FROM generate_series(1, 200);
+			INSERT INTO servers (name, host, kind, device_id)
+				SELECT 'Server ' || n, 'https://server-' || n || '.example.com', 'central', id
+				FROM (SELECT id, row_number() OVER () AS n FROM devices) AS d",
+		)
+		.await
+		.unwrap();
+
+		let version_id = "11111111-1111-1111-1111-111111111111".parse().unwrap();
+		let mut previous = 0;
+		for percentage in [0, 10, 50, 90, 100] {
+			let rollout = VersionRollout::upsert(
+				&mut conn,
+				NewVersionRollout {
+					version_id,
+					allowlist: Vec::new(),
+					percentage,
+					paused: false,
+				},
+			)
+			.await
+			.unwrap();
+			let (eligible, total) = rollout.eligible_servers(&mut conn).await.unwrap();
+			assert_eq!(total, 200);
+			assert!(
+				eligible >= previous,
+				"{eligible} < {previous} at {percentage}%"
+			);
+			previous = eligible;
+
+			// stable hashing should spread servers out roughly evenly
+			let expected = 2 * percentage as u64;
+			assert!(
+				eligible.abs_diff(expected) <= 30,
+				"{eligible} servers at {percentage}%"
+			);
+		}
+		assert_eq!(previous, 200);
+
+		let paused = VersionRollout::upsert(
+			&mut conn,
+			NewVersionRollout {
+				version_id,
+				allowlist: Vec::new(),
+				percentage: 100,
+				paused: true,
+			},
+		)
+		.await
+		.unwrap();
+		assert_eq!(paused.eligible_servers(&mut conn).await.unwrap(), (0, 200));
+
+		assert!(
+			VersionRollout::upsert(
+				&mut conn,
+				NewVersionRollout {
+					version_id,
+					allowlist: Vec::new(),
+					percentage: 150,
+					paused: false,
+				},
+			)
+			.await
+			.is_err()
+		);
+
+		VersionRollout::remove(&mut conn, version_id).await.unwrap();
+		assert!(
+			VersionRollout::get_for_version(&mut conn, version_id)
+				.await
+				.unwrap()
+				.is_none()
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rollout_allowlist_must_name_servers() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, status, changelog) VALUES
+			('11111111-1111-1111-1111-111111111111', 2, 0, 0, 'published', '');
+			INSERT INTO servers (id, name, host, kind) VALUES
+			('22222222-2222-2222-2222-222222222222', 'Canary', 'https://canary.example.com', 'central')",
+		)
+		.await
+		.unwrap();
+
+		let rollout = |allowlist: &[&str]| NewVersionRollout {
+			version_id: "11111111-1111-1111-1111-111111111111".parse().unwrap(),
+			allowlist: allowlist.iter().map(|id| id.parse().unwrap()).collect(),
+			percentage: 0,
+			paused: false,
+		};
+
+		let err = VersionRollout::upsert(
+			&mut conn,
+			rollout(&[
+				"22222222-2222-2222-2222-222222222222",
+				"33333333-3333-3333-3333-333333333333",
+			]),
+		)
+		.await
+		.unwrap_err();
+		assert!(
+			err.to_string()
+				.contains("33333333-3333-3333-3333-333333333333"),
+			"{err}"
+		);
+
+		let saved = VersionRollout::upsert(
+			&mut conn,
+			rollout(&["22222222-2222-2222-2222-222222222222"]),
+		)
+		.await
+		.unwrap();
+		assert_eq!(saved.allowlist.len(), 1);
+	})
+	.await
+}
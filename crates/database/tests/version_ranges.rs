@@ -0,0 +1,162 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use commons_tests::diesel_async::{AsyncPgConnection, RunQueryDsl, SimpleAsyncConnection};
+use database::{version_ranges, versions::Version};
+use diesel::prelude::*;
+use node_semver::{Range, Version as Semver};
+use proptest::{prelude::*, test_runner::TestRunner};
+
+const PRERELEASES: [&str; 3] = ["alpha", "rc.1", "rc.2"];
+
+/// Every release of 0..=2.0..=2.0..=2, and a few pre-releases of the first two patches.
+fn all_versions() -> Vec<Semver> {
+	let mut versions = Vec::new();
+	for major in 0..=2 {
+		for minor in 0..=2 {
+			for patch in 0..=2 {
+				versions.push(Semver::new(major, minor, patch));
+				if patch < 2 {
+					for prerelease in PRERELEASES {
+						versions.push(
+							Semver::parse(format!("{major}.{minor}.{patch}-{prerelease}")).unwrap(),
+						);
+					}
+				}
+			}
+		}
+	}
+	versions
+}
+
+fn partial_version() -> impl Strategy<Value = String> {
+	prop_oneof![
+		(0..=3u8, 0..=3u8, 0..=3u8).prop_map(|(a, b, c)| format!("{a}.{b}.{c}")),
+		(
+			0..=3u8,
+			0..=3u8,
+			0..=3u8,
+			prop::sample::select(PRERELEASES.to_vec())
+		)
+			.prop_map(|(a, b, c, pre)| format!("{a}.{b}.{c}-{pre}")),
+		(0..=3u8, 0..=3u8).prop_map(|(a, b)| format!("{a}.{b}")),
+		(0..=3u8, 0..=3u8).prop_map(|(a, b)| format!("{a}.{b}.x")),
+		(0..=3u8).prop_map(|a| format!("{a}")),
+		(0..=3u8).prop_map(|a| format!("{a}.x")),
+	]
+}
+
+fn comparator() -> impl Strategy<Value = String> {
+	prop_oneof![
+		(
+			prop::sample::select(vec!["", "=", ">", ">=", "<", "<=", "^", "~"]),
+			partial_version(),
+		)
+			.prop_map(|(op, version)| format!("{op}{version}")),
+		// node-semver panics on operators applied to a bare wildcard
+		Just("*".to_string()),
+	]
+}
+
+fn comparator_set() -> impl Strategy<Value = String> {
+	prop_oneof![
+		prop::collection::vec(comparator(), 1..=2).prop_map(|set| set.join(" ")),
+		(partial_version(), partial_version()).prop_map(|(a, b)| format!("{a} - {b}")),
+	]
+}
+
+fn range() -> impl Strategy<Value = String> {
+	prop::collection::vec(comparator_set(), 1..=2)
+		.prop_map(|sets| sets.join(" || "))
+		.prop_filter("must be a valid range", |range| Range::parse(range).is_ok())
+}
+
+async fn matching_in_db(db: &mut AsyncPgConnection, range: &Range) -> BTreeSet<String> {
+	use database::schema::versions::dsl::*;
+
+	versions
+		.filter(version_ranges::matching(range))
+		.select(Version::as_select())
+		.load::<Version>(db)
+		.await
+		.unwrap()
+		.into_iter()
+		.map(|v| v.as_semver().to_string())
+		.collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sql_matching_agrees_with_satisfies() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let all = all_versions();
+		for version in &all {
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (major, minor, patch, prerelease, status, changelog)
+				VALUES ({}, {}, {}, '{}', 'published', '')",
+				version.major,
+				version.minor,
+				version.patch,
+				commons_types::version::format_identifiers(&version.pre_release),
+			))
+			.await
+			.unwrap();
+		}
+
+		let conn = RefCell::new(conn);
+		let handle = tokio::runtime::Handle::current();
+		TestRunner::new(ProptestConfig {
+			failure_persistence: None,
+			..ProptestConfig::default()
+		})
+		.run(&range(), |pattern| {
+			let range = Range::parse(&pattern).unwrap();
+			let expected: BTreeSet<String> = all
+				.iter()
+				.filter(|v| range.satisfies(v))
+				.map(|v| v.to_string())
+				.collect();
+			let actual = tokio::task::block_in_place(|| {
+				handle.block_on(matching_in_db(&mut conn.borrow_mut(), &range))
+			});
+			prop_assert_eq!(actual, expected, "range {} ({})", pattern, range);
+			Ok(())
+		})
+		.unwrap();
+	})
+	.await
+}
+
+proptest! {
+	#[test]
+	fn hull_covers_every_satisfying_version(pattern in range()) {
+		let range = Range::parse(&pattern).unwrap();
+		let (floor, ceiling) = version_ranges::hull(&range);
+		for version in all_versions().iter().filter(|v| range.satisfies(v)) {
+			if let Some(floor) = &floor {
+				prop_assert!(floor <= version, "{} below {} for {}", version, floor, pattern);
+			}
+			if let Some(ceiling) = &ceiling {
+				prop_assert!(version <= ceiling, "{} above {} for {}", version, ceiling, pattern);
+			}
+		}
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn latest_matching_crosses_minors() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES
+			(1, 2, 5, 'published', ''),
+			(1, 3, 0, 'published', ''),
+			(2, 0, 0, 'published', '')",
+		)
+		.await
+		.unwrap();
+
+		let version = Version::get_latest_matching(&mut conn, Range::parse("^1.2.5").unwrap())
+			.await
+			.unwrap();
+		assert_eq!(version.as_semver().to_string(), "1.3.0");
+	})
+	.await
+}
@@ -0,0 +1,53 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use database::versions::Version;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sort_key_follows_semver_precedence() {
+	commons_tests::db::TestDb::run(async |mut conn, _| {
+		let mut expected: Vec<node_semver::Version> = [
+			"1.0.0-alpha",
+			"1.0.0-alpha.1",
+			"1.0.0-alpha.beta",
+			"1.0.0-alpha-b",
+			"1.0.0-beta",
+			"1.0.0-beta.2",
+			"1.0.0-beta.11",
+			"1.0.0-rc.1",
+			"1.0.0-0",
+			"1.0.0-9.a",
+			"1.0.0",
+			"1.0.1-rc.1",
+			"1.0.10",
+			"1.2.0",
+			"10.0.0-rc.1",
+		]
+		.into_iter()
+		.map(|v| node_semver::Version::parse(v).unwrap())
+		.collect();
+
+		for version in &expected {
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (major, minor, patch, prerelease, status, changelog)
+				VALUES ({}, {}, {}, '{}', 'published', '')",
+				version.major,
+				version.minor,
+				version.patch,
+				commons_types::version::format_identifiers(&version.pre_release),
+			))
+			.await
+			.unwrap();
+		}
+
+		expected.sort();
+		expected.reverse();
+
+		let sorted: Vec<_> = Version::get_all_with_prereleases(&mut conn)
+			.await
+			.unwrap()
+			.iter()
+			.map(|v| v.as_semver())
+			.collect();
+		assert_eq!(sorted, expected);
+	})
+	.await
+}
@@ -0,0 +1,74 @@
+//! Check that the download URLs of published versions' artifacts still work.
+//!
+//! Meant to be run on a schedule. Only the latest result for each artifact is kept, and broken
+//! links are shown on the version pages of the private server.
+
+use std::time::Duration;
+
+use clap::Parser;
+use commons_errors::{AppError, Result};
+use database::{Db, link_checks::ArtifactLinkCheck};
+use lloggs::{LoggingArgs, PreArgs};
+use tracing::{error, info, warn};
+
+async fn check(pool: Db, args: &Args) -> Result<()> {
+	let mut db = pool.get().await?;
+	let client = reqwest::Client::builder()
+		.timeout(Duration::from_secs(args.timeout))
+		.build()
+		.map_err(|e| AppError::Custom(e.to_string()))?;
+
+	let checks = ArtifactLinkCheck::check_all(&mut db, &client, args.concurrency).await?;
+	let broken: Vec<_> = checks.iter().filter(|check| !check.healthy).collect();
+	for check in &broken {
+		warn!(
+			artifact = %check.artifact_id,
+			status = check.status_code,
+			error = check.error,
+			"Broken artifact link"
+		);
+	}
+
+	info!(
+		checked = checks.len(),
+		broken = broken.len(),
+		"Checked artifact links"
+	);
+	Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct Args {
+	#[command(flatten)]
+	logging: LoggingArgs,
+
+	/// How many links to check at the same time.
+	#[arg(long, env = "CHECK_LINKS_CONCURRENCY", default_value = "8")]
+	concurrency: usize,
+
+	/// Timeout for each request, in seconds.
+	#[arg(long, env = "CHECK_LINKS_TIMEOUT", default_value = "30")]
+	timeout: u64,
+}
+
+#[tokio::main]
+async fn main() -> miette::Result<()> {
+	let mut _guard = PreArgs::parse().setup()?;
+	let args = Args::parse();
+	if _guard.is_none() {
+		_guard = Some(args.logging.setup(|v| match v {
+			0 => "info",
+			1 => "debug",
+			_ => "trace",
+		})?);
+	}
+
+	let pool = database::init();
+
+	check(pool, &args).await.map_err(|err| {
+		error!("Failed to check artifact links: {}", err);
+		miette::miette!("{}", err)
+	})?;
+
+	Ok(())
+}
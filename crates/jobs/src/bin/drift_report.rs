@@ -0,0 +1,132 @@
+//! Weekly summary of how far behind the fleet is, for account managers to follow up on.
+//!
+//! Meant to be run on a weekly schedule. The summary is logged, and optionally posted to a chat
+//! webhook and written out as CSV.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use clap::Parser;
+use commons_errors::{AppError, Result};
+use commons_types::server::{kind::ServerKind, rank::ServerRank};
+use database::{Db, drift::ServerDrift};
+use lloggs::{LoggingArgs, PreArgs};
+use serde_json::json;
+use tracing::{error, info};
+
+fn summarise(drift: &[ServerDrift], rank: Option<ServerRank>, threshold: u64) -> String {
+	let mut summary = format!(
+		"Version drift report for {} servers ({} reporting)\n",
+		rank.map_or("all".into(), |rank| rank.to_string()),
+		drift.len()
+	);
+
+	let groups = drift.iter().fold(BTreeMap::new(), |mut groups, entry| {
+		*groups.entry(entry.minors_behind).or_insert(0) += 1;
+		groups
+	});
+	for (behind, count) in groups {
+		match behind {
+			0 => summary.push_str(&format!("- on the latest minor: {count}\n")),
+			1 => summary.push_str(&format!("- 1 minor behind: {count}\n")),
+			n => summary.push_str(&format!("- {n} minors behind: {count}\n")),
+		}
+	}
+
+	let laggards: Vec<_> = drift
+		.iter()
+		.filter(|entry| entry.minors_behind >= threshold)
+		.collect();
+	if !laggards.is_empty() {
+		summary.push_str(&format!("\nServers {threshold} or more minors behind:\n"));
+		for entry in laggards {
+			summary.push_str(&format!(
+				"- {} ({}) on {}, {} minors behind, for {} days\n",
+				entry
+					.server
+					.name
+					.clone()
+					.unwrap_or_else(|| entry.server.host.0.to_string()),
+				entry.server.kind,
+				entry.version,
+				entry.minors_behind,
+				entry.time_on_minor().as_hours() / 24,
+			));
+		}
+	}
+
+	summary
+}
+
+async fn report(pool: Db, args: &Args) -> Result<()> {
+	let mut db = pool.get().await?;
+	let drift = ServerDrift::for_fleet(&mut db, args.rank, args.kind).await?;
+	let summary = summarise(&drift, args.rank, args.threshold);
+	info!("{summary}");
+
+	if let Some(path) = &args.csv {
+		std::fs::write(path, ServerDrift::to_csv(&drift))
+			.map_err(|e| AppError::Custom(e.to_string()))?;
+		info!(path=%path.display(), "Wrote drift report CSV");
+	}
+
+	if let Some(url) = &args.webhook {
+		reqwest::Client::new()
+			.post(url)
+			.json(&json!({ "text": summary }))
+			.send()
+			.await
+			.and_then(|res| res.error_for_status())
+			.map_err(|e| AppError::Custom(e.to_string()))?;
+		info!("Posted drift report to webhook");
+	}
+
+	Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct Args {
+	#[command(flatten)]
+	logging: LoggingArgs,
+
+	/// Only report on servers of this rank, or on servers of every rank if not given.
+	#[arg(long, env = "DRIFT_REPORT_RANK")]
+	rank: Option<ServerRank>,
+
+	/// Only report on servers of this kind.
+	#[arg(long, env = "DRIFT_REPORT_KIND")]
+	kind: Option<ServerKind>,
+
+	/// List servers individually when they're at least this many minors behind.
+	#[arg(long, env = "DRIFT_REPORT_THRESHOLD", default_value = "2")]
+	threshold: u64,
+
+	/// Post the summary to this webhook, as a JSON object with a `text` field.
+	#[arg(long, env = "DRIFT_REPORT_WEBHOOK")]
+	webhook: Option<String>,
+
+	/// Also write the full report as CSV to this path.
+	#[arg(long)]
+	csv: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> miette::Result<()> {
+	let mut _guard = PreArgs::parse().setup()?;
+	let args = Args::parse();
+	if _guard.is_none() {
+		_guard = Some(args.logging.setup(|v| match v {
+			0 => "info",
+			1 => "debug",
+			_ => "trace",
+		})?);
+	}
+
+	let pool = database::init();
+
+	report(pool, &args).await.map_err(|err| {
+		error!("Failed to produce drift report: {}", err);
+		miette::miette!("{}", err)
+	})?;
+
+	Ok(())
+}
@@ -1,28 +1,59 @@
 use std::time::Duration;
 
 use clap::Parser;
-use database::statuses::Status;
+use database::{
+	probes::ServerProbeResult,
+	statuses::{PingOptions, Status},
+};
+use jiff::SignedDuration;
 use lloggs::{LoggingArgs, PreArgs};
 use miette::IntoDiagnostic;
 use tokio::{
 	task::{self, JoinHandle},
-	time::sleep,
+	time::{Instant, MissedTickBehavior, interval_at},
 };
-use tracing::error;
+use tracing::{error, info, warn};
+
+const PROBE_RESULTS_RETENTION: SignedDuration = SignedDuration::from_hours(24 * 30);
 
-pub fn spawn() -> JoinHandle<()> {
+const ROUND_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn spawn(options: PingOptions) -> JoinHandle<()> {
 	let pool = database::init();
 	task::spawn(async move {
+		let mut rounds = interval_at(Instant::now() + ROUND_INTERVAL, ROUND_INTERVAL);
+		// if a round runs long, skip the ticks it overlapped instead of firing them back to back
+		rounds.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
 		loop {
-			sleep(Duration::from_secs(60)).await;
+			rounds.tick().await;
 			let Ok(mut db) = pool.get().await else {
 				error!("Failed to get database connection");
 				continue;
 			};
 
-			if let Err(err) = Status::ping_servers_and_save(&mut db).await {
-				error!("Failed to ping servers: {err}");
-				continue;
+			let start = Instant::now();
+			let pinged = match Status::ping_servers_and_save(&mut db, &options).await {
+				Ok(up) => up.len(),
+				Err(err) => {
+					error!("Failed to ping servers: {err}");
+					continue;
+				}
+			};
+
+			let took = start.elapsed();
+			if took > ROUND_INTERVAL {
+				warn!(
+					?took,
+					up = pinged,
+					"ping round overran, skipping the next round"
+				);
+			} else {
+				info!(?took, up = pinged, "ping round complete");
+			}
+
+			if let Err(err) = ServerProbeResult::prune(&mut db, PROBE_RESULTS_RETENTION).await {
+				error!("Failed to prune old probe results: {err}");
 			}
 		}
 	})
@@ -32,6 +63,26 @@ pub fn spawn() -> JoinHandle<()> {
 struct Args {
 	#[command(flatten)]
 	logging: LoggingArgs,
+
+	/// How many servers to ping at the same time.
+	#[arg(long, env = "PING_CONCURRENCY", default_value = "16")]
+	concurrency: usize,
+
+	/// Timeout for each ping attempt, in seconds.
+	#[arg(long, env = "PING_TIMEOUT", default_value = "10")]
+	timeout: u64,
+
+	/// How many times to retry a failed ping.
+	#[arg(long, env = "PING_RETRIES", default_value = "2")]
+	retries: u32,
+
+	/// Delay before the first retry in milliseconds, doubled for each further retry.
+	#[arg(long, env = "PING_BACKOFF", default_value = "1000")]
+	backoff: u64,
+
+	/// Window over which pings are spread within each round, in seconds.
+	#[arg(long, env = "PING_SPREAD", default_value = "20")]
+	spread: u64,
 }
 
 #[tokio::main]
@@ -46,6 +97,14 @@ async fn main() -> miette::Result<()> {
 		})?);
 	}
 
-	spawn().await.into_diagnostic()?;
+	spawn(PingOptions {
+		concurrency: args.concurrency,
+		timeout: Duration::from_secs(args.timeout),
+		retries: args.retries,
+		backoff: Duration::from_millis(args.backoff),
+		spread: Duration::from_secs(args.spread),
+	})
+	.await
+	.into_diagnostic()?;
 	Ok(())
 }
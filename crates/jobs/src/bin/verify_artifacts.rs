@@ -0,0 +1,75 @@
+//! Download artifacts which haven't been verified yet, to check or fill in their checksum and size.
+//!
+//! Meant to be run on a schedule. Each artifact is only downloaded until it's verified, so
+//! mismatches are flagged once and then left for a releaser to look at.
+
+use std::time::Duration;
+
+use clap::Parser;
+use commons_errors::{AppError, Result};
+use database::{Db, artifacts::Artifact};
+use lloggs::{LoggingArgs, PreArgs};
+use tracing::{error, info, warn};
+
+async fn verify(pool: Db, args: &Args) -> Result<()> {
+	let mut db = pool.get().await?;
+	let client = reqwest::Client::builder()
+		.timeout(Duration::from_secs(args.timeout))
+		.build()
+		.map_err(|e| AppError::Custom(e.to_string()))?;
+
+	let checked = Artifact::verify_unverified(&mut db, &client).await?;
+	let (verified, failed): (Vec<_>, Vec<_>) = checked
+		.iter()
+		.partition(|artifact| artifact.verified_at.is_some());
+	let mismatched = verified
+		.iter()
+		.filter(|artifact| artifact.verification_error.is_some())
+		.count();
+
+	if !failed.is_empty() {
+		warn!(
+			count = failed.len(),
+			"Some artifacts could not be downloaded, they'll be retried next time"
+		);
+	}
+	info!(
+		verified = verified.len(),
+		mismatched,
+		failed = failed.len(),
+		"Verified artifacts"
+	);
+	Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct Args {
+	#[command(flatten)]
+	logging: LoggingArgs,
+
+	/// Timeout for downloading each artifact, in seconds.
+	#[arg(long, env = "VERIFY_ARTIFACTS_TIMEOUT", default_value = "3600")]
+	timeout: u64,
+}
+
+#[tokio::main]
+async fn main() -> miette::Result<()> {
+	let mut _guard = PreArgs::parse().setup()?;
+	let args = Args::parse();
+	if _guard.is_none() {
+		_guard = Some(args.logging.setup(|v| match v {
+			0 => "info",
+			1 => "debug",
+			_ => "trace",
+		})?);
+	}
+
+	let pool = database::init();
+
+	verify(pool, &args).await.map_err(|err| {
+		error!("Failed to verify artifacts: {}", err);
+		miette::miette!("{}", err)
+	})?;
+
+	Ok(())
+}
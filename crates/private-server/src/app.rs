@@ -83,6 +83,11 @@ pub fn App() -> impl IntoView {
 							<ParentRoute path=path!("servers") view=servers::Page>
 								<Route path=path!("") view=servers::list::Centrals />
 								<Route path=path!("facilities") view=servers::list::Facilities />
+								<Route path=path!("mismatches") view=servers::Mismatches />
+								<Route path=path!("drift") view=servers::Drift />
+								<Route path=path!("yanked") view=servers::Yanked />
+								<Route path=path!("unsupported") view=servers::Unsupported />
+								<Route path=path!("plan") view=servers::Planner />
 								<Route path=path!(":id/edit") view=servers::Edit />
 								<Route path=path!(":id") view=servers::Detail />
 							</ParentRoute>
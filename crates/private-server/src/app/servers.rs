@@ -11,12 +11,23 @@ use crate::{
 };
 
 mod detail;
+mod drift;
 mod edit;
 mod geo;
 pub mod list;
+mod mismatches;
+mod planner;
+mod probes;
+mod unsupported;
+mod yanked;
 
 pub use detail::Detail;
+pub use drift::Drift;
 pub use edit::Edit;
+pub use mismatches::Mismatches;
+pub use planner::Planner;
+pub use unsupported::Unsupported;
+pub use yanked::Yanked;
 
 #[component]
 fn ServerBreadcrumb() -> impl IntoView {
@@ -56,6 +67,11 @@ pub fn Page() -> impl IntoView {
 			<SubTabs>
 				<A href="" exact=true>Central Servers</A>
 				<A href="facilities">Facility Servers</A>
+				<A href="mismatches">Version Mismatches</A>
+				<A href="drift">Version Drift</A>
+				<A href="yanked">Yanked Versions</A>
+				<A href="unsupported">Unsupported Stacks</A>
+				<A href="plan">Upgrade Planner</A>
 
 				<EndTabs slot>
 					<ServerBreadcrumb />
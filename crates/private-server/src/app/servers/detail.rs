@@ -1,15 +1,17 @@
 use std::sync::Arc;
 
-use commons_types::{Uuid, geo::GeoPoint, server::kind::ServerKind};
+use commons_types::{
+	Uuid, geo::GeoPoint, requirements::Platform, server::kind::ServerKind, version::SupportStatus,
+};
 use leptos::{prelude::*, serde_json};
 use leptos_meta::Stylesheet;
 use leptos_router::{components::A, hooks::use_params_map};
 
 use crate::{
-	app::servers::geo::CloudRegion,
+	app::servers::{geo::CloudRegion, mismatches::MismatchTable, probes::ProbesSection},
 	components::{
 		DeviceShorty, LoadingBar, ServerKindBadge, ServerRankBadge, ServerShorty, StatusDot,
-		StatusLegend, TimeAgo, VersionIndicator, VersionLegend,
+		StatusLegend, SupportStatusBadge, TimeAgo, VersionIndicator, VersionLegend,
 	},
 	fns::servers::{ServerDetailData, ServerInfo, ServerLastStatusData, get_detail},
 };
@@ -73,7 +75,14 @@ fn ServerDetailView(data: ServerDetailData, is_admin: Resource<bool>) -> impl In
 			<PageHeader data=data.clone() is_admin />
 			<UrlSection data=data.clone() />
 			<InfoSection status=data.last_status.clone() server=data.server.clone() />
+			{(!data.version_mismatches.is_empty()).then(|| view! {
+				<h2 class="is-size-4 mb-4">"Version mismatches"</h2>
+				<div class="box">
+					<MismatchTable mismatches=data.version_mismatches.clone() show_central=false />
+				</div>
+			})}
 			{(!data.child_servers.is_empty()).then(|| view! { <ChildServersSection data=data.clone() /> })}
+			<ProbesSection server_id=data.server.id kind=data.server.kind is_admin />
 			<aside class="legend">
 				<VersionLegend />
 				<StatusLegend />
@@ -210,6 +219,17 @@ fn InfoSection(
 #[component]
 fn StatusInfo(status: Arc<ServerLastStatusData>) -> impl IntoView {
 	let min_chrome_version = status.min_chrome_version;
+	let unsupported_tag = |platform: Platform| {
+		status
+			.unsupported
+			.iter()
+			.find(|violation| violation.platform() == platform)
+			.map(|violation| {
+				view! {
+					<span class="tag is-danger ml-2" title=violation.to_string()>"unsupported"</span>
+				}
+			})
+	};
 	view! {
 		<div class:info-item>
 			<span class="info-label">"Last seen"</span>
@@ -246,12 +266,33 @@ fn StatusInfo(status: Arc<ServerLastStatusData>) -> impl IntoView {
 				</div>
 			}
 		})}
+		{status.support.as_ref().filter(|s| s.status != SupportStatus::Unknown).map(|support| {
+			let until = match support.status {
+				SupportStatus::Active => support.active_support_until,
+				_ => support.security_support_until,
+			};
+			view! {
+				<div class:info-item>
+					<span class="info-label">"Support"</span>
+					<span class:info-value>
+						<SupportStatusBadge status={support.status} />
+						{until.map(|date| match support.status {
+							SupportStatus::EndOfLife => format!(" after {date}"),
+							_ => format!(" until {date}"),
+						})}
+					</span>
+				</div>
+			}
+		})}
 		{status.postgres.as_ref().map(|pg| {
 			let pg = pg.clone();
 			view! {
 				<div class:info-item class:version>
 					<span class="info-label">"PostgreSQL"</span>
-					<span class:info-value class:monospace>{pg}</span>
+					<span class:info-value class:monospace>
+						{pg}
+						{unsupported_tag(Platform::Postgres)}
+					</span>
 				</div>
 			}
 		})}
@@ -260,7 +301,10 @@ fn StatusInfo(status: Arc<ServerLastStatusData>) -> impl IntoView {
 			view! {
 				<div class:info-item class:version>
 					<span class="info-label">"Node.js"</span>
-					<span class:info-value class:monospace>{node}</span>
+					<span class:info-value class:monospace>
+						{node}
+						{unsupported_tag(Platform::Nodejs)}
+					</span>
 				</div>
 			}
 		})}
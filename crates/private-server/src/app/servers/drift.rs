@@ -0,0 +1,132 @@
+use commons_types::server::{kind::ServerKind, rank::ServerRank};
+use itertools::Itertools;
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar, ServerRankBadge, TimeAgo},
+	fns::drift::{DriftEntry, report},
+};
+
+#[component]
+pub fn Drift() -> impl IntoView {
+	let (rank, set_rank) = signal(Some(ServerRank::Production));
+	let (kind, set_kind) = signal(None::<ServerKind>);
+
+	let drift = Resource::new(
+		move || (rank.get(), kind.get()),
+		async |(rank, kind)| report(rank, kind).await,
+	);
+
+	let csv_href = move || {
+		let mut query = Vec::new();
+		if let Some(rank) = rank.get() {
+			query.push(format!("rank={rank}"));
+		}
+		if let Some(kind) = kind.get() {
+			query.push(format!("kind={kind}"));
+		}
+		format!("/api/private_server/drift.csv?{}", query.join("&"))
+	};
+
+	view! {
+		<section class="section">
+			<div class="level">
+				<div class="level-left">
+					<div class="level-item">
+						<div class="select">
+							<select
+								prop:value=move || rank.get().map_or("".to_string(), |rank| rank.to_string())
+								on:change=move |ev| set_rank.set(event_target_value(&ev).parse().ok())
+							>
+								<option value="">"all ranks"</option>
+								<option value={ServerRank::Production}>{ServerRank::Production}</option>
+								<option value={ServerRank::Clone}>{ServerRank::Clone}</option>
+								<option value={ServerRank::Demo}>{ServerRank::Demo}</option>
+								<option value={ServerRank::Test}>{ServerRank::Test}</option>
+								<option value={ServerRank::Dev}>{ServerRank::Dev}</option>
+							</select>
+						</div>
+					</div>
+					<div class="level-item">
+						<div class="select">
+							<select
+								prop:value=move || kind.get().map_or("".to_string(), |kind| kind.to_string())
+								on:change=move |ev| set_kind.set(event_target_value(&ev).parse().ok())
+							>
+								<option value="">"all kinds"</option>
+								<option value={ServerKind::Central}>{ServerKind::Central}</option>
+								<option value={ServerKind::Facility}>{ServerKind::Facility}</option>
+							</select>
+						</div>
+					</div>
+				</div>
+				<div class="level-right">
+					<div class="level-item">
+						<a class="button" href=csv_href rel="external" download>"Export CSV"</a>
+					</div>
+				</div>
+			</div>
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || drift.and_then(|entries| {
+						if entries.is_empty() {
+							return view! { <div class="box has-text-info">"No servers have reported a version recently"</div> }.into_any();
+						}
+
+						entries
+							.iter()
+							.chunk_by(|entry| entry.minors_behind)
+							.into_iter()
+							.map(|(behind, group)| {
+								let group: Vec<DriftEntry> = group.cloned().collect();
+								view! { <DriftGroup behind entries=group /> }
+							})
+							.collect_view()
+							.into_any()
+					})}
+				</ErrorHandler>
+			</Transition>
+		</section>
+	}
+}
+
+#[component]
+fn DriftGroup(behind: u64, entries: Vec<DriftEntry>) -> impl IntoView {
+	let heading = match behind {
+		0 => "On the latest minor".to_string(),
+		1 => "1 minor behind".to_string(),
+		n => format!("{n} minors behind"),
+	};
+
+	view! {
+		<h2 class="is-size-4 mb-4">{heading} " (" {entries.len()} ")"</h2>
+		<div class="box">
+			<table class="table is-fullwidth is-hoverable">
+				<thead>
+					<tr>
+						<th>"Server"</th>
+						<th>"Kind"</th>
+						<th>"Version"</th>
+						<th>"On this minor since"</th>
+					</tr>
+				</thead>
+				<tbody>
+					{entries.into_iter().map(|entry| view! {
+						<tr>
+							<td>
+								{entry.rank.map(|rank| view! { <ServerRankBadge rank /> })}
+								<A href=format!("/servers/{}", entry.server_id)>
+									{entry.name.clone().unwrap_or_else(|| entry.server_id.to_string())}
+								</A>
+							</td>
+							<td>{entry.kind.to_string()}</td>
+							<td class="monospace">{entry.version.to_string()}</td>
+							<td><TimeAgo timestamp={entry.on_minor_since} /></td>
+						</tr>
+					}).collect_view()}
+				</tbody>
+			</table>
+		</div>
+	}
+}
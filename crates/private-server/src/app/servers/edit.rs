@@ -3,6 +3,7 @@ use commons_types::{
 	Uuid,
 	geo::GeoPoint,
 	server::{kind::ServerKind, rank::ServerRank},
+	version::ReleaseChannel,
 };
 use leptos::leptos_dom::helpers::request_animation_frame;
 use leptos::prelude::*;
@@ -46,6 +47,8 @@ pub fn EditForm(info: ServerInfo) -> impl IntoView {
 	let (host, set_host) = signal(info.host.clone());
 	let (kind, set_kind) = signal(info.kind);
 	let (rank, set_rank) = signal(info.rank);
+	let (default_channel, set_default_channel) = signal(info.default_channel);
+	let (ping_timeout, set_ping_timeout) = signal(info.ping_timeout_secs.and_then(|secs| u32::try_from(secs).ok()));
 
 	let (listed, set_listed) = signal(info.listed);
 
@@ -69,7 +72,8 @@ pub fn EditForm(info: ServerInfo) -> impl IntoView {
 			(Some(lat), Some(lon)) => Some(GeoPoint { lat, lon }),
 			_ => None,
 		}),
-		..Default::default()
+		default_channel: Some(default_channel.get()),
+		ping_timeout_secs: Some(ping_timeout.get()),
 	});
 
 	let navigate = leptos_router::hooks::use_navigate();
@@ -174,6 +178,53 @@ pub fn EditForm(info: ServerInfo) -> impl IntoView {
 					</div>
 				</div>
 			</div>
+			<div class="field is-horizontal">
+				<div class="field-label is-normal">
+					<label class="label" for="field-default-channel">"Release channel"</label>
+				</div>
+				<div class="field-body">
+					<div class="field">
+						<div class="control">
+							<div class="select">
+								<select
+									id="field-default-channel"
+									disabled=move || submit.pending().get()
+									prop:value=move || default_channel.get()
+									on:change=move |ev| set_default_channel.set(event_target_value(&ev).parse().unwrap_or_default())
+								>
+									<For each=move || ReleaseChannel::ALL key=|c| *c let:channel>
+										<option value={channel}>{channel}</option>
+									</For>
+								</select>
+							</div>
+						</div>
+						<p class="help">"Channel that this server's update checks use, unless they ask for a specific one."</p>
+					</div>
+				</div>
+			</div>
+			<div class="field is-horizontal">
+				<div class="field-label is-normal">
+					<label class="label" for="field-ping-timeout">"Ping timeout"</label>
+				</div>
+				<div class="field-body">
+					<div class="field">
+						<div class="control">
+							<input
+								id="field-ping-timeout"
+								name="ping_timeout"
+								class="input"
+								type="number"
+								min="1"
+								placeholder="Default"
+								disabled=move || submit.pending().get()
+								prop:value=move || ping_timeout.get().map(|secs| secs.to_string()).unwrap_or_default()
+								on:input=move |ev| set_ping_timeout.set(event_target_value(&ev).parse().ok().filter(|secs| *secs > 0))
+							/>
+						</div>
+						<p class="help">"Seconds to wait for this server to answer a ping, if it needs longer than the default."</p>
+					</div>
+				</div>
+			</div>
 			<div class="field is-horizontal">
 				<div class="field-label is-normal">
 					<label class="label" for="field-device-id">"Device ID"</label>
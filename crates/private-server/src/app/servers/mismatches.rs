@@ -0,0 +1,123 @@
+use commons_types::server::compat::{CompatibilityRule, VersionMismatch};
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar},
+	fns::servers::{get_compatibility_rules, list_mismatches, set_compatibility_rules},
+};
+
+#[component]
+pub fn Mismatches() -> impl IntoView {
+	let mismatches = Resource::new(|| (), async |_| list_mismatches().await);
+	let rules = Resource::new(|| (), async |_| get_compatibility_rules().await);
+	let is_admin = Resource::new(
+		|| (),
+		|_| async {
+			crate::fns::commons::is_current_user_admin()
+				.await
+				.unwrap_or(false)
+		},
+	);
+
+	let toggle = Action::new(move |(rule, checked): &(CompatibilityRule, bool)| {
+		let (rule, checked) = (*rule, *checked);
+		let mut enabled = rules.get().and_then(|rules| rules.ok()).unwrap_or_default();
+		enabled.retain(|enabled| *enabled != rule);
+		if checked {
+			enabled.push(rule);
+		}
+		async move {
+			let result = set_compatibility_rules(enabled).await;
+			rules.refetch();
+			mismatches.refetch();
+			result
+		}
+	});
+
+	view! {
+		<section class="section">
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || rules.and_then(|enabled| {
+						let enabled = enabled.clone();
+						let admin = is_admin.get().unwrap_or(false);
+						view! {
+							<div class="box">
+								<h2 class="title is-5">"Rules"</h2>
+								{CompatibilityRule::ALL.into_iter().map(|rule| {
+									let checked = enabled.contains(&rule);
+									view! {
+										<label class="checkbox is-block">
+											<input
+												type="checkbox"
+												checked=checked
+												disabled=move || !admin || toggle.pending().get()
+												on:change=move |ev| { toggle.dispatch((rule, event_target_checked(&ev))); }
+											/>
+											" Flag facilities " {rule.to_string()}
+										</label>
+									}
+								}).collect_view()}
+							</div>
+						}
+					})}
+				</ErrorHandler>
+			</Transition>
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || mismatches.and_then(|list| {
+						if list.is_empty() {
+							view! {
+								<div class="box has-text-success">"All facilities are compatible with their central servers"</div>
+							}.into_any()
+						} else {
+							view! {
+								<div class="box">
+									<MismatchTable mismatches=list.clone() show_central=true />
+								</div>
+							}.into_any()
+						}
+					})}
+				</ErrorHandler>
+			</Transition>
+		</section>
+	}
+}
+
+#[component]
+pub fn MismatchTable(mismatches: Vec<VersionMismatch>, show_central: bool) -> impl IntoView {
+	view! {
+		<table class="table is-fullwidth is-hoverable">
+			<thead>
+				<tr>
+					{show_central.then(|| view! { <th>"Central"</th> })}
+					<th>"Facility"</th>
+					<th>"Central version"</th>
+					<th>"Facility version"</th>
+					<th>"Problem"</th>
+				</tr>
+			</thead>
+			<tbody>
+				{mismatches.into_iter().map(|mismatch| {
+					let problem = mismatch.describe();
+					view! {
+						<tr>
+							{show_central.then(|| view! {
+								<td>
+									<A href=format!("/servers/{}", mismatch.central_id)>{mismatch.central_name.clone()}</A>
+								</td>
+							})}
+							<td>
+								<A href=format!("/servers/{}", mismatch.facility_id)>{mismatch.facility_name.clone()}</A>
+							</td>
+							<td class="monospace">{mismatch.central_version.to_string()}</td>
+							<td class="monospace">{mismatch.facility_version.to_string()}</td>
+							<td class="has-text-danger">{problem}</td>
+						</tr>
+					}
+				}).collect_view()}
+			</tbody>
+		</table>
+	}
+}
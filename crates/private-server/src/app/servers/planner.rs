@@ -0,0 +1,274 @@
+use commons_types::{
+	Uuid,
+	server::kind::ServerKind,
+	version::{UpdateUrgency, VersionStr},
+};
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar, ServerKindBadge, ServerRankBadge},
+	fns::{
+		servers::{ServerInfo, list_all},
+		upgrade_plans::{PlanStopData, UpgradePlanData, list_targets, plan},
+	},
+};
+
+#[component]
+pub fn Planner() -> impl IntoView {
+	let servers = Resource::new(|| (), async |_| list_all().await);
+	let targets = Resource::new(|| (), async |_| list_targets().await);
+
+	let selected = RwSignal::new(Vec::<Uuid>::new());
+	let (target, set_target) = signal(None::<VersionStr>);
+
+	let upgrade_plan = Resource::new(
+		move || (selected.get(), target.get()),
+		async |(server_ids, target)| match target {
+			Some(target) if !server_ids.is_empty() => plan(server_ids, target).await.map(Some),
+			_ => Ok(None),
+		},
+	);
+
+	let markdown_href = move || {
+		let servers = selected
+			.get()
+			.iter()
+			.map(|id| id.to_string())
+			.collect::<Vec<_>>()
+			.join(",");
+		let target = target.get().map(|v| v.to_string()).unwrap_or_default();
+		format!("/api/private_server/upgrade-plan.md?servers={servers}&target={target}")
+	};
+
+	view! {
+		<section class="section" id="upgrade-planner">
+			<div class="columns no-print">
+				<div class="column is-one-third">
+					<h2 class="is-size-5 mb-2">"Servers"</h2>
+					<Transition fallback=|| view! { <LoadingBar /> }>
+						<ErrorHandler>
+							{move || servers.and_then(|servers| view! {
+								<ServerPicker servers=servers.clone() selected />
+							})}
+						</ErrorHandler>
+					</Transition>
+				</div>
+				<div class="column">
+					<h2 class="is-size-5 mb-2">"Target version"</h2>
+					<Transition fallback=|| view! { <LoadingBar /> }>
+						<ErrorHandler>
+							{move || targets.and_then(|targets| {
+								let targets = targets.clone();
+								view! {
+									<div class="select">
+										<select
+											prop:value=move || target.get().map(|v| v.to_string()).unwrap_or_default()
+											on:change=move |ev| set_target.set(event_target_value(&ev).parse().ok())
+										>
+											<option value="">"choose a version"</option>
+											{targets.into_iter().map(|version| {
+												let version = version.to_string();
+												view! { <option value=version.clone()>{version.clone()}</option> }
+											}).collect_view()}
+										</select>
+									</div>
+								}
+							})}
+						</ErrorHandler>
+					</Transition>
+				</div>
+			</div>
+
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || upgrade_plan.and_then(|upgrade_plan| match upgrade_plan {
+						None => view! {
+							<div class="box has-text-info no-print">"Pick servers and a target version to plan an upgrade"</div>
+						}.into_any(),
+						Some(upgrade_plan) => view! {
+							<div class="buttons is-right no-print">
+								<button class="button" on:click=move |_| {
+									if let Some(window) = web_sys::window() {
+										let _ = window.print();
+									}
+								}>"Print"</button>
+								<a class="button" href=markdown_href rel="external" download>"Download markdown"</a>
+							</div>
+							<PlanView upgrade_plan=upgrade_plan.clone() />
+						}.into_any(),
+					})}
+				</ErrorHandler>
+			</Transition>
+		</section>
+	}
+}
+
+/// Centrals with their facilities under them, each with a checkbox.
+#[component]
+fn ServerPicker(servers: Vec<ServerInfo>, selected: RwSignal<Vec<Uuid>>) -> impl IntoView {
+	let toggle = move |id: Uuid, checked: bool| {
+		selected.update(|selected| {
+			selected.retain(|s| *s != id);
+			if checked {
+				selected.push(id);
+			}
+		});
+	};
+
+	let entry = move |server: &ServerInfo, indent: bool| {
+		let id = server.id;
+		let name = server.name.clone().unwrap_or_else(|| server.host.clone());
+		let rank = server.rank;
+		view! {
+			<label class="checkbox is-block" class:ml-5=indent>
+				<input
+					class="mr-1"
+					type="checkbox"
+					prop:checked=move || selected.with(|selected| selected.contains(&id))
+					on:change=move |ev| toggle(id, event_target_checked(&ev)) />
+				{rank.map(|rank| view! { <ServerRankBadge rank /> })}
+				{name}
+			</label>
+		}
+	};
+
+	let centrals: Vec<&ServerInfo> = servers
+		.iter()
+		.filter(|server| server.kind == ServerKind::Central)
+		.collect();
+	let orphans: Vec<&ServerInfo> = servers
+		.iter()
+		.filter(|server| {
+			server.kind != ServerKind::Central
+				&& !server
+					.parent_server_id
+					.is_some_and(|parent| centrals.iter().any(|central| central.id == parent))
+		})
+		.collect();
+
+	view! {
+		<div class="box server-picker">
+			{centrals.iter().map(|central| {
+				let facilities = servers
+					.iter()
+					.filter(|server| server.parent_server_id == Some(central.id))
+					.map(|facility| entry(facility, true))
+					.collect_view();
+				view! { {entry(central, false)} {facilities} }
+			}).collect_view()}
+			{orphans.into_iter().map(|server| entry(server, false)).collect_view()}
+		</div>
+	}
+}
+
+#[component]
+fn StopTag(stop: PlanStopData) -> impl IntoView {
+	let class = match stop.urgency {
+		UpdateUrgency::Normal => "tag",
+		UpdateUrgency::Recommended => "tag is-info",
+		UpdateUrgency::Security | UpdateUrgency::Mandatory => "tag is-danger",
+	};
+	view! {
+		<span class=class class:has-text-weight-bold=stop.required>{stop.version.to_string()}</span>
+	}
+}
+
+#[component]
+fn PlanView(upgrade_plan: UpgradePlanData) -> impl IntoView {
+	view! {
+		<div class="upgrade-plan">
+			<h1 class="is-size-3 mb-4">"Upgrade plan to Tamanu " {upgrade_plan.target.to_string()}</h1>
+
+			<h2 class="is-size-4 mb-2">"Servers"</h2>
+			<table class="table is-fullwidth">
+				<thead>
+					<tr>
+						<th>"Server"</th>
+						<th>"Kind"</th>
+						<th>"Current version"</th>
+						<th>"Stops"</th>
+					</tr>
+				</thead>
+				<tbody>
+					{upgrade_plan.servers.into_iter().map(|entry| {
+						let stops = match (&entry.version, entry.stops.is_empty()) {
+							(None, _) => view! { <em>"unknown"</em> }.into_any(),
+							(Some(_), true) => view! { <em>"up to date"</em> }.into_any(),
+							(Some(_), false) => view! {
+								<div class="tags">
+									{entry.stops.into_iter().map(|stop| view! { <StopTag stop /> }).collect_view()}
+								</div>
+							}.into_any(),
+						};
+						view! {
+							<tr>
+								<td>
+									{entry.rank.map(|rank| view! { <ServerRankBadge rank /> })}
+									<A href=format!("/servers/{}", entry.server_id)>
+										{entry.name.clone().unwrap_or_else(|| entry.server_id.to_string())}
+									</A>
+								</td>
+								<td><ServerKindBadge kind=entry.kind /></td>
+								<td class="monospace">{entry.version.map(|v| v.to_string())}</td>
+								<td>{stops}</td>
+							</tr>
+						}
+					}).collect_view()}
+				</tbody>
+			</table>
+
+			{(!upgrade_plan.stops.is_empty()).then(|| view! {
+				<h2 class="is-size-4 mb-2">"Upgrade steps"</h2>
+				<ol class="content ml-5 mb-5">
+					{upgrade_plan.stops.into_iter().map(|stop| view! {
+						<li>
+							"Upgrade to " <span class="monospace">{stop.version.to_string()}</span>
+							{stop.required.then_some(" (required: its migrations must run before going further)")}
+						</li>
+					}).collect_view()}
+				</ol>
+			})}
+
+			{(!upgrade_plan.facilities.is_empty()).then(|| view! {
+				<h2 class="is-size-4 mb-2">"Facilities to upgrade with their central"</h2>
+				<table class="table is-fullwidth">
+					<thead>
+						<tr>
+							<th>"Facility"</th>
+							<th>"Current version"</th>
+							<th>"Why"</th>
+						</tr>
+					</thead>
+					<tbody>
+						{upgrade_plan.facilities.into_iter().map(|entry| view! {
+							<tr>
+								<td>
+									<A href=format!("/servers/{}", entry.server_id)>
+										{entry.name.clone().unwrap_or_else(|| entry.server_id.to_string())}
+									</A>
+								</td>
+								<td class="monospace">{entry.version.map(|v| v.to_string())}</td>
+								<td>
+									{if entry.reasons.is_empty() {
+										"hasn't reported its version recently, check it".to_string()
+									} else {
+										format!("would be {}", entry.reasons.join(", "))
+									}}
+								</td>
+							</tr>
+						}).collect_view()}
+					</tbody>
+				</table>
+			})}
+
+			{(!upgrade_plan.changelog.is_empty()).then(|| view! {
+				<h2 class="is-size-4 mb-2">"Changes"</h2>
+				{upgrade_plan.changelog.into_iter().map(|entry| view! {
+					<h3 class="is-size-5 monospace">{entry.version.to_string()}</h3>
+					<div class="content mb-4" inner_html=entry.changelog_html></div>
+				}).collect_view()}
+			})}
+		</div>
+	}
+}
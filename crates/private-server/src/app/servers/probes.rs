@@ -0,0 +1,292 @@
+use commons_types::{
+	Uuid,
+	server::{kind::ServerKind, probe::ProbeType},
+};
+use leptos::prelude::*;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar, TimeAgo, ToastCtx},
+	fns::probes::{NewProbeData, ProbeInfo, create, delete, list_for_server, set_enabled},
+};
+
+#[component]
+pub fn ProbesSection(server_id: Uuid, kind: ServerKind, is_admin: Resource<bool>) -> impl IntoView {
+	let probes = Resource::new(move || server_id, async |id| list_for_server(id).await);
+
+	view! {
+		<h2 class="is-size-4 mb-4">"Probes"</h2>
+		<Transition fallback=|| view! { <LoadingBar /> }>
+			<ErrorHandler>
+				{move || probes.and_then(|list| {
+					if list.is_empty() {
+						view! { <div class="box has-text-info">"No probes configured"</div> }.into_any()
+					} else {
+						let list = list.clone();
+						view! { <ProbesTable probes=list is_admin after_change=move || probes.refetch() /> }.into_any()
+					}
+				})}
+			</ErrorHandler>
+		</Transition>
+		<Transition>
+			{move || is_admin.get().unwrap_or(false).then(|| view! {
+				<AddProbe server_id kind after_add=move || probes.refetch() />
+			})}
+		</Transition>
+	}
+}
+
+#[component]
+fn ProbesTable(
+	probes: Vec<ProbeInfo>,
+	is_admin: Resource<bool>,
+	after_change: impl Fn() + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+	let ToastCtx(set_message) = use_context().unwrap();
+
+	let toggle = Action::new(move |(id, enabled): &(Uuid, bool)| {
+		let (id, enabled) = (*id, *enabled);
+		async move { set_enabled(id, enabled).await }
+	});
+	let remove = Action::new(move |id: &Uuid| {
+		let id = *id;
+		async move { delete(id).await }
+	});
+
+	Effect::new(move |_| {
+		let result = toggle.value().get().or_else(|| remove.value().get());
+		match result {
+			Some(Ok(())) => after_change(),
+			Some(Err(err)) => set_message.set(Some(format!("Error updating probe: {err}"))),
+			None => {}
+		}
+	});
+
+	let admin = move || is_admin.get().unwrap_or(false);
+	let pending = move || toggle.pending().get() || remove.pending().get();
+
+	view! {
+		<div class="box">
+			<table class="table is-fullwidth is-hoverable">
+				<thead>
+					<tr>
+						<th>"Result"</th>
+						<th>"Name"</th>
+						<th>"Check"</th>
+						<th>"Applies to"</th>
+						<th>"Every"</th>
+						<th>"Last run"</th>
+						<Transition>{move || admin().then(|| view! { <th></th> })}</Transition>
+					</tr>
+				</thead>
+				<tbody>
+					{probes.into_iter().map(|probe| {
+						let id = probe.id;
+						let enabled = probe.enabled;
+						view! {
+							<tr class:has-text-grey-light={!enabled}>
+								<td>{match (&probe.last_result, enabled) {
+									(_, false) => view! { <span class="tag">"disabled"</span> }.into_any(),
+									(None, true) => view! { <span class="tag is-light">"pending"</span> }.into_any(),
+									(Some(result), true) if result.success => view! { <span class="tag is-success">"ok"</span> }.into_any(),
+									(Some(result), true) => view! {
+										<span class="tag is-danger" title={result.error.clone()}>"failed"</span>
+									}.into_any(),
+								}}</td>
+								<td>{probe.name.clone()}</td>
+								<td>
+									<span class="tag is-uppercase mr-2">{probe.probe_type}</span>
+									<span class="monospace">{probe.description.clone()}</span>
+								</td>
+								<td>{match probe.server_kind {
+									Some(kind) => format!("all {kind} servers"),
+									None => "this server".into(),
+								}}</td>
+								<td>{format!("{}s (timeout {}s)", probe.interval_secs, probe.timeout_secs)}</td>
+								<td>{probe.last_result.clone().map(|result| view! {
+									<TimeAgo timestamp={result.created_at} />
+									{format!(" in {}ms", result.latency_ms)}
+									{result.error.map(|err| view! { <div class="has-text-danger is-size-7">{err}</div> })}
+								})}</td>
+								<Transition>{move || admin().then(|| view! {
+									<td class="buttons are-small">
+										<button
+											class="button"
+											disabled=pending
+											on:click=move |_| drop(toggle.dispatch((id, !enabled)))
+										>{if enabled { "Disable" } else { "Enable" }}</button>
+										<button
+											class="button is-danger"
+											disabled=pending
+											on:click=move |_| drop(remove.dispatch(id))
+										>"Delete"</button>
+									</td>
+								})}</Transition>
+							</tr>
+						}
+					}).collect_view()}
+				</tbody>
+			</table>
+		</div>
+	}
+}
+
+#[component]
+fn AddProbe(
+	server_id: Uuid,
+	kind: ServerKind,
+	after_add: impl Fn() + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+	let ToastCtx(set_message) = use_context().unwrap();
+
+	let (name, set_name) = signal(String::new());
+	let (probe_type, set_probe_type) = signal(ProbeType::Http);
+	let (path, set_path) = signal("/".to_string());
+	let (expected_status, set_expected_status) = signal(String::new());
+	let (expected_body, set_expected_body) = signal(String::new());
+	let (port, set_port) = signal(String::new());
+	let (interval, set_interval) = signal(60_u32);
+	let (timeout, set_timeout) = signal(10_u32);
+	let (all_of_kind, set_all_of_kind) = signal(false);
+
+	let submit = Action::new(move |data: &NewProbeData| {
+		let data = data.clone();
+		async move { create(server_id, data).await }
+	});
+
+	Effect::new(move |_| match submit.value().get() {
+		Some(Ok(_)) => {
+			set_name.set(String::new());
+			after_add();
+		}
+		Some(Err(err)) => set_message.set(Some(format!("Error adding probe: {err}"))),
+		None => {}
+	});
+
+	let on_submit = move |ev: web_sys::SubmitEvent| {
+		ev.prevent_default();
+		let data = NewProbeData {
+			name: name.get(),
+			probe_type: probe_type.get(),
+			all_of_kind: all_of_kind.get(),
+			interval_secs: interval.get(),
+			timeout_secs: timeout.get(),
+			path: Some(path.get()),
+			expected_status: expected_status.get().trim().parse().ok(),
+			expected_body: Some(expected_body.get()),
+			port: port.get().trim().parse().ok(),
+		};
+		submit.dispatch(data);
+	};
+
+	view! {
+		<form class="box" on:submit=on_submit>
+			<h3 class="is-size-5 mb-3">"Add a probe"</h3>
+			<div class="field is-grouped is-grouped-multiline">
+				<div class="control is-expanded">
+					<input
+						class="input"
+						type="text"
+						placeholder="Name"
+						required
+						disabled=move || submit.pending().get()
+						prop:value=move || name.get()
+						on:input=move |ev| set_name.set(event_target_value(&ev)) />
+				</div>
+				<div class="control">
+					<div class="select">
+						<select
+							disabled=move || submit.pending().get()
+							prop:value=move || probe_type.get()
+							on:change=move |ev| set_probe_type.set(event_target_value(&ev).parse().unwrap_or_default())
+						>
+							{ProbeType::ALL.map(|ty| view! { <option value={ty}>{ty}</option> })}
+						</select>
+					</div>
+				</div>
+			</div>
+			{move || match probe_type.get() {
+				ProbeType::Http => view! {
+					<div class="field is-grouped is-grouped-multiline">
+						<div class="control is-expanded">
+							<input
+								class="input monospace"
+								type="text"
+								placeholder="/api/public/ping"
+								prop:value=move || path.get()
+								on:input=move |ev| set_path.set(event_target_value(&ev)) />
+						</div>
+						<div class="control">
+							<input
+								class="input"
+								type="number"
+								min="100"
+								max="599"
+								placeholder="Status (2xx)"
+								prop:value=move || expected_status.get()
+								on:input=move |ev| set_expected_status.set(event_target_value(&ev)) />
+						</div>
+						<div class="control is-expanded">
+							<input
+								class="input"
+								type="text"
+								placeholder="Body contains (optional)"
+								prop:value=move || expected_body.get()
+								on:input=move |ev| set_expected_body.set(event_target_value(&ev)) />
+						</div>
+					</div>
+				}.into_any(),
+				ProbeType::Tcp => view! {
+					<div class="field">
+						<div class="control">
+							<input
+								class="input"
+								type="number"
+								min="1"
+								max="65535"
+								placeholder="Port"
+								required
+								prop:value=move || port.get()
+								on:input=move |ev| set_port.set(event_target_value(&ev)) />
+						</div>
+					</div>
+				}.into_any(),
+				ProbeType::Dns => ().into_any(),
+			}}
+			<div class="field is-grouped is-grouped-multiline">
+				<div class="control">
+					<label class="label is-small">"Every (seconds)"</label>
+					<input
+						class="input"
+						type="number"
+						min="60"
+						prop:value=move || interval.get()
+						on:input=move |ev| set_interval.set(event_target_value(&ev).parse().unwrap_or(60)) />
+				</div>
+				<div class="control">
+					<label class="label is-small">"Timeout (seconds)"</label>
+					<input
+						class="input"
+						type="number"
+						min="1"
+						prop:value=move || timeout.get()
+						on:input=move |ev| set_timeout.set(event_target_value(&ev).parse().unwrap_or(10)) />
+				</div>
+			</div>
+			<div class="field">
+				<label class="checkbox">
+					<input
+						class="mr-2"
+						type="checkbox"
+						prop:checked=move || all_of_kind.get()
+						on:change=move |ev| set_all_of_kind.set(event_target_checked(&ev)) />
+					{format!("Apply to all {kind} servers")}
+				</label>
+			</div>
+			<div class="control">
+				<button type="submit" class="button is-primary" disabled=move || submit.pending().get()>
+					{move || if submit.pending().get() { "Adding..." } else { "Add probe" }}
+				</button>
+			</div>
+		</form>
+	}
+}
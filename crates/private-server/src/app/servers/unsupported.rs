@@ -0,0 +1,71 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar, ServerRankBadge},
+	fns::servers::{UnsupportedServerEntry, list_unsupported},
+};
+
+#[component]
+pub fn Unsupported() -> impl IntoView {
+	let unsupported = Resource::new(|| (), async |_| list_unsupported().await);
+
+	view! {
+		<section class="section">
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || unsupported.and_then(|list| {
+						if list.is_empty() {
+							view! {
+								<div class="box has-text-success">"No servers are running an unsupported stack"</div>
+							}.into_any()
+						} else {
+							view! {
+								<div class="box">
+									<UnsupportedTable entries=list.clone() />
+								</div>
+							}.into_any()
+						}
+					})}
+				</ErrorHandler>
+			</Transition>
+		</section>
+	}
+}
+
+#[component]
+fn UnsupportedTable(entries: Vec<UnsupportedServerEntry>) -> impl IntoView {
+	view! {
+		<table class="table is-fullwidth is-hoverable">
+			<thead>
+				<tr>
+					<th>"Server"</th>
+					<th>"Version"</th>
+					<th>"Problems"</th>
+				</tr>
+			</thead>
+			<tbody>
+				{entries.into_iter().map(|entry| {
+					view! {
+						<tr>
+							<td>
+								{entry.rank.map(|rank| view! { <ServerRankBadge rank /> })}
+								<A href=format!("/servers/{}", entry.server_id)>
+									{entry.name.clone().unwrap_or_else(|| entry.server_id.to_string())}
+								</A>
+							</td>
+							<td class="monospace">
+								<A href=format!("/versions/{}", entry.version)>{entry.version.to_string()}</A>
+							</td>
+							<td>
+								{entry.violations.iter().map(|violation| view! {
+									<div>{violation.to_string()}</div>
+								}).collect_view()}
+							</td>
+						</tr>
+					}
+				}).collect_view()}
+			</tbody>
+		</table>
+	}
+}
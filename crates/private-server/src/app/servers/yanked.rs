@@ -0,0 +1,77 @@
+use commons_types::version::YankSeverity;
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::{
+	components::{ErrorHandler, LoadingBar, ServerRankBadge},
+	fns::servers::{YankedServerEntry, list_on_yanked},
+};
+
+#[component]
+pub fn Yanked() -> impl IntoView {
+	let yanked = Resource::new(|| (), async |_| list_on_yanked().await);
+
+	view! {
+		<section class="section">
+			<Transition fallback=|| view! { <LoadingBar /> }>
+				<ErrorHandler>
+					{move || yanked.and_then(|list| {
+						if list.is_empty() {
+							view! {
+								<div class="box has-text-success">"No servers are on a yanked version"</div>
+							}.into_any()
+						} else {
+							view! {
+								<div class="box">
+									<YankedTable entries=list.clone() />
+								</div>
+							}.into_any()
+						}
+					})}
+				</ErrorHandler>
+			</Transition>
+		</section>
+	}
+}
+
+#[component]
+fn YankedTable(entries: Vec<YankedServerEntry>) -> impl IntoView {
+	view! {
+		<table class="table is-fullwidth is-hoverable">
+			<thead>
+				<tr>
+					<th>"Server"</th>
+					<th>"Version"</th>
+					<th>"Severity"</th>
+					<th>"Reason"</th>
+					<th>"Move to"</th>
+				</tr>
+			</thead>
+			<tbody>
+				{entries.into_iter().map(|entry| {
+					let severity_class = match entry.severity {
+						YankSeverity::Low => "tag is-info",
+						YankSeverity::Medium => "tag is-warning",
+						YankSeverity::High | YankSeverity::Critical => "tag is-danger",
+					};
+					view! {
+						<tr>
+							<td>
+								{entry.rank.map(|rank| view! { <ServerRankBadge rank /> })}
+								<A href=format!("/servers/{}", entry.server_id)>
+									{entry.name.clone().unwrap_or_else(|| entry.server_id.to_string())}
+								</A>
+							</td>
+							<td class="monospace">
+								<A href=format!("/versions/{}", entry.version)>{entry.version.to_string()}</A>
+							</td>
+							<td><span class=severity_class>{entry.severity.to_string()}</span></td>
+							<td>{entry.reason.clone()}</td>
+							<td class="monospace">{entry.replacement.map(|version| version.to_string())}</td>
+						</tr>
+					}
+				}).collect_view()}
+			</tbody>
+		</table>
+	}
+}
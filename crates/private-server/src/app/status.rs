@@ -220,7 +220,14 @@ pub fn ServerCard(server: commons_types::server::cards::CentralServerCard) -> im
 			>
 				<span
 					class:status-dot class:facility-dot class={facility.up}
-					title={format!("{}: {}", facility.name, facility.up)}
+					class:version-mismatch={!facility.mismatches.is_empty()}
+					title={
+						let mut title = format!("{}: {}", facility.name, facility.up);
+						for rule in &facility.mismatches {
+							title.push_str(&format!(" ({rule})"));
+						}
+						title
+					}
 				></span>
 			</For>
 		</div>
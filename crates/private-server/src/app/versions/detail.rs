@@ -5,14 +5,21 @@ use leptos_router::hooks::use_params_map;
 use uuid::Uuid;
 
 use crate::{
-	components::{ErrorHandler, LoadingBar, TimeAgo, ToggleSignal as _},
+	components::{ErrorHandler, LoadingBar, SupportStatusBadge, TimeAgo, ToggleSignal as _},
 	fns::versions::{
-		ArtifactData, RelatedVersionData, VersionDetail, create_artifact, delete_artifact,
-		get_artifacts_by_version_id, get_version_detail, update_artifact, update_version_changelog,
-		update_version_status,
+		ArtifactData, DownloadCountData, LinkCheckData, PlatformRequirementData,
+		RelatedVersionData, RolloutData, SupportData, VersionDetail, create_artifact,
+		delete_artifact, get_artifacts_by_version_id, get_download_counts,
+		get_release_completeness, get_rollout, get_version_detail, remove_rollout,
+		update_artifact, update_platform_requirements, update_rollout, update_support_policy,
+		update_version_changelog, update_version_channels, update_version_status,
+		update_version_stepping_stone, update_version_urgency,
 	},
 };
-use commons_types::version::VersionStatus;
+use commons_types::{
+	requirements::Platform,
+	version::{ReleaseChannel, UpdateUrgency, VersionStatus},
+};
 
 #[component]
 pub fn Detail() -> impl IntoView {
@@ -42,14 +49,25 @@ fn VersionDetailView() -> impl IntoView {
 					view! {
 						<header class="level mt-4">
 							<div class="level-left">
-								<h1 class="level-item is-size-3">{detail.major} "." {detail.minor} "." {detail.patch}</h1>
+								<h1 class="level-item is-size-3">{detail.version_string()}</h1>
+								{(!detail.prerelease.is_empty()).then(|| view! {
+									<span class="level-item tag is-warning">"pre-release"</span>
+								})}
 							</div>
 							<div class="level-right">
 								<StatusSelection detail=detail.clone() is_admin {..} class:level-item />
 							</div>
 						</header>
-						<VersionInfo detail=detail.clone() />
+						<VersionInfo detail=detail.clone() is_admin />
+						<SupportSection support=detail.support.clone() is_admin />
+						<RequirementsSection
+							major=detail.major
+							minor=detail.minor
+							requirements=detail.requirements.clone()
+							is_admin />
+						<RolloutSection version_id=detail.id is_admin />
 						<ArtifactsSection version_id=detail.id is_admin />
+						<DownloadsSection version_id=detail.id />
 						<ChangelogSection detail=detail.clone() is_admin />
 						{(!detail.related_versions.is_empty()).then(|| {
 							view! { <RelatedVersionsSection related_versions=detail.related_versions.clone() /> }
@@ -62,7 +80,8 @@ fn VersionDetailView() -> impl IntoView {
 }
 
 #[component]
-fn VersionInfo(detail: VersionDetail) -> impl IntoView {
+fn VersionInfo(detail: VersionDetail, is_admin: bool) -> impl IntoView {
+	let version_str = detail.version_string();
 	view! {
 		<section class="box">
 			<div class="info-grid">
@@ -74,6 +93,24 @@ fn VersionInfo(detail: VersionDetail) -> impl IntoView {
 					<span class="info-label">"Last updated"</span>
 					<TimeAgo timestamp={detail.updated_at} {..} class:info-value />
 				</div>
+				{(!detail.build.is_empty()).then(|| view! {
+					<div class="info-item">
+						<span class="info-label">"Build"</span>
+						<span class="info-value is-family-monospace">{detail.build.clone()}</span>
+					</div>
+				})}
+				<div class="info-item">
+					<span class="info-label">"Channels"</span>
+					<ChannelSelection version=version_str.clone() channels=detail.channels.clone() is_admin {..} class:info-value />
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Urgency"</span>
+					<UrgencySelection version=version_str.clone() urgency=detail.urgency is_admin {..} class:info-value />
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Stepping stone"</span>
+					<SteppingStoneToggle version=version_str stepping_stone=detail.stepping_stone is_admin {..} class:info-value />
+				</div>
 				{detail.min_chrome_version.map(|chrome_ver| {
 					view! {
 						<div class="info-item">
@@ -89,11 +126,135 @@ fn VersionInfo(detail: VersionDetail) -> impl IntoView {
 	}
 }
 
+#[component]
+fn ChannelSelection(
+	version: String,
+	channels: Vec<ReleaseChannel>,
+	is_admin: bool,
+) -> impl IntoView {
+	let (selected, set_selected) = signal(channels);
+
+	let update_channels = Action::new(move |channels: &Vec<ReleaseChannel>| {
+		let version = version.clone();
+		let channels = channels.clone();
+		async move { update_version_channels(version, channels).await }
+	});
+
+	let toggle = move |channel: ReleaseChannel, checked: bool| {
+		let mut channels = selected.get();
+		channels.retain(|c| *c != channel);
+		if checked {
+			channels.push(channel);
+			channels.sort();
+		}
+		set_selected.set(channels.clone());
+		update_channels.dispatch(channels);
+	};
+
+	view! {
+		<span>
+			<For each=move || ReleaseChannel::ALL key=|c| *c let:channel>
+				<label class="checkbox mr-3">
+					<input
+						class="mr-1"
+						type="checkbox"
+						disabled=move || !is_admin || update_channels.pending().get()
+						prop:checked=move || selected.get().contains(&channel)
+						on:change=move |ev| toggle(channel, event_target_checked(&ev)) />
+					{channel}
+				</label>
+			</For>
+			{move || {
+				update_channels
+					.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</span>
+	}
+}
+
+#[component]
+fn UrgencySelection(version: String, urgency: UpdateUrgency, is_admin: bool) -> impl IntoView {
+	let (selected, set_selected) = signal(urgency);
+
+	let update_urgency = Action::new(move |urgency: &UpdateUrgency| {
+		let version = version.clone();
+		let urgency = *urgency;
+		async move { update_version_urgency(version, urgency).await }
+	});
+
+	view! {
+		<span>
+			<div class="select is-small">
+				<select
+					disabled=move || !is_admin || update_urgency.pending().get()
+					prop:value=move || selected.get().to_string()
+					on:change=move |ev| {
+						if let Ok(urgency) = event_target_value(&ev).parse::<UpdateUrgency>() {
+							set_selected.set(urgency);
+							update_urgency.dispatch(urgency);
+						}
+					}
+				>
+					<For each=move || UpdateUrgency::ALL key=|u| *u let:urgency>
+						<option value={urgency} selected=move || selected.get() == urgency>{urgency}</option>
+					</For>
+				</select>
+			</div>
+			{move || {
+				update_urgency
+					.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</span>
+	}
+}
+
+#[component]
+fn SteppingStoneToggle(version: String, stepping_stone: bool, is_admin: bool) -> impl IntoView {
+	let (checked, set_checked) = signal(stepping_stone);
+
+	let update_stepping_stone = Action::new(move |stepping_stone: &bool| {
+		let version = version.clone();
+		let stepping_stone = *stepping_stone;
+		async move { update_version_stepping_stone(version, stepping_stone).await }
+	});
+
+	view! {
+		<span>
+			<label class="checkbox">
+				<input
+					class="mr-1"
+					type="checkbox"
+					disabled=move || !is_admin || update_stepping_stone.pending().get()
+					prop:checked=move || checked.get()
+					on:change=move |ev| {
+						let stepping_stone = event_target_checked(&ev);
+						set_checked.set(stepping_stone);
+						update_stepping_stone.dispatch(stepping_stone);
+					} />
+				"Servers must upgrade through this version"
+			</label>
+			{move || {
+				update_stepping_stone
+					.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</span>
+	}
+}
+
 #[component]
 fn StatusSelection(detail: VersionDetail, is_admin: bool) -> impl IntoView {
 	let (selected_status, set_selected_status) = signal(detail.status);
 	let (is_changing, set_is_changing) = signal(false);
-	let version_str = format!("{}.{}.{}", detail.major, detail.minor, detail.patch);
+	let version_str = detail.version_string();
 	let can_switch_to_draft =
 		detail.status != VersionStatus::Published || detail.is_latest_in_minor;
 
@@ -185,6 +346,475 @@ fn StatusSelection(detail: VersionDetail, is_admin: bool) -> impl IntoView {
 	}
 }
 
+#[component]
+fn SupportSection(support: SupportData, is_admin: bool) -> impl IntoView {
+	let (is_editing, set_is_editing) = signal(false);
+	let (active, set_active) = signal(support.active_support_until);
+	let (security, set_security) = signal(support.security_support_until);
+	let (major, minor) = (support.major, support.minor);
+
+	let save = Action::new(move |_: &()| async move {
+		update_support_policy(
+			major,
+			minor,
+			active.get_untracked(),
+			security.get_untracked(),
+		)
+		.await
+	});
+
+	Effect::new(move || {
+		if let Some(Ok(())) = save.value().get() {
+			window().location().reload().expect("Failed to reload page");
+		}
+	});
+
+	let date_value =
+		|date: Option<jiff::civil::Date>| date.map_or(String::new(), |d| d.to_string());
+
+	view! {
+		<header class="level mt-4">
+			<div class="level-left">
+				<h2 class="level-item is-size-4">"Support for " {major} "." {minor}</h2>
+				<span class="level-item"><SupportStatusBadge status={support.status} /></span>
+			</div>
+			{is_admin.then(|| view! {
+				<div class="level-right">
+					{move || if is_editing.get() {
+						view! {
+							<button
+								class="level-item button is-success mr-2"
+								disabled=move || save.pending().get()
+								on:click=move |_| drop(save.dispatch(()))
+							>"Save"</button>
+							<button
+								class="level-item button is-danger is-light"
+								on:click=move |_| set_is_editing.set(false)
+							>"Cancel"</button>
+						}.into_any()
+					} else {
+						view! {
+							<button
+								class="level-item button"
+								on:click=move |_| set_is_editing.set(true)
+							>"Edit"</button>
+						}.into_any()
+					}}
+				</div>
+			})}
+		</header>
+		<section class="box">
+			<div class="info-grid">
+				<div class="info-item">
+					<span class="info-label">"Released"</span>
+					<span class="info-value">
+						{support.released_at.map_or("unreleased".to_string(), |at| at.strftime("%Y-%m-%d").to_string())}
+					</span>
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Active support until"</span>
+					{move || if is_editing.get() {
+						view! {
+							<input
+								class="input info-value"
+								type="date"
+								prop:value=move || date_value(active.get())
+								on:change=move |ev| set_active.set(event_target_value(&ev).parse().ok()) />
+						}.into_any()
+					} else {
+						view! { <span class="info-value">{date_value(active.get()).is_empty().then_some("not set")} {date_value(active.get())}</span> }.into_any()
+					}}
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Security fixes until"</span>
+					{move || if is_editing.get() {
+						view! {
+							<input
+								class="input info-value"
+								type="date"
+								prop:value=move || date_value(security.get())
+								on:change=move |ev| set_security.set(event_target_value(&ev).parse().ok()) />
+						}.into_any()
+					} else {
+						view! { <span class="info-value">{date_value(security.get()).is_empty().then_some("not set")} {date_value(security.get())}</span> }.into_any()
+					}}
+				</div>
+			</div>
+			{move || {
+				save.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</section>
+	}
+}
+
+#[component]
+fn RequirementsSection(
+	major: i32,
+	minor: i32,
+	requirements: Vec<PlatformRequirementData>,
+	is_admin: bool,
+) -> impl IntoView {
+	let (is_editing, set_is_editing) = signal(false);
+	let rows: Vec<(Platform, RwSignal<String>, RwSignal<String>)> = Platform::ALL
+		.into_iter()
+		.map(|platform| {
+			let requirement = requirements.iter().find(|r| r.platform == platform);
+			let min = requirement.and_then(|r| r.min_version.clone());
+			let max = requirement.and_then(|r| r.max_version.clone());
+			(
+				platform,
+				RwSignal::new(min.unwrap_or_default()),
+				RwSignal::new(max.unwrap_or_default()),
+			)
+		})
+		.collect();
+
+	let save = Action::new({
+		let rows = rows.clone();
+		move |_: &()| {
+			let requirements = rows
+				.iter()
+				.map(|(platform, min, max)| PlatformRequirementData {
+					platform: *platform,
+					min_version: Some(min.get_untracked()),
+					max_version: Some(max.get_untracked()),
+				})
+				.collect();
+			async move { update_platform_requirements(major, minor, requirements).await }
+		}
+	});
+
+	Effect::new(move || {
+		if let Some(Ok(())) = save.value().get() {
+			window().location().reload().expect("Failed to reload page");
+		}
+	});
+
+	view! {
+		<header class="level mt-4">
+			<div class="level-left">
+				<h2 class="level-item is-size-4">"Platform requirements for " {major} "." {minor}</h2>
+			</div>
+			{is_admin.then(|| view! {
+				<div class="level-right">
+					{move || if is_editing.get() {
+						view! {
+							<button
+								class="level-item button is-success mr-2"
+								disabled=move || save.pending().get()
+								on:click=move |_| drop(save.dispatch(()))
+							>"Save"</button>
+							<button
+								class="level-item button is-danger is-light"
+								on:click=move |_| set_is_editing.set(false)
+							>"Cancel"</button>
+						}.into_any()
+					} else {
+						view! {
+							<button
+								class="level-item button"
+								on:click=move |_| set_is_editing.set(true)
+							>"Edit"</button>
+						}.into_any()
+					}}
+				</div>
+			})}
+		</header>
+		<section class="box">
+			<table class="table is-fullwidth">
+				<thead>
+					<tr>
+						<th>"Platform"</th>
+						<th>"Minimum"</th>
+						<th>"Maximum"</th>
+					</tr>
+				</thead>
+				<tbody>
+					{rows.into_iter().map(|(platform, min, max)| view! {
+						<tr>
+							<td>{platform}</td>
+							<td><RequirementBound value=min is_editing /></td>
+							<td><RequirementBound value=max is_editing /></td>
+						</tr>
+					}).collect_view()}
+				</tbody>
+			</table>
+			{move || {
+				save.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</section>
+	}
+}
+
+/// One end of a platform requirement, where blank means there's no bound.
+#[component]
+fn RequirementBound(value: RwSignal<String>, is_editing: ReadSignal<bool>) -> impl IntoView {
+	move || {
+		if is_editing.get() {
+			view! {
+				<input
+					class="input is-small monospace"
+					type="text"
+					placeholder="any"
+					prop:value=move || value.get()
+					on:input=move |ev| value.set(event_target_value(&ev)) />
+			}
+			.into_any()
+		} else {
+			let bound = value.get();
+			if bound.is_empty() {
+				view! { <span class="has-text-grey">"any"</span> }.into_any()
+			} else {
+				view! { <span class="monospace">{bound}</span> }.into_any()
+			}
+		}
+	}
+}
+
+#[component]
+fn RolloutSection(version_id: Uuid, is_admin: bool) -> impl IntoView {
+	let resource = Resource::new(
+		move || version_id,
+		|id| async move { get_rollout(id).await },
+	);
+
+	let start = Action::new(move |_: &()| async move {
+		update_rollout(version_id, Vec::new(), 0, false).await?;
+		resource.refetch();
+		Ok::<_, AppError>(())
+	});
+
+	let end = Action::new(move |_: &()| async move {
+		remove_rollout(version_id).await?;
+		resource.refetch();
+		Ok::<_, AppError>(())
+	});
+
+	view! {
+		<header class="level mt-4">
+			<div class="level-left">
+				<h2 class="level-item is-size-4">"Rollout"</h2>
+			</div>
+			{move || is_admin.then(|| {
+				let has_rollout = resource.get().and_then(|r| r.ok()).flatten().is_some();
+				view! {
+					<div class="level-right">
+						{if has_rollout {
+							view! {
+								<button
+									class="level-item button is-warning is-light"
+									disabled=move || end.pending().get()
+									on:click=move |_| drop(end.dispatch(()))
+								>"Release to everyone"</button>
+							}.into_any()
+						} else {
+							view! {
+								<button
+									class="level-item button is-primary"
+									disabled=move || start.pending().get()
+									on:click=move |_| drop(start.dispatch(()))
+								>"Start staged rollout"</button>
+							}.into_any()
+						}}
+					</div>
+				}
+			})}
+		</header>
+		<Transition fallback=|| view! { <LoadingBar /> }>
+			<ErrorHandler>
+				{move || resource.and_then(|rollout| match rollout.clone() {
+					None => view! {
+						<div class="box">"Offered to all servers as soon as it's published."</div>
+					}.into_any(),
+					Some(rollout) => view! {
+						<RolloutControls version_id rollout is_admin refresh=move || resource.refetch() />
+					}.into_any(),
+				})}
+			</ErrorHandler>
+		</Transition>
+	}
+}
+
+#[component]
+fn RolloutControls(
+	version_id: Uuid,
+	rollout: RolloutData,
+	is_admin: bool,
+	refresh: impl Fn() + Send + Sync + Copy + 'static,
+) -> impl IntoView {
+	let allowlist = StoredValue::new(
+		rollout
+			.allowlist
+			.iter()
+			.map(|server| server.id)
+			.collect::<Vec<_>>(),
+	);
+	let (percentage, set_percentage) = signal(rollout.percentage);
+	let (new_server, set_new_server) = signal(String::new());
+
+	let save = Action::new(
+		move |(allowlist, percentage, paused): &(Vec<Uuid>, i32, bool)| {
+			let (allowlist, percentage, paused) = (allowlist.clone(), *percentage, *paused);
+			async move {
+				update_rollout(version_id, allowlist, percentage, paused).await?;
+				refresh();
+				Ok::<_, AppError>(())
+			}
+		},
+	);
+
+	let paused = rollout.paused;
+	let stage = if rollout.paused {
+		"Paused".to_string()
+	} else if rollout.percentage >= 100 {
+		"Everyone".to_string()
+	} else if rollout.percentage == 0 {
+		"Allowlist only".to_string()
+	} else {
+		format!("Allowlist and {}% of servers", rollout.percentage)
+	};
+
+	view! {
+		<section class="box">
+			<div class="info-grid">
+				<div class="info-item">
+					<span class="info-label">"Stage"</span>
+					<span class="info-value" class:has-text-danger=paused>{stage}</span>
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Offered to"</span>
+					<span class="info-value">
+						{rollout.eligible_servers} " of " {rollout.total_servers} " servers"
+					</span>
+				</div>
+				<div class="info-item">
+					<span class="info-label">"Last changed"</span>
+					<TimeAgo timestamp={rollout.updated_at} {..} class:info-value />
+				</div>
+			</div>
+			{is_admin.then(|| view! {
+				<form class="field has-addons mt-4" on:submit=move |ev| {
+					ev.prevent_default();
+					save.dispatch((allowlist.get_value(), percentage.get(), paused));
+				}>
+					<p class="control">
+						<input
+							class="input"
+							type="number"
+							min="0"
+							max="100"
+							disabled=move || save.pending().get()
+							prop:value=move || percentage.get()
+							on:input=move |ev| set_percentage.set(event_target_value(&ev).parse().unwrap_or_default()) />
+					</p>
+					<p class="control">
+						<span class="button is-static">"%"</span>
+					</p>
+					<p class="control">
+						<button
+							type="submit"
+							class="button is-primary"
+							disabled=move || save.pending().get()
+						>"Set percentage"</button>
+					</p>
+					<p class="control ml-4">
+						<button
+							type="button"
+							class="button"
+							class:is-danger=!paused
+							class:is-success=paused
+							disabled=move || save.pending().get()
+							on:click=move |_| drop(save.dispatch((allowlist.get_value(), percentage.get(), !paused)))
+						>{if paused { "Resume" } else { "Pause" }}</button>
+					</p>
+				</form>
+			})}
+			<h3 class="is-size-5 mt-4 mb-2">"Allowlist"</h3>
+			{if rollout.allowlist.is_empty() {
+				view! { <p class="has-text-grey">"No servers are on the allowlist"</p> }.into_any()
+			} else {
+				view! {
+					<table class="table is-fullwidth is-hoverable">
+						<tbody>
+							{rollout.allowlist.into_iter().map(|server| {
+								let id = server.id;
+								view! {
+									<tr>
+										<td>
+											<a href=format!("/servers/{id}")>
+												{server.name.clone().unwrap_or_else(|| id.to_string())}
+											</a>
+										</td>
+										<td class="monospace">{server.host.clone()}</td>
+										<td class="has-text-right">
+											{is_admin.then(|| view! {
+												<button
+													class="button is-small is-danger is-light"
+													disabled=move || save.pending().get()
+													on:click=move |_| {
+														let mut list = allowlist.get_value();
+														list.retain(|s| *s != id);
+														save.dispatch((list, percentage.get_untracked(), paused));
+													}
+												>"Remove"</button>
+											})}
+										</td>
+									</tr>
+								}
+							}).collect_view()}
+						</tbody>
+					</table>
+				}.into_any()
+			}}
+			{is_admin.then(|| view! {
+				<form class="field has-addons" on:submit=move |ev| {
+					ev.prevent_default();
+					if let Ok(id) = new_server.get().trim().parse::<Uuid>() {
+						let mut list = allowlist.get_value();
+						list.push(id);
+						save.dispatch((list, percentage.get_untracked(), paused));
+						set_new_server.set(String::new());
+					}
+				}>
+					<p class="control is-expanded">
+						<input
+							class="input"
+							type="text"
+							placeholder="Server ID"
+							disabled=move || save.pending().get()
+							prop:value=move || new_server.get()
+							on:input=move |ev| set_new_server.set(event_target_value(&ev)) />
+					</p>
+					<p class="control">
+						<button
+							type="submit"
+							class="button"
+							disabled=move || { save.pending().get() || new_server.get().trim().parse::<Uuid>().is_err() }
+						>"Add to allowlist"</button>
+					</p>
+				</form>
+				{move || {
+					let input = new_server.get();
+					(!input.trim().is_empty() && input.trim().parse::<Uuid>().is_err())
+						.then(|| view! { <p class="help is-danger">"Not a server ID"</p> })
+				}}
+			})}
+			{move || {
+				save.value()
+					.get()
+					.and_then(|result| result.err())
+					.map(|e| view! { <div class="error-message">{format!("Error: {}", e)}</div> })
+			}}
+		</section>
+	}
+}
+
 #[component]
 fn ArtifactsSection(version_id: Uuid, is_admin: bool) -> impl IntoView {
 	let (is_unlocked, set_is_unlocked) = signal(false);
@@ -194,12 +824,42 @@ fn ArtifactsSection(version_id: Uuid, is_admin: bool) -> impl IntoView {
 		move || version_id,
 		|id| async move { get_artifacts_by_version_id(id).await },
 	);
-	let refresh = move || resource.refetch();
+	let completeness = Resource::new(
+		move || version_id,
+		|id| async move { get_release_completeness(id).await },
+	);
+	let refresh = move || {
+		resource.refetch();
+		completeness.refetch();
+	};
 
 	view! {
 		<header class="level">
 			<div class="level-left">
 				<h2 class="level-item is-size-4">"Artifacts"</h2>
+				<Transition>
+					{move || completeness.get().and_then(|r| r.ok()).map(|completeness| {
+						let mut problems = completeness.missing.iter().map(|kind| format!("missing {kind}")).collect::<Vec<_>>();
+						if completeness.broken > 0 {
+							problems.push(format!("{} broken link(s)", completeness.broken));
+						}
+						if completeness.unchecked > 0 {
+							problems.push(format!("{} link(s) not checked yet", completeness.unchecked));
+						}
+						let compared = completeness.previous.clone().map(|previous| format!("Compared with {previous}"));
+						view! {
+							<span
+								class="level-item tag"
+								class:is-success=completeness.is_complete()
+								class:is-warning=!completeness.is_complete()
+								title=compared
+							>{if completeness.is_complete() { "Complete" } else { "Incomplete" }}</span>
+							{(!problems.is_empty()).then(|| view! {
+								<span class="level-item is-size-7 has-text-grey">{problems.join(", ")}</span>
+							})}
+						}
+					})}
+				</Transition>
 			</div>
 			{is_admin.then(|| {
 				view! {
@@ -233,6 +893,61 @@ fn ArtifactsSection(version_id: Uuid, is_admin: bool) -> impl IntoView {
 	}
 }
 
+#[component]
+fn DownloadsSection(version_id: Uuid) -> impl IntoView {
+	let resource = Resource::new(
+		move || version_id,
+		|id| async move { get_download_counts(id).await },
+	);
+
+	view! {
+		<header class="level mt-4">
+			<div class="level-left">
+				<h2 class="level-item is-size-4">"Downloads"</h2>
+			</div>
+		</header>
+		<Transition fallback=|| view! { <LoadingBar /> }>
+			<ErrorHandler>
+				{move || resource.and_then(|counts| if counts.is_empty() {
+					view! { <div class="box">"Not downloaded through the meta server yet."</div> }.into_any()
+				} else {
+					view! { <DownloadCounts counts=counts.clone() /> }.into_any()
+				})}
+			</ErrorHandler>
+		</Transition>
+	}
+}
+
+#[component]
+fn DownloadCounts(counts: Vec<DownloadCountData>) -> impl IntoView {
+	view! {
+		<div class="box">
+			<table class="table is-fullwidth is-hoverable">
+				<thead>
+					<tr>
+						<th>"Type"</th>
+						<th>"Platform"</th>
+						<th>"Downloads"</th>
+						<th title="Distinct /24 (IPv4) or /48 (IPv6) networks">"Networks"</th>
+						<th>"Last downloaded"</th>
+					</tr>
+				</thead>
+				<tbody>
+					{counts.into_iter().map(|count| view! {
+						<tr>
+							<td>{count.artifact_type}</td>
+							<td>{count.platform}</td>
+							<td>{count.downloads}</td>
+							<td>{count.networks}</td>
+							<td><TimeAgo timestamp=count.last_downloaded_at /></td>
+						</tr>
+					}).collect_view()}
+				</tbody>
+			</table>
+		</div>
+	}
+}
+
 #[component]
 fn ArtifactsList(
 	resource: Resource<Result<Vec<ArtifactData>, AppError>>,
@@ -379,11 +1094,13 @@ fn ArtifactItemView(
 					})}
 				</div>
 				<div class="column">{artifact.platform.clone()}</div>
-				<a
-					class="column is-half"
-					href={artifact.download_url.starts_with("https://").then(|| artifact.download_url.clone())}
-					class:has-text-primary-dark={!artifact.download_url.starts_with("https://")}
-				>{artifact.download_url.clone()}</a>
+				<div class="column is-half">
+					<a
+						href={artifact.download_url.starts_with("https://").then(|| artifact.download_url.clone())}
+						class:has-text-primary-dark={!artifact.download_url.starts_with("https://")}
+					>{artifact.download_url.clone()}</a>
+					{artifact.link_check.clone().map(|check| view! { <LinkCheckStatus check /> })}
+				</div>
 				<div class="column">
 					<div class="field is-grouped buttons are-small is-justify-content-end" class:is-invisible={move || !is_unlocked.get()}>
 					{move || if show_delete_confirm.get() {
@@ -427,6 +1144,28 @@ fn ArtifactItemView(
 	}
 }
 
+#[component]
+fn LinkCheckStatus(check: LinkCheckData) -> impl IntoView {
+	if check.healthy {
+		view! {
+			<p class="is-size-7 has-text-grey-light">"Link works, checked " <TimeAgo timestamp=check.checked_at /></p>
+		}
+		.into_any()
+	} else {
+		let reason = check
+			.error
+			.clone()
+			.or_else(|| check.status_code.map(|code| format!("status {code}")))
+			.unwrap_or_default();
+		view! {
+			<p class="is-size-7 has-text-danger">
+				"Broken link: " {reason} ", checked " <TimeAgo timestamp=check.checked_at />
+			</p>
+		}
+		.into_any()
+	}
+}
+
 #[component]
 fn ArtifactItemEdit(artifact: ArtifactData, set_is_editing: WriteSignal<bool>) -> impl IntoView {
 	let (artifact_type, set_artifact_type) = signal(artifact.artifact_type.clone());
@@ -503,7 +1242,7 @@ fn ArtifactItemEdit(artifact: ArtifactData, set_is_editing: WriteSignal<bool>) -
 fn ChangelogSection(detail: VersionDetail, is_admin: bool) -> impl IntoView {
 	let (is_editing, set_is_editing) = signal(false);
 	let (changelog_text, set_changelog_text) = signal(detail.changelog.clone());
-	let version_str = format!("{}.{}.{}", detail.major, detail.minor, detail.patch);
+	let version_str = detail.version_string();
 	let original_changelog = StoredValue::new(detail.changelog.clone());
 
 	let update_changelog = Action::new(move |new_changelog: &String| {
@@ -570,7 +1309,7 @@ fn ChangelogSection(detail: VersionDetail, is_admin: bool) -> impl IntoView {
 					}.into_any()
 				} else {
 					view! {
-						<div class="content" inner_html=parse_markdown(&detail.changelog)></div>
+						<div class="content" inner_html=detail.changelog_html.clone()></div>
 					}.into_any()
 				}
 			}}
@@ -585,31 +1324,10 @@ fn RelatedVersionsSection(related_versions: Vec<RelatedVersionData>) -> impl Int
 			.into_iter()
 			.map(|related| {
 				view! {
-					<h4 class="is-size-5">
-						{related.major}
-						"."
-						{related.minor}
-						"."
-						{related.patch}
-					</h4>
-					<div class="box content" inner_html=parse_markdown(&related.changelog) />
+					<h4 class="is-size-5">{related.version_string()}</h4>
+					<div class="box content" inner_html=related.changelog_html />
 				}
 			})
 			.collect_view()}
 	}
 }
-
-fn parse_markdown(text: &str) -> String {
-	use pulldown_cmark::{Options, Parser, html};
-
-	let mut options = Options::empty();
-	options.insert(Options::ENABLE_FOOTNOTES);
-	options.insert(Options::ENABLE_GFM);
-	options.insert(Options::ENABLE_SMART_PUNCTUATION);
-	options.insert(Options::ENABLE_STRIKETHROUGH);
-	options.insert(Options::ENABLE_TABLES);
-	let parser = Parser::new_ext(text, options);
-	let mut html_output = String::new();
-	html::push_html(&mut html_output, parser);
-	html_output
-}
@@ -60,9 +60,9 @@ pub fn MinorVersionGroupComponent(group: MinorVersionGroup) -> impl IntoView {
 				</div>
 			</summary>
 			<div class="minor-versions">
-				<For each=move || versions.clone() key=|v| (v.major, v.minor, v.patch) let:v>
+				<For each=move || versions.clone() key=|v| v.version_string() let:v>
 					<A
-						href={format!("/versions/{}.{}.{}", v.major, v.minor, v.patch)}
+						href={format!("/versions/{}", v.version_string())}
 						{..}
 						class="level box minor-version"
 						class:has-background-warning-light={v.status == VersionStatus::Draft}
@@ -72,6 +72,9 @@ pub fn MinorVersionGroupComponent(group: MinorVersionGroup) -> impl IntoView {
 							<div class="level-item grouped-version">
 								{v.major} "." {v.minor}
 								<span class="version-patch">"." {v.patch}</span>
+								{(!v.prerelease.is_empty()).then(|| view! {
+									<span class="version-prerelease">"-" {v.prerelease.clone()}</span>
+								})}
 							</div>
 							{(v.status != VersionStatus::Published).then(|| {
 								view! {
@@ -44,6 +44,11 @@ pub fn StatusLegend() -> impl IntoView {
 				<span class="legend-label">"Up (seen a minute ago)"</span>
 			</span>
 			" "
+			<span class="legend-item">
+				<StatusDot up=ShortStatus::Degraded />
+				<span class="legend-label">"Degraded (up, but failing probes)"</span>
+			</span>
+			" "
 			<span class="legend-item">
 				<StatusDot up=ShortStatus::Blip />
 				<span class="legend-label">"Blip (missed 2 checks)"</span>
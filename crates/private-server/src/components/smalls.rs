@@ -4,7 +4,7 @@ use commons_types::{
 	device::DeviceRole,
 	server::{kind::ServerKind, rank::ServerRank},
 	status::ShortStatus,
-	version::VersionStatus,
+	version::{SupportStatus, VersionStatus},
 };
 use leptos::prelude::*;
 
@@ -114,6 +114,18 @@ pub fn VersionStatusBadge(status: VersionStatus) -> impl IntoView {
 	}
 }
 
+#[component]
+pub fn SupportStatusBadge(status: SupportStatus) -> impl IntoView {
+	view! {
+		<span class={format!("tag {}", match status {
+			SupportStatus::Active => "is-success",
+			SupportStatus::SecurityOnly => "is-warning",
+			SupportStatus::EndOfLife => "is-danger",
+			SupportStatus::Unknown => "is-light",
+		})}>{status}</span>
+	}
+}
+
 pub trait ToggleSignal {
 	// Toggles the signal, and returns the value it is after toggling
 	fn toggle_and_return(&self) -> bool;
@@ -2,12 +2,22 @@ pub mod admins;
 pub mod bestool;
 pub mod commons;
 pub mod devices;
+pub mod drift;
+pub mod probes;
 pub mod servers;
 pub mod sql;
 pub mod statuses;
+pub mod upgrade_plans;
 pub mod versions;
 
 #[cfg(feature = "ssr")]
 pub fn routes() -> axum::Router<crate::state::AppState> {
+	use axum::routing::get;
+
 	axum::Router::new()
+		.route("/api/private_server/drift.csv", get(drift::csv))
+		.route(
+			"/api/private_server/upgrade-plan.md",
+			get(upgrade_plans::markdown),
+		)
 }
@@ -243,6 +243,8 @@ mod ssr {
 				listed: s.listed,
 				cloud: s.cloud,
 				geolocation: s.geolocation,
+				default_channel: s.default_channel,
+				ping_timeout_secs: s.ping_timeout.as_ref().map(|timeout| timeout.0.as_secs()),
 			})
 			.collect())
 	}
@@ -288,6 +290,8 @@ mod ssr {
 				listed: s.listed,
 				cloud: s.cloud,
 				geolocation: s.geolocation,
+				default_channel: s.default_channel,
+				ping_timeout_secs: s.ping_timeout.as_ref().map(|timeout| timeout.0.as_secs()),
 			})
 			.collect())
 	}
@@ -0,0 +1,93 @@
+use commons_errors::Result;
+use commons_types::{
+	Uuid,
+	server::{kind::ServerKind, rank::ServerRank},
+	version::VersionStr,
+};
+use jiff::Timestamp;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftEntry {
+	pub server_id: Uuid,
+	pub name: Option<String>,
+	pub kind: ServerKind,
+	pub rank: Option<ServerRank>,
+	pub version: VersionStr,
+	pub minors_behind: u64,
+	pub on_minor_since: Timestamp,
+}
+
+#[server]
+pub async fn report(rank: Option<ServerRank>, kind: Option<ServerKind>) -> Result<Vec<DriftEntry>> {
+	ssr::report(rank, kind).await
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::csv;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+	use axum::{
+		extract::{Query, State},
+		http::header,
+		response::IntoResponse,
+	};
+	use commons_errors::Result;
+	use commons_types::server::{kind::ServerKind, rank::ServerRank};
+	use database::{Db, drift::ServerDrift};
+	use leptos::prelude::expect_context;
+	use leptos_axum::extract_with_state;
+	use serde::Deserialize;
+
+	use super::DriftEntry;
+	use crate::state::AppState;
+
+	pub async fn report(
+		rank: Option<ServerRank>,
+		kind: Option<ServerKind>,
+	) -> Result<Vec<DriftEntry>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		Ok(ServerDrift::for_fleet(&mut conn, rank, kind)
+			.await?
+			.into_iter()
+			.map(|drift| DriftEntry {
+				server_id: drift.server.id,
+				name: drift.server.name,
+				kind: drift.server.kind,
+				rank: drift.server.rank,
+				version: drift.version,
+				minors_behind: drift.minors_behind,
+				on_minor_since: drift.on_minor_since,
+			})
+			.collect())
+	}
+
+	#[derive(Debug, Deserialize)]
+	pub struct CsvQuery {
+		rank: Option<ServerRank>,
+		kind: Option<ServerKind>,
+	}
+
+	pub async fn csv(
+		State(db): State<Db>,
+		Query(query): Query<CsvQuery>,
+	) -> Result<impl IntoResponse> {
+		let mut conn = db.get().await?;
+		let drift = ServerDrift::for_fleet(&mut conn, query.rank, query.kind).await?;
+		Ok((
+			[
+				(header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+				(
+					header::CONTENT_DISPOSITION,
+					"attachment; filename=\"version-drift.csv\"",
+				),
+			],
+			ServerDrift::to_csv(&drift),
+		))
+	}
+}
@@ -0,0 +1,167 @@
+use commons_errors::Result;
+use commons_types::{
+	Uuid,
+	server::{kind::ServerKind, probe::ProbeType},
+};
+use jiff::Timestamp;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeInfo {
+	pub id: Uuid,
+	pub name: String,
+	pub probe_type: ProbeType,
+	/// Set if the probe applies to every server of this kind rather than just this one.
+	pub server_kind: Option<ServerKind>,
+	pub enabled: bool,
+	pub interval_secs: i64,
+	pub timeout_secs: i64,
+	pub description: String,
+	pub last_result: Option<ProbeResultInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResultInfo {
+	pub created_at: Timestamp,
+	pub success: bool,
+	pub latency_ms: i32,
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewProbeData {
+	pub name: String,
+	pub probe_type: ProbeType,
+	/// Apply the probe to every server of the same kind instead of just this one.
+	pub all_of_kind: bool,
+	pub interval_secs: u32,
+	pub timeout_secs: u32,
+	pub path: Option<String>,
+	pub expected_status: Option<u16>,
+	pub expected_body: Option<String>,
+	pub port: Option<u16>,
+}
+
+#[server]
+pub async fn list_for_server(server_id: Uuid) -> Result<Vec<ProbeInfo>> {
+	ssr::list_for_server(server_id).await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn create(server_id: Uuid, data: NewProbeData) -> Result<Uuid> {
+	ssr::create(server_id, data).await
+}
+
+#[server]
+pub async fn set_enabled(probe_id: Uuid, enabled: bool) -> Result<()> {
+	let db = crate::fns::commons::admin_guard().await?;
+	let mut conn = db.get().await?;
+	database::probes::ServerProbe::set_enabled(&mut conn, probe_id, enabled).await
+}
+
+#[server]
+pub async fn delete(probe_id: Uuid) -> Result<()> {
+	let db = crate::fns::commons::admin_guard().await?;
+	let mut conn = db.get().await?;
+	database::probes::ServerProbe::delete(&mut conn, probe_id).await
+}
+
+#[cfg(feature = "ssr")]
+mod ssr {
+	use std::collections::HashMap;
+
+	use axum::extract::State;
+	use commons_errors::Result;
+	use commons_types::server::probe::ProbeType;
+	use database::{
+		Db,
+		pg_duration::PgDuration,
+		probes::{NewServerProbe, ServerProbe, ServerProbeResult},
+		servers::Server,
+	};
+	use jiff::SignedDuration;
+	use leptos::prelude::expect_context;
+	use leptos_axum::extract_with_state;
+	use uuid::Uuid;
+
+	use super::{NewProbeData, ProbeInfo, ProbeResultInfo};
+	use crate::state::AppState;
+
+	pub async fn list_for_server(server_id: Uuid) -> Result<Vec<ProbeInfo>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		let server = Server::get_by_id(&mut conn, server_id).await?;
+		let probes = ServerProbe::for_server(&mut conn, &server).await?;
+		let mut results: HashMap<Uuid, ServerProbeResult> =
+			ServerProbeResult::latest_for_server(&mut conn, server_id)
+				.await?
+				.into_iter()
+				.map(|result| (result.probe_id, result))
+				.collect();
+
+		Ok(probes
+			.into_iter()
+			.map(|probe| ProbeInfo {
+				description: describe(&probe),
+				last_result: results.remove(&probe.id).map(|result| ProbeResultInfo {
+					created_at: result.created_at,
+					success: result.success,
+					latency_ms: result.latency_ms,
+					error: result.error,
+				}),
+				id: probe.id,
+				name: probe.name,
+				probe_type: probe.probe_type,
+				server_kind: probe.server_kind,
+				enabled: probe.enabled,
+				interval_secs: probe.check_interval.0.as_secs(),
+				timeout_secs: probe.timeout.0.as_secs(),
+			})
+			.collect())
+	}
+
+	pub async fn create(server_id: Uuid, data: NewProbeData) -> Result<Uuid> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		let server = Server::get_by_id(&mut conn, server_id).await?;
+		let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+		let probe = NewServerProbe {
+			server_id: (!data.all_of_kind).then_some(server.id),
+			server_kind: data.all_of_kind.then_some(server.kind),
+			name: data.name.trim().to_string(),
+			probe_type: data.probe_type,
+			check_interval: PgDuration(SignedDuration::from_secs(data.interval_secs.into())),
+			timeout: PgDuration(SignedDuration::from_secs(data.timeout_secs.into())),
+			path: non_empty(data.path),
+			expected_status: data.expected_status.map(i32::from),
+			expected_body: non_empty(data.expected_body),
+			port: data.port.map(i32::from),
+		}
+		.save(&mut conn)
+		.await?;
+
+		Ok(probe.id)
+	}
+
+	fn describe(probe: &ServerProbe) -> String {
+		match probe.probe_type {
+			ProbeType::Http => {
+				let mut desc = format!("GET {}", probe.path.as_deref().unwrap_or("/"));
+				match probe.expected_status {
+					Some(status) => desc.push_str(&format!(" → {status}")),
+					None => desc.push_str(" → 2xx"),
+				}
+				if let Some(body) = &probe.expected_body {
+					desc.push_str(&format!(" containing {body:?}"));
+				}
+				desc
+			}
+			ProbeType::Tcp => format!("connect to port {}", probe.port.unwrap_or_default()),
+			ProbeType::Dns => "resolve hostname".into(),
+		}
+	}
+}
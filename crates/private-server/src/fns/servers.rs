@@ -4,9 +4,14 @@ use commons_errors::Result;
 use commons_types::{
 	Uuid,
 	geo::GeoPoint,
-	server::{kind::ServerKind, rank::ServerRank},
+	requirements::RequirementViolation,
+	server::{
+		compat::{CompatibilityRule, VersionMismatch},
+		kind::ServerKind,
+		rank::ServerRank,
+	},
 	status::ShortStatus,
-	version::VersionStr,
+	version::{ReleaseChannel, VersionStr, YankSeverity},
 };
 use jiff::Timestamp;
 use leptos::serde_json::Value as JsonValue;
@@ -20,6 +25,8 @@ pub struct ServerDetailData {
 	pub last_status: Option<Arc<ServerLastStatusData>>,
 	pub up: ShortStatus,
 	pub child_servers: Vec<(ShortStatus, Arc<ServerInfo>)>,
+	/// Child servers whose versions violate a compatibility rule with this server.
+	pub version_mismatches: Vec<VersionMismatch>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +42,8 @@ pub struct ServerInfo {
 	pub listed: bool,
 	pub cloud: Option<bool>,
 	pub geolocation: Option<GeoPoint>,
+	pub default_channel: ReleaseChannel,
+	pub ping_timeout_secs: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,13 +53,41 @@ pub struct ServerLastStatusData {
 	pub version: Option<VersionStr>,
 	pub version_distance: Option<u64>,
 	pub min_chrome_version: Option<u32>,
+	/// Support lifecycle of the minor the server is running.
+	pub support: Option<super::versions::SupportData>,
 	pub platform: Option<String>,
 	pub postgres: Option<String>,
 	pub nodejs: Option<String>,
+	/// Where the PostgreSQL and Node.js versions are outside of what the release supports.
+	pub unsupported: Vec<RequirementViolation>,
 	pub timezone: Option<String>,
 	pub extra: JsonValue,
 }
 
+/// A server which last reported a version that has since been yanked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YankedServerEntry {
+	pub server_id: Uuid,
+	pub name: Option<String>,
+	pub kind: ServerKind,
+	pub rank: Option<ServerRank>,
+	pub version: VersionStr,
+	pub reason: String,
+	pub severity: YankSeverity,
+	pub replacement: Option<VersionStr>,
+}
+
+/// A server whose PostgreSQL or Node.js version is outside of what its release supports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsupportedServerEntry {
+	pub server_id: Uuid,
+	pub name: Option<String>,
+	pub kind: ServerKind,
+	pub rank: Option<ServerRank>,
+	pub version: VersionStr,
+	pub violations: Vec<RequirementViolation>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ServerDataUpdate {
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -87,6 +124,14 @@ pub struct ServerDataUpdate {
 		skip_serializing_if = "Option::is_none"
 	)]
 	pub geolocation: Option<Option<GeoPoint>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub default_channel: Option<ReleaseChannel>,
+	#[serde(
+		default,
+		deserialize_with = "deserialize_some",
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub ping_timeout_secs: Option<Option<u32>>,
 }
 
 fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
@@ -143,6 +188,31 @@ pub async fn get_detail(server_id: Uuid) -> Result<ServerDetailData> {
 	ssr::get_detail(server_id).await
 }
 
+#[server]
+pub async fn list_mismatches() -> Result<Vec<VersionMismatch>> {
+	ssr::list_mismatches().await
+}
+
+#[server]
+pub async fn get_compatibility_rules() -> Result<Vec<CompatibilityRule>> {
+	ssr::get_compatibility_rules().await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn set_compatibility_rules(rules: Vec<CompatibilityRule>) -> Result<()> {
+	ssr::set_compatibility_rules(rules).await
+}
+
+#[server]
+pub async fn list_on_yanked() -> Result<Vec<YankedServerEntry>> {
+	ssr::list_on_yanked().await
+}
+
+#[server]
+pub async fn list_unsupported() -> Result<Vec<UnsupportedServerEntry>> {
+	ssr::list_unsupported().await
+}
+
 #[server(input = leptos::server_fn::codec::Json)]
 pub async fn update(server_id: Uuid, data: ServerDataUpdate) -> Result<()> {
 	ssr::update(server_id, data).await
@@ -174,16 +244,27 @@ mod ssr {
 	use axum::extract::State;
 	use commons_errors::{AppError, Result};
 	use futures::future::join;
+	use jiff::SignedDuration;
 
 	use commons_types::server::MetaTicket;
-	use commons_types::server::{kind::ServerKind, rank::ServerRank};
+	use commons_types::server::{
+		compat::{CompatibilityRule, VersionMismatch},
+		kind::ServerKind,
+		rank::ServerRank,
+	};
 	use database::{
 		Db,
+		compatibility_rules::CompatibilityRules,
 		devices::{Device, DeviceConnection},
+		pg_duration::PgDuration,
+		platform_requirements::{PlatformRequirement, UnsupportedServer},
+		probes::ServerProbeResult,
 		servers::{PartialServer, Server},
 		statuses::Status,
+		support_policies::MinorSupport,
 		url_field::UrlField,
 		versions::Version,
+		yanks::YankedServer,
 	};
 	use leptos::prelude::expect_context;
 	use leptos_axum::extract_with_state;
@@ -245,6 +326,8 @@ mod ssr {
 				listed: s.listed,
 				cloud: s.cloud,
 				geolocation: s.geolocation,
+				default_channel: s.default_channel,
+				ping_timeout_secs: s.ping_timeout.as_ref().map(|timeout| timeout.0.as_secs()),
 			})
 			.collect())
 	}
@@ -284,6 +367,11 @@ mod ssr {
 			listed: server.listed,
 			cloud: server.cloud,
 			geolocation: server.geolocation,
+			default_channel: server.default_channel,
+			ping_timeout_secs: server
+				.ping_timeout
+				.as_ref()
+				.map(|timeout| timeout.0.as_secs()),
 		})
 	}
 
@@ -334,11 +422,20 @@ mod ssr {
 			listed: server.listed,
 			cloud: server.cloud,
 			geolocation: server.geolocation,
+			default_channel: server.default_channel,
+			ping_timeout_secs: server
+				.ping_timeout
+				.as_ref()
+				.map(|timeout| timeout.0.as_secs()),
 		};
 
+		let probe_results = ServerProbeResult::latest_for_servers(&mut conn, &[server.id])
+			.await?
+			.remove(&server.id)
+			.unwrap_or_default();
 		let up = status
 			.as_ref()
-			.map(|s| s.short_status())
+			.map(|s| s.short_status_with_probes(&probe_results))
 			.unwrap_or_default();
 
 		let last_status = if let Some(st) = status.as_ref() {
@@ -363,15 +460,42 @@ mod ssr {
 				None
 			};
 
+			let support = if let Some(ref version) = st.version {
+				MinorSupport::for_version(&mut conn, version.clone())
+					.await
+					.ok()
+					.map(Into::into)
+			} else {
+				None
+			};
+
+			let unsupported = if let Some(ref version) = st.version {
+				let requirements = PlatformRequirement::get_for_minor(
+					&mut conn,
+					version.0.major as i32,
+					version.0.minor as i32,
+				)
+				.await?;
+				PlatformRequirement::check_stack(
+					&requirements,
+					postgres.as_deref(),
+					nodejs.as_deref(),
+				)
+			} else {
+				Vec::new()
+			};
+
 			Some(super::ServerLastStatusData {
 				id: st.id,
 				created_at: st.created_at,
 				version: st.version.clone(),
 				version_distance,
 				min_chrome_version,
+				support,
 				platform,
 				postgres,
 				nodejs,
+				unsupported,
 				timezone: st
 					.extra("timezone")
 					.and_then(|s| s.as_str().map(|s| s.to_string())),
@@ -388,6 +512,12 @@ mod ssr {
 			None
 		};
 
+		let version_mismatches = if server.kind == ServerKind::Central {
+			Status::version_mismatches_for_central(&mut conn, &server).await?
+		} else {
+			Vec::new()
+		};
+
 		let child_servers = if server.kind.to_string() == "central" {
 			let children = server.get_children(&mut conn).await?;
 
@@ -397,6 +527,8 @@ mod ssr {
 				// Fetch child statuses in a single optimised query
 				let child_ids: Vec<Uuid> = children.iter().map(|c| c.id).collect();
 				let statuses = Status::latest_for_servers(&mut conn, &child_ids).await?;
+				let mut probe_results =
+					ServerProbeResult::latest_for_servers(&mut conn, &child_ids).await?;
 
 				// Create a map of server_id -> status for O(1) lookup
 				let status_map: std::collections::HashMap<Uuid, &Status> =
@@ -407,7 +539,13 @@ mod ssr {
 					.into_iter()
 					.map(|child| {
 						let child_status = status_map.get(&child.id).copied();
-						let child_up = child_status.map(|s| s.short_status()).unwrap_or_default();
+						let child_up = child_status
+							.map(|s| {
+								s.short_status_with_probes(
+									&probe_results.remove(&child.id).unwrap_or_default(),
+								)
+							})
+							.unwrap_or_default();
 
 						(
 							child_up,
@@ -420,6 +558,11 @@ mod ssr {
 								listed: child.listed,
 								cloud: child.cloud,
 								geolocation: child.geolocation,
+								default_channel: child.default_channel,
+								ping_timeout_secs: child
+									.ping_timeout
+									.as_ref()
+									.map(|timeout| timeout.0.as_secs()),
 								device_id: child.device_id,
 								parent_server_id: Some(server.id),
 								parent_server_name: server.name.clone(),
@@ -438,9 +581,71 @@ mod ssr {
 			last_status: last_status.map(Arc::new),
 			up,
 			child_servers,
+			version_mismatches,
 		})
 	}
 
+	pub async fn list_mismatches() -> Result<Vec<VersionMismatch>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+		Status::version_mismatches(&mut conn).await
+	}
+
+	pub async fn get_compatibility_rules() -> Result<Vec<CompatibilityRule>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+		CompatibilityRules::enabled(&mut conn).await
+	}
+
+	pub async fn set_compatibility_rules(rules: Vec<CompatibilityRule>) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+		CompatibilityRules::set(&mut conn, rules).await?;
+		Ok(())
+	}
+
+	pub async fn list_on_yanked() -> Result<Vec<super::YankedServerEntry>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		Ok(YankedServer::for_fleet(&mut conn)
+			.await?
+			.into_iter()
+			.map(|yanked| super::YankedServerEntry {
+				server_id: yanked.server.id,
+				name: yanked.server.name,
+				kind: yanked.server.kind,
+				rank: yanked.server.rank,
+				version: yanked.notice.version,
+				reason: yanked.notice.reason,
+				severity: yanked.notice.severity,
+				replacement: yanked.notice.replacement,
+			})
+			.collect())
+	}
+
+	pub async fn list_unsupported() -> Result<Vec<super::UnsupportedServerEntry>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		Ok(UnsupportedServer::for_fleet(&mut conn)
+			.await?
+			.into_iter()
+			.map(|unsupported| super::UnsupportedServerEntry {
+				server_id: unsupported.server.id,
+				name: unsupported.server.name,
+				kind: unsupported.server.kind,
+				rank: unsupported.server.rank,
+				version: unsupported.version,
+				violations: unsupported.violations,
+			})
+			.collect())
+	}
+
 	pub async fn update(server_id: Uuid, data: ServerDataUpdate) -> Result<()> {
 		let db = crate::fns::commons::admin_guard().await?;
 		let mut conn = db.get().await?;
@@ -462,6 +667,10 @@ mod ssr {
 			listed: data.listed,
 			cloud: data.cloud,
 			geolocation: data.geolocation,
+			default_channel: data.default_channel,
+			ping_timeout: data
+				.ping_timeout_secs
+				.map(|secs| secs.map(|secs| PgDuration(SignedDuration::from_secs(secs.into())))),
 		};
 
 		Server::update(&mut conn, server_id, update_data).await?;
@@ -501,6 +710,8 @@ mod ssr {
 				listed: s.listed,
 				cloud: s.cloud,
 				geolocation: s.geolocation,
+				default_channel: s.default_channel,
+				ping_timeout_secs: s.ping_timeout.as_ref().map(|timeout| timeout.0.as_secs()),
 			})
 			.collect())
 	}
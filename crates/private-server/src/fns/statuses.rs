@@ -66,10 +66,12 @@ mod ssr {
 	use axum::extract::State;
 	use commons_errors::Result;
 	use commons_types::{
-		server::{cards::FacilityServerStatus, kind::ServerKind},
+		server::{cards::FacilityServerStatus, compat::CompatibilityRule, kind::ServerKind},
 		version::VersionStr,
 	};
-	use database::{Db, servers::Server, statuses::Status, versions::Version};
+	use database::{
+		Db, probes::ServerProbeResult, servers::Server, statuses::Status, versions::Version,
+	};
 	use itertools::Itertools;
 	use leptos::prelude::expect_context;
 	use leptos_axum::extract_with_state;
@@ -140,32 +142,53 @@ mod ssr {
 			.await?
 			.as_semver();
 
+		let facilities = central.get_children(&mut conn).await?;
+		let facility_ids = facilities.iter().map(|f| f.id).collect::<Vec<_>>();
+		let mut probe_results = ServerProbeResult::latest_for_servers(
+			&mut conn,
+			&[id]
+				.into_iter()
+				.chain(facility_ids.iter().copied())
+				.collect::<Vec<_>>(),
+		)
+		.await?;
+
 		let central_status = Status::latest_for_server(&mut conn, id).await?;
 		let central_up = central_status
 			.as_ref()
-			.map(|s| s.short_status())
+			.map(|s| s.short_status_with_probes(&probe_results.remove(&id).unwrap_or_default()))
 			.unwrap_or_default();
 		let version_distance = central_status
 			.as_ref()
 			.and_then(|s| s.distance_from_version(&latest_version));
 
-		let facilities = central.get_children(&mut conn).await?;
-		let facility_ids = facilities.iter().map(|f| f.id).collect::<Vec<_>>();
 		let facility_statuses = Status::latest_for_servers(&mut conn, &facility_ids)
 			.await?
 			.into_iter()
 			.map(|s| (s.server_id, s))
 			.collect::<HashMap<_, _>>();
+		let mut mismatches: HashMap<Uuid, Vec<CompatibilityRule>> =
+			Status::version_mismatches_for_central(&mut conn, &central)
+				.await?
+				.into_iter()
+				.map(|mismatch| (mismatch.facility_id, mismatch.violations))
+				.collect();
 		let facility_servers = facilities
 			.into_iter()
 			.map(|f| {
 				let facility_status = facility_statuses.get(&f.id);
+				let mismatches = mismatches.remove(&f.id).unwrap_or_default();
 				FacilityServerStatus {
 					id: f.id,
 					name: f.name.clone().unwrap_or_default(),
 					up: facility_status
-						.map(|s| s.short_status())
+						.map(|s| {
+							s.short_status_with_probes(
+								&probe_results.remove(&f.id).unwrap_or_default(),
+							)
+						})
 						.unwrap_or_default(),
+					mismatches,
 				}
 			})
 			.collect();
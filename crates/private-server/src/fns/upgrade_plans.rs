@@ -0,0 +1,206 @@
+use commons_errors::Result;
+use commons_types::{
+	Uuid,
+	server::{kind::ServerKind, rank::ServerRank},
+	version::{UpdateUrgency, VersionStr},
+};
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStopData {
+	pub version: VersionStr,
+	pub urgency: UpdateUrgency,
+	pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanServerEntry {
+	pub server_id: Uuid,
+	pub name: Option<String>,
+	pub kind: ServerKind,
+	pub rank: Option<ServerRank>,
+	pub version: Option<VersionStr>,
+	pub stops: Vec<PlanStopData>,
+}
+
+/// A facility which needs to be upgraded alongside its central.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanFacilityEntry {
+	pub server_id: Uuid,
+	pub name: Option<String>,
+	pub central_id: Uuid,
+	pub version: Option<VersionStr>,
+	/// Why it needs to move, or empty if it hasn't reported a version to tell.
+	pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanChangelogEntry {
+	pub version: VersionStr,
+	pub changelog: String,
+	/// The changelog rendered and sanitised on the server.
+	pub changelog_html: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradePlanData {
+	pub target: VersionStr,
+	pub servers: Vec<PlanServerEntry>,
+	pub stops: Vec<PlanStopData>,
+	pub facilities: Vec<PlanFacilityEntry>,
+	pub changelog: Vec<PlanChangelogEntry>,
+}
+
+/// Published releases which can be planned to, newest first. Pre-releases aren't targets.
+#[server]
+pub async fn list_targets() -> Result<Vec<VersionStr>> {
+	ssr::list_targets().await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn plan(server_ids: Vec<Uuid>, target: VersionStr) -> Result<UpgradePlanData> {
+	ssr::plan(server_ids, target).await
+}
+
+#[cfg(feature = "ssr")]
+pub use ssr::markdown;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+	use std::str::FromStr as _;
+
+	use axum::{
+		extract::{Query, State},
+		http::header,
+		response::IntoResponse,
+	};
+	use commons_errors::{AppError, Result};
+	use commons_types::{Uuid, markdown, version::VersionStr};
+	use database::{
+		Db,
+		upgrade_plans::{PlanStop, UpgradePlan},
+		versions::Version,
+	};
+	use leptos::prelude::expect_context;
+	use leptos_axum::extract_with_state;
+	use serde::Deserialize;
+
+	use super::{
+		PlanChangelogEntry, PlanFacilityEntry, PlanServerEntry, PlanStopData, UpgradePlanData,
+	};
+	use crate::state::AppState;
+
+	impl From<PlanStop> for PlanStopData {
+		fn from(stop: PlanStop) -> Self {
+			Self {
+				version: stop.version,
+				urgency: stop.urgency,
+				required: stop.required,
+			}
+		}
+	}
+
+	impl From<UpgradePlan> for UpgradePlanData {
+		fn from(plan: UpgradePlan) -> Self {
+			Self {
+				target: plan.target,
+				servers: plan
+					.servers
+					.into_iter()
+					.map(|planned| PlanServerEntry {
+						server_id: planned.server.id,
+						name: planned.server.name,
+						kind: planned.server.kind,
+						rank: planned.server.rank,
+						version: planned.version,
+						stops: planned.stops.into_iter().map(Into::into).collect(),
+					})
+					.collect(),
+				stops: plan.stops.into_iter().map(Into::into).collect(),
+				facilities: plan
+					.facilities
+					.into_iter()
+					.map(|moving| PlanFacilityEntry {
+						server_id: moving.facility.id,
+						name: moving.facility.name,
+						central_id: moving.central_id,
+						version: moving.version,
+						reasons: moving
+							.violations
+							.iter()
+							.map(|rule| rule.to_string())
+							.collect(),
+					})
+					.collect(),
+				changelog: plan
+					.changelog
+					.into_iter()
+					.map(|version| PlanChangelogEntry {
+						version: VersionStr(version.as_semver()),
+						changelog_html: markdown::render(&version.changelog),
+						changelog: version.changelog,
+					})
+					.collect(),
+			}
+		}
+	}
+
+	pub async fn list_targets() -> Result<Vec<VersionStr>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		Ok(Version::get_all(&mut conn)
+			.await?
+			.into_iter()
+			.map(|version| VersionStr(version.as_semver()))
+			.collect())
+	}
+
+	pub async fn plan(server_ids: Vec<Uuid>, target: VersionStr) -> Result<UpgradePlanData> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		Ok(UpgradePlan::for_servers(&mut conn, &server_ids, target)
+			.await?
+			.into())
+	}
+
+	#[derive(Debug, Deserialize)]
+	pub struct MarkdownQuery {
+		/// Comma-separated server ids.
+		servers: String,
+		target: String,
+	}
+
+	pub async fn markdown(
+		State(db): State<Db>,
+		Query(query): Query<MarkdownQuery>,
+	) -> Result<impl IntoResponse> {
+		let server_ids = query
+			.servers
+			.split(',')
+			.map(|id| Uuid::from_str(id.trim()))
+			.collect::<std::result::Result<Vec<_>, _>>()
+			.map_err(AppError::custom)?;
+		let target = VersionStr::from_str(&query.target)?;
+
+		let mut conn = db.get().await?;
+		let plan = UpgradePlan::for_servers(&mut conn, &server_ids, target).await?;
+		Ok((
+			[
+				(
+					header::CONTENT_TYPE,
+					"text/markdown; charset=utf-8".to_string(),
+				),
+				(
+					header::CONTENT_DISPOSITION,
+					format!("attachment; filename=\"upgrade-plan-{}.md\"", plan.target),
+				),
+			],
+			plan.to_markdown(),
+		))
+	}
+}
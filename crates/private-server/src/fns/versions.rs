@@ -1,6 +1,9 @@
 use commons_errors::Result;
-use commons_types::version::VersionStatus;
-use jiff::Timestamp;
+use commons_types::{
+	requirements::Platform,
+	version::{ReleaseChannel, SupportStatus, UpdateUrgency, VersionStatus},
+};
+use jiff::{Timestamp, civil::Date};
 use leptos::server;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -10,6 +13,7 @@ pub struct VersionData {
 	pub major: i32,
 	pub minor: i32,
 	pub patch: i32,
+	pub prerelease: String,
 	pub status: VersionStatus,
 	pub created_at: Timestamp,
 }
@@ -31,13 +35,66 @@ pub struct VersionDetail {
 	pub major: i32,
 	pub minor: i32,
 	pub patch: i32,
+	pub prerelease: String,
+	pub build: String,
 	pub status: VersionStatus,
+	pub channels: Vec<ReleaseChannel>,
+	pub urgency: UpdateUrgency,
+	pub stepping_stone: bool,
 	pub created_at: Timestamp,
 	pub updated_at: Timestamp,
 	pub changelog: String,
+	/// The changelog rendered and sanitised on the server.
+	pub changelog_html: String,
 	pub min_chrome_version: Option<u32>,
 	pub is_latest_in_minor: bool,
 	pub related_versions: Vec<RelatedVersionData>,
+	pub support: SupportData,
+	pub requirements: Vec<PlatformRequirementData>,
+}
+
+/// The support lifecycle of a minor release.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportData {
+	pub major: i32,
+	pub minor: i32,
+	pub released_at: Option<Timestamp>,
+	pub active_support_until: Option<Date>,
+	pub security_support_until: Option<Date>,
+	pub status: SupportStatus,
+}
+
+#[cfg(feature = "ssr")]
+impl From<database::support_policies::MinorSupport> for SupportData {
+	fn from(support: database::support_policies::MinorSupport) -> Self {
+		Self {
+			major: support.major,
+			minor: support.minor,
+			released_at: support.released_at,
+			active_support_until: support.active_support_until,
+			security_support_until: support.security_support_until,
+			status: support.status,
+		}
+	}
+}
+
+/// The versions of a platform that a minor release supports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformRequirementData {
+	pub platform: Platform,
+	pub min_version: Option<String>,
+	pub max_version: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<database::platform_requirements::PlatformRequirement> for PlatformRequirementData {
+	fn from(requirement: database::platform_requirements::PlatformRequirement) -> Self {
+		Self {
+			platform: requirement.platform,
+			min_version: requirement.min_version,
+			max_version: requirement.max_version,
+		}
+	}
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,7 +102,50 @@ pub struct RelatedVersionData {
 	pub major: i32,
 	pub minor: i32,
 	pub patch: i32,
+	pub prerelease: String,
 	pub changelog: String,
+	/// The changelog rendered and sanitised on the server.
+	pub changelog_html: String,
+}
+
+/// Format a version number, with its pre-release identifiers if it has any.
+pub fn format_version(major: i32, minor: i32, patch: i32, prerelease: &str) -> String {
+	if prerelease.is_empty() {
+		format!("{major}.{minor}.{patch}")
+	} else {
+		format!("{major}.{minor}.{patch}-{prerelease}")
+	}
+}
+
+macro_rules! impl_version_string {
+	($($ty:ty),+) => {$(
+		impl $ty {
+			pub fn version_string(&self) -> String {
+				format_version(self.major, self.minor, self.patch, &self.prerelease)
+			}
+		}
+	)+};
+}
+
+impl_version_string!(VersionData, VersionDetail, RelatedVersionData);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutData {
+	pub allowlist: Vec<RolloutServer>,
+	pub percentage: i32,
+	pub paused: bool,
+	pub updated_at: Timestamp,
+	/// How many servers the version is currently offered to
+	pub eligible_servers: u64,
+	/// How many servers could be offered the version, i.e. those identified by a device
+	pub total_servers: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutServer {
+	pub id: Uuid,
+	pub name: Option<String>,
+	pub host: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +162,68 @@ pub struct ArtifactData {
 	pub has_range_override: bool,
 	/// If true, this is the artifact that will be served to public API clients
 	pub is_used_in_public_api: bool,
+	/// The last time the download URL was checked, if it has been
+	pub link_check: Option<LinkCheckData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkCheckData {
+	pub checked_at: Timestamp,
+	pub healthy: bool,
+	pub status_code: Option<i32>,
+	pub error: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<database::link_checks::ArtifactLinkCheck> for LinkCheckData {
+	fn from(check: database::link_checks::ArtifactLinkCheck) -> Self {
+		Self {
+			checked_at: check.checked_at,
+			healthy: check.healthy,
+			status_code: check.status_code,
+			error: check.error,
+		}
+	}
+}
+
+/// Whether a version has the same kinds of artifacts as the previous release, with working links.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseCompletenessData {
+	pub previous: Option<String>,
+	/// Artifact types and platforms the previous release had, as `type/platform`
+	pub missing: Vec<String>,
+	pub broken: usize,
+	pub unchecked: usize,
+}
+
+impl ReleaseCompletenessData {
+	pub fn is_complete(&self) -> bool {
+		self.missing.is_empty() && self.broken == 0
+	}
+}
+
+/// Downloads of one type of artifact on one platform, for a version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadCountData {
+	pub artifact_type: String,
+	pub platform: String,
+	pub downloads: i64,
+	/// How many different client networks downloaded it
+	pub networks: i64,
+	pub last_downloaded_at: Timestamp,
+}
+
+#[cfg(feature = "ssr")]
+impl From<database::artifact_downloads::DownloadCount> for DownloadCountData {
+	fn from(count: database::artifact_downloads::DownloadCount) -> Self {
+		Self {
+			artifact_type: count.artifact_type,
+			platform: count.platform,
+			downloads: count.downloads,
+			networks: count.networks,
+			last_downloaded_at: count.last_downloaded_at,
+		}
+	}
 }
 
 #[server]
@@ -84,11 +246,36 @@ pub async fn get_artifacts_by_version_id(version_id: Uuid) -> Result<Vec<Artifac
 	ssr::get_artifacts_by_version_id(version_id).await
 }
 
+#[server]
+pub async fn get_release_completeness(version_id: Uuid) -> Result<ReleaseCompletenessData> {
+	ssr::get_release_completeness(version_id).await
+}
+
+#[server]
+pub async fn get_download_counts(version_id: Uuid) -> Result<Vec<DownloadCountData>> {
+	ssr::get_download_counts(version_id).await
+}
+
 #[server]
 pub async fn update_version_status(version: String, status: String) -> Result<()> {
 	ssr::update_version_status(version, status).await
 }
 
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn update_version_channels(version: String, channels: Vec<ReleaseChannel>) -> Result<()> {
+	ssr::update_version_channels(version, channels).await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn update_version_urgency(version: String, urgency: UpdateUrgency) -> Result<()> {
+	ssr::update_version_urgency(version, urgency).await
+}
+
+#[server]
+pub async fn update_version_stepping_stone(version: String, stepping_stone: bool) -> Result<()> {
+	ssr::update_version_stepping_stone(version, stepping_stone).await
+}
+
 #[server]
 pub async fn update_version_changelog(version: String, changelog: String) -> Result<()> {
 	ssr::update_version_changelog(version, changelog).await
@@ -119,16 +306,68 @@ pub async fn delete_artifact(artifact_id: Uuid) -> Result<()> {
 	ssr::delete_artifact(artifact_id).await
 }
 
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn update_support_policy(
+	major: i32,
+	minor: i32,
+	active_support_until: Option<Date>,
+	security_support_until: Option<Date>,
+) -> Result<()> {
+	ssr::update_support_policy(major, minor, active_support_until, security_support_until).await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn update_platform_requirements(
+	major: i32,
+	minor: i32,
+	requirements: Vec<PlatformRequirementData>,
+) -> Result<()> {
+	ssr::update_platform_requirements(major, minor, requirements).await
+}
+
+#[server]
+pub async fn get_rollout(version_id: Uuid) -> Result<Option<RolloutData>> {
+	ssr::get_rollout(version_id).await
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn update_rollout(
+	version_id: Uuid,
+	allowlist: Vec<Uuid>,
+	percentage: i32,
+	paused: bool,
+) -> Result<()> {
+	ssr::update_rollout(version_id, allowlist, percentage, paused).await
+}
+
+#[server]
+pub async fn remove_rollout(version_id: Uuid) -> Result<()> {
+	ssr::remove_rollout(version_id).await
+}
+
 #[cfg(feature = "ssr")]
 mod ssr {
 	use super::*;
-	use std::collections::BTreeMap;
+	use std::collections::{BTreeMap, HashMap};
 	use std::str::FromStr;
 
 	use axum::extract::State;
 	use commons_errors::Result;
-	use commons_types::version::{VersionStatus, VersionStr};
-	use database::{Db, artifacts::Artifact, versions::Version};
+	use commons_types::{
+		markdown,
+		version::{VersionStatus, VersionStr},
+	};
+	use database::{
+		Db,
+		artifact_downloads::DownloadCount,
+		artifacts::Artifact,
+		link_checks::{ArtifactLinkCheck, ReleaseCompleteness},
+		platform_requirements::{NewPlatformRequirement, PlatformRequirement},
+		rollouts::{NewVersionRollout, VersionRollout},
+		servers::Server,
+		support_policies::{MinorSupport, NewSupportPolicy, SupportPolicy},
+		versions::Version,
+	};
 	use jiff::Timestamp;
 	use leptos::prelude::expect_context;
 	use leptos_axum::extract_with_state;
@@ -157,10 +396,13 @@ mod ssr {
 
 				let count = versions.len();
 
-				// Filter to only published versions for calculating latest patch and dates
+				// Filter to only published releases for calculating latest patch and dates
 				let published_versions: Vec<_> = versions
 					.iter()
-					.filter(|v| v.status == commons_types::version::VersionStatus::Published)
+					.filter(|v| {
+						v.status == commons_types::version::VersionStatus::Published
+							&& !v.is_prerelease()
+					})
 					.collect();
 
 				let latest_patch = published_versions.first().map(|v| v.patch).unwrap_or(0);
@@ -187,6 +429,7 @@ mod ssr {
 						major: v.major,
 						minor: v.minor,
 						patch: v.patch,
+						prerelease: v.prerelease,
 						status: v.status,
 						created_at: v.created_at,
 					})
@@ -246,22 +489,40 @@ mod ssr {
 				major: v.major,
 				minor: v.minor,
 				patch: v.patch,
+				prerelease: v.prerelease,
+				changelog_html: markdown::render(&v.changelog),
 				changelog: v.changelog,
 			})
 			.collect();
 
+		let support = MinorSupport::for_version(&mut conn, version.clone()).await?;
+		let requirements = PlatformRequirement::get_for_minor(
+			&mut conn,
+			version_record.major,
+			version_record.minor,
+		)
+		.await?;
+
 		Ok(super::VersionDetail {
 			id: version_record.id,
 			major: version_record.major,
 			minor: version_record.minor,
 			patch: version_record.patch,
+			prerelease: version_record.prerelease,
+			build: version_record.build,
 			status: version_record.status,
+			channels: version_record.channels,
+			urgency: version_record.urgency,
+			stepping_stone: version_record.stepping_stone,
 			created_at: version_record.created_at,
 			updated_at: version_record.updated_at,
+			changelog_html: markdown::render(&version_record.changelog),
 			changelog: version_record.changelog,
 			min_chrome_version,
 			is_latest_in_minor,
 			related_versions,
+			support: support.into(),
+			requirements: requirements.into_iter().map(Into::into).collect(),
 		})
 	}
 
@@ -275,6 +536,14 @@ mod ssr {
 		let artifacts_with_metadata =
 			Artifact::get_for_version_with_metadata(&mut conn, version_record.id).await?;
 
+		let ids: Vec<Uuid> = artifacts_with_metadata.iter().map(|(a, ..)| a.id).collect();
+		let mut link_checks: HashMap<Uuid, ArtifactLinkCheck> =
+			ArtifactLinkCheck::get_for_artifacts(&mut conn, &ids)
+				.await?
+				.into_iter()
+				.map(|check| (check.artifact_id, check))
+				.collect();
+
 		Ok(artifacts_with_metadata
 			.into_iter()
 			.map(
@@ -287,6 +556,7 @@ mod ssr {
 					version_range_pattern: a.version_range_pattern,
 					has_range_override,
 					is_used_in_public_api,
+					link_check: link_checks.remove(&a.id).map(Into::into),
 				},
 			)
 			.collect())
@@ -301,6 +571,14 @@ mod ssr {
 		let artifacts_with_metadata =
 			Artifact::get_for_version_all_matches_with_metadata(&mut conn, version_id).await?;
 
+		let ids: Vec<Uuid> = artifacts_with_metadata.iter().map(|(a, ..)| a.id).collect();
+		let mut link_checks: HashMap<Uuid, ArtifactLinkCheck> =
+			ArtifactLinkCheck::get_for_artifacts(&mut conn, &ids)
+				.await?
+				.into_iter()
+				.map(|check| (check.artifact_id, check))
+				.collect();
+
 		Ok(artifacts_with_metadata
 			.into_iter()
 			.map(
@@ -313,11 +591,41 @@ mod ssr {
 					version_range_pattern: a.version_range_pattern,
 					has_range_override,
 					is_used_in_public_api,
+					link_check: link_checks.remove(&a.id).map(Into::into),
 				},
 			)
 			.collect())
 	}
 
+	pub async fn get_release_completeness(
+		version_id: Uuid,
+	) -> Result<super::ReleaseCompletenessData> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		let completeness = ReleaseCompleteness::for_version(&mut conn, version_id).await?;
+		Ok(super::ReleaseCompletenessData {
+			previous: completeness.previous,
+			missing: completeness
+				.missing
+				.into_iter()
+				.map(|kind| format!("{}/{}", kind.artifact_type, kind.platform))
+				.collect(),
+			broken: completeness.broken,
+			unchecked: completeness.unchecked,
+		})
+	}
+
+	pub async fn get_download_counts(version_id: Uuid) -> Result<Vec<super::DownloadCountData>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		let counts = DownloadCount::for_version(&mut conn, version_id).await?;
+		Ok(counts.into_iter().map(Into::into).collect())
+	}
+
 	pub async fn update_version_status(version_str: String, status_str: String) -> Result<()> {
 		let db = crate::fns::commons::admin_guard().await?;
 		let mut conn = db.get().await?;
@@ -342,6 +650,48 @@ mod ssr {
 		Ok(())
 	}
 
+	pub async fn update_version_channels(
+		version_str: String,
+		channels: Vec<ReleaseChannel>,
+	) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		if channels.is_empty() {
+			return Err(commons_errors::AppError::custom(
+				"A version must be released to at least one channel",
+			));
+		}
+
+		let version = VersionStr::from_str(&version_str)?;
+		Version::update_channels(&mut conn, version, channels).await?;
+
+		Ok(())
+	}
+
+	pub async fn update_version_urgency(version_str: String, urgency: UpdateUrgency) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		let version = VersionStr::from_str(&version_str)?;
+		Version::update_urgency(&mut conn, version, urgency).await?;
+
+		Ok(())
+	}
+
+	pub async fn update_version_stepping_stone(
+		version_str: String,
+		stepping_stone: bool,
+	) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		let version = VersionStr::from_str(&version_str)?;
+		Version::update_stepping_stone(&mut conn, version, stepping_stone).await?;
+
+		Ok(())
+	}
+
 	pub async fn update_version_changelog(
 		version_str: String,
 		new_changelog: String,
@@ -404,6 +754,7 @@ mod ssr {
 			version_range_pattern: None,
 			has_range_override: false,
 			is_used_in_public_api: true,
+			link_check: None,
 		})
 	}
 
@@ -415,4 +766,139 @@ mod ssr {
 
 		Ok(())
 	}
+
+	pub async fn get_rollout(version_id: Uuid) -> Result<Option<super::RolloutData>> {
+		let state = expect_context::<AppState>();
+		let State(db): State<Db> = extract_with_state(&state).await?;
+		let mut conn = db.get().await?;
+
+		let Some(rollout) = VersionRollout::get_for_version(&mut conn, version_id).await? else {
+			return Ok(None);
+		};
+
+		let (eligible_servers, total_servers) = rollout.eligible_servers(&mut conn).await?;
+
+		let mut allowlist = Vec::with_capacity(rollout.allowlist.len());
+		for id in &rollout.allowlist {
+			allowlist.push(match Server::get_by_id(&mut conn, *id).await {
+				Ok(server) => super::RolloutServer {
+					id: server.id,
+					name: server.name,
+					host: server.host.0.to_string(),
+				},
+				// the server may have been deleted since it was added
+				Err(_) => super::RolloutServer {
+					id: *id,
+					name: None,
+					host: String::new(),
+				},
+			});
+		}
+
+		Ok(Some(super::RolloutData {
+			allowlist,
+			percentage: rollout.percentage,
+			paused: rollout.paused,
+			updated_at: rollout.updated_at,
+			eligible_servers,
+			total_servers,
+		}))
+	}
+
+	pub async fn update_rollout(
+		version_id: Uuid,
+		mut allowlist: Vec<Uuid>,
+		percentage: i32,
+		paused: bool,
+	) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		allowlist.sort();
+		allowlist.dedup();
+
+		VersionRollout::upsert(
+			&mut conn,
+			NewVersionRollout {
+				version_id,
+				allowlist,
+				percentage,
+				paused,
+			},
+		)
+		.await?;
+
+		Ok(())
+	}
+
+	pub async fn remove_rollout(version_id: Uuid) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		VersionRollout::remove(&mut conn, version_id).await?;
+
+		Ok(())
+	}
+
+	pub async fn update_support_policy(
+		major: i32,
+		minor: i32,
+		active_support_until: Option<Date>,
+		security_support_until: Option<Date>,
+	) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		if active_support_until.is_none() && security_support_until.is_none() {
+			SupportPolicy::remove(&mut conn, major, minor).await?;
+		} else {
+			SupportPolicy::upsert(
+				&mut conn,
+				NewSupportPolicy {
+					major,
+					minor,
+					active_support_until,
+					security_support_until,
+				},
+			)
+			.await?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn update_platform_requirements(
+		major: i32,
+		minor: i32,
+		requirements: Vec<PlatformRequirementData>,
+	) -> Result<()> {
+		let db = crate::fns::commons::admin_guard().await?;
+		let mut conn = db.get().await?;
+
+		// Blank fields in the form mean there's no bound
+		let bound = |bound: Option<String>| {
+			bound
+				.map(|bound| bound.trim().to_string())
+				.filter(|bound| !bound.is_empty())
+		};
+
+		PlatformRequirement::set_for_minor(
+			&mut conn,
+			major,
+			minor,
+			requirements
+				.into_iter()
+				.map(|requirement| NewPlatformRequirement {
+					major,
+					minor,
+					platform: requirement.platform,
+					min_version: bound(requirement.min_version),
+					max_version: bound(requirement.max_version),
+				})
+				.collect(),
+		)
+		.await?;
+
+		Ok(())
+	}
 }
@@ -81,3 +81,46 @@ async fn artifact_multiple_ranges_pattern_specificity_private_endpoint() {
 	})
 	.await
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn artifact_link_checks_and_release_completeness() {
+	commons_tests::server::run(async |mut conn, _public, private| {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('11111111-1111-1111-1111-111111111111', 1, 0, 0, 'v1.0.0', 'published'),
+			('22222222-2222-2222-2222-222222222222', 1, 1, 0, 'v1.1.0', 'published');
+
+			INSERT INTO artifacts (id, version_id, platform, artifact_type, download_url) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '11111111-1111-1111-1111-111111111111', 'windows', 'server', 'https://example.com/1.0.0.exe'),
+			('bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', '11111111-1111-1111-1111-111111111111', 'linux', 'web', 'https://example.com/1.0.0.tar'),
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', '22222222-2222-2222-2222-222222222222', 'windows', 'server', 'https://example.com/1.1.0.exe');
+
+			INSERT INTO artifact_link_checks (artifact_id, healthy, status_code, error) VALUES
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', false, 404, 'responded with 404 Not Found')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_artifacts_by_version_id")
+			.form(&[("version_id", "22222222-2222-2222-2222-222222222222")])
+			.await;
+		response.assert_status_ok();
+		let artifacts: Vec<serde_json::Value> = response.json();
+		assert_eq!(artifacts.len(), 1);
+		assert_eq!(artifacts[0]["link_check"]["healthy"], false);
+		assert_eq!(artifacts[0]["link_check"]["status_code"], 404);
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_release_completeness")
+			.form(&[("version_id", "22222222-2222-2222-2222-222222222222")])
+			.await;
+		response.assert_status_ok();
+		let completeness: serde_json::Value = response.json();
+		assert_eq!(completeness["previous"], "1.0.0");
+		assert_eq!(completeness["missing"], serde_json::json!(["web/linux"]));
+		assert_eq!(completeness["broken"], 1);
+		assert_eq!(completeness["unchecked"], 0);
+	})
+	.await
+}
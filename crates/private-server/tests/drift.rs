@@ -0,0 +1,84 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+
+async fn setup(conn: &mut commons_tests::diesel_async::AsyncPgConnection) {
+	conn.batch_execute(
+		"INSERT INTO versions (major, minor, patch, status, changelog) VALUES
+		(2, 1, 0, 'published', ''), (2, 2, 0, 'published', '');
+		INSERT INTO servers (id, name, host, rank, kind) VALUES
+		('11111111-1111-1111-1111-111111111111', 'Prod', 'https://prod.example.com', 'production', 'central'),
+		('22222222-2222-2222-2222-222222222222', 'Demo', 'https://demo.example.com', 'demo', 'central');
+		INSERT INTO statuses (server_id, version, created_at) VALUES
+		('11111111-1111-1111-1111-111111111111', '2.1.4', NOW() - INTERVAL '1 hour'),
+		('22222222-2222-2222-2222-222222222222', '2.2.0', NOW() - INTERVAL '1 hour')",
+	)
+	.await
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drift_report_filters_by_rank() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.post("/api/private_server/fns/drift/report")
+			.form(&[("rank", "production")])
+			.await;
+		response.assert_status_ok();
+		let entries: Vec<serde_json::Value> = response.json();
+		assert_eq!(entries.len(), 1);
+		assert_eq!(entries[0]["name"], "Prod");
+		assert_eq!(entries[0]["minors_behind"], 1);
+
+		let response = private.post("/api/private_server/fns/drift/report").await;
+		response.assert_status_ok();
+		let entries: Vec<serde_json::Value> = response.json();
+		assert_eq!(entries.len(), 2);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drift_report_exports_csv() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.get("/api/private_server/drift.csv")
+			.add_query_param("rank", "demo")
+			.await;
+		response.assert_status_ok();
+		assert_eq!(
+			response.header("content-type").to_str().unwrap(),
+			"text/csv; charset=utf-8"
+		);
+		let csv = response.text();
+		let lines: Vec<_> = csv.lines().collect();
+		assert_eq!(lines.len(), 2);
+		assert!(lines[0].starts_with("server_id,name,"));
+		assert!(lines[1].starts_with("22222222-2222-2222-2222-222222222222,Demo,central,demo,"));
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drift_report_ignores_prereleases() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, prerelease, status, changelog) VALUES
+			(2, 3, 0, 'beta.1', 'published', '')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/drift/report")
+			.form(&[("rank", "production")])
+			.await;
+		response.assert_status_ok();
+		let entries: Vec<serde_json::Value> = response.json();
+		assert_eq!(entries[0]["minors_behind"], 1);
+	})
+	.await
+}
@@ -793,3 +793,70 @@ async fn server_grouped_ids_excludes_unnamed() {
 	})
 	.await
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn status_json_failing_probe_degrades_server() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		// a stand-in that answers pings
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let up = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let app = axum::Router::new().route(
+				"/api/public/ping",
+				axum::routing::get(|| async { ([("X-Version", "1.0.0")], "") }),
+			);
+			axum::serve(listener, app).await.unwrap();
+		});
+		let closed = {
+			let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+			listener.local_addr().unwrap().port()
+		};
+
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, status, changelog, created_at) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, 'published', 'Test version', NOW());
+
+			INSERT INTO servers (id, name, host, rank, kind, parent_server_id) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Central', 'http://127.0.0.1:{up}/', 'production', 'central', NULL),
+			('22222222-2222-2222-2222-222222222222', 'Facility', 'http://localhost:{up}/', 'production', 'facility', '11111111-1111-1111-1111-111111111111');
+
+			INSERT INTO server_probes (server_id, name, probe_type, port) VALUES
+			('22222222-2222-2222-2222-222222222222', 'postgres', 'tcp', {closed})"
+		))
+		.await
+		.unwrap();
+
+		let pinged = database::statuses::Status::ping_servers_and_save(
+			&mut conn,
+			&database::statuses::PingOptions {
+				concurrency: 2,
+				timeout: std::time::Duration::from_secs(2),
+				retries: 0,
+				backoff: std::time::Duration::from_millis(10),
+				spread: std::time::Duration::from_millis(50),
+			},
+		)
+		.await
+		.unwrap();
+		assert_eq!(pinged.len(), 2, "both servers answer pings");
+
+		let details_response = private
+			.post("/api/private_server/fns/statuses/server_details")
+			.form(&[("server_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		details_response.assert_status_ok();
+		let details: CentralServerCardResponse = details_response.json();
+		assert_eq!(details.up, "up");
+		assert_eq!(details.facility_servers.len(), 1);
+		assert_eq!(details.facility_servers[0].up, "degraded");
+
+		let detail_response = private
+			.post("/api/private_server/fns/servers/get_detail")
+			.form(&[("server_id", "22222222-2222-2222-2222-222222222222")])
+			.await;
+		detail_response.assert_status_ok();
+		let detail: ServerDetailResponse = detail_response.json();
+		assert_eq!(detail.up, "degraded");
+	})
+	.await
+}
@@ -0,0 +1,103 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use serde_json::json;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_and_list_probes() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		conn.batch_execute(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Facility One', 'https://one.example.com', 'facility'),
+			('22222222-2222-2222-2222-222222222222', 'Facility Two', 'https://two.example.com', 'facility');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/probes/create")
+			.json(&json!({
+				"server_id": "11111111-1111-1111-1111-111111111111",
+				"data": {
+					"name": "Sync port",
+					"probe_type": "tcp",
+					"all_of_kind": true,
+					"interval_secs": 300,
+					"timeout_secs": 5,
+					"port": 3000
+				}
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/probes/create")
+			.json(&json!({
+				"server_id": "11111111-1111-1111-1111-111111111111",
+				"data": {
+					"name": "Health",
+					"probe_type": "http",
+					"all_of_kind": false,
+					"interval_secs": 60,
+					"timeout_secs": 10,
+					"path": "/api/health",
+					"expected_status": 200
+				}
+			}))
+			.await;
+		response.assert_status_ok();
+
+		// Kind-wide probes apply to other servers of that kind too
+		let response = private
+			.post("/api/private_server/fns/probes/list_for_server")
+			.form(&[("server_id", "22222222-2222-2222-2222-222222222222")])
+			.await;
+		response.assert_status_ok();
+		let probes: Vec<serde_json::Value> = response.json();
+		assert_eq!(probes.len(), 1);
+		assert_eq!(probes[0]["name"], "Sync port");
+		assert_eq!(probes[0]["server_kind"], "facility");
+		assert_eq!(probes[0]["description"], "connect to port 3000");
+
+		let response = private
+			.post("/api/private_server/fns/probes/list_for_server")
+			.form(&[("server_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		let probes: Vec<serde_json::Value> = response.json();
+		assert_eq!(probes.len(), 2);
+		assert_eq!(probes[0]["name"], "Health");
+		assert_eq!(probes[0]["description"], "GET /api/health → 200");
+		assert_eq!(probes[0]["last_result"], serde_json::Value::Null);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_probe_rejects_invalid_definition() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		conn.batch_execute(
+			"INSERT INTO servers (id, name, host, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Central', 'https://central.example.com', 'central');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/probes/create")
+			.json(&json!({
+				"server_id": "11111111-1111-1111-1111-111111111111",
+				"data": {
+					"name": "No path",
+					"probe_type": "http",
+					"all_of_kind": false,
+					"interval_secs": 60,
+					"timeout_secs": 10
+				}
+			}))
+			.await;
+		assert!(
+			response.status_code().is_client_error() || response.status_code().is_server_error()
+		);
+	})
+	.await
+}
@@ -0,0 +1,90 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn servers_outside_platform_requirements_are_listed() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES
+			(2, 5, 0, 'published', ''),
+			(2, 5, 1, 'published', '');
+			INSERT INTO admins (email) VALUES ('admin@example.com');
+			INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('11111111-1111-1111-1111-111111111111', 'Supported', 'https://a.example.com', 'production', 'central'),
+			('22222222-2222-2222-2222-222222222222', 'Too Old', 'https://b.example.com', 'production', 'central'),
+			('33333333-3333-3333-3333-333333333333', 'Too New', 'https://c.example.com', 'production', 'central'),
+			('44444444-4444-4444-4444-444444444444', 'Elsewhere', 'https://d.example.com', 'production', 'central');
+			INSERT INTO statuses (server_id, version, extra, created_at) VALUES
+			('11111111-1111-1111-1111-111111111111', '2.5.1', '{\"pgVersion\": \"PostgreSQL 16.4, compiled by gcc\"}'::jsonb, NOW()),
+			('22222222-2222-2222-2222-222222222222', '2.5.0', '{\"pgVersion\": \"PostgreSQL 12.3, compiled by gcc\"}'::jsonb, NOW()),
+			('33333333-3333-3333-3333-333333333333', '2.5.1', '{\"pgVersion\": \"PostgreSQL 17.0 on x86_64-windows\"}'::jsonb, NOW()),
+			('44444444-4444-4444-4444-444444444444', '2.4.0', '{\"pgVersion\": \"PostgreSQL 12.3, compiled by gcc\"}'::jsonb, NOW())",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/versions/update_platform_requirements")
+			.json(&serde_json::json!({
+				"major": 2,
+				"minor": 5,
+				"requirements": [
+					{ "platform": "postgres", "min_version": "14", "max_version": "16" },
+					{ "platform": "nodejs", "min_version": "", "max_version": "" },
+				],
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_version_detail")
+			.form(&[("version", "2.5.1")])
+			.await;
+		response.assert_status_ok();
+		let detail: serde_json::Value = response.json();
+		assert_eq!(
+			detail["requirements"],
+			serde_json::json!([
+				{ "platform": "postgres", "min_version": "14", "max_version": "16" },
+			])
+		);
+
+		let response = private
+			.post("/api/private_server/fns/servers/list_unsupported")
+			.await;
+		response.assert_status_ok();
+		let unsupported: Vec<serde_json::Value> = response.json();
+		assert_eq!(unsupported.len(), 2, "{unsupported:#?}");
+
+		assert_eq!(unsupported[0]["name"], "Too New");
+		assert_eq!(unsupported[0]["violations"][0]["kind"], "above_maximum");
+		assert_eq!(unsupported[0]["violations"][0]["version"], "17.0");
+
+		assert_eq!(unsupported[1]["name"], "Too Old");
+		assert_eq!(unsupported[1]["version"], "2.5.0");
+		assert_eq!(
+			unsupported[1]["violations"],
+			serde_json::json!([{
+				"kind": "below_minimum",
+				"platform": "postgres",
+				"version": "12.3",
+				"minimum": "14",
+			}])
+		);
+
+		// bounds must be versions, and in order
+		let response = private
+			.post("/api/private_server/fns/versions/update_platform_requirements")
+			.json(&serde_json::json!({
+				"major": 2,
+				"minor": 5,
+				"requirements": [
+					{ "platform": "postgres", "min_version": "17", "max_version": "16" },
+				],
+			}))
+			.await;
+		assert!(
+			response.status_code().is_client_error() || response.status_code().is_server_error()
+		);
+	})
+	.await
+}
@@ -1,5 +1,6 @@
 use axum::http::StatusCode;
 use commons_tests::diesel_async::SimpleAsyncConnection;
+use commons_types::version::ReleaseChannel;
 use database::servers::Server;
 use serde_json::json;
 
@@ -526,3 +527,76 @@ async fn update_server_sets_new_device_id() {
 	})
 	.await
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_server_default_channel() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		conn.batch_execute(
+			"INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('cccccccc-cccc-cccc-cccc-cccccccccccc', 'Channel Server', 'https://channel.example.com', 'test', 'central')"
+		)
+		.await
+		.unwrap();
+
+		conn.batch_execute("INSERT INTO admins (email) VALUES ('admin@example.com')")
+			.await
+			.unwrap();
+
+		let server_id = "cccccccc-cccc-cccc-cccc-cccccccccccc".parse().unwrap();
+		let server = Server::get_by_id(&mut conn, server_id).await.unwrap();
+		assert_eq!(server.default_channel, ReleaseChannel::Stable);
+
+		let response = private
+			.post("/api/private_server/fns/servers/update")
+			.json(&json!({
+				"server_id": "cccccccc-cccc-cccc-cccc-cccccccccccc",
+				"data": {
+					"default_channel": "beta"
+				}
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let server = Server::get_by_id(&mut conn, server_id).await.unwrap();
+		assert_eq!(server.default_channel, ReleaseChannel::Beta);
+		assert_eq!(server.name, Some("Channel Server".to_string()));
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_server_ping_timeout() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		conn.batch_execute(
+			"INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('dddddddd-dddd-dddd-dddd-dddddddddddd', 'Slow Server', 'https://slow.example.com', 'test', 'central');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let server_id = "dddddddd-dddd-dddd-dddd-dddddddddddd".parse().unwrap();
+		let update = async |timeout: serde_json::Value| {
+			private
+				.post("/api/private_server/fns/servers/update")
+				.json(&json!({
+					"server_id": "dddddddd-dddd-dddd-dddd-dddddddddddd",
+					"data": { "ping_timeout_secs": timeout }
+				}))
+				.await
+				.assert_status_ok();
+		};
+
+		update(json!(30)).await;
+		let server = Server::get_by_id(&mut conn, server_id).await.unwrap();
+		assert_eq!(
+			server.ping_timeout.map(|timeout| timeout.0.as_secs()),
+			Some(30)
+		);
+
+		update(json!(null)).await;
+		let server = Server::get_by_id(&mut conn, server_id).await.unwrap();
+		assert_eq!(server.ping_timeout, None);
+	})
+	.await
+}
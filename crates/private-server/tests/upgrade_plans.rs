@@ -0,0 +1,194 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use serde_json::json;
+
+async fn setup(conn: &mut commons_tests::diesel_async::AsyncPgConnection) {
+	conn.batch_execute(
+		"INSERT INTO versions (major, minor, patch, status, changelog, stepping_stone) VALUES
+		(2, 22, 0, 'published', '', false),
+		(2, 22, 4, 'published', 'Reindexes encounters', true),
+		(2, 22, 5, 'published', '', false),
+		(2, 23, 0, 'published', 'Faster sync', false),
+		(3, 0, 0, 'published', '', false),
+		(3, 1, 0, 'published', '', false);
+		INSERT INTO servers (id, name, host, rank, kind, parent_server_id) VALUES
+		('11111111-1111-1111-1111-111111111111', 'Central', 'https://central.example.com', 'production', 'central', NULL),
+		('22222222-2222-2222-2222-222222222222', 'Clinic A', 'https://a.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111'),
+		('33333333-3333-3333-3333-333333333333', 'Clinic B', 'https://b.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111');
+		INSERT INTO statuses (server_id, version, created_at) VALUES
+		('11111111-1111-1111-1111-111111111111', '2.22.0', NOW() - INTERVAL '1 hour'),
+		('22222222-2222-2222-2222-222222222222', '2.22.0', NOW() - INTERVAL '1 hour')",
+	)
+	.await
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plan_goes_through_stepping_stones() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.post("/api/private_server/fns/upgrade_plans/plan")
+			.json(&json!({
+				"server_ids": ["11111111-1111-1111-1111-111111111111"],
+				"target": "3.1.0",
+			}))
+			.await;
+		response.assert_status_ok();
+		let plan: serde_json::Value = response.json();
+
+		let stops: Vec<_> = plan["stops"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|stop| {
+				(
+					stop["version"].as_str().unwrap(),
+					stop["required"].as_bool().unwrap(),
+				)
+			})
+			.collect();
+		assert_eq!(stops, vec![("2.22.5", true), ("3.1.0", false)]);
+
+		assert_eq!(plan["servers"].as_array().unwrap().len(), 1);
+		assert_eq!(plan["servers"][0]["version"], "2.22.0");
+		assert_eq!(plan["changelog"].as_array().unwrap().len(), 5);
+
+		// Both facilities move with the central: one is on an older minor, the other is unknown
+		let facilities = plan["facilities"].as_array().unwrap();
+		assert_eq!(facilities.len(), 2);
+		assert_eq!(facilities[0]["name"], "Clinic A");
+		assert!(!facilities[0]["reasons"].as_array().unwrap().is_empty());
+		assert_eq!(facilities[1]["name"], "Clinic B");
+		assert!(facilities[1]["reasons"].as_array().unwrap().is_empty());
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plan_exports_markdown() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.get("/api/private_server/upgrade-plan.md")
+			.add_query_param(
+				"servers",
+				"11111111-1111-1111-1111-111111111111,22222222-2222-2222-2222-222222222222",
+			)
+			.add_query_param("target", "2.23.0")
+			.await;
+		response.assert_status_ok();
+		assert_eq!(
+			response.header("content-type").to_str().unwrap(),
+			"text/markdown; charset=utf-8"
+		);
+		let doc = response.text();
+		assert!(doc.starts_with("# Upgrade plan to Tamanu 2.23.0"), "{doc}");
+		assert!(
+			doc.contains("| Central | central | 2.22.0 | 2.22.5 → 2.23.0 |"),
+			"{doc}"
+		);
+		assert!(doc.contains("Faster sync"), "{doc}");
+
+		// Planned facilities aren't listed as moving with their central
+		assert!(!doc.contains("Clinic A:"), "{doc}");
+		assert!(
+			doc.contains("- Clinic B: hasn't reported its version recently"),
+			"{doc}"
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plan_escapes_server_names_in_markdown() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+		conn.batch_execute(
+			"UPDATE servers SET name = 'Central | North' WHERE id = '11111111-1111-1111-1111-111111111111'",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.get("/api/private_server/upgrade-plan.md")
+			.add_query_param("servers", "11111111-1111-1111-1111-111111111111")
+			.add_query_param("target", "2.23.0")
+			.await;
+		response.assert_status_ok();
+		let doc = response.text();
+		assert!(
+			doc.contains("| Central \\| North | central | 2.22.0 | 2.22.5 → 2.23.0 |"),
+			"{doc}"
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plan_stops_where_update_checks_lead() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+		// The newest patch of the stepping stone's minor isn't rolled out to anyone yet
+		conn.batch_execute(
+			"INSERT INTO version_rollouts (version_id, percentage, paused)
+			SELECT id, 0, false FROM versions WHERE major = 2 AND minor = 22 AND patch = 5",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/upgrade_plans/plan")
+			.json(&json!({
+				"server_ids": ["11111111-1111-1111-1111-111111111111"],
+				"target": "3.1.0",
+			}))
+			.await;
+		response.assert_status_ok();
+		let plan: serde_json::Value = response.json();
+
+		let stops: Vec<_> = plan["stops"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|stop| stop["version"].as_str().unwrap())
+			.collect();
+		assert_eq!(stops, vec!["2.22.4", "3.1.0"]);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn plan_targets_leave_out_prereleases() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, prerelease, status, changelog) VALUES
+			(3, 2, 0, 'rc.1', 'published', '')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/upgrade_plans/list_targets")
+			.await;
+		response.assert_status_ok();
+		let targets: Vec<String> = response.json();
+		assert_eq!(targets.first().map(String::as_str), Some("3.1.0"));
+		assert!(
+			!targets.iter().any(|target| target.contains("rc")),
+			"{targets:?}"
+		);
+
+		let response = private
+			.post("/api/private_server/fns/upgrade_plans/plan")
+			.json(&json!({
+				"server_ids": ["11111111-1111-1111-1111-111111111111"],
+				"target": "3.2.0-rc.1",
+			}))
+			.await;
+		response.assert_status_failure();
+	})
+	.await
+}
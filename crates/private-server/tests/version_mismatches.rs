@@ -0,0 +1,153 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+
+const CENTRAL: &str = "11111111-1111-1111-1111-111111111111";
+
+async fn setup(conn: &mut commons_tests::diesel_async::AsyncPgConnection) {
+	conn.batch_execute(
+		"INSERT INTO versions (id, major, minor, patch, status, changelog) VALUES
+		('00000000-0000-0000-0000-000000000001', 1, 3, 0, 'published', 'Test version');
+		INSERT INTO servers (id, name, host, rank, kind) VALUES
+		('11111111-1111-1111-1111-111111111111', 'Central', 'https://central.example.com', 'production', 'central');
+		INSERT INTO servers (id, name, host, rank, kind, parent_server_id) VALUES
+		('22222222-2222-2222-2222-222222222222', 'In step', 'https://a.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111'),
+		('33333333-3333-3333-3333-333333333333', 'Ahead', 'https://b.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111'),
+		('44444444-4444-4444-4444-444444444444', 'Behind', 'https://c.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111'),
+		('55555555-5555-5555-5555-555555555555', 'Silent', 'https://d.example.com', 'production', 'facility', '11111111-1111-1111-1111-111111111111');
+		INSERT INTO statuses (server_id, version, created_at) VALUES
+		('11111111-1111-1111-1111-111111111111', '1.2.3', NOW()),
+		('22222222-2222-2222-2222-222222222222', '1.2.1', NOW()),
+		('33333333-3333-3333-3333-333333333333', '1.3.0', NOW()),
+		('44444444-4444-4444-4444-444444444444', '1.1.9', NOW())",
+	)
+	.await
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fleet_mismatches_list_violations() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.post("/api/private_server/fns/servers/list_mismatches")
+			.await;
+		response.assert_status_ok();
+		let mismatches: Vec<serde_json::Value> = response.json();
+		assert_eq!(mismatches.len(), 2, "{mismatches:#?}");
+
+		assert_eq!(mismatches[0]["facility_name"], "Ahead");
+		assert_eq!(mismatches[0]["central_version"], "1.2.3");
+		assert_eq!(mismatches[0]["facility_version"], "1.3.0");
+		assert_eq!(
+			mismatches[0]["violations"],
+			serde_json::json!(["same_minor", "facility_not_ahead"])
+		);
+
+		assert_eq!(mismatches[1]["facility_name"], "Behind");
+		assert_eq!(
+			mismatches[1]["violations"],
+			serde_json::json!(["same_minor"])
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mismatches_flagged_on_central_card_and_detail() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+
+		let response = private
+			.post("/api/private_server/fns/statuses/server_details")
+			.form(&[("server_id", CENTRAL)])
+			.await;
+		response.assert_status_ok();
+		let card: serde_json::Value = response.json();
+		let mut flagged: Vec<_> = card["facility_servers"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.filter(|facility| !facility["mismatches"].as_array().unwrap().is_empty())
+			.map(|facility| facility["name"].as_str().unwrap().to_string())
+			.collect();
+		flagged.sort();
+		assert_eq!(flagged, vec!["Ahead", "Behind"]);
+
+		let response = private
+			.post("/api/private_server/fns/servers/get_detail")
+			.form(&[("server_id", CENTRAL)])
+			.await;
+		response.assert_status_ok();
+		let detail: serde_json::Value = response.json();
+		let mut flagged: Vec<_> = detail["version_mismatches"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|mismatch| mismatch["facility_name"].as_str().unwrap().to_string())
+			.collect();
+		flagged.sort();
+		assert_eq!(flagged, vec!["Ahead", "Behind"]);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_enabled_rules_are_checked() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		setup(&mut conn).await;
+		conn.batch_execute("INSERT INTO admins (email) VALUES ('admin@example.com')")
+			.await
+			.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/servers/set_compatibility_rules")
+			.json(&serde_json::json!({ "rules": ["facility_not_ahead"] }))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/servers/get_compatibility_rules")
+			.await;
+		response.assert_status_ok();
+		assert_eq!(
+			response.json::<serde_json::Value>(),
+			serde_json::json!(["facility_not_ahead"])
+		);
+
+		let response = private
+			.post("/api/private_server/fns/servers/list_mismatches")
+			.await;
+		response.assert_status_ok();
+		let mismatches: Vec<serde_json::Value> = response.json();
+		assert_eq!(mismatches.len(), 1, "{mismatches:#?}");
+		assert_eq!(mismatches[0]["facility_name"], "Ahead");
+		assert_eq!(
+			mismatches[0]["violations"],
+			serde_json::json!(["facility_not_ahead"])
+		);
+
+		let response = private
+			.post("/api/private_server/fns/statuses/server_details")
+			.form(&[("server_id", CENTRAL)])
+			.await;
+		response.assert_status_ok();
+		let card: serde_json::Value = response.json();
+		let flagged: Vec<_> = card["facility_servers"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.filter(|facility| !facility["mismatches"].as_array().unwrap().is_empty())
+			.map(|facility| facility["name"].as_str().unwrap().to_string())
+			.collect();
+		assert_eq!(flagged, vec!["Ahead"]);
+
+		let response = private
+			.post("/api/private_server/fns/servers/get_detail")
+			.form(&[("server_id", CENTRAL)])
+			.await;
+		response.assert_status_ok();
+		let detail: serde_json::Value = response.json();
+		assert_eq!(detail["version_mismatches"].as_array().unwrap().len(), 1);
+	})
+	.await
+}
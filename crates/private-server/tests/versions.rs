@@ -72,3 +72,166 @@ async fn test_get_grouped_versions() {
 	})
 	.await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_version_channels() {
+	server::run(|mut conn, _public, private| async move {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES (1, 0, 0, 'published', '');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/versions/update_version_channels")
+			.json(&serde_json::json!({ "version": "1.0.0", "channels": ["stable", "beta"] }))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_version_detail")
+			.form(&[("version", "1.0.0")])
+			.await;
+		response.assert_status_ok();
+		let detail: serde_json::Value = response.json();
+		assert_eq!(detail["channels"], serde_json::json!(["stable", "beta"]));
+
+		let response = private
+			.post("/api/private_server/fns/versions/update_version_channels")
+			.json(&serde_json::json!({ "version": "1.0.0", "channels": [] }))
+			.await;
+		assert!(
+			response.status_code().is_client_error() || response.status_code().is_server_error()
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_rollout() {
+	server::run(|mut conn, _public, private| async move {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, status, changelog) VALUES
+			('11111111-1111-1111-1111-111111111111', 1, 0, 0, 'published', '');
+			INSERT INTO servers (id, name, host, kind) VALUES
+			('22222222-2222-2222-2222-222222222222', 'Canary', 'https://canary.example.com', 'central');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_rollout")
+			.form(&[("version_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		response.assert_status_ok();
+		assert_eq!(
+			response.json::<serde_json::Value>(),
+			serde_json::Value::Null
+		);
+
+		let response = private
+			.post("/api/private_server/fns/versions/update_rollout")
+			.json(&serde_json::json!({
+				"version_id": "11111111-1111-1111-1111-111111111111",
+				"allowlist": ["22222222-2222-2222-2222-222222222222"],
+				"percentage": 25,
+				"paused": false,
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_rollout")
+			.form(&[("version_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		response.assert_status_ok();
+		let rollout: serde_json::Value = response.json();
+		assert_eq!(rollout["percentage"], 25);
+		assert_eq!(rollout["paused"], false);
+		assert_eq!(rollout["allowlist"][0]["name"], "Canary");
+
+		let response = private
+			.post("/api/private_server/fns/versions/remove_rollout")
+			.form(&[("version_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_rollout")
+			.form(&[("version_id", "11111111-1111-1111-1111-111111111111")])
+			.await;
+		assert_eq!(
+			response.json::<serde_json::Value>(),
+			serde_json::Value::Null
+		);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_support_policy() {
+	server::run(|mut conn, _public, private| async move {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, status, changelog) VALUES (3, 4, 0, 'published', '');
+			INSERT INTO admins (email) VALUES ('admin@example.com')",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/versions/update_support_policy")
+			.json(&serde_json::json!({
+				"major": 3,
+				"minor": 4,
+				"active_support_until": "2099-01-31",
+				"security_support_until": "2099-07-31",
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_version_detail")
+			.form(&[("version", "3.4.0")])
+			.await;
+		response.assert_status_ok();
+		let detail: serde_json::Value = response.json();
+		assert_eq!(detail["support"]["status"], "active");
+		assert_eq!(detail["support"]["security_support_until"], "2099-07-31");
+
+		// security support can't end before active support
+		let response = private
+			.post("/api/private_server/fns/versions/update_support_policy")
+			.json(&serde_json::json!({
+				"major": 3,
+				"minor": 4,
+				"active_support_until": "2099-07-31",
+				"security_support_until": "2099-01-31",
+			}))
+			.await;
+		assert!(
+			response.status_code().is_client_error() || response.status_code().is_server_error()
+		);
+
+		// clearing both dates removes the policy
+		let response = private
+			.post("/api/private_server/fns/versions/update_support_policy")
+			.json(&serde_json::json!({
+				"major": 3,
+				"minor": 4,
+				"active_support_until": null,
+				"security_support_until": null,
+			}))
+			.await;
+		response.assert_status_ok();
+
+		let response = private
+			.post("/api/private_server/fns/versions/get_version_detail")
+			.form(&[("version", "3.4.0")])
+			.await;
+		let detail: serde_json::Value = response.json();
+		assert_eq!(detail["support"]["status"], "unknown");
+	})
+	.await
+}
@@ -0,0 +1,54 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn servers_on_yanked_versions_are_listed() {
+	commons_tests::server::run(async |mut conn, _, private| {
+		// status partitions only start from the current week, so make room for some history
+		conn.batch_execute(
+			"CREATE TABLE statuses_history PARTITION OF statuses
+				FOR VALUES FROM (MINVALUE) TO (date_trunc('week', NOW()))",
+		)
+		.await
+		.unwrap();
+
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, status, changelog) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 2, 0, 'yanked', ''),
+			('00000000-0000-0000-0000-000000000002', 1, 2, 1, 'published', ''),
+			('00000000-0000-0000-0000-000000000003', 1, 1, 0, 'yanked', '');
+			INSERT INTO version_yanks (version_id, reason, severity, replacement_version_id) VALUES
+			('00000000-0000-0000-0000-000000000001', 'Loses data', 'critical', '00000000-0000-0000-0000-000000000002');
+			INSERT INTO servers (id, name, host, rank, kind) VALUES
+			('22222222-2222-2222-2222-222222222222', 'Upgraded', 'https://a.example.com', 'production', 'central'),
+			('33333333-3333-3333-3333-333333333333', 'Stuck', 'https://b.example.com', 'production', 'central'),
+			('44444444-4444-4444-4444-444444444444', 'Old', 'https://c.example.com', 'production', 'central');
+			INSERT INTO statuses (server_id, version, created_at) VALUES
+			('22222222-2222-2222-2222-222222222222', '1.2.0', NOW() - INTERVAL '1 day'),
+			('22222222-2222-2222-2222-222222222222', '1.2.1', NOW()),
+			('33333333-3333-3333-3333-333333333333', '1.2.0+build.5', NOW()),
+			('44444444-4444-4444-4444-444444444444', '1.1.0', NOW())",
+		)
+		.await
+		.unwrap();
+
+		let response = private
+			.post("/api/private_server/fns/servers/list_on_yanked")
+			.await;
+		response.assert_status_ok();
+		let yanked: Vec<serde_json::Value> = response.json();
+		assert_eq!(yanked.len(), 2, "{yanked:#?}");
+
+		// Most severe first
+		assert_eq!(yanked[0]["name"], "Stuck");
+		assert_eq!(yanked[0]["version"], "1.2.0");
+		assert_eq!(yanked[0]["severity"], "critical");
+		assert_eq!(yanked[0]["reason"], "Loses data");
+		assert_eq!(yanked[0]["replacement"], "1.2.1");
+
+		// Yanked without a reason
+		assert_eq!(yanked[1]["name"], "Old");
+		assert_eq!(yanked[1]["severity"], "medium");
+		assert_eq!(yanked[1]["replacement"], serde_json::Value::Null);
+	})
+	.await
+}
@@ -0,0 +1,361 @@
+//! An optional on-disk cache of artifact downloads.
+//!
+//! Artifacts are kept in a directory, keyed by artifact ID and checksum, up to a total size. When
+//! that's exceeded, the least recently served artifacts are evicted first. Downloads which miss
+//! the cache are proxied as usual while the artifact is fetched in the background, and the
+//! artifacts of a version are fetched ahead of time when it's published.
+
+use std::{
+	collections::HashSet,
+	io::{ErrorKind, SeekFrom},
+	ops::RangeInclusive,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::SystemTime,
+};
+
+use axum::{
+	body::Body,
+	http::{HeaderMap, HeaderValue, StatusCode, header},
+	response::{IntoResponse as _, Response},
+};
+use commons_errors::{AppError, Result};
+use commons_servers::Uncompressed;
+use database::artifacts::Artifact;
+use sha2::{Digest as _, Sha256};
+use tokio::{
+	fs::File,
+	io::{AsyncReadExt as _, AsyncSeekExt as _, AsyncWriteExt as _},
+};
+use tokio_util::io::ReaderStream;
+use tracing::{debug, warn};
+
+/// 10 GiB.
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug)]
+pub struct ArtifactCache {
+	dir: PathBuf,
+	max_bytes: u64,
+	client: reqwest::Client,
+
+	/// Keys of the artifacts being fetched, so that each is only fetched once at a time.
+	filling: Mutex<HashSet<String>>,
+
+	/// Held while making room for a fetched artifact and moving it in place.
+	storing: tokio::sync::Mutex<()>,
+}
+
+impl ArtifactCache {
+	pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+		Self {
+			dir: dir.into(),
+			max_bytes,
+			client: reqwest::Client::new(),
+			filling: Default::default(),
+			storing: Default::default(),
+		}
+	}
+
+	/// Configure the cache from `ARTIFACT_CACHE_DIR` and `ARTIFACT_CACHE_MAX_BYTES`.
+	///
+	/// The cache is disabled if no directory is set.
+	pub fn from_env() -> Result<Option<Self>> {
+		let Some(dir) = std::env::var_os("ARTIFACT_CACHE_DIR") else {
+			return Ok(None);
+		};
+
+		let max_bytes = match std::env::var("ARTIFACT_CACHE_MAX_BYTES") {
+			Ok(max) => max.parse().map_err(|_| {
+				AppError::custom("ARTIFACT_CACHE_MAX_BYTES must be a number of bytes")
+			})?,
+			Err(_) => DEFAULT_MAX_BYTES,
+		};
+
+		Ok(Some(Self::new(dir, max_bytes)))
+	}
+
+	/// The artifact's checksum, or a hash of its URL if the checksum isn't known yet.
+	fn checksum(artifact: &Artifact) -> String {
+		artifact
+			.sha256
+			.clone()
+			.unwrap_or_else(|| hex::encode(Sha256::digest(&artifact.download_url)))
+	}
+
+	/// The artifact ID and checksum.
+	///
+	/// Changing either means the cached copy is stale, and it'll be left to be evicted.
+	fn key(artifact: &Artifact) -> String {
+		format!("{}-{}", artifact.id, Self::checksum(artifact))
+	}
+
+	/// The cached copy of an artifact, if there is one. This marks it as recently used.
+	pub async fn get(&self, artifact: &Artifact) -> Result<Option<CachedArtifact>> {
+		let key = Self::key(artifact);
+		let path = self.dir.join(&key);
+		let file = match File::open(&path).await {
+			Ok(file) => file.into_std().await,
+			Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(err) => return Err(err.into()),
+		};
+
+		let len = file.metadata()?.len();
+		file.set_modified(SystemTime::now())?;
+		let content_type = tokio::fs::read_to_string(path.with_extension("type"))
+			.await
+			.unwrap_or_else(|_| DEFAULT_CONTENT_TYPE.into());
+
+		Ok(Some(CachedArtifact {
+			file: File::from_std(file),
+			len,
+			etag: format!("\"{}\"", Self::checksum(artifact)),
+			content_type,
+		}))
+	}
+
+	/// Fetch artifacts into the cache in the background.
+	pub fn warm(self: &Arc<Self>, artifacts: Vec<Artifact>) {
+		let cache = self.clone();
+		tokio::spawn(async move {
+			for artifact in artifacts {
+				if let Err(err) = cache.fill(&artifact).await {
+					warn!(artifact = %artifact.id, "Failed to cache artifact: {err}");
+				}
+			}
+		});
+	}
+
+	/// Fetch an artifact into the cache, unless it's already there or being fetched.
+	pub async fn fill(&self, artifact: &Artifact) -> Result<()> {
+		let key = Self::key(artifact);
+		if tokio::fs::try_exists(self.dir.join(&key)).await? {
+			return Ok(());
+		}
+
+		if !self.filling.lock().unwrap().insert(key.clone()) {
+			return Ok(());
+		}
+		let result = self.download(artifact, &key).await;
+		self.filling.lock().unwrap().remove(&key);
+		result
+	}
+
+	async fn download(&self, artifact: &Artifact, key: &str) -> Result<()> {
+		if artifact
+			.size_bytes
+			.is_some_and(|size| size as u64 > self.max_bytes)
+		{
+			debug!(artifact = %artifact.id, "Artifact is larger than the cache, not caching it");
+			return Ok(());
+		}
+
+		tokio::fs::create_dir_all(&self.dir).await?;
+		let partial = self.dir.join(format!("{key}.partial"));
+		let mut response = self
+			.client
+			.get(&artifact.download_url)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|err| AppError::custom(format!("download failed: {err}")))?;
+		let content_type = response
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.and_then(|value| value.to_str().ok())
+			.unwrap_or(DEFAULT_CONTENT_TYPE)
+			.to_string();
+
+		let mut file = File::create(&partial).await?;
+		let downloaded: Result<u64> = async {
+			let mut hasher = Sha256::new();
+			let mut len = 0;
+			while let Some(chunk) = response
+				.chunk()
+				.await
+				.map_err(|err| AppError::custom(format!("download failed: {err}")))?
+			{
+				len += chunk.len() as u64;
+				if len > self.max_bytes {
+					return Err(AppError::custom("artifact is larger than the cache"));
+				}
+				hasher.update(&chunk);
+				file.write_all(&chunk).await?;
+			}
+			file.flush().await?;
+
+			let actual = hex::encode(hasher.finalize());
+			if let Some(expected) = &artifact.sha256
+				&& *expected != actual
+			{
+				return Err(AppError::custom(format!(
+					"checksum mismatch: expected {expected}, downloaded {actual}"
+				)));
+			}
+			Ok(len)
+		}
+		.await;
+
+		let len = match downloaded {
+			Ok(len) => len,
+			Err(err) => {
+				let _ = tokio::fs::remove_file(&partial).await;
+				return Err(err);
+			}
+		};
+
+		let _storing = self.storing.lock().await;
+		self.evict(self.max_bytes - len).await?;
+		tokio::fs::write(self.dir.join(format!("{key}.type")), content_type).await?;
+		tokio::fs::rename(&partial, self.dir.join(key)).await?;
+		debug!(artifact = %artifact.id, len, "Cached artifact");
+		Ok(())
+	}
+
+	/// Evict the least recently used artifacts until the cache holds at most `budget` bytes.
+	async fn evict(&self, budget: u64) -> Result<()> {
+		let mut entries = Vec::new();
+		let mut dir = tokio::fs::read_dir(&self.dir).await?;
+		while let Some(entry) = dir.next_entry().await? {
+			// Artifacts are stored without an extension, next to their content type and partial downloads
+			if entry.path().extension().is_some() {
+				continue;
+			}
+			let metadata = entry.metadata().await?;
+			if metadata.is_file() {
+				entries.push((metadata.modified()?, metadata.len(), entry.path()));
+			}
+		}
+
+		let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+		entries.sort();
+		for (_, len, path) in entries {
+			if total <= budget {
+				break;
+			}
+			tokio::fs::remove_file(&path).await?;
+			let _ = tokio::fs::remove_file(path.with_extension("type")).await;
+			total -= len;
+			debug!(path = %path.display(), "Evicted cached artifact");
+		}
+
+		Ok(())
+	}
+}
+
+/// An artifact in the cache, opened and ready to be served.
+#[derive(Debug)]
+pub struct CachedArtifact {
+	file: File,
+	len: u64,
+	etag: String,
+	content_type: String,
+}
+
+impl CachedArtifact {
+	/// Serve the artifact, honouring `If-None-Match`, `Range`, and `If-Range` request headers.
+	pub async fn serve(mut self, request: &HeaderMap) -> Result<Response> {
+		let mut headers = HeaderMap::new();
+		headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+		headers.insert(header::ETAG, header_value(&self.etag)?);
+
+		if self.matches_none_of(request) {
+			return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+		}
+
+		headers.insert(header::CONTENT_TYPE, header_value(&self.content_type)?);
+		let mut response = match self.byte_range(request) {
+			ByteRange::Full => {
+				headers.insert(header::CONTENT_LENGTH, self.len.into());
+				let body = Body::from_stream(ReaderStream::new(self.file));
+				(StatusCode::OK, headers, body).into_response()
+			}
+			ByteRange::Partial(range) => {
+				let (start, end) = range.into_inner();
+				headers.insert(header::CONTENT_LENGTH, (end - start + 1).into());
+				headers.insert(
+					header::CONTENT_RANGE,
+					header_value(&format!("bytes {start}-{end}/{}", self.len))?,
+				);
+				self.file.seek(SeekFrom::Start(start)).await?;
+				let body = Body::from_stream(ReaderStream::new(self.file.take(end - start + 1)));
+				(StatusCode::PARTIAL_CONTENT, headers, body).into_response()
+			}
+			ByteRange::Unsatisfiable => {
+				headers.insert(
+					header::CONTENT_RANGE,
+					header_value(&format!("bytes */{}", self.len))?,
+				);
+				(StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response()
+			}
+		};
+
+		response.extensions_mut().insert(Uncompressed);
+		Ok(response)
+	}
+
+	fn matches_none_of(&self, request: &HeaderMap) -> bool {
+		request
+			.get_all(header::IF_NONE_MATCH)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.flat_map(|value| value.split(','))
+			.map(|tag| tag.trim())
+			.any(|tag| tag == "*" || tag.trim_start_matches("W/") == self.etag)
+	}
+
+	/// The single byte range requested, if any.
+	///
+	/// Invalid and multiple ranges are ignored, which serves the whole artifact.
+	fn byte_range(&self, request: &HeaderMap) -> ByteRange {
+		let Some(range) = request
+			.get(header::RANGE)
+			.and_then(|value| value.to_str().ok())
+		else {
+			return ByteRange::Full;
+		};
+
+		if let Some(if_range) = request.get(header::IF_RANGE)
+			&& if_range.as_bytes() != self.etag.as_bytes()
+		{
+			return ByteRange::Full;
+		}
+
+		let Some((first, last)) = range
+			.strip_prefix("bytes=")
+			.filter(|spec| !spec.contains(','))
+			.and_then(|spec| spec.trim().split_once('-'))
+		else {
+			return ByteRange::Full;
+		};
+
+		match (first.parse::<u64>().ok(), last.parse::<u64>().ok()) {
+			// The last N bytes
+			(None, Some(suffix)) if first.is_empty() => {
+				if suffix == 0 || self.len == 0 {
+					ByteRange::Unsatisfiable
+				} else {
+					ByteRange::Partial(self.len.saturating_sub(suffix)..=self.len - 1)
+				}
+			}
+			(Some(start), _) if start >= self.len => ByteRange::Unsatisfiable,
+			(Some(start), None) if last.is_empty() => ByteRange::Partial(start..=self.len - 1),
+			(Some(start), Some(end)) if start <= end => {
+				ByteRange::Partial(start..=end.min(self.len - 1))
+			}
+			_ => ByteRange::Full,
+		}
+	}
+}
+
+enum ByteRange {
+	Full,
+	Partial(RangeInclusive<u64>),
+	Unsatisfiable,
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+	HeaderValue::from_str(value).map_err(|err| AppError::custom(err.to_string()))
+}
@@ -1,34 +1,106 @@
+use std::sync::Arc;
+
 use axum::{
 	Json,
 	extract::{Path, State},
+	http::{HeaderMap, header},
 	routing::{Router, post},
 };
-use commons_errors::Result;
+use commons_errors::{AppError, Result};
 use commons_servers::device_auth::ReleaserDevice;
-use commons_types::version::{VersionStatus, VersionStr};
+use commons_types::version::{ReleaseChannel, VersionStatus, VersionStr};
 use database::{
 	Db,
-	artifacts::{Artifact, NewArtifact},
+	artifacts::{Artifact, NewArtifact, is_bundle_id, is_sha256},
 	versions::{NewVersion, Version},
 };
 use diesel::SelectableHelper as _;
 use diesel_async::RunQueryDsl as _;
+use serde::Deserialize;
 
-use crate::state::AppState;
+use crate::{artifact_cache::ArtifactCache, state::AppState};
 
 pub fn routes() -> Router<AppState> {
 	Router::new().route("/{version}/{artifact_type}/{platform}", post(create))
 }
 
+/// An artifact upload: either just the download URL as plain text, or this as JSON.
+#[derive(Debug, Deserialize)]
+struct ArtifactUpload {
+	url: String,
+	/// Hex-encoded SHA-256 of the artifact.
+	sha256: Option<String>,
+	size_bytes: Option<i64>,
+	/// Bundle identifier of a mobile app, needed to install it on iOS.
+	bundle_id: Option<String>,
+	/// Build number of a mobile app.
+	version_code: Option<i64>,
+}
+
+impl ArtifactUpload {
+	fn parse(headers: &HeaderMap, body: String) -> Result<Self> {
+		let is_json = headers
+			.get(header::CONTENT_TYPE)
+			.is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+		if !is_json {
+			return Ok(Self {
+				url: body,
+				sha256: None,
+				size_bytes: None,
+				bundle_id: None,
+				version_code: None,
+			});
+		}
+
+		let mut upload: Self = serde_json::from_str(&body)
+			.map_err(|err| AppError::custom(format!("Invalid artifact: {err}")))?;
+		if let Some(sha256) = &mut upload.sha256 {
+			sha256.make_ascii_lowercase();
+			if !is_sha256(sha256) {
+				return Err(AppError::custom(
+					"Invalid artifact: sha256 must be 64 hex digits",
+				));
+			}
+		}
+		if upload.size_bytes.is_some_and(|size| size < 0) {
+			return Err(AppError::custom(
+				"Invalid artifact: size_bytes must not be negative",
+			));
+		}
+		if let Some(bundle_id) = &upload.bundle_id
+			&& !is_bundle_id(bundle_id)
+		{
+			return Err(AppError::custom(
+				"Invalid artifact: bundle_id must be in reverse-DNS form, like com.example.app",
+			));
+		}
+		if upload.version_code.is_some_and(|code| code < 0) {
+			return Err(AppError::custom(
+				"Invalid artifact: version_code must not be negative",
+			));
+		}
+		Ok(upload)
+	}
+}
+
 #[axum::debug_handler]
 async fn create(
 	device: ReleaserDevice,
 	State(db): State<Db>,
+	State(cache): State<Option<Arc<ArtifactCache>>>,
 	Path((version, artifact_type, platform)): Path<(String, String, String)>,
-	url: String,
+	headers: HeaderMap,
+	body: String,
 ) -> Result<Json<Artifact>> {
 	use node_semver::{Range, Version as SemverVersion};
 
+	let ArtifactUpload {
+		url,
+		sha256,
+		size_bytes,
+		bundle_id,
+		version_code,
+	} = ArtifactUpload::parse(&headers, body)?;
 	let mut db = db.get().await?;
 	let device_id = device.0.0.id;
 
@@ -38,36 +110,39 @@ async fn create(
 		let version_str = VersionStr(semver);
 
 		// Try to get the version, or create it as a draft if it doesn't exist
-		let version_id = match Version::get_by_version(&mut db, version_str.clone()).await {
-			Ok(version) => version.id,
+		let version = match Version::get_by_version(&mut db, version_str.clone()).await {
+			Ok(version) => version,
 			Err(_) => {
 				// Version doesn't exist, create it as a draft
-				let new_version = NewVersion {
-					major: version_str.0.major as _,
-					minor: version_str.0.minor as _,
-					patch: version_str.0.patch as _,
-					changelog: String::new(),
-					status: VersionStatus::Draft,
-					device_id: Some(device_id),
-				};
-
-				let version = diesel::insert_into(database::schema::versions::table)
+				let new_version = NewVersion::new(
+					&version_str,
+					String::new(),
+					VersionStatus::Draft,
+					Some(device_id),
+					vec![ReleaseChannel::default_for(&version_str)],
+				);
+
+				diesel::insert_into(database::schema::versions::table)
 					.values(new_version)
 					.returning(Version::as_select())
 					.get_result(&mut db)
-					.await?;
-
-				version.id
+					.await?
 			}
 		};
 
 		let input = NewArtifact {
-			version_id: Some(version_id),
+			version_id: Some(version.id),
 			platform,
 			artifact_type,
 			download_url: url,
 			device_id: Some(device_id),
 			version_range_pattern: None,
+			version_range_floor: None,
+			version_range_ceiling: None,
+			sha256,
+			size_bytes,
+			bundle_id,
+			version_code,
 		};
 
 		let artifact = diesel::insert_into(database::schema::artifacts::table)
@@ -76,19 +151,32 @@ async fn create(
 			.get_result(&mut db)
 			.await?;
 
+		// Artifacts of drafts are cached when the version is published
+		if let Some(cache) = cache
+			&& version.status == VersionStatus::Published
+		{
+			cache.warm(vec![artifact.clone()]);
+		}
+
 		Ok(Json(artifact))
 	} else {
 		// Try to parse as a range (e.g., "1.0.x", "^1.0.0")
-		Range::parse(&version)
-			.map_err(|_| commons_errors::AppError::custom("Invalid version or version range"))?;
+		let range = Range::parse(&version)
+			.map_err(|_| AppError::custom("Invalid version or version range"))?;
 
 		let input = NewArtifact {
-			version_id: None,
-			platform,
-			artifact_type,
-			download_url: url,
-			device_id: Some(device_id),
-			version_range_pattern: Some(version),
+			sha256,
+			size_bytes,
+			bundle_id,
+			version_code,
+			..NewArtifact::for_range(
+				&range,
+				version,
+				artifact_type,
+				platform,
+				url,
+				Some(device_id),
+			)
 		};
 
 		let artifact = diesel::insert_into(database::schema::artifacts::table)
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use axum::{
+	extract::{Query, State},
+	http::header,
+	response::IntoResponse,
+	routing::{Router, get},
+};
+use commons_errors::Result;
+use commons_types::{
+	markdown,
+	version::{ReleaseChannel, VersionStatus},
+};
+use database::{Db, versions::Version, yanks::YankNotice};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera};
+use uuid::Uuid;
+
+use crate::state::{AppState, PublicUrl};
+
+/// How many versions a feed lists.
+const FEED_LENGTH: i64 = 50;
+
+pub fn routes() -> Router<AppState> {
+	Router::new()
+		.route("/feed.atom", get(atom))
+		.route("/feed.rss", get(rss))
+}
+
+#[derive(Debug, Deserialize)]
+struct FeedQuery {
+	major: Option<i32>,
+	minor: Option<i32>,
+	channel: Option<ReleaseChannel>,
+}
+
+#[derive(Debug, Serialize)]
+struct FeedEntry {
+	id: Uuid,
+	title: String,
+	link: String,
+
+	/// The changelog as HTML, after the yank notice if the version was yanked.
+	content: String,
+
+	published: Timestamp,
+	published_rfc2822: String,
+	updated: Timestamp,
+	updated_rfc2822: String,
+}
+
+fn rfc2822(timestamp: Timestamp) -> String {
+	timestamp.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+async fn render(
+	db: Db,
+	tera: &Tera,
+	public_url: Option<PublicUrl>,
+	query: FeedQuery,
+	template: &str,
+	path: &str,
+) -> Result<String> {
+	// Without a public URL, links are relative to the root
+	let base_url = public_url.map(|url| url.0).unwrap_or_default();
+	let mut db = db.get().await?;
+	let channel = query.channel.unwrap_or_default();
+	let versions =
+		Version::get_for_feed(&mut db, channel, query.major, query.minor, FEED_LENGTH).await?;
+	let yanks = if versions
+		.iter()
+		.any(|version| version.status == VersionStatus::Yanked)
+	{
+		YankNotice::for_all(&mut db).await?
+	} else {
+		Default::default()
+	};
+
+	let entries: Vec<FeedEntry> = versions
+		.into_iter()
+		.map(|version| {
+			let semver = version.as_semver();
+			let mut content = String::new();
+			let published = version.published_at.unwrap_or(version.created_at);
+			let updated = version.yanked_at.unwrap_or(published);
+			let mut title = format!("Tamanu {semver}");
+
+			if let Some(yank) = yanks.get(&version.id) {
+				title.push_str(" (yanked)");
+				content.push_str("<p><strong>This version has been yanked");
+				if !yank.reason.is_empty() {
+					content.push_str(": ");
+					content.push_str(&tera::escape_html(&yank.reason));
+				}
+				content.push_str("</strong></p>");
+				if let Some(replacement) = &yank.replacement {
+					content.push_str(&format!("<p>Upgrade to {replacement} instead.</p>"));
+				}
+			}
+			content.push_str(&markdown::render(&version.changelog));
+
+			FeedEntry {
+				id: version.id,
+				title,
+				link: format!("{base_url}/versions/{semver}"),
+				content,
+				published,
+				published_rfc2822: rfc2822(published),
+				updated,
+				updated_rfc2822: rfc2822(updated),
+			}
+		})
+		.collect();
+
+	// The feed changed when its most recently changed entry did
+	let updated = entries
+		.iter()
+		.map(|entry| entry.updated)
+		.max()
+		.unwrap_or(Timestamp::UNIX_EPOCH);
+
+	let mut title = String::from("Tamanu releases");
+	match (query.major, query.minor) {
+		(Some(major), Some(minor)) => title.push_str(&format!(" {major}.{minor}")),
+		(Some(major), None) => title.push_str(&format!(" {major}.x")),
+		_ => {}
+	}
+	if channel != ReleaseChannel::default() {
+		title.push_str(&format!(" ({channel})"));
+	}
+
+	let mut filters = Vec::new();
+	if let Some(major) = query.major {
+		filters.push(format!("major={major}"));
+	}
+	if let Some(minor) = query.minor {
+		filters.push(format!("minor={minor}"));
+	}
+	if let Some(channel) = query.channel {
+		filters.push(format!("channel={channel}"));
+	}
+	let mut feed_url = format!("{base_url}{path}");
+	if !filters.is_empty() {
+		feed_url.push('?');
+		feed_url.push_str(&filters.join("&"));
+	}
+
+	let mut context = Context::new();
+	context.insert("title", &title);
+	context.insert("base_url", &base_url);
+	context.insert("feed_url", &feed_url);
+	context.insert("updated", &updated);
+	context.insert("updated_rfc2822", &rfc2822(updated));
+	context.insert("entries", &entries);
+	Ok(tera.render(template, &context)?)
+}
+
+async fn atom(
+	State(db): State<Db>,
+	State(tera): State<Arc<Tera>>,
+	Query(query): Query<FeedQuery>,
+	State(public_url): State<Option<PublicUrl>>,
+) -> Result<impl IntoResponse> {
+	let feed = render(db, &tera, public_url, query, "feed_atom", "/feed.atom").await?;
+	Ok((
+		[(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+		feed,
+	))
+}
+
+async fn rss(
+	State(db): State<Db>,
+	State(tera): State<Arc<Tera>>,
+	Query(query): Query<FeedQuery>,
+	State(public_url): State<Option<PublicUrl>>,
+) -> Result<impl IntoResponse> {
+	let feed = render(db, &tera, public_url, query, "feed_rss", "/feed.rss").await?;
+	Ok((
+		[(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+		feed,
+	))
+}
@@ -4,13 +4,18 @@ use axum::routing::Router;
 
 use crate::state::AppState;
 
+pub mod artifact_cache;
 pub mod artifacts;
 pub mod bestool;
 #[cfg(feature = "ui")]
+pub mod feeds;
+#[cfg(feature = "ui")]
 pub mod password;
+pub mod releases;
 #[cfg(feature = "ui")]
 pub mod server_versions;
 pub mod servers;
+pub mod signing;
 pub mod state;
 pub mod statuses;
 #[cfg(feature = "ui")]
@@ -22,6 +27,7 @@ pub fn routes() -> Router<AppState> {
 	let mut router = Router::new()
 		.nest("/artifacts", artifacts::routes())
 		.nest("/bestool", bestool::routes())
+		.nest("/releases", releases::routes())
 		.nest("/servers", servers::routes())
 		.nest("/status", statuses::routes())
 		.nest("/versions", versions::routes());
@@ -33,6 +39,7 @@ pub fn routes() -> Router<AppState> {
 		router = router
 			.route("/", get(index))
 			.route("/errors/{slug}", get(error))
+			.merge(feeds::routes())
 			.merge(commons_servers::health::routes())
 			.merge(timesync::routes())
 			.merge(password::routes())
@@ -50,8 +57,8 @@ async fn index(
 	State(db): State<database::Db>,
 	State(tera): State<std::sync::Arc<tera::Tera>>,
 ) -> commons_errors::Result<axum::response::Html<String>> {
-	use commons_types::version::VersionStatus;
-	use database::versions::Version;
+	use commons_types::version::{SupportStatus, VersionStatus};
+	use database::{support_policies::MinorSupport, versions::Version};
 	use serde::Serialize;
 	use std::collections::BTreeMap;
 	use tera::Context;
@@ -77,10 +84,34 @@ async fn index(
 		#[serde(rename = "first_created_at")]
 		formatted_first_created_at: String,
 		versions: Vec<VersionData>,
+		support: Option<SupportData>,
+	}
+
+	#[derive(Debug, Clone, Serialize)]
+	struct SupportData {
+		status: SupportStatus,
+		label: String,
+		active_support_until: Option<String>,
+		security_support_until: Option<String>,
 	}
 
 	let mut db = db.get().await?;
 	let versions = Version::get_all_including_drafts(&mut db).await?;
+	let support: BTreeMap<(i32, i32), SupportData> = MinorSupport::for_all_minors(&mut db)
+		.await?
+		.into_iter()
+		.map(|support| {
+			(
+				(support.major, support.minor),
+				SupportData {
+					status: support.status,
+					label: support.status.to_string(),
+					active_support_until: support.active_support_until.map(|d| d.to_string()),
+					security_support_until: support.security_support_until.map(|d| d.to_string()),
+				},
+			)
+		})
+		.collect();
 
 	let mut grouped: BTreeMap<(i32, i32), Vec<Version>> = BTreeMap::new();
 	for version in versions {
@@ -93,8 +124,8 @@ async fn index(
 	let mut groups: Vec<MinorVersionGroup> = grouped
 		.into_iter()
 		.filter_map(|((major, minor), mut versions)| {
-			// Filter to only published versions
-			versions.retain(|v| v.status == VersionStatus::Published);
+			// Filter to only published releases, pre-releases are for QA
+			versions.retain(|v| v.status == VersionStatus::Published && !v.is_prerelease());
 
 			// Skip groups with no published versions
 			if versions.is_empty() {
@@ -134,6 +165,7 @@ async fn index(
 				first_created_at,
 				formatted_first_created_at,
 				versions: version_data,
+				support: support.get(&(major, minor)).cloned(),
 			})
 		})
 		.collect();
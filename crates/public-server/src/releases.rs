@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::{HeaderMap, header},
+	routing::{Router, post},
+};
+use commons_errors::{AppError, Result};
+use commons_servers::device_auth::ReleaserDevice;
+use database::{
+	Db,
+	releases::{NewRelease, PublishedRelease},
+};
+use serde::Deserialize;
+
+use crate::{artifact_cache::ArtifactCache, state::AppState};
+
+pub fn routes() -> Router<AppState> {
+	Router::new().route("/", post(publish))
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishQuery {
+	/// Validate and apply the release, then roll it back.
+	#[serde(default)]
+	dry_run: bool,
+}
+
+/// Parse a release manifest, as TOML if the content type says so, or JSON otherwise.
+fn parse_release(headers: &HeaderMap, body: &str) -> Result<NewRelease> {
+	let is_toml = headers
+		.get(header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.contains("toml"));
+	if is_toml {
+		toml::from_str(body).map_err(|err| AppError::custom(format!("Invalid release: {err}")))
+	} else {
+		serde_json::from_str(body).map_err(|err| AppError::custom(format!("Invalid release: {err}")))
+	}
+}
+
+/// Publish a version and all its artifacts at once.
+#[axum::debug_handler]
+async fn publish(
+	device: ReleaserDevice,
+	Query(query): Query<PublishQuery>,
+	State(db): State<Db>,
+	State(cache): State<Option<Arc<ArtifactCache>>>,
+	headers: HeaderMap,
+	body: String,
+) -> Result<Json<PublishedRelease>> {
+	let release = parse_release(&headers, &body)?;
+	let mut db = db.get().await?;
+	let published = release
+		.publish(&mut db, device.0.0.id, query.dry_run)
+		.await?;
+
+	if let Some(cache) = cache
+		&& !published.dry_run
+	{
+		cache.warm(published.artifacts.clone());
+	}
+
+	Ok(Json(published))
+}
@@ -13,7 +13,7 @@ use commons_types::{
 	status::ShortStatus,
 	version::VersionStr,
 };
-use database::{statuses::Status, versions::Version};
+use database::{probes::ServerProbeResult, statuses::Status, versions::Version};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 use futures::future::join_all;
@@ -182,13 +182,16 @@ async fn server_versions_page(
 	} else {
 		Vec::new()
 	};
+	let mut probe_results = ServerProbeResult::latest_for_servers(&mut conn, &server_ids).await?;
 
 	let mut server_infos: Vec<ServerVersionInfo> = Vec::new();
 	for (id, name, host) in servers {
 		let status = statuses.iter().find(|s| s.server_id == id);
 
 		let version = status.and_then(|s| s.version.clone());
-		let up = status.map(|s| s.short_status()).unwrap_or_default();
+		let up = status
+			.map(|s| s.short_status_with_probes(&probe_results.remove(&id).unwrap_or_default()))
+			.unwrap_or_default();
 
 		let version_distance = if let (Some(_), Some(latest)) = (&version, &latest_version) {
 			status.and_then(|s| s.distance_from_version(latest))
@@ -0,0 +1,166 @@
+use std::{collections::HashMap, fmt};
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use commons_errors::{AppError, Result};
+use database::{
+	artifacts::Artifact,
+	manifests::{ReleaseManifest, VersionSignature},
+	versions::Version,
+};
+use diesel_async::AsyncPgConnection;
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The meta server's Ed25519 key, which it signs release manifests with.
+#[derive(Clone)]
+pub struct ManifestSigner(SigningKey);
+
+impl fmt::Debug for ManifestSigner {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("ManifestSigner")
+			.field(&self.public_key())
+			.finish()
+	}
+}
+
+impl ManifestSigner {
+	pub fn from_seed(seed: &[u8; 32]) -> Self {
+		Self(SigningKey::from_bytes(seed))
+	}
+
+	/// From a base64-encoded 32-byte seed, as given in `RELEASE_SIGNING_KEY`.
+	pub fn from_base64(seed: &str) -> Result<Self> {
+		let seed = BASE64_STANDARD
+			.decode(seed.trim())
+			.ok()
+			.and_then(|seed| <[u8; 32]>::try_from(seed).ok())
+			.ok_or_else(|| {
+				AppError::custom(
+					"RELEASE_SIGNING_KEY must be a base64-encoded 32-byte Ed25519 seed",
+				)
+			})?;
+		Ok(Self::from_seed(&seed))
+	}
+
+	/// Base64-encoded raw public key.
+	pub fn public_key(&self) -> String {
+		BASE64_STANDARD.encode(self.0.verifying_key().as_bytes())
+	}
+
+	/// Base64-encoded signature over the payload.
+	pub fn sign(&self, payload: &str) -> String {
+		BASE64_STANDARD.encode(self.0.sign(payload.as_bytes()).to_bytes())
+	}
+}
+
+fn verifying_key(public_key: &[u8]) -> Result<VerifyingKey> {
+	<[u8; 32]>::try_from(public_key)
+		.ok()
+		.and_then(|key| VerifyingKey::from_bytes(&key).ok())
+		.ok_or_else(|| AppError::custom("Public key is not a valid Ed25519 key"))
+}
+
+/// Decode a base64-encoded raw Ed25519 public key, checking that it's usable.
+pub fn parse_public_key(public_key: &str) -> Result<Vec<u8>> {
+	let public_key = decode_base64("Public key", public_key)?;
+	verifying_key(&public_key)?;
+	Ok(public_key)
+}
+
+/// Check a detached Ed25519 signature over a payload.
+pub fn verify(public_key: &[u8], payload: &str, signature: &[u8]) -> Result<()> {
+	let key = verifying_key(public_key)?;
+	let signature = Signature::from_slice(signature)
+		.map_err(|_| AppError::custom("Signature is not a valid Ed25519 signature"))?;
+
+	key.verify_strict(payload.as_bytes(), &signature)
+		.map_err(|_| AppError::AuthFailed {
+			reason: "signature does not match the manifest".into(),
+		})
+}
+
+pub fn decode_base64(field: &str, value: &str) -> Result<Vec<u8>> {
+	BASE64_STANDARD
+		.decode(value.trim())
+		.map_err(|err| AppError::custom(format!("{field} is not valid base64: {err}")))
+}
+
+/// A version's manifest, with the signatures which vouch for it.
+///
+/// Signatures are over the bytes of `payload`, which should be verified before it's parsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedManifest {
+	/// Compact JSON of the [`ReleaseManifest`].
+	pub payload: String,
+
+	/// Base64 signature by the meta server's key, if it has one configured.
+	pub signature: Option<String>,
+
+	/// Detached signatures uploaded by releasers over this same payload.
+	pub releaser_signatures: Vec<ReleaserSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaserSignature {
+	pub key_id: Uuid,
+	pub key_name: Option<String>,
+	pub public_key: String,
+	pub signature: String,
+	pub signed_at: Timestamp,
+}
+
+impl SignedManifest {
+	pub async fn for_version(
+		db: &mut AsyncPgConnection,
+		signer: Option<&ManifestSigner>,
+		version: &Version,
+	) -> Result<Self> {
+		let mut manifests = Self::for_versions(db, signer, std::slice::from_ref(version)).await?;
+		Ok(manifests
+			.remove(&version.id)
+			.expect("every version gets a manifest"))
+	}
+
+	/// The manifest of each of the versions, keyed by version ID, in a fixed number of queries.
+	pub async fn for_versions(
+		db: &mut AsyncPgConnection,
+		signer: Option<&ManifestSigner>,
+		versions: &[Version],
+	) -> Result<HashMap<Uuid, Self>> {
+		let mut artifacts = Artifact::get_for_versions(db, versions).await?;
+		let ids: Vec<Uuid> = versions.iter().map(|version| version.id).collect();
+		let signatures = VersionSignature::get_for_versions(db, &ids).await?;
+
+		Ok(versions
+			.iter()
+			.map(|version| {
+				let artifacts = artifacts.remove(&version.id).unwrap_or_default();
+				let payload = ReleaseManifest::new(version, &artifacts).payload();
+
+				// Releaser signatures only apply for as long as the manifest stays the same
+				let releaser_signatures = signatures
+					.iter()
+					.filter(|(signature, _)| {
+						signature.version_id == version.id && signature.payload == payload
+					})
+					.map(|(signature, key)| ReleaserSignature {
+						key_id: key.id,
+						key_name: key.name.clone(),
+						public_key: BASE64_STANDARD.encode(&key.public_key),
+						signature: BASE64_STANDARD.encode(&signature.signature),
+						signed_at: signature.created_at,
+					})
+					.collect();
+
+				let manifest = Self {
+					signature: signer.map(|signer| signer.sign(&payload)),
+					payload,
+					releaser_signatures,
+				};
+				(version.id, manifest)
+			})
+			.collect())
+	}
+}
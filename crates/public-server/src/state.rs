@@ -1,4 +1,3 @@
-#[cfg(feature = "ui")]
 use std::sync::Arc;
 
 use axum::extract::FromRef;
@@ -7,13 +6,48 @@ use database::Db;
 #[cfg(feature = "ui")]
 use tera::Tera;
 
+use crate::{artifact_cache::ArtifactCache, signing::ManifestSigner};
+
 #[derive(Clone, Debug)]
 pub struct AppState {
 	pub db: Db,
+	pub manifest_signer: Option<ManifestSigner>,
+	pub artifact_cache: Option<Arc<ArtifactCache>>,
 	#[cfg(feature = "ui")]
 	pub tera: Arc<Tera>,
 	#[cfg(feature = "ui")]
 	pub server_versions_secret: Option<String>,
+	#[cfg(feature = "ui")]
+	pub public_url: Option<PublicUrl>,
+}
+
+/// The URL the server is reached at, from `PUBLIC_URL`, for links which must be absolute.
+#[cfg(feature = "ui")]
+#[derive(Clone, Debug)]
+pub struct PublicUrl(pub String);
+
+/// Formats a version object from a template context as `major.minor.patch[-prerelease]`.
+#[cfg(feature = "ui")]
+fn version_filter(
+	value: &tera::Value,
+	_: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+	let part = |name: &str| {
+		value
+			.get(name)
+			.and_then(|part| part.as_i64())
+			.ok_or_else(|| tera::Error::msg(format!("version is missing its {name}")))
+	};
+	let mut version = format!("{}.{}.{}", part("major")?, part("minor")?, part("patch")?);
+	if let Some(prerelease) = value
+		.get("prerelease")
+		.and_then(|prerelease| prerelease.as_str())
+		.filter(|prerelease| !prerelease.is_empty())
+	{
+		version.push('-');
+		version.push_str(prerelease);
+	}
+	Ok(version.into())
 }
 
 impl AppState {
@@ -23,20 +57,29 @@ impl AppState {
 
 		macro_rules! embed_template {
 			($name:expr) => {
+				embed_template!($name, "html");
+			};
+			($name:expr, $ext:expr) => {
 				tera.add_raw_template(
 					$name,
-					include_str!(concat!("../templates/", $name, ".html.tera")),
+					include_str!(concat!("../templates/", $name, ".", $ext, ".tera")),
 				)
 				.unwrap();
 			};
 		}
 
 		embed_template!("artifacts");
+		embed_template!("changelog");
+		embed_template!("feed_atom", "xml");
+		embed_template!("feed_rss", "xml");
+		embed_template!("ios_manifest", "plist");
 		embed_template!("mobile");
 		embed_template!("password");
 		embed_template!("server_versions");
 		embed_template!("versions");
 
+		tera.register_filter("version", version_filter);
+
 		Ok(Arc::new(tera))
 	}
 
@@ -47,10 +90,19 @@ impl AppState {
 	pub fn from_db(db: Db) -> Result<Self> {
 		Ok(Self {
 			db,
+			manifest_signer: std::env::var("RELEASE_SIGNING_KEY")
+				.ok()
+				.map(|seed| ManifestSigner::from_base64(&seed))
+				.transpose()?,
+			artifact_cache: ArtifactCache::from_env()?.map(Arc::new),
 			#[cfg(feature = "ui")]
 			tera: Self::init_tera()?,
 			#[cfg(feature = "ui")]
 			server_versions_secret: std::env::var("SERVER_VERSIONS_SECRET").ok(),
+			#[cfg(feature = "ui")]
+			public_url: std::env::var("PUBLIC_URL")
+				.ok()
+				.map(|url| PublicUrl(url.trim_end_matches('/').to_string())),
 		})
 	}
 }
@@ -61,9 +113,28 @@ impl FromRef<AppState> for Db {
 	}
 }
 
+impl FromRef<AppState> for Option<ManifestSigner> {
+	fn from_ref(state: &AppState) -> Self {
+		state.manifest_signer.clone()
+	}
+}
+
+impl FromRef<AppState> for Option<Arc<ArtifactCache>> {
+	fn from_ref(state: &AppState) -> Self {
+		state.artifact_cache.clone()
+	}
+}
+
 #[cfg(feature = "ui")]
 impl FromRef<AppState> for Arc<Tera> {
 	fn from_ref(state: &AppState) -> Self {
 		state.tera.clone()
 	}
 }
+
+#[cfg(feature = "ui")]
+impl FromRef<AppState> for Option<PublicUrl> {
+	fn from_ref(state: &AppState) -> Self {
+		state.public_url.clone()
+	}
+}
@@ -1,121 +1,288 @@
-use std::str::FromStr as _;
-#[cfg(feature = "ui")]
-use std::sync::Arc;
+use std::{str::FromStr as _, sync::Arc};
 
 #[cfg(feature = "ui")]
 use axum::response::Html;
 use axum::{
 	Json,
 	body::{Body, Bytes},
-	extract::{Path, State},
-	http::header,
-	response::IntoResponse,
+	extract::{Path, Query, State},
+	http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+	response::{IntoResponse, Redirect, Response},
 	routing::{Router, delete, get, post},
 };
+use base64::{Engine as _, prelude::BASE64_STANDARD};
 use commons_errors::{AppError, Result};
-use commons_servers::device_auth::{AdminDevice, ReleaserDevice};
-use commons_types::version::{VersionRange, VersionStr};
+use commons_servers::{
+	Uncompressed,
+	device_auth::{AdminDevice, AuthDevice, ReleaserDevice, ServerDevice},
+	headers::ClientOrigin,
+};
+use commons_types::markdown;
+use commons_types::version::{
+	ReleaseChannel, UpdateUrgency, VersionRange, VersionStr, YankSeverity,
+};
 use database::{
 	Db,
+	artifact_downloads::NewArtifactDownload,
 	artifacts::Artifact,
+	manifests::{NewVersionSignature, ReleaseManifest, VersionSignature},
+	releaser_keys::{NewReleaserKey, ReleaserKey},
+	servers::Server,
+	support_policies::MinorSupport,
+	update_advice::{UpdateAdvice, UpgradeHop},
 	versions::{NewVersion, Version, ViewVersion},
+	yanks::{VersionYank, YankNotice},
 };
 use diesel::{ExpressionMethods as _, SelectableHelper as _};
-use diesel_async::RunQueryDsl as _;
+use diesel_async::{AsyncPgConnection, RunQueryDsl as _};
 use futures::AsyncReadExt;
 #[cfg(feature = "ui")]
-use pulldown_cmark::{Options, Parser, html};
-#[cfg(feature = "ui")]
 use qrcode::{QrCode, render::svg};
-#[cfg(feature = "ui")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ui")]
 use tera::{Context, Tera};
+use tracing::warn;
+use uuid::Uuid;
+
+#[cfg(feature = "ui")]
+use crate::state::PublicUrl;
+use crate::{
+	artifact_cache::ArtifactCache,
+	signing::{self, ManifestSigner, SignedManifest},
+	state::AppState,
+};
 
-use crate::state::AppState;
+/// Base64 of the JSON yank notice, sent with update checks from a version which has been yanked.
+const X_VERSION_YANKED: &str = "X-Version-Yanked";
+
+/// Request headers passed on when proxying artifact downloads, so they can be resumed.
+const FORWARDED_REQUEST_HEADERS: [HeaderName; 4] = [
+	header::RANGE,
+	header::IF_RANGE,
+	header::IF_NONE_MATCH,
+	header::IF_MODIFIED_SINCE,
+];
+
+/// Response headers passed back when proxying artifact downloads.
+const FORWARDED_RESPONSE_HEADERS: [HeaderName; 7] = [
+	header::ACCEPT_RANGES,
+	header::CONTENT_DISPOSITION,
+	header::CONTENT_LENGTH,
+	header::CONTENT_RANGE,
+	header::CONTENT_TYPE,
+	header::ETAG,
+	header::LAST_MODIFIED,
+];
 
 pub fn routes() -> Router<AppState> {
 	#[cfg_attr(not(feature = "ui"), expect(unused_mut))]
 	let mut router = Router::new()
 		.route("/", get(list))
 		.route("/update-for/{version}", get(update_for))
+		.route("/update-for/{version}/advice", get(update_advice))
+		.route("/update-for/{version}/path", get(upgrade_path))
+		.route("/support", get(list_support))
+		.route("/signing-key", get(signing_key))
+		.route("/releaser-keys", get(list_releaser_keys))
+		.route("/releaser-keys", post(register_releaser_key))
 		.route("/{version}", post(create))
 		.route("/{version}", delete(remove))
 		.route("/{version}/artifacts", get(list_artifacts))
+		.route("/{version}/support", get(support_for))
+		.route("/{version}/changelog/{to}", get(changelog_between))
+		.route("/{version}/manifest", get(manifest))
+		.route("/{version}/signature", post(sign_manifest))
 		.route(
 			"/{version}/artifacts/{artifact_id}/download",
 			get(download_artifact),
+		)
+		.route(
+			"/{version}/artifacts/{artifact_id}/redirect",
+			get(redirect_artifact),
 		);
 
 	#[cfg(feature = "ui")]
 	{
 		router = router
 			.route("/{version}", get(view_artifacts))
-			.route("/{version}/mobile", get(view_mobile_install));
+			.route("/{version}/mobile", get(view_mobile_install))
+			.route(
+				"/{version}/artifacts/{artifact_id}/manifest.plist",
+				get(ios_install_manifest),
+			);
 	}
 
 	router
 }
 
+/// A mobile platform which can install apps from the install page.
+#[cfg(feature = "ui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MobilePlatform {
+	Android,
+	Ios,
+}
+
 #[cfg(feature = "ui")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ArtifactWithQR {
+impl MobilePlatform {
+	/// The platform an artifact is for, going by its platform name.
+	fn of_artifact(artifact: &Artifact) -> Option<Self> {
+		match artifact.platform.to_ascii_lowercase().as_str() {
+			"android" => Some(Self::Android),
+			"ios" => Some(Self::Ios),
+			_ => None,
+		}
+	}
+
+	/// The platform a browser is running on, going by its user agent.
+	///
+	/// iPads present themselves as Macs by default, so they're only recognised when they ask for
+	/// the mobile site.
+	fn of_user_agent(user_agent: &str) -> Option<Self> {
+		if user_agent.contains("Android") {
+			Some(Self::Android)
+		} else if ["iPhone", "iPad", "iPod"]
+			.iter()
+			.any(|device| user_agent.contains(device))
+		{
+			Some(Self::Ios)
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(feature = "ui")]
+#[derive(Debug, Clone, Serialize)]
+struct MobileArtifact {
 	#[serde(flatten)]
 	artifact: Artifact,
-	qr_code_svg: String,
+	mobile_platform: Option<MobilePlatform>,
+
+	/// Where the install button goes: the APK or other file, through the download redirect.
+	install_url: String,
+
+	/// For iOS apps with a bundle identifier, the manifest to give to `itms-services`.
+	manifest_url: Option<String>,
+
+	/// Scanned from another device, this leads to the download redirect, or to the install page for
+	/// iOS apps as they must be installed from Safari.
+	qr_code_svg: Option<String>,
 }
 
 #[cfg(feature = "ui")]
-impl From<Artifact> for ArtifactWithQR {
-	fn from(artifact: Artifact) -> Self {
-		let code = QrCode::new(&artifact.download_url).expect("Failed to generate QR code");
-		let svg_image = code
-			.render::<svg::Color>()
-			.min_dimensions(100, 100)
-			.dark_color(svg::Color("#000000"))
-			.light_color(svg::Color("#ffffff"))
-			.build();
+impl MobileArtifact {
+	/// Install links are absolute when the server's public URL is known: the `itms-services` flow
+	/// and QR codes need it, so they're left out without one.
+	fn new(artifact: Artifact, version: &Version, public_url: Option<&PublicUrl>) -> Self {
+		let version_path = format!("/versions/{}", version.as_semver());
+		let mobile_platform = MobilePlatform::of_artifact(&artifact);
+		let install_path = format!("{version_path}/artifacts/{}/redirect", artifact.id);
+		let installs_from_manifest =
+			mobile_platform == Some(MobilePlatform::Ios) && artifact.bundle_id.is_some();
+
+		let (manifest_url, qr_code_svg) = match public_url {
+			Some(PublicUrl(base_url)) => {
+				let manifest_url = installs_from_manifest.then(|| {
+					format!(
+						"{base_url}{version_path}/artifacts/{}/manifest.plist",
+						artifact.id
+					)
+				});
+				let qr_target = if installs_from_manifest {
+					format!("{base_url}{version_path}/mobile")
+				} else {
+					format!("{base_url}{install_path}")
+				};
+				let code = QrCode::new(&qr_target).expect("Failed to generate QR code");
+				let svg_image = code
+					.render::<svg::Color>()
+					.min_dimensions(100, 100)
+					.dark_color(svg::Color("#000000"))
+					.light_color(svg::Color("#ffffff"))
+					.build();
+				(manifest_url, Some(svg_image))
+			}
+			None => (None, None),
+		};
 
 		Self {
 			artifact,
-			qr_code_svg: svg_image,
+			mobile_platform,
+			install_url: install_path,
+			manifest_url,
+			qr_code_svg,
 		}
 	}
 }
 
-#[cfg(feature = "ui")]
-pub fn parse_markdown(text: &str) -> String {
-	let mut options = Options::empty();
-	options.insert(Options::ENABLE_FOOTNOTES);
-	options.insert(Options::ENABLE_GFM);
-	options.insert(Options::ENABLE_SMART_PUNCTUATION);
-	options.insert(Options::ENABLE_STRIKETHROUGH);
-	options.insert(Options::ENABLE_TABLES);
-	let parser = Parser::new_ext(text, options);
-	let mut html_output = String::new();
-	html::push_html(&mut html_output, parser);
-	html_output
-}
-
-async fn list(State(db): State<Db>) -> Result<Json<Vec<Version>>> {
+#[derive(Debug, Deserialize)]
+struct ChannelQuery {
+	channel: Option<ReleaseChannel>,
+}
+
+async fn list(
+	State(db): State<Db>,
+	Query(query): Query<ChannelQuery>,
+) -> Result<Json<Vec<Version>>> {
 	let mut db = db.get().await?;
-	let versions = Version::get_all(&mut db).await?;
+	let versions = Version::get_all_in_channel(&mut db, query.channel.unwrap_or_default()).await?;
 	Ok(Json(versions))
 }
 
+async fn list_support(State(db): State<Db>) -> Result<Json<Vec<MinorSupport>>> {
+	let mut db = db.get().await?;
+	let support = MinorSupport::for_all_minors(&mut db).await?;
+	Ok(Json(support))
+}
+
+async fn support_for(
+	State(db): State<Db>,
+	Path(version): Path<String>,
+) -> Result<Json<MinorSupport>> {
+	let mut db = db.get().await?;
+	let version = VersionStr::from_str(&version)?;
+	let support = MinorSupport::for_version(&mut db, version).await?;
+	Ok(Json(support))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateQuery {
+	/// Comma-separated list of channels to release to, defaults to stable, or beta for pre-releases.
+	channels: Option<String>,
+	/// How urgently servers should update, defaults to normal.
+	urgency: Option<UpdateUrgency>,
+	/// Whether servers must upgrade through this version before moving on to anything newer.
+	stepping_stone: Option<bool>,
+}
+
 async fn create(
 	device: ReleaserDevice,
 	Path(version): Path<String>,
+	Query(query): Query<CreateQuery>,
 	State(db): State<Db>,
+	State(cache): State<Option<Arc<ArtifactCache>>>,
 	data: Bytes,
 ) -> Result<Json<Version>> {
 	use commons_types::version::VersionStatus;
 
+	let version_str = VersionStr::from_str(&version)?;
+	let channels = query
+		.channels
+		.map(|channels| {
+			channels
+				.split(',')
+				.map(|channel| ReleaseChannel::from_str(channel.trim()))
+				.collect::<std::result::Result<Vec<_>, _>>()
+				.map_err(|err| AppError::custom(err.to_string()))
+		})
+		.transpose()?;
+
 	let mut db = db.get().await?;
 	let mut stream = data.take(1024 * 1024 * 1024); // up to a MiB
 	let mut changelog = String::with_capacity(data.len().min(1024 * 1024 * 1024));
 	stream.read_to_string(&mut changelog).await?;
-	let version_str = VersionStr::from_str(&version)?;
 	let device_id = device.0.0.id;
 
 	// Check if a draft version already exists
@@ -125,18 +292,32 @@ async fn create(
 			Version::update_status(&mut db, version_str.clone(), VersionStatus::Published).await?;
 			Version::update_changelog(&mut db, version_str.clone(), changelog).await?;
 			Version::update_device_id(&mut db, version_str.clone(), device_id).await?;
+			// keep the channels set on the draft unless new ones are given
+			if let Some(channels) = channels {
+				Version::update_channels(&mut db, version_str.clone(), channels).await?;
+			}
+			if let Some(urgency) = query.urgency {
+				Version::update_urgency(&mut db, version_str.clone(), urgency).await?;
+			}
+			if let Some(stepping_stone) = query.stepping_stone {
+				Version::update_stepping_stone(&mut db, version_str.clone(), stepping_stone)
+					.await?;
+			}
 			Version::get_by_version(&mut db, version_str).await?
 		}
 		Ok(_) => {
 			// Version exists but is not a draft, let the insert fail with constraint violation
 			diesel::insert_into(database::schema::versions::table)
 				.values(NewVersion {
-					major: version_str.0.major as _,
-					minor: version_str.0.minor as _,
-					patch: version_str.0.patch as _,
-					changelog,
-					status: VersionStatus::Published,
-					device_id: Some(device_id),
+					urgency: query.urgency.unwrap_or_default(),
+					stepping_stone: query.stepping_stone.unwrap_or_default(),
+					..NewVersion::new(
+						&version_str,
+						changelog,
+						VersionStatus::Published,
+						Some(device_id),
+						channels.unwrap_or_else(|| vec![ReleaseChannel::default_for(&version_str)]),
+					)
 				})
 				.returning(Version::as_select())
 				.get_result(&mut db)
@@ -146,12 +327,15 @@ async fn create(
 			// Version doesn't exist, create it as published
 			diesel::insert_into(database::schema::versions::table)
 				.values(NewVersion {
-					major: version_str.0.major as _,
-					minor: version_str.0.minor as _,
-					patch: version_str.0.patch as _,
-					changelog,
-					status: VersionStatus::Published,
-					device_id: Some(device_id),
+					urgency: query.urgency.unwrap_or_default(),
+					stepping_stone: query.stepping_stone.unwrap_or_default(),
+					..NewVersion::new(
+						&version_str,
+						changelog,
+						VersionStatus::Published,
+						Some(device_id),
+						channels.unwrap_or_else(|| vec![ReleaseChannel::default_for(&version_str)]),
+					)
 				})
 				.returning(Version::as_select())
 				.get_result(&mut db)
@@ -159,26 +343,54 @@ async fn create(
 		}
 	};
 
+	if let Some(cache) = cache {
+		cache.warm(Artifact::get_for_version(&mut db, version.id).await?);
+	}
+
 	Ok(Json(version))
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct YankInput {
+	#[serde(default)]
+	reason: String,
+	#[serde(default)]
+	severity: YankSeverity,
+	/// The version servers should move to instead.
+	replacement: Option<String>,
+}
+
+/// Yank a version, optionally with a JSON body giving the reason, severity, and replacement.
 async fn remove(
-	_device: AdminDevice,
+	device: AdminDevice,
 	Path(version): Path<String>,
 	State(db): State<Db>,
-) -> Result<()> {
-	use commons_types::version::VersionStatus;
-	use database::schema::versions::dsl::*;
+	body: Bytes,
+) -> Result<Json<YankNotice>> {
+	let input: YankInput = if body.is_empty() {
+		Default::default()
+	} else {
+		serde_json::from_slice(&body)
+			.map_err(|err| AppError::custom(format!("Invalid yank: {err}")))?
+	};
+	let replacement = input
+		.replacement
+		.as_deref()
+		.map(VersionStr::from_str)
+		.transpose()?;
 
 	let mut db = db.get().await?;
-	let version = VersionStr::from_str(&version)?;
-	diesel::update(versions)
-		.filter(database::versions::predicate_version!(version.0))
-		.set(status.eq(VersionStatus::Yanked))
-		.execute(&mut db)
-		.await?;
+	let notice = VersionYank::yank(
+		&mut db,
+		VersionStr::from_str(&version)?,
+		input.reason,
+		input.severity,
+		replacement,
+		Some(device.0.0.id),
+	)
+	.await?;
 
-	Ok(())
+	Ok(Json(notice))
 }
 
 #[cfg(feature = "ui")]
@@ -187,7 +399,8 @@ async fn view_artifacts(
 	State(db): State<Db>,
 	State(tera): State<Arc<Tera>>,
 ) -> Result<Html<String>> {
-	use commons_types::version::VersionStatus;
+	use commons_types::{requirements::Platform, version::VersionStatus};
+	use database::platform_requirements::PlatformRequirement;
 	use diesel::QueryDsl;
 	use serde::Serialize;
 
@@ -205,23 +418,48 @@ async fn view_artifacts(
 		major: i32,
 		minor: i32,
 		patch: i32,
+		prerelease: String,
 		changelog: String,
+		yank: Option<YankNotice>,
+	}
+
+	#[derive(Debug, Clone, Serialize)]
+	struct RequirementForTemplate {
+		platform: String,
+		min_version: Option<String>,
+		max_version: Option<String>,
 	}
 
 	let mut db = db.get().await?;
-	let version = VersionRange::from_str(&version)?;
-	let mut version = Version::get_latest_matching(&mut db, version.0).await?;
-	version.changelog = parse_markdown(&version.changelog);
+
+	// Yanked versions aren't matched by ranges, but their own page still shows them
+	let yanked = match VersionStr::from_str(&version) {
+		Ok(exact) => Version::get_by_version(&mut db, exact)
+			.await
+			.ok()
+			.filter(|version| version.status == VersionStatus::Yanked),
+		Err(_) => None,
+	};
+	let mut version = match yanked {
+		Some(version) => version,
+		None => {
+			let version = VersionRange::from_str(&version)?;
+			Version::get_latest_matching(&mut db, version.0).await?
+		}
+	};
+	version.changelog = markdown::render(&version.changelog);
+	let yank = YankNotice::for_version(&mut db, &version).await?;
 	let artifacts = Artifact::get_for_version(&mut db, version.id).await?;
 
-	// Check if this is the latest published version in its minor
+	// Check if this is the latest published release in its minor
 	let latest_in_minor = {
-		use database::schema::versions::dsl::*;
+		use database::{schema::versions::dsl::*, versions::version_sort_key};
 		versions
 			.filter(major.eq(version.major))
 			.filter(minor.eq(version.minor))
 			.filter(status.eq(VersionStatus::Published))
-			.order_by(patch.desc())
+			.filter(prerelease.eq(""))
+			.order_by(version_sort_key(major, minor, patch, prerelease).desc())
 			.select(Version::as_select())
 			.first(&mut db)
 			.await
@@ -230,7 +468,7 @@ async fn view_artifacts(
 
 	let is_latest = latest_in_minor
 		.as_ref()
-		.map(|v| v.patch == version.patch)
+		.map(|v| v.as_semver() == version.as_semver())
 		.unwrap_or(true);
 
 	let latest_version_str =
@@ -238,8 +476,17 @@ async fn view_artifacts(
 
 	let created_at_date = version.created_at.strftime("%Y-%m-%d").to_string();
 
-	// Compute min chrome version based on head release date (X.Y.0)
-	let min_chrome_version = if let Ok(head_release_date) =
+	let requirements =
+		PlatformRequirement::get_for_minor(&mut db, version.major, version.minor).await?;
+
+	// Compute min chrome version based on head release date (X.Y.0),
+	// unless the minor states its own Chrome requirement
+	let min_chrome_version = if requirements
+		.iter()
+		.any(|requirement| requirement.platform == Platform::Chrome)
+	{
+		None
+	} else if let Ok(head_release_date) =
 		Version::get_head_release_date(&mut db, VersionStr(version.as_semver())).await
 	{
 		database::chrome_releases::ChromeRelease::get_min_version_at_date(
@@ -254,18 +501,19 @@ async fn view_artifacts(
 	};
 
 	// Get all lower patch versions in this minor release
-	let related_versions = Version::get_all_in_minor(&mut db, VersionStr(version.as_semver()))
+	let in_minor = Version::get_all_in_minor(&mut db, VersionStr(version.as_semver()))
 		.await
-		.unwrap_or_default()
+		.unwrap_or_default();
+	let mut yanks = YankNotice::for_versions(&mut db, &in_minor).await?;
+	let related_versions: Vec<RelatedVersion> = in_minor
 		.into_iter()
-		.map(|mut v| {
-			v.changelog = parse_markdown(&v.changelog);
-			RelatedVersion {
-				major: v.major,
-				minor: v.minor,
-				patch: v.patch,
-				changelog: v.changelog,
-			}
+		.map(|v| RelatedVersion {
+			yank: yanks.remove(&v.id),
+			major: v.major,
+			minor: v.minor,
+			patch: v.patch,
+			prerelease: v.prerelease,
+			changelog: markdown::render(&v.changelog),
 		})
 		.collect();
 
@@ -281,19 +529,182 @@ async fn view_artifacts(
 	context.insert("artifacts", &artifacts);
 	context.insert("is_latest", &is_latest);
 	context.insert("latest_version", &latest_version_str);
+	context.insert("yank", &yank);
+	context.insert(
+		"requirements",
+		&requirements
+			.into_iter()
+			.map(|requirement| RequirementForTemplate {
+				platform: requirement.platform.to_string(),
+				min_version: requirement.min_version,
+				max_version: requirement.max_version,
+			})
+			.collect::<Vec<_>>(),
+	);
 	Ok(Html(tera.render("artifacts", &context)?))
 }
 
+#[derive(Debug, Deserialize)]
+struct ArtifactsQuery {
+	/// Send the signed manifest along with the artifacts.
+	#[serde(default)]
+	manifest: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum ArtifactList {
+	Plain(Vec<Artifact>),
+	WithManifest {
+		artifacts: Vec<Artifact>,
+		manifest: SignedManifest,
+	},
+}
+
 async fn list_artifacts(
 	Path(version): Path<String>,
 	State(db): State<Db>,
-) -> Result<Json<Vec<Artifact>>> {
+	State(signer): State<Option<ManifestSigner>>,
+	Query(query): Query<ArtifactsQuery>,
+) -> Result<Json<ArtifactList>> {
 	let mut db = db.get().await?;
 	let version = VersionRange::from_str(&version)?;
 	let version = Version::get_latest_matching(&mut db, version.0).await?;
 	let artifacts = Artifact::get_for_version(&mut db, version.id).await?;
+	if !query.manifest {
+		return Ok(Json(ArtifactList::Plain(artifacts)));
+	}
+
+	let manifest = SignedManifest::for_version(&mut db, signer.as_ref(), &version).await?;
+	Ok(Json(ArtifactList::WithManifest {
+		artifacts,
+		manifest,
+	}))
+}
+
+#[derive(Debug, Serialize)]
+struct SigningKey {
+	algorithm: &'static str,
+	public_key: String,
+}
+
+/// The public key which manifests are signed with, for servers to pin.
+async fn signing_key(State(signer): State<Option<ManifestSigner>>) -> Result<Json<SigningKey>> {
+	let signer = signer.ok_or(AppError::NotImplemented)?;
+	Ok(Json(SigningKey {
+		algorithm: "ed25519",
+		public_key: signer.public_key(),
+	}))
+}
+
+#[derive(Debug, Serialize)]
+struct ReleaserKeyData {
+	id: Uuid,
+	name: Option<String>,
+	public_key: String,
+	created_at: jiff::Timestamp,
+}
+
+impl From<ReleaserKey> for ReleaserKeyData {
+	fn from(key: ReleaserKey) -> Self {
+		Self {
+			id: key.id,
+			name: key.name,
+			public_key: BASE64_STANDARD.encode(key.public_key),
+			created_at: key.created_at,
+		}
+	}
+}
+
+async fn list_releaser_keys(State(db): State<Db>) -> Result<Json<Vec<ReleaserKeyData>>> {
+	let mut db = db.get().await?;
+	let keys = ReleaserKey::get_all(&mut db).await?;
+	Ok(Json(keys.into_iter().map(Into::into).collect()))
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterReleaserKey {
+	/// Base64-encoded raw Ed25519 public key.
+	public_key: String,
+	name: Option<String>,
+}
+
+async fn register_releaser_key(
+	device: ReleaserDevice,
+	State(db): State<Db>,
+	Json(input): Json<RegisterReleaserKey>,
+) -> Result<Json<ReleaserKeyData>> {
+	let mut db = db.get().await?;
+	let key = ReleaserKey::create(
+		&mut db,
+		NewReleaserKey {
+			device_id: device.0.0.id,
+			name: input.name,
+			public_key: signing::parse_public_key(&input.public_key)?,
+		},
+	)
+	.await?;
+	Ok(Json(key.into()))
+}
+
+async fn manifest(
+	Path(version): Path<String>,
+	State(db): State<Db>,
+	State(signer): State<Option<ManifestSigner>>,
+) -> Result<Json<SignedManifest>> {
+	let mut db = db.get().await?;
+	let version = VersionRange::from_str(&version)?;
+	let version = Version::get_latest_matching(&mut db, version.0).await?;
+	let manifest = SignedManifest::for_version(&mut db, signer.as_ref(), &version).await?;
+	Ok(Json(manifest))
+}
+
+#[derive(Debug, Deserialize)]
+struct DetachedSignature {
+	/// Base64-encoded public key of a releaser key registered by the calling device.
+	public_key: String,
+	/// Base64-encoded Ed25519 signature over the manifest payload.
+	signature: String,
+}
+
+/// Add a releaser's signature over the version's current manifest.
+async fn sign_manifest(
+	device: ReleaserDevice,
+	Path(version): Path<String>,
+	State(db): State<Db>,
+	State(signer): State<Option<ManifestSigner>>,
+	Json(input): Json<DetachedSignature>,
+) -> Result<Json<SignedManifest>> {
+	let mut db = db.get().await?;
+	let version = Version::get_by_version(&mut db, VersionStr::from_str(&version)?).await?;
+
+	let public_key = signing::decode_base64("Public key", &input.public_key)?;
+	let key = ReleaserKey::get_by_public_key(&mut db, &public_key)
+		.await?
+		.filter(|key| key.device_id == device.0.0.id)
+		.ok_or_else(|| AppError::AuthFailed {
+			reason: "public key is not registered to this releaser".into(),
+		})?;
+
+	let payload = ReleaseManifest::for_version(&mut db, &version)
+		.await?
+		.payload();
+	let signature = signing::decode_base64("Signature", &input.signature)?;
+	signing::verify(&key.public_key, &payload, &signature)?;
+
+	VersionSignature::create(
+		&mut db,
+		NewVersionSignature {
+			version_id: version.id,
+			releaser_key_id: key.id,
+			payload,
+			signature,
+		},
+	)
+	.await?;
 
-	Ok(Json(artifacts))
+	let manifest = SignedManifest::for_version(&mut db, signer.as_ref(), &version).await?;
+	Ok(Json(manifest))
 }
 
 #[cfg(feature = "ui")]
@@ -301,68 +712,466 @@ async fn view_mobile_install(
 	Path(version): Path<String>,
 	State(db): State<Db>,
 	State(tera): State<Arc<Tera>>,
+	State(public_url): State<Option<PublicUrl>>,
+	headers: HeaderMap,
 ) -> Result<Html<String>> {
 	let mut db = db.get().await?;
 	let version = VersionRange::from_str(&version)?;
 	let version = Version::get_latest_matching(&mut db, version.0).await?;
-	let artifacts = Artifact::get_for_version(&mut db, version.id)
+
+	let client_platform = headers
+		.get(header::USER_AGENT)
+		.and_then(|agent| agent.to_str().ok())
+		.and_then(MobilePlatform::of_user_agent);
+	let mut artifacts = Artifact::get_for_version(&mut db, version.id)
 		.await?
 		.into_iter()
 		.filter(|a| a.artifact_type == "mobile")
-		.map(ArtifactWithQR::from)
+		.map(|artifact| MobileArtifact::new(artifact, &version, public_url.as_ref()))
 		.collect::<Vec<_>>();
 
+	// Put what can be installed on the device the page is open on first
+	if client_platform.is_some() {
+		artifacts.sort_by_key(|artifact| artifact.mobile_platform != client_platform);
+	}
+
 	let mut context = Context::new();
 	context.insert("version", &version);
 	context.insert("artifacts", &artifacts);
+	context.insert("client_platform", &client_platform);
 	Ok(Html(tera.render("mobile", &context)?))
 }
 
+/// The manifest iOS downloads to install an app from an `itms-services` link.
+#[cfg(feature = "ui")]
+async fn ios_install_manifest(
+	Path((version, artifact_id)): Path<(String, String)>,
+	State(db): State<Db>,
+	State(tera): State<Arc<Tera>>,
+	State(public_url): State<Option<PublicUrl>>,
+) -> Result<impl IntoResponse> {
+	let Some(PublicUrl(base_url)) = public_url else {
+		return Err(AppError::custom(
+			"PUBLIC_URL must be set to install iOS apps, as their manifest needs absolute links",
+		));
+	};
+	let mut db = db.get().await?;
+	let (version, artifact) = find_artifact(&mut db, &version, &artifact_id).await?;
+	let bundle_id = match (MobilePlatform::of_artifact(&artifact), &artifact.bundle_id) {
+		(Some(MobilePlatform::Ios), Some(bundle_id)) => bundle_id,
+		_ => {
+			return Err(AppError::custom(
+				"Artifact is not an iOS app with a bundle identifier",
+			));
+		}
+	};
+
+	// The app must be fetched over https, which the download route gives us
+	let mut context = Context::new();
+	context.insert(
+		"url",
+		&format!(
+			"{base_url}/versions/{}/artifacts/{}/download",
+			version.as_semver(),
+			artifact.id
+		),
+	);
+	context.insert("bundle_id", bundle_id);
+	// iOS compares this to the app's CFBundleVersion, which is its build number
+	context.insert(
+		"bundle_version",
+		&artifact.version_code.map_or_else(
+			|| format!("{}.{}.{}", version.major, version.minor, version.patch),
+			|code| code.to_string(),
+		),
+	);
+	context.insert("title", "Tamanu Mobile");
+
+	Ok((
+		[(header::CONTENT_TYPE, "text/xml; charset=utf-8")],
+		tera.render("ios_manifest", &context)?,
+	))
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateWithManifest {
+	#[serde(flatten)]
+	version: ViewVersion,
+	manifest: SignedManifest,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateForQuery {
+	channel: Option<ReleaseChannel>,
+
+	/// Wrap the updates in an object which also says what the server should know about its version,
+	/// and what to do about updating.
+	#[serde(default)]
+	detailed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum UpdateCheck {
+	Plain(Vec<UpdateWithManifest>),
+	Detailed(Box<DetailedUpdateCheck>),
+}
+
+#[derive(Debug, Serialize)]
+struct DetailedUpdateCheck {
+	updates: Vec<UpdateWithManifest>,
+
+	/// Why the server's version was yanked, and what to move to, if it has been.
+	yank: Option<YankNotice>,
+
+	/// How urgently the server should update, and to what.
+	advice: UpdateAdvice,
+}
+
 async fn update_for(
 	State(db): State<Db>,
+	State(signer): State<Option<ManifestSigner>>,
 	Path(version): Path<String>,
-) -> Result<Json<Vec<ViewVersion>>> {
+	Query(query): Query<UpdateForQuery>,
+	device: Option<ServerDevice>,
+) -> Result<(HeaderMap, Json<UpdateCheck>)> {
 	let mut db = db.get().await?;
 	let version = VersionStr::from_str(&version)?;
-	let updates = Version::get_updates_for_version(&mut db, version).await?;
-	Ok(Json(updates))
+	let (channel, server_id) = caller_channel(&mut db, query.channel, device).await?;
+
+	// Tell servers on a yanked version why, and what to move to
+	let yank = match Version::get_by_version(&mut db, version.clone()).await {
+		Ok(current) => YankNotice::for_version(&mut db, &current).await?,
+		Err(_) => None,
+	};
+	let mut headers = HeaderMap::new();
+	if let Some(notice) = &yank {
+		let notice = serde_json::to_vec(notice).map_err(AppError::custom)?;
+		headers.insert(
+			X_VERSION_YANKED,
+			HeaderValue::try_from(BASE64_STANDARD.encode(notice)).map_err(AppError::custom)?,
+		);
+	}
+
+	let advice = UpdateAdvice::for_version(&mut db, version.clone(), channel, server_id).await?;
+	let mut updates =
+		Version::get_updates_for_version(&mut db, version, channel, server_id).await?;
+
+	// Nothing past the next stepping stone is offered until the server has upgraded through it
+	if let [next, _, ..] = &advice.path[..] {
+		let stone = (next.version.major, next.version.minor);
+		updates.retain(|update| (update.major, update.minor) <= stone);
+	}
+
+	let ids: Vec<Uuid> = updates.iter().map(|update| update.id).collect();
+	let versions = Version::get_by_ids(&mut db, &ids).await?;
+	let mut manifests = SignedManifest::for_versions(&mut db, signer.as_ref(), &versions).await?;
+
+	let updates = updates
+		.into_iter()
+		.filter_map(|update| {
+			Some(UpdateWithManifest {
+				manifest: manifests.remove(&update.id)?,
+				version: update,
+			})
+		})
+		.collect();
+
+	let check = if query.detailed {
+		UpdateCheck::Detailed(Box::new(DetailedUpdateCheck {
+			updates,
+			yank,
+			advice,
+		}))
+	} else {
+		UpdateCheck::Plain(updates)
+	};
+	Ok((headers, Json(check)))
 }
 
-async fn download_artifact(
+/// The channel to check for updates in, and the calling server if it identified itself.
+async fn caller_channel(
+	db: &mut AsyncPgConnection,
+	channel: Option<ReleaseChannel>,
+	device: Option<ServerDevice>,
+) -> Result<(ReleaseChannel, Option<Uuid>)> {
+	// Servers identify themselves with their device, for their default channel and rollouts
+	let server = match device {
+		Some(device) => Server::get_by_device_id(db, device.0.0.id)
+			.await?
+			.into_iter()
+			.next(),
+		None => None,
+	};
+
+	// An explicit channel wins, then the calling server's default, then stable
+	let channel = channel
+		.or(server.as_ref().map(|server| server.default_channel))
+		.unwrap_or_default();
+
+	Ok((channel, server.map(|server| server.id)))
+}
+
+/// Structured advice for a server on the version: how urgently to update, and to what.
+async fn update_advice(
 	State(db): State<Db>,
-	Path((version, artifact_id)): Path<(String, String)>,
-) -> Result<impl IntoResponse> {
-	use uuid::Uuid;
+	Path(version): Path<String>,
+	Query(query): Query<ChannelQuery>,
+	device: Option<ServerDevice>,
+) -> Result<Json<UpdateAdvice>> {
+	let mut db = db.get().await?;
+	let version = VersionStr::from_str(&version)?;
+	let (channel, server_id) = caller_channel(&mut db, query.channel, device).await?;
+	let advice = UpdateAdvice::for_version(&mut db, version, channel, server_id).await?;
+	Ok(Json(advice))
+}
 
+/// The versions a server on the version should upgrade to, in order, to reach the latest.
+async fn upgrade_path(
+	State(db): State<Db>,
+	Path(version): Path<String>,
+	Query(query): Query<ChannelQuery>,
+	device: Option<ServerDevice>,
+) -> Result<Json<Vec<UpgradeHop>>> {
 	let mut db = db.get().await?;
-	let version = VersionRange::from_str(&version)?;
-	let version = Version::get_latest_matching(&mut db, version.0).await?;
+	let version = VersionStr::from_str(&version)?;
+	let (channel, server_id) = caller_channel(&mut db, query.channel, device).await?;
+	let advice = UpdateAdvice::for_version(&mut db, version, channel, server_id).await?;
+	Ok(Json(advice.path))
+}
+
+/// The changelogs of the versions in a minor, as rendered HTML.
+#[derive(Debug, Clone, Serialize)]
+struct MinorChangelogs {
+	major: i32,
+	minor: i32,
+	versions: Vec<VersionChangelog>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct VersionChangelog {
+	major: i32,
+	minor: i32,
+	patch: i32,
+	prerelease: String,
+	created_at: jiff::Timestamp,
+	created_at_date: String,
+	changelog: String,
+}
+
+/// Every published version after `from` up to and including `to`, oldest first, grouped by minor.
+async fn changelogs_between(
+	db: &mut AsyncPgConnection,
+	from: VersionStr,
+	to: VersionStr,
+) -> Result<Vec<MinorChangelogs>> {
+	let mut minors: Vec<MinorChangelogs> = Vec::new();
+	for version in Version::get_between(db, from, to).await? {
+		let changelog = VersionChangelog {
+			major: version.major,
+			minor: version.minor,
+			patch: version.patch,
+			prerelease: version.prerelease.clone(),
+			created_at: version.created_at,
+			created_at_date: version.created_at.strftime("%Y-%m-%d").to_string(),
+			changelog: markdown::render(&version.changelog),
+		};
+
+		match minors.last_mut() {
+			Some(group) if group.major == version.major && group.minor == version.minor => {
+				group.versions.push(changelog);
+			}
+			_ => minors.push(MinorChangelogs {
+				major: version.major,
+				minor: version.minor,
+				versions: vec![changelog],
+			}),
+		}
+	}
+
+	Ok(minors)
+}
+
+/// The changelogs a server goes through when upgrading from `from` to `to`.
+///
+/// Browsers get the page, anything else gets JSON.
+async fn changelog_between(
+	State(db): State<Db>,
+	#[cfg(feature = "ui")] State(tera): State<Arc<Tera>>,
+	#[cfg(feature = "ui")] headers: HeaderMap,
+	Path((from, to)): Path<(String, String)>,
+) -> Result<Response> {
+	let mut db = db.get().await?;
+	let from = VersionStr::from_str(&from)?;
+	let to = VersionStr::from_str(&to)?;
+	let minors = changelogs_between(&mut db, from.clone(), to.clone()).await?;
+
+	#[cfg(feature = "ui")]
+	if headers
+		.get(header::ACCEPT)
+		.and_then(|accept| accept.to_str().ok())
+		.is_none_or(|accept| !accept.contains("application/json"))
+	{
+		let mut context = Context::new();
+		context.insert("from", &from.0.to_string());
+		context.insert("to", &to.0.to_string());
+		context.insert("minors", &minors);
+		return Ok(Html(tera.render("changelog", &context)?).into_response());
+	}
+
+	Ok(Json(minors).into_response())
+}
+
+/// Find an artifact of the latest version matching a range.
+async fn find_artifact(
+	db: &mut AsyncPgConnection,
+	version: &str,
+	artifact_id: &str,
+) -> Result<(Version, Artifact)> {
+	let version = VersionRange::from_str(version)?;
+	let version = Version::get_latest_matching(db, version.0).await?;
 
 	let artifact_uuid =
-		Uuid::parse_str(&artifact_id).map_err(|_| AppError::custom("Invalid artifact ID"))?;
+		Uuid::parse_str(artifact_id).map_err(|_| AppError::custom("Invalid artifact ID"))?;
 
-	let artifacts = Artifact::get_for_version(&mut db, version.id).await?;
+	let artifacts = Artifact::get_for_version(db, version.id).await?;
 	let artifact = artifacts
 		.into_iter()
 		.find(|a| a.id == artifact_uuid)
 		.ok_or_else(|| AppError::custom("Artifact not found for this version"))?;
 
-	let client = reqwest::Client::new();
-	let response = client
-		.get(&artifact.download_url)
+	Ok((version, artifact))
+}
+
+/// Whether a request starts a new download, rather than only looking at the artifact, or
+/// continuing a download that was already recorded.
+///
+/// Checking for range support by asking for just the first byte doesn't count either.
+fn starts_download(method: &Method, headers: &HeaderMap) -> bool {
+	if method == Method::HEAD {
+		return false;
+	}
+
+	let Some(range) = headers.get(header::RANGE) else {
+		return true;
+	};
+	let Some(first) = range
+		.to_str()
+		.ok()
+		.and_then(|range| range.strip_prefix("bytes="))
+		.and_then(|ranges| ranges.split(',').next())
+	else {
+		return false;
+	};
+	matches!(
+		first.trim().split_once('-'),
+		Some((start, end)) if start.trim() == "0" && end.trim() != "0"
+	)
+}
+
+/// Record a download.
+///
+/// This never fails the download.
+async fn record_download(
+	db: &mut AsyncPgConnection,
+	version: &Version,
+	artifact: &Artifact,
+	redirected: bool,
+	origin: ClientOrigin,
+	device: Option<AuthDevice>,
+) {
+	let download = NewArtifactDownload {
+		artifact_id: artifact.id,
+		version_id: version.id,
+		redirected,
+		client_network: origin.ip.map(NewArtifactDownload::network_of),
+		country: origin.country,
+		user_agent: origin.user_agent,
+		device_id: device.map(|device| device.0.id),
+	};
+	if let Err(err) = download.create(db).await {
+		warn!(artifact = %artifact.id, "Failed to record download: {err}");
+	}
+}
+
+async fn redirect_artifact(
+	State(db): State<Db>,
+	Path((version, artifact_id)): Path<(String, String)>,
+	method: Method,
+	origin: ClientOrigin,
+	device: Option<AuthDevice>,
+	headers: HeaderMap,
+) -> Result<Redirect> {
+	let mut db = db.get().await?;
+	let (version, artifact) = find_artifact(&mut db, &version, &artifact_id).await?;
+	if starts_download(&method, &headers) {
+		record_download(&mut db, &version, &artifact, true, origin, device).await;
+	}
+	Ok(Redirect::temporary(&artifact.download_url))
+}
+
+async fn download_artifact(
+	State(db): State<Db>,
+	State(cache): State<Option<Arc<ArtifactCache>>>,
+	Path((version, artifact_id)): Path<(String, String)>,
+	method: Method,
+	origin: ClientOrigin,
+	device: Option<AuthDevice>,
+	headers: HeaderMap,
+) -> Result<Response> {
+	let mut db = db.get().await?;
+	let (version, artifact) = find_artifact(&mut db, &version, &artifact_id).await?;
+	let response = serve_artifact(cache, &artifact, &headers).await?;
+
+	// only count downloads which are actually being sent, from the start
+	if matches!(
+		response.status(),
+		StatusCode::OK | StatusCode::PARTIAL_CONTENT
+	) && starts_download(&method, &headers)
+	{
+		record_download(&mut db, &version, &artifact, false, origin, device).await;
+	}
+	Ok(response)
+}
+
+/// Serve an artifact from the cache, or by proxying it from where it's hosted.
+async fn serve_artifact(
+	cache: Option<Arc<ArtifactCache>>,
+	artifact: &Artifact,
+	headers: &HeaderMap,
+) -> Result<Response> {
+	if let Some(cache) = cache {
+		if let Some(cached) = cache.get(artifact).await? {
+			return cached.serve(headers).await;
+		}
+		cache.warm(vec![artifact.clone()]);
+	}
+
+	let mut request = reqwest::Client::new().get(&artifact.download_url);
+	for name in FORWARDED_REQUEST_HEADERS {
+		for value in headers.get_all(&name) {
+			request = request.header(&name, value);
+		}
+	}
+	let response = request
 		.send()
 		.await
 		.map_err(|err| AppError::custom(format!("Failed to download artifact: {err}")))?;
 
 	let status = response.status();
-	let content_type = response
-		.headers()
-		.get(header::CONTENT_TYPE)
-		.and_then(|v| v.to_str().ok())
-		.unwrap_or("application/octet-stream")
-		.to_string();
+	let mut forwarded = HeaderMap::new();
+	for name in FORWARDED_RESPONSE_HEADERS {
+		if let Some(value) = response.headers().get(&name) {
+			forwarded.insert(name, value.clone());
+		}
+	}
+	forwarded
+		.entry(header::CONTENT_TYPE)
+		.or_insert(HeaderValue::from_static("application/octet-stream"));
 
 	let body = Body::from_stream(response.bytes_stream());
 
-	Ok((status, [(header::CONTENT_TYPE, content_type)], body).into_response())
+	let mut response = (status, forwarded, body).into_response();
+	response.extensions_mut().insert(Uncompressed);
+	Ok(response)
 }
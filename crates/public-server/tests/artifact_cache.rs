@@ -0,0 +1,192 @@
+use std::{
+	path::{Path, PathBuf},
+	sync::{
+		Arc,
+		atomic::{AtomicUsize, Ordering},
+	},
+	time::Duration,
+};
+
+use axum::http::StatusCode;
+use diesel_async::SimpleAsyncConnection;
+use public_server::artifact_cache::ArtifactCache;
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+use uuid::Uuid;
+
+const BODY: &str = "tamanu installer bytes";
+const BODY_SHA256: &str = "e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0";
+
+/// A tiny HTTP server that serves the same APK for every path, and counts requests.
+async fn stand_in() -> (u16, Arc<AtomicUsize>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let hits = Arc::new(AtomicUsize::new(0));
+	let counter = hits.clone();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let _ = stream.read(&mut request).await;
+			counter.fetch_add(1, Ordering::SeqCst);
+			let response = format!(
+				"HTTP/1.1 200 OK\r\nContent-Type: application/vnd.android.package-archive\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{BODY}",
+				BODY.len()
+			);
+			let _ = stream.write_all(response.as_bytes()).await;
+		}
+	});
+	(port, hits)
+}
+
+fn cache_dir() -> PathBuf {
+	std::env::temp_dir().join(format!("artifact-cache-{}", Uuid::new_v4()))
+}
+
+/// Names of the artifacts stored in the cache directory.
+fn cached_files(dir: &Path) -> Vec<String> {
+	let mut names: Vec<String> = std::fs::read_dir(dir)
+		.into_iter()
+		.flatten()
+		.filter_map(|entry| entry.ok())
+		.map(|entry| entry.file_name().to_string_lossy().into_owned())
+		.filter(|name| !name.contains('.'))
+		.collect();
+	names.sort();
+	names
+}
+
+async fn eventually(what: &str, mut check: impl FnMut() -> bool) {
+	for _ in 0..100 {
+		if check() {
+			return;
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+	panic!("timed out waiting for {what}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn downloads_are_cached_and_served_with_ranges() {
+	let dir = cache_dir();
+	let cache = Arc::new(ArtifactCache::new(&dir, 1024 * 1024));
+	commons_tests::server::run_configured(
+		|state| state.artifact_cache = Some(cache),
+		async |mut conn, public, _| {
+			let (port, hits) = stand_in().await;
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+				('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+				INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url, sha256) VALUES
+				('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'mobile', 'android', 'http://127.0.0.1:{port}/app.apk', '{BODY_SHA256}')"
+			))
+			.await
+			.unwrap();
+			let url = "/versions/1.0.0/artifacts/aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa/download";
+
+			// The first download is proxied, and fills the cache in the background
+			let response = public.get(url).await;
+			response.assert_status_ok();
+			response.assert_text(BODY);
+			eventually("the artifact to be cached", || cached_files(&dir).len() == 1).await;
+			assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+			let etag = format!("\"{BODY_SHA256}\"");
+			let response = public.get(url).await;
+			response.assert_status_ok();
+			response.assert_text(BODY);
+			response.assert_header("ETag", &etag);
+			response.assert_header("Accept-Ranges", "bytes");
+			response.assert_header("Content-Type", "application/vnd.android.package-archive");
+
+			let response = public.get(url).add_header("Range", "bytes=7-15").await;
+			response.assert_status(StatusCode::PARTIAL_CONTENT);
+			response.assert_text(&BODY[7..=15]);
+			response.assert_header("Content-Range", "bytes 7-15/22");
+
+			let response = public.get(url).add_header("Range", "bytes=-5").await;
+			response.assert_status(StatusCode::PARTIAL_CONTENT);
+			response.assert_text(&BODY[17..]);
+			response.assert_header("Content-Range", "bytes 17-21/22");
+
+			let response = public.get(url).add_header("Range", "bytes=10-").await;
+			response.assert_status(StatusCode::PARTIAL_CONTENT);
+			response.assert_text(&BODY[10..]);
+
+			let response = public.get(url).add_header("Range", "bytes=100-").await;
+			response.assert_status(StatusCode::RANGE_NOT_SATISFIABLE);
+			response.assert_header("Content-Range", "bytes */22");
+
+			// A range for a different version of the file gets the whole of it
+			let response = public
+				.get(url)
+				.add_header("Range", "bytes=7-15")
+				.add_header("If-Range", "\"something-else\"")
+				.await;
+			response.assert_status_ok();
+			response.assert_text(BODY);
+
+			let response = public.get(url).add_header("If-None-Match", &etag).await;
+			response.assert_status(StatusCode::NOT_MODIFIED);
+			response.assert_header("ETag", &etag);
+
+			// None of these went upstream
+			assert_eq!(hits.load(Ordering::SeqCst), 2);
+		},
+	)
+	.await;
+
+	let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publishing_warms_the_cache_within_its_size() {
+	let dir = cache_dir();
+	// Room for two copies of the body, but not three
+	let cache = Arc::new(ArtifactCache::new(&dir, BODY.len() as u64 * 2 + 10));
+	commons_tests::server::run_with_device_auth_configured(
+		"releaser",
+		|state| state.artifact_cache = Some(cache),
+		async |_conn, cert, _device_id, public, _| {
+			let (port, hits) = stand_in().await;
+
+			let mut ids = Vec::new();
+			for platform in ["android", "ios", "windows"] {
+				let artifact: serde_json::Value = public
+					.post(&format!("/artifacts/1.2.3/mobile/{platform}"))
+					.add_header("mtls-certificate", &cert)
+					.text(format!("http://127.0.0.1:{port}/{platform}"))
+					.await
+					.json();
+				ids.push(artifact["id"].as_str().unwrap().to_string());
+			}
+
+			// Drafts aren't cached
+			tokio::time::sleep(Duration::from_millis(200)).await;
+			assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+			public
+				.post("/versions/1.2.3")
+				.add_header("mtls-certificate", &cert)
+				.text("Release notes")
+				.await
+				.assert_status_ok();
+
+			// Artifacts are cached in order of type and platform, so android gets evicted
+			eventually("the artifacts to be cached", || {
+				let cached = cached_files(&dir);
+				hits.load(Ordering::SeqCst) == 3
+					&& cached.len() == 2
+					&& cached.iter().any(|name| name.starts_with(&ids[2]))
+			})
+			.await;
+			let cached = cached_files(&dir);
+			assert!(cached.iter().any(|name| name.starts_with(&ids[1])));
+			assert!(!cached.iter().any(|name| name.starts_with(&ids[0])));
+		},
+	)
+	.await;
+
+	let _ = std::fs::remove_dir_all(&dir);
+}
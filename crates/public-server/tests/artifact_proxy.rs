@@ -0,0 +1,136 @@
+use axum::http::StatusCode;
+use diesel_async::SimpleAsyncConnection;
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+
+/// Long enough to be worth compressing.
+const BODY: &str = "tamanu installer bytes, resumable over slow links";
+const ETAG: &str = "\"upstream-etag\"";
+const LAST_MODIFIED: &str = "Wed, 01 Oct 2025 00:00:00 GMT";
+
+/// A tiny HTTP server for a single file, which supports single byte ranges and `If-None-Match`.
+async fn stand_in() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 2048];
+			let read = stream.read(&mut request).await.unwrap_or(0);
+			let request = String::from_utf8_lossy(&request[..read]).to_ascii_lowercase();
+			let header = |name: &str| {
+				request
+					.lines()
+					.find_map(|line| line.strip_prefix(&format!("{name}: ")))
+					.map(|value| value.trim().to_string())
+			};
+
+			let len = BODY.len();
+			let (status, extra, body) = if header("if-none-match").as_deref() == Some(ETAG) {
+				("304 Not Modified", String::new(), "")
+			} else if let Some(range) = header("range") {
+				let (first, last) = range
+					.strip_prefix("bytes=")
+					.and_then(|spec| spec.split_once('-'))
+					.unwrap();
+				let start: usize = first.parse().unwrap();
+				let end: usize = last.parse().unwrap_or(len - 1).min(len - 1);
+				if start >= len {
+					(
+						"416 Range Not Satisfiable",
+						format!("Content-Range: bytes */{len}\r\n"),
+						"",
+					)
+				} else {
+					(
+						"206 Partial Content",
+						format!("Content-Range: bytes {start}-{end}/{len}\r\n"),
+						&BODY[start..=end],
+					)
+				}
+			} else {
+				("200 OK", String::new(), BODY)
+			};
+
+			let response = format!(
+				"HTTP/1.1 {status}\r\n\
+				Content-Type: application/octet-stream\r\n\
+				Content-Disposition: attachment; filename=\"installer.exe\"\r\n\
+				Accept-Ranges: bytes\r\n\
+				ETag: {ETAG}\r\n\
+				Last-Modified: {LAST_MODIFIED}\r\n\
+				{extra}\
+				Content-Length: {}\r\n\
+				Connection: close\r\n\r\n{body}",
+				body.len()
+			);
+			let _ = stream.write_all(response.as_bytes()).await;
+		}
+	});
+	port
+}
+
+async fn insert_artifact(conn: &mut diesel_async::AsyncPgConnection, port: u16) {
+	conn.batch_execute(&format!(
+		"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+		('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+		INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url) VALUES
+		('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'installer', 'windows', 'http://127.0.0.1:{port}/installer.exe')"
+	))
+	.await
+	.unwrap();
+}
+
+const URL: &str = "/versions/1.0.0/artifacts/aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa/download";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn proxied_downloads_keep_their_headers() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		insert_artifact(&mut conn, stand_in().await).await;
+
+		// Not compressed, so that the length and ranges stay meaningful
+		let response = public.get(URL).add_header("Accept-Encoding", "gzip").await;
+		response.assert_status_ok();
+		response.assert_text(BODY);
+		response.assert_header("Content-Length", BODY.len().to_string());
+		response.assert_header("Accept-Ranges", "bytes");
+		response.assert_header("ETag", ETAG);
+		response.assert_header("Last-Modified", LAST_MODIFIED);
+		response.assert_header(
+			"Content-Disposition",
+			"attachment; filename=\"installer.exe\"",
+		);
+		assert!(response.maybe_header("Content-Encoding").is_none());
+
+		let response = public.get(URL).add_header("If-None-Match", ETAG).await;
+		response.assert_status(StatusCode::NOT_MODIFIED);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn proxied_downloads_can_be_resumed() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		insert_artifact(&mut conn, stand_in().await).await;
+
+		let response = public.get(URL).add_header("Range", "bytes=7-").await;
+		response.assert_status(StatusCode::PARTIAL_CONTENT);
+		response.assert_text(&BODY[7..]);
+		response.assert_header("Content-Range", format!("bytes 7-48/{}", BODY.len()));
+		response.assert_header("Content-Length", "42");
+
+		let response = public
+			.get(URL)
+			.add_header("Range", "bytes=0-5")
+			.add_header("If-Range", ETAG)
+			.await;
+		response.assert_status(StatusCode::PARTIAL_CONTENT);
+		response.assert_text(&BODY[..=5]);
+
+		let response = public.get(URL).add_header("Range", "bytes=50-").await;
+		response.assert_status(StatusCode::RANGE_NOT_SATISFIABLE);
+		response.assert_header("Content-Range", format!("bytes */{}", BODY.len()));
+	})
+	.await
+}
@@ -0,0 +1,204 @@
+use axum::http::{Method, StatusCode};
+use database::artifact_downloads::{ArtifactDownload, DownloadCount, NewArtifactDownload};
+use diesel::{ExpressionMethods as _, QueryDsl as _, SelectableHelper as _};
+use diesel_async::{RunQueryDsl as _, SimpleAsyncConnection};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+use uuid::Uuid;
+
+/// A tiny HTTP server that serves the same file for every path.
+async fn stand_in() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let _ = stream.read(&mut request).await;
+			let _ = stream
+				.write_all(
+					b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nbytes",
+				)
+				.await;
+		}
+	});
+	port
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn downloads_are_recorded_and_counted() {
+	commons_tests::server::run_with_device_auth(
+		"server",
+		async |mut conn, cert, device_id, public, private| {
+			let port = stand_in().await;
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+				('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+				INSERT INTO artifacts (id, version_id, version_range_pattern, artifact_type, platform, download_url) VALUES
+				('aaaaaaaa-0000-0000-0000-000000000001', '00000000-0000-0000-0000-000000000001', NULL, 'installer', 'windows', 'http://127.0.0.1:{port}/installer.exe'),
+				('aaaaaaaa-0000-0000-0000-000000000002', NULL, '1.0.x', 'mobile', 'android', 'http://127.0.0.1:{port}/app.apk')"
+			))
+			.await
+			.unwrap();
+			let version_id: Uuid = "00000000-0000-0000-0000-000000000001".parse().unwrap();
+
+			let response = public
+				.get("/versions/1.0.0/artifacts/aaaaaaaa-0000-0000-0000-000000000002/redirect")
+				.add_header("User-Agent", "Tamanu Mobile")
+				.add_header("CF-IPCountry", "nz")
+				.await;
+			response.assert_status(StatusCode::TEMPORARY_REDIRECT);
+			response.assert_header("Location", format!("http://127.0.0.1:{port}/app.apk"));
+
+			let url = "/versions/1.0/artifacts/aaaaaaaa-0000-0000-0000-000000000001/download";
+			public
+				.get(url)
+				.add_header("mtls-certificate", &cert)
+				.await
+				.assert_status_ok();
+			public
+				.get(url)
+				.add_header("Range", "bytes=0-")
+				.await
+				.assert_status_ok();
+
+			// Resuming a download doesn't count it again
+			public.get(url).add_header("Range", "bytes=2-").await;
+
+			let downloads: Vec<ArtifactDownload> = {
+				use database::schema::artifact_downloads::dsl::*;
+				artifact_downloads
+					.select(ArtifactDownload::as_select())
+					.order_by(created_at.asc())
+					.load(&mut conn)
+					.await
+					.unwrap()
+			};
+			assert_eq!(downloads.len(), 3);
+			assert!(downloads.iter().all(|d| d.version_id == version_id));
+
+			let redirect = &downloads[0];
+			assert!(redirect.redirected);
+			assert_eq!(redirect.country.as_deref(), Some("NZ"));
+			assert_eq!(redirect.user_agent.as_deref(), Some("Tamanu Mobile"));
+			assert_eq!(
+				redirect.client_network,
+				Some("192.0.1.0/24".parse().unwrap())
+			);
+			assert_eq!(redirect.device_id, None);
+
+			assert!(!downloads[1].redirected);
+			assert_eq!(downloads[1].device_id, Some(device_id));
+			assert_eq!(downloads[2].device_id, None);
+
+			let counts = DownloadCount::for_version(&mut conn, version_id)
+				.await
+				.unwrap();
+			let counts: Vec<_> = counts
+				.iter()
+				.map(|c| (c.platform.as_str(), c.downloads, c.networks))
+				.collect();
+			assert_eq!(counts, vec![("windows", 2, 1), ("android", 1, 1)]);
+
+			let response = private
+				.post("/api/private_server/fns/versions/get_download_counts")
+				.form(&[("version_id", version_id.to_string())])
+				.await;
+			response.assert_status_ok();
+			let counts: Vec<serde_json::Value> = response.json();
+			assert_eq!(counts.len(), 2);
+			assert_eq!(counts[0]["artifact_type"], "installer");
+			assert_eq!(counts[0]["downloads"], 2);
+		},
+	)
+	.await
+}
+
+/// A stand-in which answers conditional requests as not modified, and range requests with the
+/// first byte.
+async fn conditional_stand_in() -> u16 {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = [0; 1024];
+			let len = stream.read(&mut request).await.unwrap_or(0);
+			let request = String::from_utf8_lossy(&request[..len]).to_ascii_lowercase();
+			let response: &[u8] = if request.contains("if-none-match:") {
+				b"HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n"
+			} else if request.contains("range:") {
+				b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-0/5\r\nContent-Length: 1\r\nConnection: close\r\n\r\nb"
+			} else {
+				b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 5\r\nConnection: close\r\n\r\nbytes"
+			};
+			let _ = stream.write_all(response).await;
+		}
+	});
+	port
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn looking_at_artifacts_is_not_counted() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		let port = conditional_stand_in().await;
+		conn.batch_execute(&format!(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, '', 'published');
+			INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url) VALUES
+			('aaaaaaaa-0000-0000-0000-000000000001', '00000000-0000-0000-0000-000000000001', 'installer', 'windows', 'http://127.0.0.1:{port}/installer.exe')"
+		))
+		.await
+		.unwrap();
+
+		let base = "/versions/1.0.0/artifacts/aaaaaaaa-0000-0000-0000-000000000001";
+		public
+			.method(Method::HEAD, &format!("{base}/download"))
+			.await
+			.assert_status_ok();
+		public
+			.method(Method::HEAD, &format!("{base}/redirect"))
+			.await
+			.assert_status(StatusCode::TEMPORARY_REDIRECT);
+		public
+			.get(&format!("{base}/download"))
+			.add_header("If-None-Match", "\"v1\"")
+			.await
+			.assert_status(StatusCode::NOT_MODIFIED);
+
+		// Checking whether ranges are supported isn't a download either
+		public
+			.get(&format!("{base}/download"))
+			.add_header("Range", "bytes=0-0")
+			.await
+			.assert_status(StatusCode::PARTIAL_CONTENT);
+		public
+			.get(&format!("{base}/redirect"))
+			.add_header("Range", "bytes=0-0")
+			.await
+			.assert_status(StatusCode::TEMPORARY_REDIRECT);
+
+		let downloads: i64 = {
+			use database::schema::artifact_downloads::dsl::*;
+			artifact_downloads.count().get_result(&mut conn).await.unwrap()
+		};
+		assert_eq!(downloads, 0);
+	})
+	.await
+}
+
+#[test]
+fn networks_are_coarse() {
+	assert_eq!(
+		NewArtifactDownload::network_of("203.0.113.77".parse().unwrap()),
+		"203.0.113.0/24".parse().unwrap()
+	);
+	assert_eq!(
+		NewArtifactDownload::network_of("2001:db8:1234:5678::1".parse().unwrap()),
+		"2001:db8:1234::/48".parse().unwrap()
+	);
+	assert_eq!(
+		NewArtifactDownload::network_of("::ffff:203.0.113.77".parse().unwrap()),
+		"203.0.113.0/24".parse().unwrap()
+	);
+}
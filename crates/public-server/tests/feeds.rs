@@ -0,0 +1,119 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+
+async fn setup(conn: &mut commons_tests::diesel_async::AsyncPgConnection) {
+	conn.batch_execute(
+		"INSERT INTO versions (id, major, minor, patch, changelog, status, channels, created_at, published_at) VALUES
+		('00000000-0000-0000-0000-000000000001', 2, 4, 0, '- Faster <sync>', 'published', '{stable}', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z'),
+		('00000000-0000-0000-0000-000000000002', 2, 5, 0, '- New forms', 'published', '{stable}', '2026-02-01T00:00:00Z', '2026-02-01T00:00:00Z'),
+		('00000000-0000-0000-0000-000000000003', 2, 5, 1, '- Fixes', 'published', '{stable}', '2026-03-01T00:00:00Z', '2026-03-01T00:00:00Z'),
+		('00000000-0000-0000-0000-000000000004', 2, 6, 0, '- Preview', 'published', '{beta}', '2026-03-02T00:00:00Z', '2026-03-02T00:00:00Z'),
+		('00000000-0000-0000-0000-000000000005', 2, 7, 0, '', 'draft', '{stable}', '2026-03-03T00:00:00Z', NULL)",
+	)
+	.await
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn atom_feed_lists_published_versions() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		setup(&mut conn).await;
+
+		let response = public.get("/feed.atom").await;
+		response.assert_status_ok();
+		response.assert_header("content-type", "application/atom+xml; charset=utf-8");
+
+		let feed = response.text();
+		assert_eq!(feed.matches("<entry>").count(), 3, "{feed}");
+		assert!(feed.contains("<title>Tamanu 2.5.1</title>"), "{feed}");
+		assert!(!feed.contains("Tamanu 2.6.0"), "{feed}");
+		assert!(!feed.contains("Tamanu 2.7.0"), "{feed}");
+
+		// Changelogs are rendered, then escaped into the feed
+		assert!(feed.contains("&lt;li&gt;Faster"), "{feed}");
+		assert!(
+			feed.contains("<updated>2026-03-01T00:00:00Z</updated>"),
+			"{feed}"
+		);
+
+		// Links don't trust the request's host
+		assert!(
+			feed.contains(r#"<link rel="alternate" type="text/html" href="/versions/2.5.1" />"#),
+			"{feed}"
+		);
+
+		let response = public
+			.get("/feed.atom")
+			.add_query_param("minor", "5")
+			.add_query_param("major", "2")
+			.await;
+		let feed = response.text();
+		assert_eq!(feed.matches("<entry>").count(), 2, "{feed}");
+		assert!(
+			feed.contains("<title>Tamanu releases 2.5</title>"),
+			"{feed}"
+		);
+
+		let response = public
+			.get("/feed.atom")
+			.add_query_param("channel", "beta")
+			.await;
+		let feed = response.text();
+		assert_eq!(feed.matches("<entry>").count(), 1, "{feed}");
+		assert!(feed.contains("Tamanu 2.6.0"), "{feed}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rss_feed_includes_yanks() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		setup(&mut conn).await;
+		conn.batch_execute(
+			"UPDATE versions SET status = 'yanked' WHERE id = '00000000-0000-0000-0000-000000000002';
+			INSERT INTO version_yanks (version_id, reason, severity, replacement_version_id) VALUES
+			('00000000-0000-0000-0000-000000000002', 'Breaks <labs>', 'high', '00000000-0000-0000-0000-000000000003')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/feed.rss").await;
+		response.assert_status_ok();
+		response.assert_header("content-type", "application/rss+xml; charset=utf-8");
+
+		let feed = response.text();
+		assert_eq!(feed.matches("<item>").count(), 3, "{feed}");
+
+		// The yank is the most recent change
+		let first = &feed[feed.find("<item>").unwrap()..feed.find("</item>").unwrap()];
+		assert!(
+			first.contains("urn:uuid:00000000-0000-0000-0000-000000000002"),
+			"{feed}"
+		);
+		assert!(feed.contains("<title>Tamanu 2.5.0 (yanked)</title>"), "{feed}");
+		assert!(feed.contains("Breaks &amp;lt;labs&amp;gt;"), "{feed}");
+		assert!(feed.contains("Upgrade to 2.5.1 instead"), "{feed}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn feed_ignores_edits_after_publishing() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		setup(&mut conn).await;
+		conn.batch_execute(
+			"UPDATE versions SET changelog = '- Faster sync, fixed typo' WHERE id = '00000000-0000-0000-0000-000000000001'",
+		)
+		.await
+		.unwrap();
+
+		let feed = public.get("/feed.atom").await.text();
+		let first = &feed[feed.find("<entry>").unwrap()..feed.find("</entry>").unwrap()];
+		assert!(first.contains("<title>Tamanu 2.5.1</title>"), "{feed}");
+		assert!(feed.contains("<updated>2026-03-01T00:00:00Z</updated>"), "{feed}");
+		assert!(
+			feed.contains("<published>2026-01-01T00:00:00Z</published>"),
+			"{feed}"
+		);
+	})
+	.await
+}
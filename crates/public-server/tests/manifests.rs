@@ -0,0 +1,210 @@
+use axum::http::StatusCode;
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use commons_tests::server::TEST_SIGNING_SEED;
+use database::manifests::ReleaseManifest;
+use diesel_async::SimpleAsyncConnection;
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use public_server::signing::SignedManifest;
+use serde_json::{Value, json};
+
+fn server_key() -> VerifyingKey {
+	SigningKey::from_bytes(&TEST_SIGNING_SEED).verifying_key()
+}
+
+fn assert_signed_by(key: &VerifyingKey, payload: &str, signature: &str) {
+	let signature = BASE64_STANDARD.decode(signature).unwrap();
+	let signature = Signature::from_slice(&signature).unwrap();
+	key.verify_strict(payload.as_bytes(), &signature)
+		.expect("signature verifies");
+}
+
+async fn insert_release(conn: &mut diesel_async::AsyncPgConnection) {
+	conn.batch_execute(
+		"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+		('00000000-0000-0000-0000-000000000001', 1, 0, 0, 'Release notes', 'published'),
+		('00000000-0000-0000-0000-000000000002', 1, 0, 1, 'Patch notes', 'published');
+		INSERT INTO artifacts (version_id, artifact_type, platform, download_url, sha256, size_bytes) VALUES
+		('00000000-0000-0000-0000-000000000001', 'server', 'windows', 'https://example.com/server.zip', NULL, NULL),
+		('00000000-0000-0000-0000-000000000001', 'installer', 'windows', 'https://example.com/installer.exe',
+			'e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0', 22)",
+	)
+	.await
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signing_key_is_published() {
+	commons_tests::server::run(async |_conn, public, _| {
+		let response = public.get("/versions/signing-key").await;
+		response.assert_status_ok();
+		response.assert_json(&json!({
+			"algorithm": "ed25519",
+			"public_key": BASE64_STANDARD.encode(server_key().as_bytes()),
+		}));
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn artifacts_are_served_with_a_signed_manifest() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		insert_release(&mut conn).await;
+
+		// The plain listing is unchanged
+		let response = public.get("/versions/1.0.0/artifacts").await;
+		response.assert_status_ok();
+		let artifacts: Vec<Value> = response.json();
+		assert_eq!(artifacts.len(), 2);
+
+		let response = public.get("/versions/1.0.0/artifacts?manifest=true").await;
+		response.assert_status_ok();
+		let listing: Value = response.json();
+		assert_eq!(listing["artifacts"].as_array().unwrap().len(), 2);
+		let signed: SignedManifest = serde_json::from_value(listing["manifest"].clone()).unwrap();
+		let payload = signed.payload;
+		assert_signed_by(&server_key(), &payload, signed.signature.as_deref().unwrap());
+
+		let manifest: ReleaseManifest = serde_json::from_str(&payload).unwrap();
+		assert_eq!(manifest.version, "1.0.0");
+		assert_eq!(
+			manifest.changelog_sha256,
+			"1254829bd9996472de64de2e8c130a27d2a3841c0209bfeefcad382cd3609ace"
+		);
+		let urls: Vec<_> = manifest
+			.artifacts
+			.iter()
+			.map(|artifact| artifact.download_url.as_str())
+			.collect();
+		assert_eq!(
+			urls,
+			[
+				"https://example.com/installer.exe",
+				"https://example.com/server.zip"
+			]
+		);
+		assert_eq!(
+			manifest.artifacts[0].sha256.as_deref(),
+			Some("e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0")
+		);
+		assert_eq!(manifest.artifacts[0].size_bytes, Some(22));
+		assert_eq!(manifest.artifacts[1].sha256, None);
+
+		// The standalone manifest is the same payload
+		let response = public.get("/versions/1.0.0/manifest").await;
+		response.assert_status_ok();
+		let signed: SignedManifest = response.json();
+		assert_eq!(signed.payload, payload);
+		assert!(signed.releaser_signatures.is_empty());
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_for_includes_signed_manifests() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		insert_release(&mut conn).await;
+
+		let response = public.get("/versions/update-for/1.0.0").await;
+		response.assert_status_ok();
+		let updates: Vec<Value> = response.json();
+		assert_eq!(updates.len(), 1);
+		assert_eq!(updates[0]["patch"], 1);
+
+		let signed: SignedManifest =
+			serde_json::from_value(updates[0]["manifest"].clone()).unwrap();
+		assert_signed_by(
+			&server_key(),
+			&signed.payload,
+			signed.signature.as_deref().unwrap(),
+		);
+		let manifest: ReleaseManifest = serde_json::from_str(&signed.payload).unwrap();
+		assert_eq!(manifest.version, "1.0.1");
+		assert!(manifest.artifacts.is_empty());
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn releaser_signatures_are_verified_and_follow_the_manifest() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			insert_release(&mut conn).await;
+			let releaser = SigningKey::from_bytes(&[9; 32]);
+			let public_key = BASE64_STANDARD.encode(releaser.verifying_key().as_bytes());
+
+			let response = public
+				.post("/versions/releaser-keys")
+				.add_header("mtls-certificate", &cert)
+				.json(&json!({ "public_key": public_key, "name": "CI" }))
+				.await;
+			response.assert_status_ok();
+
+			let response = public.get("/versions/releaser-keys").await;
+			response.assert_status_ok();
+			let keys: Vec<Value> = response.json();
+			assert_eq!(keys.len(), 1);
+			assert_eq!(keys[0]["public_key"], public_key);
+
+			let signed: SignedManifest = public.get("/versions/1.0.0/manifest").await.json();
+
+			// A signature over something else is refused
+			let response = public
+				.post("/versions/1.0.0/signature")
+				.add_header("mtls-certificate", &cert)
+				.json(&json!({
+					"public_key": public_key,
+					"signature": BASE64_STANDARD.encode(releaser.sign(b"something else").to_bytes()),
+				}))
+				.await;
+			response.assert_status(StatusCode::UNAUTHORIZED);
+
+			// So is a signature by a key which isn't registered
+			let stranger = SigningKey::from_bytes(&[10; 32]);
+			let response = public
+				.post("/versions/1.0.0/signature")
+				.add_header("mtls-certificate", &cert)
+				.json(&json!({
+					"public_key": BASE64_STANDARD.encode(stranger.verifying_key().as_bytes()),
+					"signature": BASE64_STANDARD.encode(stranger.sign(signed.payload.as_bytes()).to_bytes()),
+				}))
+				.await;
+			response.assert_status(StatusCode::UNAUTHORIZED);
+
+			let signature =
+				BASE64_STANDARD.encode(releaser.sign(signed.payload.as_bytes()).to_bytes());
+			let response = public
+				.post("/versions/1.0.0/signature")
+				.add_header("mtls-certificate", &cert)
+				.json(&json!({ "public_key": public_key, "signature": signature }))
+				.await;
+			response.assert_status_ok();
+
+			let signed: SignedManifest = public.get("/versions/1.0.0/manifest").await.json();
+			assert_eq!(signed.releaser_signatures.len(), 1);
+			assert_eq!(signed.releaser_signatures[0].public_key, public_key);
+			assert_eq!(
+				signed.releaser_signatures[0].key_name.as_deref(),
+				Some("CI")
+			);
+			assert_signed_by(
+				&releaser.verifying_key(),
+				&signed.payload,
+				&signed.releaser_signatures[0].signature,
+			);
+
+			// Once the manifest changes, the releaser signature no longer applies
+			public
+				.post("/artifacts/1.0.0/mobile/android")
+				.add_header("mtls-certificate", &cert)
+				.text("https://example.com/app.apk")
+				.await
+				.assert_status_ok();
+
+			let signed: SignedManifest = public.get("/versions/1.0.0/manifest").await.json();
+			assert!(signed.releaser_signatures.is_empty());
+			assert!(signed.signature.is_some());
+		},
+	)
+	.await
+}
@@ -0,0 +1,257 @@
+use commons_tests::diesel_async::SimpleAsyncConnection;
+use commons_types::version::VersionStatus;
+use database::{artifacts::Artifact, versions::Version};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_publishes_version_and_artifacts() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, device_id, public, _| {
+			let response = public
+				.post("/releases")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"version": "4.1.0",
+					"changelog": "# Version 4.1.0",
+					"artifacts": [
+						{
+							"artifact_type": "installer",
+							"platform": "windows",
+							"download_url": "https://example.com/installer.exe",
+							"sha256": "E5A14B9C100C505CA8BE7A55BA3DB07FEC66C29DFA61FEB5C07C6FF68FC1D2F0",
+							"size_bytes": 22,
+						},
+						{
+							"artifact_type": "mobile",
+							"platform": "android",
+							"download_url": "https://example.com/app.apk",
+						},
+					],
+				}))
+				.await;
+			response.assert_status_ok();
+
+			let published: serde_json::Value = response.json();
+			assert_eq!(published["dry_run"], false);
+			assert_eq!(published["replaced_draft"], false);
+			assert_eq!(published["artifacts"].as_array().unwrap().len(), 2);
+
+			let version = Version::get_by_version(&mut conn, "4.1.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.status, VersionStatus::Published);
+			assert_eq!(version.changelog, "# Version 4.1.0");
+			assert_eq!(version.device_id, Some(device_id));
+
+			let artifacts = Artifact::get_for_version(&mut conn, version.id)
+				.await
+				.unwrap();
+			assert_eq!(artifacts.len(), 2);
+			let installer = artifacts
+				.iter()
+				.find(|artifact| artifact.platform == "windows")
+				.unwrap();
+			assert_eq!(
+				installer.sha256.as_deref(),
+				Some("e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0")
+			);
+			assert_eq!(installer.size_bytes, Some(22));
+
+			// Publishing the same version again fails
+			public
+				.post("/releases")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({ "version": "4.1.0" }))
+				.await
+				.assert_status_failure();
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_from_toml_replaces_draft() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			public
+				.post("/artifacts/4.2.0/installer/linux")
+				.add_header("mtls-certificate", &cert)
+				.text("https://example.com/old.tar.gz")
+				.await
+				.assert_status_ok();
+
+			let response = public
+				.post("/releases")
+				.add_header("mtls-certificate", &cert)
+				.text(
+					r#"
+					version = "4.2.0"
+					changelog = "Fixes"
+					channels = ["beta"]
+
+					[[artifacts]]
+					artifact_type = "installer"
+					platform = "windows"
+					download_url = "https://example.com/installer.exe"
+					"#,
+				)
+				.content_type("application/toml")
+				.await;
+			response.assert_status_ok();
+			let published: serde_json::Value = response.json();
+			assert_eq!(published["replaced_draft"], true);
+
+			let version = Version::get_by_version(&mut conn, "4.2.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.status, VersionStatus::Published);
+			assert_eq!(version.changelog, "Fixes");
+
+			// The draft's artifacts are replaced by the release's
+			let artifacts = Artifact::get_for_version(&mut conn, version.id)
+				.await
+				.unwrap();
+			assert_eq!(artifacts.len(), 1);
+			assert_eq!(artifacts[0].platform, "windows");
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_replacing_draft_keeps_its_settings() {
+	use commons_types::version::{ReleaseChannel, UpdateUrgency};
+
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			conn.batch_execute(
+				"INSERT INTO versions (major, minor, patch, changelog, status, channels, urgency,
+					stepping_stone)
+				VALUES (4, 3, 0, '', 'draft', '{beta,lts}', 'security', true)",
+			)
+			.await
+			.unwrap();
+
+			let response = public
+				.post("/releases")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({ "version": "4.3.0", "changelog": "Published" }))
+				.await;
+			response.assert_status_ok();
+			let published: serde_json::Value = response.json();
+			assert_eq!(published["replaced_draft"], true);
+
+			let version = Version::get_by_version(&mut conn, "4.3.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.status, VersionStatus::Published);
+			assert_eq!(
+				version.channels,
+				vec![ReleaseChannel::Beta, ReleaseChannel::Lts]
+			);
+			assert_eq!(version.urgency, UpdateUrgency::Security);
+			assert!(version.stepping_stone);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_dry_run_changes_nothing() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			let response = public
+				.post("/releases?dry_run=true")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"version": "4.3.0",
+					"artifacts": [{
+						"artifact_type": "installer",
+						"platform": "windows",
+						"download_url": "https://example.com/installer.exe",
+					}],
+				}))
+				.await;
+			response.assert_status_ok();
+
+			let published: serde_json::Value = response.json();
+			assert_eq!(published["dry_run"], true);
+			assert_eq!(published["version"]["status"], "published");
+			assert_eq!(published["artifacts"].as_array().unwrap().len(), 1);
+
+			assert!(
+				Version::get_by_version(&mut conn, "4.3.0".parse().unwrap())
+					.await
+					.is_err()
+			);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_invalid_manifest_is_rejected_whole() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			let response = public
+				.post("/releases")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"version": "4.4.0",
+					"artifacts": [
+						{
+							"artifact_type": "installer",
+							"platform": "windows",
+							"download_url": "https://example.com/installer.exe",
+						},
+						{
+							"artifact_type": "installer",
+							"platform": "windows",
+							"download_url": "ftp://example.com/installer.exe",
+							"sha256": "not a checksum",
+						},
+						{
+							"artifact_type": "mobile",
+							"platform": "ios",
+							"download_url": "https://example.com/app.ipa",
+							"bundle_id": "tamanu",
+						},
+					],
+				}))
+				.await;
+			response.assert_status_failure();
+			let body = response.text();
+			assert!(body.contains("appears more than once"), "{body}");
+			assert!(body.contains("must be http or https"), "{body}");
+			assert!(body.contains("sha256 must be 64 hex digits"), "{body}");
+			assert!(
+				body.contains("bundle_id must be in reverse-DNS form"),
+				"{body}"
+			);
+
+			assert!(
+				Version::get_by_version(&mut conn, "4.4.0".parse().unwrap())
+					.await
+					.is_err()
+			);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn release_requires_releaser() {
+	commons_tests::server::run_with_device_auth("server", async |_conn, cert, _, public, _| {
+		public
+			.post("/releases")
+			.add_header("mtls-certificate", &cert)
+			.json(&serde_json::json!({ "version": "4.5.0" }))
+			.await
+			.assert_status_failure();
+	})
+	.await
+}
@@ -1,6 +1,7 @@
 use axum::http::StatusCode;
 use commons_types::version::VersionStatus;
 use diesel_async::SimpleAsyncConnection;
+use public_server::state::PublicUrl;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -74,6 +75,58 @@ async fn view_version_artifacts_html() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn view_version_platform_requirements() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status) VALUES (1, 0, 0, 'Test version', 'published');
+			INSERT INTO platform_requirements (major, minor, platform, min_version, max_version) VALUES
+			(1, 0, 'postgres', '14', '16'),
+			(1, 0, 'chrome', '120', NULL)",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/1.0.0").await;
+		response.assert_status_ok();
+		let body = response.text();
+		assert!(body.contains("<strong>PostgreSQL</strong>"), "{body}");
+		assert!(body.contains("14 to 16"), "{body}");
+		assert!(body.contains("120 or later"), "{body}");
+		// the stated Chrome requirement replaces the one derived from release dates
+		assert!(!body.contains("requires <strong>Chrome"), "{body}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn view_version_sanitises_changelog() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			r#"INSERT INTO versions (major, minor, patch, changelog, status) VALUES
+			(1, 0, 0, '**Fixes**
+
+<script>alert("pwned")</script>
+
+<img src="x" onerror="alert(1)"> [link](javascript:alert(2))', 'published'),
+			(1, 0, 1, 'Later', 'published')"#,
+		)
+		.await
+		.unwrap();
+
+		for path in ["/versions/1.0.0", "/versions/0.9.0/changelog/1.0.1"] {
+			let response = public.get(path).await;
+			response.assert_status_ok();
+			let body = response.text();
+			assert!(body.contains("<strong>Fixes</strong>"), "{path}: {body}");
+			assert!(!body.contains("pwned"), "{path}: {body}");
+			assert!(!body.contains("onerror"), "{path}: {body}");
+			assert!(!body.contains("javascript:"), "{path}: {body}");
+		}
+	})
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn list_version_artifacts_empty() {
 	commons_tests::server::run(async |mut conn, public, _| {
@@ -134,6 +187,164 @@ async fn view_mobile_install_page() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn mobile_install_follows_client_platform() {
+	commons_tests::server::run_with_device_auth_configured(
+		"releaser",
+		|state| state.public_url = Some(PublicUrl("https://meta.example.com".into())),
+		async |_conn, cert, _device_id, public, _| {
+			let sha256 = "e5a14b9c100c505ca8be7a55ba3db07fec66c29dfa61feb5c07c6ff68fc1d2f0";
+			public
+				.post("/artifacts/1.2.3/mobile/android")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"url": "https://example.com/app.apk",
+					"sha256": sha256,
+					"version_code": 10203,
+				}))
+				.await
+				.assert_status_ok();
+			let ios: serde_json::Value = public
+				.post("/artifacts/1.2.3/mobile/ios")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"url": "https://example.com/app.ipa",
+					"bundle_id": "com.example.tamanu",
+				}))
+				.await
+				.json();
+			assert_eq!(ios["bundle_id"], "com.example.tamanu");
+			public
+				.post("/versions/1.2.3")
+				.add_header("mtls-certificate", &cert)
+				.text("Release notes")
+				.await
+				.assert_status_ok();
+
+			let page = public
+				.get("/versions/1.2.3/mobile")
+				.add_header(
+					"user-agent",
+					"Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15",
+				)
+				.await;
+			page.assert_status_ok();
+			let page = page.text();
+			assert!(
+				page.contains("itms-services://?action=download-manifest&amp;url=https%3A%2F%2Fmeta%2Eexample%2Ecom%2Fversions%2F1%2E2%2E3%2F"),
+				"{page}"
+			);
+			assert!(
+				page.find(">iOS<").unwrap() < page.find(">Android<").unwrap(),
+				"{page}"
+			);
+
+			let page = public
+				.get("/versions/1.2.3/mobile")
+				.add_header(
+					"user-agent",
+					"Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36",
+				)
+				.await
+				.text();
+			assert!(
+				page.find(">Android<").unwrap() < page.find(">iOS<").unwrap(),
+				"{page}"
+			);
+			assert!(page.contains("Download APK"), "{page}");
+			assert!(page.contains(r#"class="qr-code""#), "{page}");
+			assert!(page.contains(sha256), "{page}");
+			assert!(page.contains("Build 10203"), "{page}");
+
+			// Bundle identifiers are checked for shape
+			public
+				.post("/artifacts/1.2.3/mobile/ipados")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"url": "https://example.com/app.ipa",
+					"bundle_id": "tamanu",
+				}))
+				.await
+				.assert_status_failure();
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ios_install_manifest() {
+	commons_tests::server::run_configured(
+		|state| state.public_url = Some(PublicUrl("https://meta.example.com".into())),
+		async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, 'Test version', 'published');
+			INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url, bundle_id, version_code) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'mobile', 'ios', 'https://example.com/app.ipa', 'com.example.tamanu', 42),
+			('bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', '00000000-0000-0000-0000-000000000001', 'mobile', 'android', 'https://example.com/app.apk', NULL, NULL)",
+		)
+		.await
+		.unwrap();
+
+		let response = public
+			.get("/versions/1.0.0/artifacts/aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa/manifest.plist")
+			.await;
+		response.assert_status_ok();
+		response.assert_header("content-type", "text/xml; charset=utf-8");
+		let plist = response.text();
+		assert!(
+			plist.contains("<string>com.example.tamanu</string>"),
+			"{plist}"
+		);
+		assert!(plist.contains("<string>42</string>"), "{plist}");
+		assert!(!plist.contains("<string>1.0.0</string>"), "{plist}");
+		assert!(
+			plist.contains(
+				"<string>https://meta.example.com/versions/1.0.0/artifacts/aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa/download</string>"
+			),
+			"{plist}"
+		);
+
+		// Only iOS apps have install manifests
+		public
+			.get("/versions/1.0.0/artifacts/bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb/manifest.plist")
+			.await
+			.assert_status_failure();
+	},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ios_install_needs_public_url() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+			('00000000-0000-0000-0000-000000000001', 1, 0, 0, 'Test version', 'published');
+			INSERT INTO artifacts (id, version_id, artifact_type, platform, download_url, bundle_id) VALUES
+			('aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa', '00000000-0000-0000-0000-000000000001', 'mobile', 'ios', 'https://example.com/app.ipa', 'com.example.tamanu')",
+		)
+		.await
+		.unwrap();
+
+		// Links would otherwise be built from the Host header, which clients control
+		let page = public
+			.get("/versions/1.0.0/mobile")
+			.add_header("host", "evil.example.com")
+			.await
+			.text();
+		assert!(!page.contains("itms-services"), "{page}");
+		assert!(!page.contains("evil.example.com"), "{page}");
+		assert!(!page.contains(r#"class="qr-code""#), "{page}");
+
+		public
+			.get("/versions/1.0.0/artifacts/aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa/manifest.plist")
+			.await
+			.assert_status_failure();
+	})
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn update_for_version_empty() {
 	commons_tests::server::run(async |mut conn, public, _| {
@@ -229,6 +440,60 @@ async fn artifacts_create_draft_version_if_not_found() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn artifact_create_with_checksum() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |_conn, cert, _device_id, public, _| {
+			let sha256 = "E5A14B9C100C505CA8BE7A55BA3DB07FEC66C29DFA61FEB5C07C6FF68FC1D2F0";
+			let response = public
+				.post("/artifacts/1.2.3/installer/windows")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"url": "https://example.com/installer.exe",
+					"sha256": sha256,
+					"size_bytes": 22,
+				}))
+				.await;
+			response.assert_status_ok();
+
+			public
+				.post("/versions/1.2.3")
+				.add_header("mtls-certificate", &cert)
+				.text("Release notes")
+				.await
+				.assert_status_ok();
+
+			let artifacts: Vec<serde_json::Value> =
+				public.get("/versions/1.2.3/artifacts").await.json();
+			assert_eq!(artifacts.len(), 1);
+			assert_eq!(
+				artifacts[0]["download_url"],
+				"https://example.com/installer.exe"
+			);
+			assert_eq!(artifacts[0]["sha256"], sha256.to_lowercase());
+			assert_eq!(artifacts[0]["size_bytes"], 22);
+			assert_eq!(artifacts[0]["verified_at"], serde_json::Value::Null);
+
+			let page = public.get("/versions/1.2.3").await;
+			page.assert_status_ok();
+			assert!(page.text().contains(&sha256.to_lowercase()));
+
+			// Checksums are checked for shape
+			let response = public
+				.post("/artifacts/1.2.3/installer/linux")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"url": "https://example.com/installer.tar.gz",
+					"sha256": "not a checksum",
+				}))
+				.await;
+			response.assert_status_failure();
+		},
+	)
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn version_create_publishes_draft_if_exists() {
 	use database::versions::Version;
@@ -281,6 +546,44 @@ async fn version_create_publishes_draft_if_exists() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn version_publishing_draft_keeps_its_settings() {
+	use commons_types::version::{ReleaseChannel, UpdateUrgency};
+	use database::versions::Version;
+
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			conn.batch_execute(
+				"INSERT INTO versions (major, minor, patch, changelog, status, channels, urgency,
+					stepping_stone)
+				VALUES (2, 1, 0, '', 'draft', '{beta,lts}', 'security', true)",
+			)
+			.await
+			.unwrap();
+
+			let response = public
+				.post("/versions/2.1.0")
+				.add_header("mtls-certificate", &cert)
+				.text("Published")
+				.await;
+			response.assert_status_ok();
+
+			let version = Version::get_by_version(&mut conn, "2.1.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.status, VersionStatus::Published);
+			assert_eq!(
+				version.channels,
+				vec![ReleaseChannel::Beta, ReleaseChannel::Lts]
+			);
+			assert_eq!(version.urgency, UpdateUrgency::Security);
+			assert!(version.stepping_stone);
+		},
+	)
+	.await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn version_create_duplicate_published_fails() {
 	commons_tests::server::run_with_device_auth(
@@ -619,3 +922,791 @@ async fn artifact_create_range_no_auth() {
 	})
 	.await
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn versions_list_filters_by_channel() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, channels) VALUES
+			(1, 0, 0, 'Stable release', 'published', '{stable}'),
+			(1, 1, 0, 'Release candidate', 'published', '{beta}'),
+			(1, 2, 0, 'Everywhere', 'published', '{stable,beta,lts}')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions").await;
+		response.assert_status_ok();
+		let versions: Vec<Version> = response.json();
+		assert_eq!(
+			versions.iter().map(|v| v.minor).collect::<Vec<_>>(),
+			vec![2, 0]
+		);
+
+		let response = public
+			.get("/versions")
+			.add_query_param("channel", "beta")
+			.await;
+		response.assert_status_ok();
+		let versions: Vec<Version> = response.json();
+		assert_eq!(
+			versions.iter().map(|v| v.minor).collect::<Vec<_>>(),
+			vec![2, 1]
+		);
+
+		let response = public
+			.get("/versions")
+			.add_query_param("channel", "nightly")
+			.await;
+		response.assert_status(StatusCode::BAD_REQUEST);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_for_version_filters_by_channel() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, channels) VALUES
+			(1, 0, 0, 'Old version', 'published', '{stable,beta}'),
+			(1, 0, 1, 'Patch update', 'published', '{stable,beta}'),
+			(1, 1, 0, 'Release candidate', 'published', '{beta}')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/update-for/1.0.0").await;
+		response.assert_status_ok();
+		let updates: Vec<Version> = response.json();
+		assert_eq!(updates.len(), 1);
+		assert_eq!(updates[0].patch, 1);
+
+		let response = public
+			.get("/versions/update-for/1.0.0")
+			.add_query_param("channel", "beta")
+			.await;
+		response.assert_status_ok();
+		let updates: Vec<Version> = response.json();
+		assert_eq!(updates.len(), 2);
+		assert!(updates.iter().any(|v| v.minor == 1));
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_for_version_uses_server_default_channel() {
+	commons_tests::server::run_with_device_auth(
+		"server",
+		async |mut conn, cert, device_id, public, _| {
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (major, minor, patch, changelog, status, channels) VALUES
+				(1, 0, 0, 'Old version', 'published', '{{stable,beta}}'),
+				(1, 1, 0, 'Release candidate', 'published', '{{beta}}');
+				INSERT INTO servers (name, host, kind, rank, device_id, default_channel) VALUES
+				('Test server', 'https://test.example.com', 'central', 'test', '{device_id}', 'beta')"
+			))
+			.await
+			.unwrap();
+
+			let response = public
+				.get("/versions/update-for/1.0.0")
+				.add_header("mtls-certificate", &cert)
+				.await;
+			response.assert_status_ok();
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates.len(), 1);
+			assert_eq!(updates[0].minor, 1);
+
+			// an explicit channel still wins over the server's default
+			let response = public
+				.get("/versions/update-for/1.0.0")
+				.add_query_param("channel", "stable")
+				.add_header("mtls-certificate", &cert)
+				.await;
+			response.assert_status_ok();
+			response.assert_json::<Vec<Version>>(&Vec::new());
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn version_create_with_channels() {
+	use commons_types::version::ReleaseChannel;
+	use database::versions::Version;
+
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			let response = public
+				.post("/versions/4.0.0")
+				.add_query_param("channels", "beta,lts")
+				.add_header("mtls-certificate", &cert)
+				.text("Release candidate")
+				.await;
+			response.assert_status_ok();
+
+			let version = Version::get_by_version(&mut conn, "4.0.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(
+				version.channels,
+				vec![ReleaseChannel::Beta, ReleaseChannel::Lts]
+			);
+
+			// without a channel, versions are released to stable
+			let response = public
+				.post("/versions/4.0.1")
+				.add_header("mtls-certificate", &cert)
+				.text("Stable release")
+				.await;
+			response.assert_status_ok();
+
+			let version = Version::get_by_version(&mut conn, "4.0.1".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.channels, vec![ReleaseChannel::Stable]);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_for_version_respects_staged_rollout() {
+	commons_tests::server::run_with_device_auth(
+		"server",
+		async |mut conn, cert, device_id, public, _| {
+			conn.batch_execute(&format!(
+				"INSERT INTO versions (id, major, minor, patch, changelog, status) VALUES
+				('11111111-1111-1111-1111-111111111111', 1, 0, 0, 'Old version', 'published'),
+				('22222222-2222-2222-2222-222222222222', 1, 0, 1, 'Patch update', 'published'),
+				('33333333-3333-3333-3333-333333333333', 1, 0, 2, 'Risky patch', 'published');
+				INSERT INTO servers (id, name, host, kind, rank, device_id) VALUES
+				('44444444-4444-4444-4444-444444444444', 'Early adopter', 'https://early.example.com', 'central', 'production', '{device_id}');
+				INSERT INTO version_rollouts (version_id, allowlist, percentage) VALUES
+				('33333333-3333-3333-3333-333333333333', '{{}}', 0)"
+			))
+			.await
+			.unwrap();
+
+			// not in the rollout: gets the latest patch it's eligible for
+			let response = public
+				.get("/versions/update-for/1.0.0")
+				.add_header("mtls-certificate", &cert)
+				.await;
+			response.assert_status_ok();
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates.len(), 1);
+			assert_eq!(updates[0].patch, 1);
+
+			// on the allowlist: gets the new patch
+			conn.batch_execute(
+				"UPDATE version_rollouts SET allowlist = '{44444444-4444-4444-4444-444444444444}'",
+			)
+			.await
+			.unwrap();
+			let response = public
+				.get("/versions/update-for/1.0.0")
+				.add_header("mtls-certificate", &cert)
+				.await;
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates[0].patch, 2);
+
+			// anonymous callers aren't in the rollout until it reaches everyone
+			let response = public.get("/versions/update-for/1.0.0").await;
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates[0].patch, 1);
+
+			// pausing holds the version back from everyone
+			conn.batch_execute("UPDATE version_rollouts SET paused = true, percentage = 100")
+				.await
+				.unwrap();
+			let response = public
+				.get("/versions/update-for/1.0.0")
+				.add_header("mtls-certificate", &cert)
+				.await;
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates[0].patch, 1);
+
+			conn.batch_execute("UPDATE version_rollouts SET paused = false")
+				.await
+				.unwrap();
+			let response = public.get("/versions/update-for/1.0.0").await;
+			let updates: Vec<Version> = response.json();
+			assert_eq!(updates[0].patch, 2);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn support_release_date_ignores_drafts() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, created_at) VALUES
+			(1, 3, 0, '', 'draft', '2024-09-01 00:00:00+00'),
+			(1, 3, 1, '', 'published', '2024-09-10 00:00:00+00')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/support").await;
+		response.assert_status_ok();
+		let support: Vec<serde_json::Value> = response.json();
+		assert_eq!(support[0]["minor"], 3);
+		assert_eq!(support[0]["released_at"], serde_json::Value::Null);
+
+		let response = public.get("/versions/1.3.1/support").await;
+		response.assert_status_ok();
+		let support: serde_json::Value = response.json();
+		assert_eq!(support["released_at"], serde_json::Value::Null);
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn support_policies_per_minor() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, created_at) VALUES
+			(1, 0, 0, '', 'published', '2024-01-15 00:00:00+00'),
+			(1, 0, 1, '', 'published', '2024-02-01 00:00:00+00'),
+			(1, 1, 0, '', 'published', '2024-06-01 00:00:00+00'),
+			(1, 2, 0, '', 'draft', '2024-09-01 00:00:00+00');
+			INSERT INTO support_policies (major, minor, active_support_until, security_support_until) VALUES
+			(1, 0, '2024-06-30', '2024-12-31'),
+			(1, 1, '2099-06-30', '2099-12-31')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/support").await;
+		response.assert_status_ok();
+		let support: Vec<serde_json::Value> = response.json();
+		assert_eq!(support.len(), 2, "drafts-only minors aren't listed");
+		assert_eq!(support[0]["minor"], 1);
+		assert_eq!(support[0]["status"], "active");
+		assert_eq!(support[0]["active_support_until"], "2099-06-30");
+		assert_eq!(support[1]["minor"], 0);
+		assert_eq!(support[1]["status"], "end_of_life");
+		assert_eq!(support[1]["released_at"], "2024-01-15T00:00:00Z");
+
+		let response = public.get("/versions/1.0.1/support").await;
+		response.assert_status_ok();
+		let support: serde_json::Value = response.json();
+		assert_eq!(support["security_support_until"], "2024-12-31");
+		assert_eq!(support["status"], "end_of_life");
+
+		let response = public.get("/versions/1.2.0/support").await;
+		response.assert_status_ok();
+		let support: serde_json::Value = response.json();
+		assert_eq!(support["status"], "unknown");
+		assert_eq!(support["active_support_until"], serde_json::Value::Null);
+
+		let response = public.get("/").await;
+		response.assert_status_ok();
+		let body = response.text();
+		assert!(body.contains("end of life"), "{body}");
+		assert!(body.contains("until 2099-06-30"), "{body}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn changelog_between_versions() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status) VALUES
+			(2, 14, 3, 'Starting point', 'published'),
+			(2, 14, 4, 'Fixed **sync** stalls', 'published'),
+			(2, 15, 0, 'New labs module', 'published'),
+			(2, 15, 1, 'Broken build', 'yanked'),
+			(2, 15, 2, 'Not ready yet', 'draft'),
+			(2, 16, 0, 'Target release', 'published'),
+			(2, 16, 1, 'Past the target', 'published')",
+		)
+		.await
+		.unwrap();
+
+		let json = |path: &'static str| public.get(path).add_header("accept", "application/json");
+
+		let response = json("/versions/2.14.3/changelog/2.16.0").await;
+		response.assert_status_ok();
+		let minors: Vec<serde_json::Value> = response.json();
+		let grouped: Vec<_> = minors
+			.iter()
+			.map(|minor| {
+				(
+					minor["minor"].as_i64().unwrap(),
+					minor["versions"]
+						.as_array()
+						.unwrap()
+						.iter()
+						.map(|v| v["patch"].as_i64().unwrap())
+						.collect::<Vec<_>>(),
+				)
+			})
+			.collect();
+		assert_eq!(grouped, vec![(14, vec![4]), (15, vec![0]), (16, vec![0])]);
+		assert_eq!(
+			minors[0]["versions"][0]["changelog"],
+			"<p>Fixed <strong>sync</strong> stalls</p>\n"
+		);
+
+		let response = json("/versions/2.16.1/changelog/2.16.1").await;
+		response.assert_status_ok();
+		response.assert_json::<Vec<serde_json::Value>>(&Vec::new());
+
+		let response = json("/versions/2.16.0/changelog/2.14.3").await;
+		response.assert_status(StatusCode::BAD_REQUEST);
+		let problem: serde_json::Value = response.json();
+		assert_eq!(problem["type"], "/errors/backwards-range");
+
+		let response = public.get("/versions/2.14.3/changelog/2.16.0").await;
+		response.assert_status_ok();
+		response.assert_header("content-type", "text/html; charset=utf-8");
+		let body = response.text();
+		assert!(
+			body.contains("<h2 class=\"title is-4\">2.14</h2>"),
+			"{body}"
+		);
+		assert!(
+			body.contains("<h2 class=\"title is-4\">2.15</h2>"),
+			"{body}"
+		);
+		assert!(body.contains("<strong>sync</strong>"), "{body}");
+		assert!(!body.contains("Starting point"), "{body}");
+		assert!(!body.contains("Broken build"), "{body}");
+		assert!(!body.contains("Past the target"), "{body}");
+		let labs = body.find("New labs module").unwrap();
+		let target = body.find("Target release").unwrap();
+		assert!(labs < target, "versions are in ascending order");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn version_create_prerelease() {
+	use commons_types::version::ReleaseChannel;
+	use database::versions::Version;
+
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			let response = public
+				.post("/versions/2.20.0-rc.3+build.42")
+				.add_header("mtls-certificate", &cert)
+				.text("Release candidate")
+				.await;
+			response.assert_status_ok();
+
+			let version = Version::get_by_version(&mut conn, "2.20.0-rc.3".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.prerelease, "rc.3");
+			assert_eq!(version.build, "build.42");
+			assert_eq!(version.as_semver().to_string(), "2.20.0-rc.3+build.42");
+			// pre-releases are kept away from stable unless asked for
+			assert_eq!(version.channels, vec![ReleaseChannel::Beta]);
+
+			// the release is a separate version from its candidates
+			let response = public
+				.post("/versions/2.20.0")
+				.add_header("mtls-certificate", &cert)
+				.text("Release")
+				.await;
+			response.assert_status_ok();
+			let version = Version::get_by_version(&mut conn, "2.20.0".parse().unwrap())
+				.await
+				.unwrap();
+			assert_eq!(version.prerelease, "");
+			assert_eq!(version.channels, vec![ReleaseChannel::Stable]);
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prereleases_are_ordered_and_hidden_from_stable() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, prerelease, changelog, status, channels) VALUES
+			(2, 19, 1, '', 'Current', 'published', '{stable,beta}'),
+			(2, 20, 0, 'rc.2', 'Second candidate', 'published', '{beta}'),
+			(2, 20, 0, 'rc.10', 'Tenth candidate', 'published', '{beta}'),
+			(2, 20, 0, 'rc.9', 'Ninth candidate', 'published', '{beta}'),
+			(2, 20, 0, 'beta', 'Beta', 'published', '{beta}')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/update-for/2.19.1").await;
+		response.assert_status_ok();
+		response.assert_json::<Vec<Version>>(&Vec::new());
+
+		let response = public
+			.get("/versions/update-for/2.19.1")
+			.add_query_param("channel", "beta")
+			.await;
+		response.assert_status_ok();
+		let updates: Vec<serde_json::Value> = response.json();
+		assert_eq!(updates.len(), 1);
+		assert_eq!(
+			updates[0]["prerelease"], "rc.10",
+			"rc.10 > rc.9 > rc.2 > beta"
+		);
+
+		let response = public
+			.get("/versions")
+			.add_query_param("channel", "beta")
+			.await;
+		response.assert_status_ok();
+		let versions: Vec<serde_json::Value> = response.json();
+		let prereleases: Vec<_> = versions
+			.iter()
+			.map(|v| v["prerelease"].as_str().unwrap())
+			.collect();
+		assert_eq!(prereleases, vec!["rc.10", "rc.9", "rc.2", "beta", ""]);
+
+		// ranges don't match pre-releases unless they ask for them
+		let response = public.get("/versions/^2.19.0/artifacts").await;
+		response.assert_status_ok();
+		let response = public.get("/versions/2.x").await;
+		response.assert_status_ok();
+		let body = response.text();
+		assert!(
+			body.contains("<h1 class=\"title mb-2\">2.19.1</h1>"),
+			"{body}"
+		);
+		let response = public.get("/versions/2.20.0-rc.9").await;
+		response.assert_status_ok();
+		let body = response.text();
+		assert!(body.contains("2.20.0-rc.9"), "{body}");
+
+		// the final release supersedes its candidates
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, prerelease, changelog, status, channels) VALUES
+			(2, 20, 0, '', 'Release', 'published', '{stable,beta}')",
+		)
+		.await
+		.unwrap();
+
+		for channel in ["stable", "beta"] {
+			let response = public
+				.get("/versions/update-for/2.20.0-rc.10")
+				.add_query_param("channel", channel)
+				.await;
+			response.assert_status_ok();
+			let updates: Vec<serde_json::Value> = response.json();
+			assert_eq!(updates.len(), 1, "{channel}");
+			assert_eq!(updates[0]["minor"], 20);
+			assert_eq!(updates[0]["prerelease"], "");
+		}
+
+		let response = public.get("/").await;
+		response.assert_status_ok();
+		let body = response.text();
+		assert!(!body.contains("rc."), "{body}");
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn yank_with_reason_and_replacement() {
+	use base64::{Engine as _, prelude::BASE64_STANDARD};
+
+	commons_tests::server::run_with_device_auth(
+		"admin",
+		async |mut conn, cert, device_id, public, _| {
+			conn.batch_execute(
+				"INSERT INTO versions (major, minor, patch, changelog, status) VALUES
+				(2, 21, 0, 'First', 'published'),
+				(2, 21, 1, 'Broken', 'published'),
+				(2, 21, 2, 'Fixed', 'published')",
+			)
+			.await
+			.unwrap();
+
+			// The replacement has to be published
+			public
+				.delete("/versions/2.21.1")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({ "replacement": "2.21.9" }))
+				.await
+				.assert_status_failure();
+
+			let response = public
+				.delete("/versions/2.21.1")
+				.add_header("mtls-certificate", &cert)
+				.json(&serde_json::json!({
+					"reason": "Migrations fail on <large> databases",
+					"severity": "critical",
+					"replacement": "2.21.2",
+				}))
+				.await;
+			response.assert_status_ok();
+			let notice: serde_json::Value = response.json();
+			assert_eq!(notice["version"], "2.21.1");
+			assert_eq!(notice["severity"], "critical");
+			assert_eq!(notice["replacement"], "2.21.2");
+
+			let version =
+				database::versions::Version::get_by_version(&mut conn, "2.21.1".parse().unwrap())
+					.await
+					.unwrap();
+			assert_eq!(version.status, VersionStatus::Yanked);
+			let yank = database::yanks::VersionYank::get_for_version(&mut conn, version.id)
+				.await
+				.unwrap()
+				.unwrap();
+			assert_eq!(yank.device_id, Some(device_id));
+
+			// Servers on the yanked version are told about it
+			let response = public.get("/versions/update-for/2.21.1").await;
+			response.assert_status_ok();
+			let notice: serde_json::Value = serde_json::from_slice(
+				&BASE64_STANDARD
+					.decode(response.header("X-Version-Yanked"))
+					.unwrap(),
+			)
+			.unwrap();
+			assert_eq!(notice["reason"], "Migrations fail on <large> databases");
+			assert_eq!(notice["replacement"], "2.21.2");
+
+			// and in the body, when the response is detailed
+			let response = public
+				.get("/versions/update-for/2.21.1?detailed=true")
+				.await;
+			response.assert_status_ok();
+			let check: serde_json::Value = response.json();
+			assert_eq!(check["yank"], notice);
+			assert_eq!(check["updates"][0]["patch"], 2);
+
+			let response = public.get("/versions/update-for/2.21.0").await;
+			response.assert_status_ok();
+			assert!(response.maybe_header("X-Version-Yanked").is_none());
+			let response = public
+				.get("/versions/update-for/2.21.0?detailed=true")
+				.await;
+			let check: serde_json::Value = response.json();
+			assert!(check["yank"].is_null());
+
+			// and it's shown on its page, and the pages of later versions
+			let response = public.get("/versions/2.21.1").await;
+			response.assert_status_ok();
+			let body = response.text();
+			assert!(body.contains("This version has been yanked"), "{body}");
+			assert!(
+				body.contains("Migrations fail on &lt;large&gt; databases"),
+				"{body}"
+			);
+			assert!(body.contains("href=\"/versions/2.21.2\""), "{body}");
+
+			let response = public.get("/versions/2.21.2").await;
+			response.assert_status_ok();
+			let body = response.text();
+			assert!(!body.contains("This version has been yanked"), "{body}");
+			assert!(
+				body.contains("<span class=\"tag is-danger\">yanked</span>"),
+				"{body}"
+			);
+
+			// Yanking without a body still works
+			public
+				.delete("/versions/2.21.0")
+				.add_header("mtls-certificate", &cert)
+				.await
+				.assert_status_ok();
+			let response = public.get("/versions/update-for/2.21.0").await;
+			assert!(response.maybe_header("X-Version-Yanked").is_some());
+		},
+	)
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_advice_reports_urgency() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, urgency) VALUES
+			(2, 22, 0, '', 'published', 'normal'),
+			(2, 22, 1, '', 'published', 'security'),
+			(2, 22, 2, '', 'published', 'mandatory'),
+			(2, 22, 3, '', 'published', 'normal'),
+			(2, 23, 0, '', 'published', 'recommended'),
+			(2, 23, 1, '', 'published', 'mandatory')",
+		)
+		.await
+		.unwrap();
+
+		let response = public.get("/versions/update-for/2.22.0").await;
+		response.assert_status_ok();
+		let updates: Vec<serde_json::Value> = response.json();
+		assert_eq!(updates[0]["patch"], 3);
+		assert_eq!(updates[0]["urgency"], "normal");
+
+		let response = public.get("/versions/update-for/2.22.0/advice").await;
+		response.assert_status_ok();
+		let advice: serde_json::Value = response.json();
+		assert_eq!(advice["current"], "2.22.0");
+		assert_eq!(advice["latest"]["minor"], 23);
+		assert_eq!(advice["latest"]["patch"], 1);
+		assert_eq!(advice["urgency"], "mandatory");
+		assert_eq!(advice["mandatory"], true);
+		assert_eq!(advice["minimum_version"], "2.22.2");
+		let fixes: Vec<_> = advice["security_fixes"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|fix| fix["version"].as_str().unwrap())
+			.collect();
+		assert_eq!(fixes, vec!["2.22.1"]);
+		assert_eq!(advice["updates"].as_array().unwrap().len(), 2);
+
+		// Past the minimum of its minor, a mandatory version in the next minor is only recommended
+		let response = public.get("/versions/update-for/2.22.2/advice").await;
+		response.assert_status_ok();
+		let advice: serde_json::Value = response.json();
+		assert_eq!(advice["urgency"], "recommended");
+		assert_eq!(advice["mandatory"], false);
+		assert_eq!(advice["minimum_version"], serde_json::Value::Null);
+		assert!(advice["security_fixes"].as_array().unwrap().is_empty());
+
+		let response = public.get("/versions/update-for/2.23.1/advice").await;
+		response.assert_status_ok();
+		let advice: serde_json::Value = response.json();
+		assert_eq!(advice["urgency"], "normal");
+		assert_eq!(advice["latest"], serde_json::Value::Null);
+
+		// The advice comes with the updates when asked for
+		let response = public
+			.get("/versions/update-for/2.22.0?detailed=true")
+			.await;
+		response.assert_status_ok();
+		let check: serde_json::Value = response.json();
+		assert_eq!(check["updates"][0]["patch"], 3);
+		assert_eq!(check["advice"]["urgency"], "mandatory");
+		assert_eq!(check["advice"]["minimum_version"], "2.22.2");
+
+		// Versions held back by a rollout don't count towards the advice
+		conn.batch_execute(
+			"INSERT INTO version_rollouts (version_id, percentage)
+			SELECT id, 0 FROM versions WHERE major = 2 AND minor = 22 AND patch IN (1, 2)",
+		)
+		.await
+		.unwrap();
+		let response = public.get("/versions/update-for/2.22.0/advice").await;
+		let advice: serde_json::Value = response.json();
+		assert_eq!(advice["urgency"], "recommended");
+		assert_eq!(advice["mandatory"], false);
+		assert_eq!(advice["minimum_version"], serde_json::Value::Null);
+		assert!(advice["security_fixes"].as_array().unwrap().is_empty());
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upgrade_path_crosses_majors_through_stepping_stones() {
+	commons_tests::server::run(async |mut conn, public, _| {
+		conn.batch_execute(
+			"INSERT INTO versions (major, minor, patch, changelog, status, stepping_stone) VALUES
+			(2, 22, 0, '', 'published', false),
+			(2, 22, 4, '', 'published', true),
+			(2, 22, 5, '', 'published', false),
+			(2, 23, 0, '', 'published', false),
+			(3, 0, 0, '', 'published', false),
+			(3, 1, 0, '', 'published', true),
+			(3, 1, 2, '', 'published', false),
+			(3, 2, 0, '', 'published', false)",
+		)
+		.await
+		.unwrap();
+
+		let minors_offered = async |version: &str| {
+			let response = public.get(&format!("/versions/update-for/{version}")).await;
+			response.assert_status_ok();
+			let updates: Vec<serde_json::Value> = response.json();
+			updates
+				.iter()
+				.map(|update| {
+					(
+						update["major"].as_i64().unwrap(),
+						update["minor"].as_i64().unwrap(),
+					)
+				})
+				.collect::<Vec<_>>()
+		};
+
+		// Update checks stay in the major, and stop at the next stepping stone
+		assert_eq!(minors_offered("2.22.0").await, vec![(2, 22)]);
+		assert_eq!(minors_offered("2.22.5").await, vec![(2, 23)]);
+		assert_eq!(minors_offered("3.0.0").await, vec![(3, 1)]);
+		assert_eq!(minors_offered("3.1.2").await, vec![(3, 2)]);
+
+		let response = public.get("/versions/update-for/2.22.0/path").await;
+		response.assert_status_ok();
+		let path: Vec<serde_json::Value> = response.json();
+		let path: Vec<_> = path
+			.iter()
+			.map(|hop| {
+				(
+					format!("{}.{}.{}", hop["major"], hop["minor"], hop["patch"]),
+					hop["required"].as_bool().unwrap(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			path,
+			vec![
+				("2.22.5".to_string(), true),
+				("3.1.2".to_string(), true),
+				("3.2.0".to_string(), false),
+			]
+		);
+
+		let response = public
+			.get("/versions/update-for/2.22.0?detailed=true")
+			.await;
+		let check: serde_json::Value = response.json();
+		assert_eq!(check["advice"]["path"].as_array().unwrap().len(), 3);
+
+		// Past the stepping stones, it's straight to the latest
+		let response = public.get("/versions/update-for/3.1.0/advice").await;
+		response.assert_status_ok();
+		let advice: serde_json::Value = response.json();
+		let path = advice["path"].as_array().unwrap();
+		assert_eq!(path.len(), 1);
+		assert_eq!(path[0]["minor"], 2);
+
+		let response = public.get("/versions/update-for/3.2.0/path").await;
+		response.assert_status_ok();
+		response.assert_json::<Vec<serde_json::Value>>(&Vec::new());
+	})
+	.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn version_create_with_urgency() {
+	commons_tests::server::run_with_device_auth(
+		"releaser",
+		async |mut conn, cert, _device_id, public, _| {
+			public
+				.post("/versions/2.24.1")
+				.add_header("mtls-certificate", &cert)
+				.add_query_param("urgency", "security")
+				.text("Security fix")
+				.await
+				.assert_status_ok();
+
+			let version =
+				database::versions::Version::get_by_version(&mut conn, "2.24.1".parse().unwrap())
+					.await
+					.unwrap();
+			assert_eq!(
+				version.urgency,
+				commons_types::version::UpdateUrgency::Security
+			);
+		},
+	)
+	.await
+}
@@ -0,0 +1,2 @@
+DROP TABLE server_probe_results;
+DROP TABLE server_probes;
@@ -0,0 +1,46 @@
+CREATE TABLE server_probes (
+	id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	-- A probe applies either to a single server or to every server of a kind
+	server_id UUID REFERENCES servers(id) ON DELETE CASCADE,
+	server_kind TEXT,
+
+	name TEXT NOT NULL,
+	probe_type TEXT NOT NULL,
+	enabled BOOLEAN NOT NULL DEFAULT TRUE,
+	check_interval INTERVAL NOT NULL DEFAULT '1 minute',
+	timeout INTERVAL NOT NULL DEFAULT '10 seconds',
+
+	-- http: path relative to the server host, and what to expect back
+	path TEXT,
+	expected_status INTEGER,
+	expected_body TEXT,
+
+	-- tcp: port to connect to on the server host
+	port INTEGER,
+
+	CONSTRAINT server_probes_target CHECK ((server_id IS NULL) <> (server_kind IS NULL)),
+	CONSTRAINT server_probes_probe_type CHECK (probe_type IN ('http', 'tcp', 'dns')),
+	CONSTRAINT server_probes_http_path CHECK (probe_type <> 'http' OR path IS NOT NULL),
+	CONSTRAINT server_probes_tcp_port CHECK (probe_type <> 'tcp' OR port BETWEEN 1 AND 65535)
+);
+
+CREATE INDEX server_probes_server_id ON server_probes (server_id);
+CREATE INDEX server_probes_server_kind ON server_probes (server_kind);
+SELECT diesel_manage_updated_at('server_probes');
+
+CREATE TABLE server_probe_results (
+	id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	probe_id UUID NOT NULL REFERENCES server_probes(id) ON DELETE CASCADE,
+	server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+	success BOOLEAN NOT NULL,
+	latency_ms INTEGER NOT NULL,
+	error TEXT
+);
+
+CREATE INDEX server_probe_results_latest ON server_probe_results (probe_id, server_id, created_at DESC);
+CREATE INDEX server_probe_results_server ON server_probe_results (server_id, created_at DESC);
+CREATE INDEX server_probe_results_date ON server_probe_results USING brin (created_at);
@@ -0,0 +1,14 @@
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT *, ROW_NUMBER() OVER (PARTITION BY major, minor ORDER BY patch DESC) as rn
+	FROM versions
+)
+SELECT id, major, minor, patch, status, changelog
+FROM ranked_versions
+WHERE rn = 1;
+
+ALTER TABLE servers DROP COLUMN default_channel;
+
+DROP INDEX IF EXISTS versions_channels_idx;
+ALTER TABLE versions DROP COLUMN channels;
@@ -0,0 +1,23 @@
+ALTER TABLE versions
+	ADD COLUMN channels TEXT[] NOT NULL DEFAULT '{stable}',
+	ADD CONSTRAINT versions_channels_check
+		CHECK (channels <@ ARRAY['stable', 'beta', 'lts']::TEXT[]);
+
+CREATE INDEX versions_channels_idx ON versions USING gin (channels);
+
+ALTER TABLE servers
+	ADD COLUMN default_channel TEXT NOT NULL DEFAULT 'stable',
+	ADD CONSTRAINT servers_default_channel_check
+		CHECK (default_channel IN ('stable', 'beta', 'lts'));
+
+-- The latest version of each minor, per channel
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (PARTITION BY channel, major, minor ORDER BY patch DESC) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, status, changelog, channel
+FROM ranked_versions
+WHERE rn = 1;
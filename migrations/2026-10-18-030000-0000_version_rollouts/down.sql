@@ -0,0 +1,2 @@
+DROP FUNCTION IF EXISTS rollout_bucket(UUID, UUID);
+DROP TABLE IF EXISTS version_rollouts;
@@ -0,0 +1,29 @@
+-- A staged rollout holds a version back from update checks until the calling
+-- server is eligible: first the allowlist, then a percentage of servers, then
+-- everyone. Versions without a rollout are offered to everyone.
+CREATE TABLE version_rollouts (
+	version_id UUID PRIMARY KEY REFERENCES versions(id) ON DELETE CASCADE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	-- servers which get the version regardless of percentage
+	allowlist UUID[] NOT NULL DEFAULT '{}',
+
+	-- share of servers, chosen by rollout_bucket(), which get the version
+	percentage INTEGER NOT NULL DEFAULT 0,
+
+	-- while paused, the version isn't offered to any server, allowlisted or not
+	paused BOOLEAN NOT NULL DEFAULT FALSE,
+
+	CONSTRAINT version_rollouts_percentage CHECK (percentage BETWEEN 0 AND 100)
+);
+
+SELECT diesel_manage_updated_at('version_rollouts');
+
+-- Stable bucket in [0, 100) for a server and version, so that a server stays in
+-- a rollout as its percentage grows, but different servers go first each time.
+CREATE FUNCTION rollout_bucket(server_id UUID, version_id UUID) RETURNS INTEGER
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+	SELECT (('x' || substr(md5(server_id::text || version_id::text), 1, 8))::bit(32)::bigint % 100)::integer
+$$;
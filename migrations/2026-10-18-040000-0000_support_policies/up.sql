@@ -0,0 +1,23 @@
+-- How long each minor release is supported for. The release date isn't stored,
+-- it's when the minor's first patch was created.
+CREATE TABLE support_policies (
+	major INTEGER NOT NULL,
+	minor INTEGER NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	-- last day of bug fixes and security fixes
+	active_support_until DATE,
+
+	-- last day of security fixes only
+	security_support_until DATE,
+
+	PRIMARY KEY (major, minor),
+	CONSTRAINT support_policies_security_after_active CHECK (
+		active_support_until IS NULL
+		OR security_support_until IS NULL
+		OR security_support_until >= active_support_until
+	)
+);
+
+SELECT diesel_manage_updated_at('support_policies');
@@ -0,0 +1,32 @@
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (PARTITION BY channel, major, minor ORDER BY patch DESC) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, status, changelog, channel
+FROM ranked_versions
+WHERE rn = 1;
+
+DROP INDEX IF EXISTS versions_sort_key_idx;
+DROP FUNCTION IF EXISTS version_sort_key(INT, INT, INT, TEXT);
+
+-- Pre-releases can't be told apart from their release without the column,
+-- so refuse to roll back rather than lose them
+DO $$
+BEGIN
+	IF EXISTS (SELECT 1 FROM versions WHERE prerelease <> '') THEN
+		RAISE EXCEPTION 'cannot drop the prerelease column while pre-release versions exist';
+	END IF;
+END
+$$;
+
+ALTER TABLE versions DROP CONSTRAINT versions_version_number;
+ALTER TABLE versions ADD CONSTRAINT versions_version_number UNIQUE (
+	major, minor, patch
+);
+
+ALTER TABLE versions
+	DROP COLUMN build,
+	DROP COLUMN prerelease;
@@ -0,0 +1,58 @@
+ALTER TABLE versions
+	ADD COLUMN prerelease TEXT NOT NULL DEFAULT '',
+	ADD COLUMN build TEXT NOT NULL DEFAULT '',
+	ADD CONSTRAINT versions_prerelease_check
+		CHECK (prerelease ~ '^([0-9A-Za-z-]+(\.[0-9A-Za-z-]+)*)?$'),
+	ADD CONSTRAINT versions_build_check
+		CHECK (build ~ '^([0-9A-Za-z-]+(\.[0-9A-Za-z-]+)*)?$');
+
+-- Build metadata doesn't take part in a version's identity
+ALTER TABLE versions DROP CONSTRAINT versions_version_number;
+ALTER TABLE versions ADD CONSTRAINT versions_version_number UNIQUE (
+	major, minor, patch, prerelease
+);
+
+-- A key which sorts versions in semver precedence order, comparing bytewise.
+--
+-- Numbers are zero-padded so they sort numerically. A release sorts after all of its
+-- pre-releases, whose identifiers are compared one by one: numeric identifiers sort before
+-- alphanumeric ones, and a shorter list of identifiers sorts before a longer one it's a prefix of.
+CREATE FUNCTION version_sort_key(major INT, minor INT, patch INT, prerelease TEXT)
+RETURNS BYTEA
+LANGUAGE SQL
+IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+	SELECT convert_to(
+		lpad(major::TEXT, 10, '0')
+		|| lpad(minor::TEXT, 10, '0')
+		|| lpad(patch::TEXT, 10, '0')
+		|| CASE WHEN prerelease = '' THEN '~' ELSE (
+			SELECT string_agg(
+				CASE WHEN identifier ~ '^[0-9]+$'
+					THEN '0' || lpad(identifier, 20, '0')
+					ELSE '1' || identifier
+				END || ' ',
+				'' ORDER BY position
+			)
+			FROM unnest(string_to_array(prerelease, '.')) WITH ORDINALITY AS t(identifier, position)
+		) END,
+		'UTF8'
+	)
+$$;
+
+CREATE INDEX versions_sort_key_idx ON versions (version_sort_key(major, minor, patch, prerelease));
+
+-- The latest version of each minor, per channel
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (
+			PARTITION BY channel, major, minor
+			ORDER BY version_sort_key(major, minor, patch, prerelease) DESC
+		) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, prerelease, build, status, changelog, channel
+FROM ranked_versions
+WHERE rn = 1;
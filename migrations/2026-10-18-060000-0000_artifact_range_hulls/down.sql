@@ -0,0 +1,5 @@
+ALTER TABLE artifacts
+	DROP COLUMN version_range_ceiling,
+	DROP COLUMN version_range_floor;
+
+DROP FUNCTION IF EXISTS version_sort_key(TEXT);
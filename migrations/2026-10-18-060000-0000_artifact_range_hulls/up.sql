@@ -0,0 +1,19 @@
+-- The sort key of a version written out, like '2.20.0-rc.3+build.42'
+CREATE FUNCTION version_sort_key(version TEXT)
+RETURNS BYTEA
+LANGUAGE SQL
+IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+	SELECT version_sort_key(parts[1]::INT, parts[2]::INT, parts[3]::INT, coalesce(parts[4], ''))
+	FROM regexp_match(
+		version,
+		'^(\d+)\.(\d+)\.(\d+)(?:-([0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*))?(?:\+[0-9A-Za-z.-]*)?$'
+	) AS parts
+$$;
+
+-- The lowest and highest versions a range pattern can match, both inclusive, so that artifacts
+-- can be narrowed down before checking the pattern itself. NULL means unbounded on that side,
+-- which is also what patterns saved before these were computed are treated as.
+ALTER TABLE artifacts
+	ADD COLUMN version_range_floor TEXT,
+	ADD COLUMN version_range_ceiling TEXT;
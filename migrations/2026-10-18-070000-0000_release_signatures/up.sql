@@ -0,0 +1,30 @@
+-- Ed25519 public keys which releasers sign version manifests with. Each key
+-- belongs to the releaser device which registered it.
+CREATE TABLE releaser_keys (
+	id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	device_id UUID NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+	name TEXT,
+	public_key BYTEA NOT NULL,
+
+	CONSTRAINT releaser_keys_public_key_length CHECK (length(public_key) = 32),
+	CONSTRAINT releaser_keys_public_key_unique UNIQUE (public_key)
+);
+
+CREATE INDEX releaser_keys_device_id ON releaser_keys (device_id);
+
+-- Detached signatures over a version's manifest, as uploaded by releasers.
+-- The signed payload is kept so that a signature stops being served once the
+-- manifest changes, e.g. when an artifact is added.
+CREATE TABLE version_signatures (
+	id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	version_id UUID NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+	releaser_key_id UUID NOT NULL REFERENCES releaser_keys(id) ON DELETE CASCADE,
+	payload TEXT NOT NULL,
+	signature BYTEA NOT NULL,
+
+	CONSTRAINT version_signatures_signature_length CHECK (length(signature) = 64)
+);
+
+CREATE INDEX version_signatures_version_id ON version_signatures (version_id, created_at DESC);
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS artifacts_unverified;
+ALTER TABLE artifacts
+	DROP COLUMN IF EXISTS verification_error,
+	DROP COLUMN IF EXISTS verified_at,
+	DROP COLUMN IF EXISTS size_bytes,
+	DROP COLUMN IF EXISTS sha256;
@@ -0,0 +1,13 @@
+-- Checksums and sizes of artifacts, either declared by the releaser or filled
+-- in by the verification job once it has downloaded the artifact.
+ALTER TABLE artifacts
+	ADD COLUMN sha256 TEXT,
+	ADD COLUMN size_bytes BIGINT,
+	-- when the job last downloaded the artifact and checked it
+	ADD COLUMN verified_at TIMESTAMPTZ,
+	-- why the artifact failed verification: a mismatch or a failed download
+	ADD COLUMN verification_error TEXT,
+	ADD CONSTRAINT artifacts_sha256 CHECK (sha256 ~ '^[0-9a-f]{64}$'),
+	ADD CONSTRAINT artifacts_size_bytes CHECK (size_bytes >= 0);
+
+CREATE INDEX artifacts_unverified ON artifacts (created_at) WHERE verified_at IS NULL;
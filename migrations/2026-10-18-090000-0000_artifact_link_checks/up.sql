@@ -0,0 +1,12 @@
+-- The last time the link checker looked at each artifact's download URL, and
+-- what it found. Only the latest result is kept.
+CREATE TABLE artifact_link_checks (
+	artifact_id UUID PRIMARY KEY REFERENCES artifacts(id) ON DELETE CASCADE,
+	checked_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	healthy BOOLEAN NOT NULL,
+	-- HTTP status of the last request made, if one got a response
+	status_code INTEGER,
+	error TEXT
+);
+
+CREATE INDEX artifact_link_checks_broken ON artifact_link_checks (checked_at) WHERE NOT healthy;
@@ -0,0 +1,20 @@
+-- One row per download of an artifact, whether proxied or redirected to its
+-- download URL. Client details are kept coarse: the network rather than the
+-- address, and the country if a CDN in front of the server provides it.
+CREATE TABLE artifact_downloads (
+	id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	artifact_id UUID NOT NULL REFERENCES artifacts(id) ON DELETE CASCADE,
+	-- The version downloaded for, as range artifacts are shared between versions
+	version_id UUID NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+	redirected BOOLEAN NOT NULL DEFAULT FALSE,
+	client_network INET,
+	country TEXT,
+	user_agent TEXT,
+	device_id UUID REFERENCES devices(id) ON DELETE SET NULL,
+
+	CONSTRAINT artifact_downloads_country_code CHECK (country ~ '^[A-Z0-9]{2}$')
+);
+
+CREATE INDEX artifact_downloads_version_id ON artifact_downloads (version_id, created_at);
+CREATE INDEX artifact_downloads_artifact_id ON artifact_downloads (artifact_id);
@@ -0,0 +1,17 @@
+-- Why a version was yanked, and what servers on it should move to instead.
+-- The version's status is still what says it's yanked: this is kept if it's
+-- put back, so that the reason is still there if it's yanked again.
+CREATE TABLE version_yanks (
+	version_id UUID PRIMARY KEY REFERENCES versions(id) ON DELETE CASCADE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	reason TEXT NOT NULL DEFAULT '',
+	severity TEXT NOT NULL DEFAULT 'medium',
+	replacement_version_id UUID REFERENCES versions(id) ON DELETE SET NULL,
+	device_id UUID REFERENCES devices(id) ON DELETE SET NULL,
+
+	CONSTRAINT version_yanks_severity CHECK (severity IN ('low', 'medium', 'high', 'critical')),
+	CONSTRAINT version_yanks_replacement_not_self CHECK (replacement_version_id <> version_id)
+);
+
+SELECT diesel_manage_updated_at('version_yanks');
@@ -0,0 +1,17 @@
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (
+			PARTITION BY channel, major, minor
+			ORDER BY version_sort_key(major, minor, patch, prerelease) DESC
+		) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, prerelease, build, status, changelog, channel
+FROM ranked_versions
+WHERE rn = 1;
+
+ALTER TABLE versions
+	DROP CONSTRAINT versions_urgency_check,
+	DROP COLUMN urgency;
@@ -0,0 +1,21 @@
+-- How urgently servers on older versions should update. A mandatory version is
+-- the minimum required version of its minor.
+ALTER TABLE versions
+	ADD COLUMN urgency TEXT NOT NULL DEFAULT 'normal',
+	ADD CONSTRAINT versions_urgency_check
+		CHECK (urgency IN ('normal', 'recommended', 'security', 'mandatory'));
+
+-- The latest version of each minor, per channel
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (
+			PARTITION BY channel, major, minor
+			ORDER BY version_sort_key(major, minor, patch, prerelease) DESC
+		) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, prerelease, build, status, changelog, channel, urgency
+FROM ranked_versions
+WHERE rn = 1;
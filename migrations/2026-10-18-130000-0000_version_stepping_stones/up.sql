@@ -0,0 +1,19 @@
+-- Versions which servers must upgrade through, because a migration has to run on them before
+-- moving on to anything newer.
+ALTER TABLE versions
+	ADD COLUMN stepping_stone BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The latest version of each minor, per channel
+DROP VIEW IF EXISTS version_updates;
+CREATE VIEW version_updates AS
+WITH ranked_versions AS (
+	SELECT versions.*, channel,
+		ROW_NUMBER() OVER (
+			PARTITION BY channel, major, minor
+			ORDER BY version_sort_key(major, minor, patch, prerelease) DESC
+		) as rn
+	FROM versions, unnest(channels) AS channel
+)
+SELECT id, major, minor, patch, prerelease, build, status, changelog, channel, urgency, stepping_stone
+FROM ranked_versions
+WHERE rn = 1;
@@ -0,0 +1,3 @@
+ALTER TABLE artifacts
+	DROP COLUMN IF EXISTS version_code,
+	DROP COLUMN IF EXISTS bundle_id;
@@ -0,0 +1,7 @@
+-- What mobile installers need to know about an app beyond where to download it:
+-- iOS manifests name the bundle, and Android builds are ordered by version code.
+ALTER TABLE artifacts
+	ADD COLUMN bundle_id TEXT,
+	ADD COLUMN version_code BIGINT,
+	ADD CONSTRAINT artifacts_bundle_id CHECK (bundle_id ~ '^[A-Za-z0-9_-]+(\.[A-Za-z0-9_-]+)+$'),
+	ADD CONSTRAINT artifacts_version_code CHECK (version_code >= 0);
@@ -0,0 +1,22 @@
+-- The versions of the software each minor release runs on or is used from,
+-- like Node.js and PostgreSQL on servers, or Chrome on clients. Bounds are
+-- dot-separated numbers, compared only as precisely as they're written.
+CREATE TABLE platform_requirements (
+	major INTEGER NOT NULL,
+	minor INTEGER NOT NULL,
+	platform TEXT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	min_version TEXT,
+	max_version TEXT,
+
+	PRIMARY KEY (major, minor, platform),
+	CONSTRAINT platform_requirements_platform
+		CHECK (platform IN ('nodejs', 'postgres', 'chrome', 'android', 'ios')),
+	CONSTRAINT platform_requirements_bounded
+		CHECK (min_version IS NOT NULL OR max_version IS NOT NULL),
+	CONSTRAINT platform_requirements_min_version CHECK (min_version ~ '^[0-9]+(\.[0-9]+)*$'),
+	CONSTRAINT platform_requirements_max_version CHECK (max_version ~ '^[0-9]+(\.[0-9]+)*$')
+);
+
+SELECT diesel_manage_updated_at('platform_requirements');
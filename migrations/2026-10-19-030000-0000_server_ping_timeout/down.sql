@@ -0,0 +1,3 @@
+ALTER TABLE servers
+	DROP CONSTRAINT servers_ping_timeout_check,
+	DROP COLUMN ping_timeout;
@@ -0,0 +1,5 @@
+-- Overrides the pingtask's global timeout for servers that are known to be slow to answer
+ALTER TABLE servers
+	ADD COLUMN ping_timeout INTERVAL,
+	ADD CONSTRAINT servers_ping_timeout_check
+		CHECK (ping_timeout IS NULL OR ping_timeout > '0 seconds');
@@ -0,0 +1 @@
+DROP TABLE compatibility_rules;
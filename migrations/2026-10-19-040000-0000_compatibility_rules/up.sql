@@ -0,0 +1,10 @@
+-- The rules that facility versions are checked against relative to their central.
+-- A rule is checked when it has a row here.
+CREATE TABLE compatibility_rules (
+	rule TEXT PRIMARY KEY,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+	CONSTRAINT compatibility_rules_rule CHECK (rule IN ('same_minor', 'facility_not_ahead'))
+);
+
+INSERT INTO compatibility_rules (rule) VALUES ('same_minor'), ('facility_not_ahead');
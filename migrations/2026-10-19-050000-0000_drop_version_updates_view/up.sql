@@ -0,0 +1 @@
+DROP VIEW version_updates;
@@ -0,0 +1,6 @@
+DROP TRIGGER set_status_timestamps ON versions;
+DROP FUNCTION versions_set_status_timestamps();
+
+ALTER TABLE versions
+	DROP COLUMN published_at,
+	DROP COLUMN yanked_at;
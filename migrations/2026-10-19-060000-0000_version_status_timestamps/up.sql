@@ -0,0 +1,32 @@
+ALTER TABLE versions
+	ADD COLUMN published_at TIMESTAMPTZ,
+	ADD COLUMN yanked_at TIMESTAMPTZ;
+
+UPDATE versions SET published_at = created_at WHERE status IN ('published', 'yanked');
+UPDATE versions SET yanked_at = COALESCE(
+	(SELECT created_at FROM version_yanks WHERE version_yanks.version_id = versions.id),
+	updated_at
+) WHERE status = 'yanked';
+
+-- Keeps the timestamps in step with the status, whichever way it's changed
+CREATE FUNCTION versions_set_status_timestamps() RETURNS trigger AS $$
+BEGIN
+	IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+		CASE NEW.status
+			WHEN 'published' THEN
+				NEW.published_at := COALESCE(NEW.published_at, current_timestamp);
+				NEW.yanked_at := NULL;
+			WHEN 'yanked' THEN
+				NEW.published_at := COALESCE(NEW.published_at, current_timestamp);
+				NEW.yanked_at := current_timestamp;
+			ELSE
+				NEW.published_at := NULL;
+				NEW.yanked_at := NULL;
+		END CASE;
+	END IF;
+	RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER set_status_timestamps BEFORE INSERT OR UPDATE OF status ON versions
+	FOR EACH ROW EXECUTE PROCEDURE versions_set_status_timestamps();